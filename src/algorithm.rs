@@ -0,0 +1,217 @@
+use crate::error::DNSError;
+use failure::Result;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    RSAMD5,
+    DSA,
+    RSASHA1,
+    DSANSEC3SHA1,
+    RSASHA1NSEC3SHA1,
+    RSASHA256,
+    RSASHA512,
+    ECCGOST,
+    ECDSAP256SHA256,
+    ECDSAP384SHA384,
+    ED25519,
+    ED448,
+    Indirect,
+    PrivateDNS,
+    PrivateOID,
+    Unknown(u8),
+}
+
+//implementation requirements from rfc8624 section 3.1
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Requirement {
+    MustNot,
+    NotRecommended,
+    May,
+    Recommended,
+    Must,
+}
+
+impl Algorithm {
+    pub fn new(value: u8) -> Self {
+        match value {
+            1 => Algorithm::RSAMD5,
+            3 => Algorithm::DSA,
+            5 => Algorithm::RSASHA1,
+            6 => Algorithm::DSANSEC3SHA1,
+            7 => Algorithm::RSASHA1NSEC3SHA1,
+            8 => Algorithm::RSASHA256,
+            10 => Algorithm::RSASHA512,
+            12 => Algorithm::ECCGOST,
+            13 => Algorithm::ECDSAP256SHA256,
+            14 => Algorithm::ECDSAP384SHA384,
+            15 => Algorithm::ED25519,
+            16 => Algorithm::ED448,
+            252 => Algorithm::Indirect,
+            253 => Algorithm::PrivateDNS,
+            254 => Algorithm::PrivateOID,
+            _ => Algorithm::Unknown(value),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Algorithm::RSAMD5 => 1,
+            Algorithm::DSA => 3,
+            Algorithm::RSASHA1 => 5,
+            Algorithm::DSANSEC3SHA1 => 6,
+            Algorithm::RSASHA1NSEC3SHA1 => 7,
+            Algorithm::RSASHA256 => 8,
+            Algorithm::RSASHA512 => 10,
+            Algorithm::ECCGOST => 12,
+            Algorithm::ECDSAP256SHA256 => 13,
+            Algorithm::ECDSAP384SHA384 => 14,
+            Algorithm::ED25519 => 15,
+            Algorithm::ED448 => 16,
+            Algorithm::Indirect => 252,
+            Algorithm::PrivateDNS => 253,
+            Algorithm::PrivateOID => 254,
+            Algorithm::Unknown(c) => c,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Algorithm::RSAMD5 => "RSAMD5",
+            Algorithm::DSA => "DSA",
+            Algorithm::RSASHA1 => "RSASHA1",
+            Algorithm::DSANSEC3SHA1 => "DSA-NSEC3-SHA1",
+            Algorithm::RSASHA1NSEC3SHA1 => "RSASHA1-NSEC3-SHA1",
+            Algorithm::RSASHA256 => "RSASHA256",
+            Algorithm::RSASHA512 => "RSASHA512",
+            Algorithm::ECCGOST => "ECC-GOST",
+            Algorithm::ECDSAP256SHA256 => "ECDSAP256SHA256",
+            Algorithm::ECDSAP384SHA384 => "ECDSAP384SHA384",
+            Algorithm::ED25519 => "ED25519",
+            Algorithm::ED448 => "ED448",
+            Algorithm::Indirect => "INDIRECT",
+            Algorithm::PrivateDNS => "PRIVATEDNS",
+            Algorithm::PrivateOID => "PRIVATEOID",
+            Algorithm::Unknown(_) => "Unknown",
+        }
+    }
+
+    pub fn signing_requirement(self) -> Requirement {
+        match self {
+            Algorithm::RSASHA256 | Algorithm::ECDSAP256SHA256 => Requirement::Must,
+            Algorithm::ED25519 => Requirement::Recommended,
+            Algorithm::ECDSAP384SHA384 | Algorithm::ED448 => Requirement::May,
+            Algorithm::RSASHA1 | Algorithm::RSASHA1NSEC3SHA1 | Algorithm::RSASHA512 => {
+                Requirement::NotRecommended
+            }
+            _ => Requirement::MustNot,
+        }
+    }
+
+    pub fn validation_requirement(self) -> Requirement {
+        match self {
+            Algorithm::RSASHA1
+            | Algorithm::RSASHA1NSEC3SHA1
+            | Algorithm::RSASHA256
+            | Algorithm::RSASHA512
+            | Algorithm::ECDSAP256SHA256 => Requirement::Must,
+            Algorithm::ECDSAP384SHA384 | Algorithm::ED25519 | Algorithm::ED448 => {
+                Requirement::Recommended
+            }
+            Algorithm::ECCGOST => Requirement::May,
+            _ => Requirement::MustNot,
+        }
+    }
+
+    pub fn is_rsa(self) -> bool {
+        matches!(
+            self,
+            Algorithm::RSAMD5
+                | Algorithm::RSASHA1
+                | Algorithm::RSASHA1NSEC3SHA1
+                | Algorithm::RSASHA256
+                | Algorithm::RSASHA512
+        )
+    }
+
+    //nsec3 capable algorithms, rfc5155 section 2
+    pub fn supports_nsec3(self) -> bool {
+        !matches!(
+            self,
+            Algorithm::RSAMD5 | Algorithm::DSA | Algorithm::RSASHA1
+        )
+    }
+
+    //fixed key size in bits, rsa keys vary
+    pub fn key_bits(self) -> Option<u16> {
+        match self {
+            Algorithm::ECCGOST | Algorithm::ECDSAP256SHA256 | Algorithm::ED25519 => Some(256),
+            Algorithm::ECDSAP384SHA384 => Some(384),
+            Algorithm::ED448 => Some(456),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Algorithm::Unknown(c) => write!(f, "{}", c),
+            _ => f.write_str(self.to_str()),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = failure::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(value) = s.parse::<u8>() {
+            return Ok(Algorithm::new(value));
+        }
+        let upper = s.to_uppercase();
+        (1..=255)
+            .map(Algorithm::new)
+            .find(|alg| alg.to_str() == upper && !matches!(alg, Algorithm::Unknown(_)))
+            .ok_or_else(|| DNSError::UnknownAlgorithm(s.to_string()).into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_algorithm() {
+        assert_eq!(Algorithm::new(13), Algorithm::ECDSAP256SHA256);
+        assert_eq!(Algorithm::ECDSAP256SHA256.to_u8(), 13);
+        assert_eq!(Algorithm::new(200), Algorithm::Unknown(200));
+        assert_eq!(Algorithm::new(200).to_string(), "200");
+        assert_eq!(
+            Algorithm::RSASHA1NSEC3SHA1.to_string(),
+            "RSASHA1-NSEC3-SHA1"
+        );
+        assert_eq!(
+            "ecdsap384sha384".parse::<Algorithm>().unwrap(),
+            Algorithm::ECDSAP384SHA384
+        );
+        assert_eq!("8".parse::<Algorithm>().unwrap(), Algorithm::RSASHA256);
+        assert!("SHA999".parse::<Algorithm>().is_err());
+
+        assert_eq!(
+            Algorithm::RSAMD5.signing_requirement(),
+            Requirement::MustNot
+        );
+        assert_eq!(
+            Algorithm::RSASHA1.signing_requirement(),
+            Requirement::NotRecommended
+        );
+        assert_eq!(
+            Algorithm::RSASHA1.validation_requirement(),
+            Requirement::Must
+        );
+        assert!(Requirement::Recommended > Requirement::May);
+        assert!(!Algorithm::RSASHA1.supports_nsec3());
+        assert!(Algorithm::RSASHA1NSEC3SHA1.supports_nsec3());
+    }
+}
@@ -0,0 +1,400 @@
+use crate::error::DNSError;
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::rr_class::RRClass;
+use crate::rr_type::RRType;
+use crate::rrset::{RRTtl, RRset};
+use failure::Result;
+use rand::Rng;
+use std::cmp::Reverse;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MemberState {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PoolMember {
+    pub rdata: RData,
+    pub weight: u32,
+    pub state: MemberState,
+    //a reported state is only trusted until it expires, then the member
+    //is considered down until the checker reports again
+    pub expires: Option<Instant>,
+}
+
+impl PoolMember {
+    pub fn typ(&self) -> RRType {
+        match self.rdata {
+            RData::AAAA(_) => RRType::AAAA,
+            _ => RRType::A,
+        }
+    }
+
+    pub fn effective_state(&self, now: Instant) -> MemberState {
+        match self.expires {
+            Some(expires) if expires <= now => MemberState::Down,
+            _ => self.state,
+        }
+    }
+
+    fn is_candidate(&self, typ: RRType) -> bool {
+        self.weight > 0 && self.typ() == typ
+    }
+
+    fn is_available(&self, typ: RRType, now: Instant) -> bool {
+        self.is_candidate(typ) && self.effective_state(now) == MemberState::Up
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnswerPool {
+    pub name: String,
+    pub ttl: RRTtl,
+    //zero means every available member is answered
+    pub max_answers: usize,
+    //down members are answered when fewer members are up
+    pub min_answers: usize,
+    members: Vec<PoolMember>,
+    fallback: Vec<RData>,
+}
+
+impl AnswerPool {
+    pub fn new(name: &str, ttl: RRTtl) -> Self {
+        AnswerPool {
+            name: name.to_string(),
+            ttl,
+            max_answers: 0,
+            min_answers: 0,
+            members: Vec::new(),
+            fallback: Vec::new(),
+        }
+    }
+
+    pub fn add_member(&mut self, rdata: RData, weight: u32) -> Result<&mut Self> {
+        match rdata {
+            RData::A(_) | RData::AAAA(_) => {}
+            _ => return Err(DNSError::InvalidPoolMember.into()),
+        }
+        self.members.retain(|m| m.rdata != rdata);
+        self.members.push(PoolMember {
+            rdata,
+            weight,
+            state: MemberState::Up,
+            expires: None,
+        });
+        Ok(self)
+    }
+
+    //fallback rdatas are answered only when no member is usable
+    pub fn add_fallback(&mut self, rdata: RData) -> Result<&mut Self> {
+        match rdata {
+            RData::A(_) | RData::AAAA(_) => {}
+            _ => return Err(DNSError::InvalidPoolMember.into()),
+        }
+        if !self.fallback.contains(&rdata) {
+            self.fallback.push(rdata);
+        }
+        Ok(self)
+    }
+
+    pub fn fallback(&self) -> &[RData] {
+        &self.fallback
+    }
+
+    pub fn remove_member(&mut self, rdata: &RData) -> bool {
+        let len = self.members.len();
+        self.members.retain(|m| &m.rdata != rdata);
+        len != self.members.len()
+    }
+
+    pub fn members(&self) -> &[PoolMember] {
+        &self.members
+    }
+
+    pub fn set_state(&mut self, rdata: &RData, state: MemberState) -> bool {
+        match self.members.iter_mut().find(|m| &m.rdata == rdata) {
+            Some(member) => {
+                member.state = state;
+                member.expires = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn report(
+        &mut self,
+        rdata: &RData,
+        state: MemberState,
+        ttl: Duration,
+        now: Instant,
+    ) -> bool {
+        match self.members.iter_mut().find(|m| &m.rdata == rdata) {
+            Some(member) => {
+                member.state = state;
+                member.expires = Some(now + ttl);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn available_count(&self, typ: RRType) -> usize {
+        let now = Instant::now();
+        self.members
+            .iter()
+            .filter(|m| m.is_available(typ, now))
+            .count()
+    }
+
+    pub fn materialize(&self, owner: &Name, typ: RRType) -> Option<RRset> {
+        self.materialize_at(owner, typ, Instant::now(), &mut rand::thread_rng())
+    }
+
+    //up members are drawn by weight without replacement, so heavier members
+    //are both more likely to be answered and more likely to come first
+    pub fn materialize_at<R: Rng>(
+        &self,
+        owner: &Name,
+        typ: RRType,
+        now: Instant,
+        rng: &mut R,
+    ) -> Option<RRset> {
+        let mut candidates: Vec<&PoolMember> = self
+            .members
+            .iter()
+            .filter(|m| m.is_available(typ, now))
+            .collect();
+        let count = if self.max_answers == 0 {
+            candidates.len()
+        } else {
+            self.max_answers.min(candidates.len())
+        };
+        let mut rdatas = Vec::with_capacity(count.max(self.min_answers));
+        while rdatas.len() < count {
+            let total: u64 = candidates.iter().map(|m| u64::from(m.weight)).sum();
+            let mut point = rng.gen_range(0, total);
+            let index = candidates
+                .iter()
+                .position(|m| {
+                    if point < u64::from(m.weight) {
+                        true
+                    } else {
+                        point -= u64::from(m.weight);
+                        false
+                    }
+                })
+                .unwrap();
+            rdatas.push(candidates.remove(index).rdata.clone());
+        }
+
+        if rdatas.len() < self.min_answers {
+            let mut down: Vec<&PoolMember> = self
+                .members
+                .iter()
+                .filter(|m| m.is_candidate(typ) && m.effective_state(now) == MemberState::Down)
+                .collect();
+            down.sort_by_key(|m| Reverse(m.weight));
+            let missing = self.min_answers - rdatas.len();
+            rdatas.extend(down.into_iter().take(missing).map(|m| m.rdata.clone()));
+        }
+
+        if rdatas.is_empty() {
+            rdatas = self
+                .fallback
+                .iter()
+                .filter(|rdata| match rdata {
+                    RData::AAAA(_) => typ == RRType::AAAA,
+                    _ => typ == RRType::A,
+                })
+                .cloned()
+                .collect();
+        }
+
+        if rdatas.is_empty() {
+            None
+        } else {
+            Some(RRset {
+                name: owner.clone(),
+                typ,
+                class: RRClass::IN,
+                ttl: self.ttl,
+                rdatas,
+            })
+        }
+    }
+}
+
+//shared handle for health checkers and lookups, every update takes the
+//write lock once so lookups never observe a partially applied report
+#[derive(Debug, Clone)]
+pub struct PoolWatchdog {
+    pool: Arc<RwLock<AnswerPool>>,
+}
+
+impl PoolWatchdog {
+    pub fn new(pool: AnswerPool) -> Self {
+        PoolWatchdog {
+            pool: Arc::new(RwLock::new(pool)),
+        }
+    }
+
+    pub fn mark_up(&self, rdata: &RData) -> bool {
+        self.pool.write().unwrap().set_state(rdata, MemberState::Up)
+    }
+
+    pub fn mark_down(&self, rdata: &RData) -> bool {
+        self.pool
+            .write()
+            .unwrap()
+            .set_state(rdata, MemberState::Down)
+    }
+
+    pub fn report(&self, reports: &[(RData, MemberState)], ttl: Duration) -> usize {
+        let now = Instant::now();
+        let mut pool = self.pool.write().unwrap();
+        reports
+            .iter()
+            .filter(|(rdata, state)| pool.report(rdata, *state, ttl, now))
+            .count()
+    }
+
+    pub fn update<F: FnOnce(&mut AnswerPool)>(&self, f: F) {
+        f(&mut self.pool.write().unwrap())
+    }
+
+    pub fn snapshot(&self) -> AnswerPool {
+        self.pool.read().unwrap().clone()
+    }
+
+    pub fn materialize(&self, owner: &Name, typ: RRType) -> Option<RRset> {
+        self.pool.read().unwrap().materialize(owner, typ)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+    use std::str::FromStr;
+
+    fn rdata(s: &str) -> RData {
+        RRset::from_str(s).unwrap().rdatas.remove(0)
+    }
+
+    #[test]
+    fn test_materialize_pool() {
+        let owner = Name::new("www.example.com.").unwrap();
+        let a1 = rdata("a. 60 IN A 192.0.2.1");
+        let a2 = rdata("a. 60 IN A 192.0.2.2");
+        let a3 = rdata("a. 60 IN A 192.0.2.3");
+        let aaaa = rdata("a. 60 IN AAAA 2001:db8::1");
+
+        let mut pool = AnswerPool::new("web", RRTtl(30));
+        pool.add_member(a1.clone(), 1)
+            .unwrap()
+            .add_member(a2.clone(), 0)
+            .unwrap()
+            .add_member(a3.clone(), 3)
+            .unwrap()
+            .add_member(aaaa.clone(), 1)
+            .unwrap();
+        assert!(pool.add_member(rdata("a. 60 IN NS ns.a."), 1).is_err());
+        assert_eq!(pool.members().len(), 4);
+        assert_eq!(pool.available_count(RRType::A), 2);
+
+        let rrset = pool.materialize(&owner, RRType::A).unwrap();
+        assert_eq!(rrset.name, owner);
+        assert_eq!(rrset.ttl, RRTtl(30));
+        assert_eq!(rrset.rdatas.len(), 2);
+        assert!(!rrset.rdatas.contains(&a2));
+
+        pool.max_answers = 1;
+        let mut rng = StepRng::new(0, 0);
+        let now = Instant::now();
+        let rrset = pool
+            .materialize_at(&owner, RRType::A, now, &mut rng)
+            .unwrap();
+        assert_eq!(rrset.rdatas, vec![a1.clone()]);
+
+        assert!(pool.set_state(&a1, MemberState::Down));
+        let rrset = pool
+            .materialize_at(&owner, RRType::A, now, &mut rng)
+            .unwrap();
+        assert_eq!(rrset.rdatas, vec![a3.clone()]);
+
+        assert!(pool.set_state(&a3, MemberState::Down));
+        assert!(pool.materialize(&owner, RRType::A).is_none());
+        assert_eq!(
+            pool.materialize(&owner, RRType::AAAA).unwrap().rdatas,
+            vec![aaaa]
+        );
+        assert!(pool.remove_member(&a3));
+        assert!(!pool.set_state(&a3, MemberState::Up));
+    }
+
+    #[test]
+    fn test_pool_health() {
+        let owner = Name::new("www.example.com.").unwrap();
+        let a1 = rdata("a. 60 IN A 192.0.2.1");
+        let a2 = rdata("a. 60 IN A 192.0.2.2");
+        let a3 = rdata("a. 60 IN A 192.0.2.3");
+        let sorry = rdata("a. 60 IN A 198.51.100.1");
+
+        let mut pool = AnswerPool::new("web", RRTtl(30));
+        pool.add_member(a1.clone(), 1)
+            .unwrap()
+            .add_member(a2.clone(), 5)
+            .unwrap()
+            .add_member(a3.clone(), 2)
+            .unwrap()
+            .add_fallback(sorry.clone())
+            .unwrap();
+        pool.min_answers = 2;
+
+        let watchdog = PoolWatchdog::new(pool);
+        let checker = watchdog.clone();
+        let ttl = Duration::from_secs(10);
+        assert_eq!(
+            checker.report(
+                &[
+                    (a1.clone(), MemberState::Down),
+                    (a2.clone(), MemberState::Down),
+                    (sorry.clone(), MemberState::Up),
+                ],
+                ttl,
+            ),
+            2
+        );
+        let rrset = watchdog.materialize(&owner, RRType::A).unwrap();
+        assert_eq!(rrset.rdatas, vec![a3.clone(), a2.clone()]);
+
+        checker.update(|pool| pool.min_answers = 0);
+        assert!(checker.mark_down(&a3));
+        let rrset = watchdog.materialize(&owner, RRType::A).unwrap();
+        assert_eq!(rrset.rdatas, vec![sorry]);
+
+        //stale reports expire into down, unreported members stay as set
+        let pool = watchdog.snapshot();
+        let later = Instant::now() + ttl * 2;
+        let mut rng = StepRng::new(0, 0);
+        assert_eq!(pool.members()[0].effective_state(later), MemberState::Down);
+        checker.update(|pool| {
+            pool.report(&a1, MemberState::Up, ttl, Instant::now());
+        });
+        assert!(checker.mark_up(&a3));
+        let pool = watchdog.snapshot();
+        let rrset = pool
+            .materialize_at(&owner, RRType::A, later, &mut rng)
+            .unwrap();
+        assert_eq!(rrset.rdatas, vec![a3.clone()]);
+        let rrset = pool
+            .materialize_at(&owner, RRType::A, Instant::now(), &mut rng)
+            .unwrap();
+        assert_eq!(rrset.rdatas, vec![a1, a3]);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::edns::{ClientSubnet, Edns, EdnsOption, OPTION_CLIENT_SUBNET};
+use crate::message::Message;
+use crate::message_builder::MessageBuilder;
+use crate::question::Question;
+use crate::rrset::RRset;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Selection {
+    pub index: usize,
+    //how many leading bits of the client address the selection depends on
+    pub scope_prefix: u8,
+}
+
+pub trait AnswerSelector {
+    fn select(
+        &self,
+        client: &ClientSubnet,
+        question: &Question,
+        candidates: &[RRset],
+    ) -> Option<Selection>;
+}
+
+pub fn query_client_subnet(query: &Message) -> Option<ClientSubnet> {
+    match query
+        .edns
+        .as_ref()
+        .and_then(|edns| edns.option(OPTION_CLIENT_SUBNET))
+    {
+        Some(EdnsOption::ClientSubnet(subnet)) => Some(subnet.clone()),
+        _ => None,
+    }
+}
+
+//without ecs the socket address is used as a full length subnet
+pub fn client_subnet(query: &Message, peer: IpAddr) -> ClientSubnet {
+    query_client_subnet(query).unwrap_or_else(|| {
+        let len = if peer.is_ipv4() { 32 } else { 128 };
+        ClientSubnet::new(peer, len)
+    })
+}
+
+pub fn select_answer<S: AnswerSelector + ?Sized>(
+    selector: &S,
+    query: &Message,
+    peer: IpAddr,
+    candidates: &[RRset],
+    response: &mut Message,
+) -> bool {
+    let question = match query.question.as_ref() {
+        Some(question) if !candidates.is_empty() => question,
+        _ => return false,
+    };
+    let client = client_subnet(query, peer);
+    let selection = match selector.select(&client, question, candidates) {
+        Some(selection) if selection.index < candidates.len() => selection,
+        _ => return false,
+    };
+
+    //rfc7871 section 7.2.1, echo the query subnet with the scope set, a
+    //source prefix of zero requires a zero scope
+    if let Some(mut subnet) = query_client_subnet(query) {
+        let max_len = if subnet.addr.is_ipv4() { 32 } else { 128 };
+        subnet.scope_prefix = if subnet.source_prefix == 0 {
+            0
+        } else {
+            selection.scope_prefix.min(max_len)
+        };
+        let edns = response.edns.get_or_insert_with(|| {
+            let query_edns = query.edns.as_ref().unwrap();
+            Edns {
+                versoin: 0,
+                extened_rcode: 0,
+                udp_size: query_edns.udp_size,
+                dnssec_aware: query_edns.dnssec_aware,
+                options: None,
+            }
+        });
+        edns.add_option(EdnsOption::ClientSubnet(subnet));
+    }
+    MessageBuilder::new(response)
+        .add_answer(candidates[selection.index].clone())
+        .done();
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::header_flag::HeaderFlag;
+    use crate::name::Name;
+    use crate::rdata::RData;
+    use crate::rr_type::RRType;
+    use crate::util::hex::from_hex;
+    use crate::util::InputBuffer;
+    use std::str::FromStr;
+
+    struct FirstOctet;
+
+    impl AnswerSelector for FirstOctet {
+        fn select(
+            &self,
+            client: &ClientSubnet,
+            _question: &Question,
+            candidates: &[RRset],
+        ) -> Option<Selection> {
+            match client.addr {
+                IpAddr::V4(v4) => Some(Selection {
+                    index: (v4.octets()[0] as usize) % candidates.len(),
+                    scope_prefix: 8,
+                }),
+                IpAddr::V6(_) => None,
+            }
+        }
+    }
+
+    fn answer_addr(response: &Message) -> String {
+        match response
+            .section(crate::message::SectionType::Answer)
+            .unwrap()[0]
+            .rdatas[0]
+        {
+            RData::A(ref a) => a.host.to_string(),
+            _ => panic!("unexpected rdata"),
+        }
+    }
+
+    #[test]
+    fn test_select_answer() {
+        let candidates = vec![
+            RRset::from_str("www.example.com. 60 IN A 192.0.2.1").unwrap(),
+            RRset::from_str("www.example.com. 60 IN A 192.0.2.2").unwrap(),
+        ];
+
+        let query = Message::with_query(Name::new("www.example.com.").unwrap(), RRType::A);
+        let mut response = query.clone();
+        MessageBuilder::new(&mut response).make_response().done();
+        assert!(select_answer(
+            &FirstOctet,
+            &query,
+            "11.0.0.1".parse().unwrap(),
+            &candidates,
+            &mut response
+        ));
+        assert_eq!(answer_addr(&response), "192.0.2.2");
+        assert!(response.edns.is_none());
+        assert!(response.header.is_flag_set(HeaderFlag::QueryRespone));
+
+        //ecs 10.0.0.0/24 in the query overrides the peer address
+        let raw = from_hex("000029100000000000000b00080007000118000a0000").unwrap();
+        let edns =
+            Edns::from_rrset(&RRset::from_wire(&mut InputBuffer::new(raw.as_slice())).unwrap());
+        let subnet = ClientSubnet::new("10.0.0.99".parse().unwrap(), 24);
+        assert_eq!(
+            edns.option(OPTION_CLIENT_SUBNET),
+            Some(&EdnsOption::ClientSubnet(subnet.clone()))
+        );
+        assert_eq!(subnet.to_string(), "10.0.0.0/24/0");
+
+        let mut query = query;
+        MessageBuilder::new(&mut query).edns(edns).done();
+        let mut response = Message::with_query(Name::new("www.example.com.").unwrap(), RRType::A);
+        assert!(select_answer(
+            &FirstOctet,
+            &query,
+            "11.0.0.1".parse().unwrap(),
+            &candidates,
+            &mut response
+        ));
+        assert_eq!(answer_addr(&response), "192.0.2.1");
+        assert_eq!(
+            query_client_subnet(&response),
+            Some(ClientSubnet {
+                scope_prefix: 8,
+                ..subnet
+            })
+        );
+
+        assert!(!select_answer(
+            &FirstOctet,
+            &query,
+            "2001:db8::1".parse().unwrap(),
+            &candidates[..0],
+            &mut response
+        ));
+
+        //bits past the source prefix, the option is kept as unknown
+        let raw = from_hex("000029100000000000000b00080007000117000a0001").unwrap();
+        let rrset = RRset::from_wire(&mut InputBuffer::new(raw.as_slice())).unwrap();
+        assert_eq!(
+            Edns::from_rrset(&rrset).option(OPTION_CLIENT_SUBNET),
+            Some(&EdnsOption::Unknown(
+                OPTION_CLIENT_SUBNET,
+                vec![0, 1, 0x17, 0, 0x0a, 0, 1]
+            ))
+        );
+    }
+}
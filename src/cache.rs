@@ -0,0 +1,604 @@
+use crate::cache_entry::CacheEntry;
+use crate::cache_policy::CachePolicy;
+use crate::error::DNSError;
+use crate::lookup_result::ValidationStatus;
+use crate::name::Name;
+use crate::rr_type::RRType;
+use crate::rrset::RRset;
+use crate::signed_rrset::SignedRRset;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{iter, mem};
+
+const SNAPSHOT_MAGIC: &[u8] = b"R53C";
+const SNAPSHOT_VERSION: u8 = 2;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct QueryKey {
+    pub name: Name,
+    pub typ: RRType,
+}
+
+impl QueryKey {
+    pub fn new(name: Name, typ: RRType) -> Self {
+        QueryKey { name, typ }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    cached: CacheEntry,
+    size: usize,
+    hits: u64,
+}
+
+impl Entry {
+    fn expiry(&self) -> SystemTime {
+        self.cached.stored + Duration::from_secs(u64::from(self.cached.original_ttl))
+    }
+}
+
+//what a lookup found, rrsigs and proofs included
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CacheAnswer {
+    pub rrsets: Vec<RRset>,
+    pub validation: ValidationStatus,
+    //served past its ttl under the serve stale policy
+    pub stale: bool,
+    //the entry is due for a refresh under the prefetch policy
+    pub prefetch: bool,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    //hits answered with stale data, also counted in hits
+    pub stale: u64,
+    pub expired: u64,
+    //entries dropped before expiry to stay within the memory budget
+    pub evicted: u64,
+}
+
+//entry times are kept in wall clock time so a snapshot taken before a
+//restart still knows which records are fresh
+#[derive(Debug, Clone, Default)]
+pub struct Cache {
+    entries: HashMap<QueryKey, Entry>,
+    memory: usize,
+    budget: Option<usize>,
+    policy: CachePolicy,
+    stats: CacheStats,
+}
+
+//the key holds a second copy of the owner name
+fn entry_size(cached: &CacheEntry) -> usize {
+    let rrsets: usize = iter::once(&cached.data)
+        .chain(&cached.proofs)
+        .flat_map(|signed| signed.clone().into_rrsets())
+        .map(|rrset| rrset.heap_size())
+        .sum();
+    mem::size_of::<(QueryKey, Entry)>()
+        + cached.proofs.capacity() * mem::size_of::<SignedRRset>()
+        + rrsets
+        + cached.data.rrset.name.len()
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+fn validation_to_u8(validation: ValidationStatus) -> u8 {
+    match validation {
+        ValidationStatus::Secure => 0,
+        ValidationStatus::Insecure => 1,
+        ValidationStatus::Bogus => 2,
+        ValidationStatus::Indeterminate => 3,
+    }
+}
+
+fn validation_from_u8(value: u8) -> Result<ValidationStatus> {
+    match value {
+        0 => Ok(ValidationStatus::Secure),
+        1 => Ok(ValidationStatus::Insecure),
+        2 => Ok(ValidationStatus::Bogus),
+        3 => Ok(ValidationStatus::Indeterminate),
+        _ => Err(DNSError::InvalidCacheSnapshot(format!("unknown validation {}", value)).into()),
+    }
+}
+
+//the rrset and its rrsig rrset, each as record count and records
+fn write_signed(buf: &mut OutputBuffer, signed: &SignedRRset) {
+    let rrsets = signed.clone().into_rrsets();
+    buf.write_u8(rrsets.len() as u8);
+    for rrset in &rrsets {
+        buf.write_u16(rrset.rdatas.len() as u16);
+        rrset.to_wire(buf);
+    }
+}
+
+fn read_signed(buf: &mut InputBuffer) -> Result<SignedRRset> {
+    let rrset_count = buf.read_u8()?;
+    let mut rrsets = Vec::with_capacity(usize::from(rrset_count));
+    for _ in 0..rrset_count {
+        let rr_count = buf.read_u16()?;
+        if rr_count == 0 {
+            return Err(DNSError::InvalidCacheSnapshot("empty rrset".to_string()).into());
+        }
+        let mut rrset = RRset::from_wire(buf)?;
+        for _ in 1..rr_count {
+            let mut rr = RRset::from_wire(buf)?;
+            if !rr.is_same_rrset(&rrset) {
+                return Err(DNSError::InvalidCacheSnapshot("mixed rrset".to_string()).into());
+            }
+            rrset.rdatas.append(&mut rr.rdatas);
+        }
+        rrsets.push(rrset);
+    }
+    let mut grouped = SignedRRset::group(rrsets);
+    if grouped.len() != 1 {
+        return Err(DNSError::InvalidCacheSnapshot("unsigned rrsig".to_string()).into());
+    }
+    Ok(grouped.remove(0))
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_budget(budget: usize) -> Self {
+        Cache {
+            budget: Some(budget),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_policy(policy: CachePolicy) -> Self {
+        Cache {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    //estimated bytes held by the entries
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    pub fn budget(&self) -> Option<usize> {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: Option<usize>, now: SystemTime) {
+        self.budget = budget;
+        self.enforce_budget(now);
+    }
+
+    pub fn policy(&self) -> &CachePolicy {
+        &self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: CachePolicy) {
+        self.policy = policy;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    //an entry is stored at cached.stored, which is taken as now
+    pub fn insert(&mut self, cached: CacheEntry) {
+        if cached.data.rrset.rdatas.is_empty() || cached.original_ttl == 0 {
+            return;
+        }
+        let now = cached.stored;
+        self.add(cached);
+        self.enforce_budget(now);
+    }
+
+    fn add(&mut self, cached: CacheEntry) {
+        let key = QueryKey::new(cached.data.rrset.name.clone(), cached.data.rrset.typ);
+        let size = entry_size(&cached);
+        self.memory += size;
+        let entry = Entry {
+            cached,
+            size,
+            hits: 0,
+        };
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.memory -= old.size;
+        }
+    }
+
+    fn take(&mut self, key: &QueryKey) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.memory -= entry.size;
+        Some(entry)
+    }
+
+    //expired past what serve stale allows
+    fn is_dead(&self, cached: &CacheEntry, now: SystemTime) -> bool {
+        cached.is_expired(now)
+            && !(self.policy.serves_stale()
+                && cached.expired_for(now) <= self.policy.serve_stale_max_age)
+    }
+
+    //dead entries go first, then the ones closest to expiry
+    fn enforce_budget(&mut self, now: SystemTime) {
+        let budget = match self.budget {
+            Some(budget) if self.memory > budget => budget,
+            _ => return,
+        };
+        self.remove_expired(now);
+        if self.memory <= budget {
+            return;
+        }
+        let mut keys: Vec<(SystemTime, QueryKey)> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.expiry(), key.clone()))
+            .collect();
+        keys.sort_by_key(|(expiry, _)| *expiry);
+        for (_, key) in keys {
+            if self.memory <= budget {
+                break;
+            }
+            self.take(&key);
+            self.stats.evicted += 1;
+        }
+    }
+
+    //the entry with its ttls counted down. An expired entry is served stale
+    //while the policy allows it, stale_in_response being the stale rrsets
+    //already in the response under construction, and dropped once dead
+    pub fn get(
+        &mut self,
+        key: &QueryKey,
+        stale_in_response: usize,
+        now: SystemTime,
+    ) -> Option<CacheAnswer> {
+        let (expired, dead) = match self.entries.get(key) {
+            Some(entry) => (
+                entry.cached.is_expired(now),
+                self.is_dead(&entry.cached, now),
+            ),
+            None => {
+                self.stats.misses += 1;
+                return None;
+            }
+        };
+        if dead {
+            self.take(key);
+            self.stats.expired += 1;
+            self.stats.misses += 1;
+            return None;
+        }
+
+        let policy = &self.policy;
+        let entry = self.entries.get_mut(key).unwrap();
+        let cached = &entry.cached;
+        let answer = if !expired {
+            CacheAnswer {
+                rrsets: cached.to_rrsets(now),
+                validation: cached.validation,
+                stale: false,
+                prefetch: policy.should_prefetch(cached.original_ttl, cached.remaining_ttl(now)),
+            }
+        } else if policy.can_serve_stale(cached.expired_for(now), stale_in_response) {
+            CacheAnswer {
+                rrsets: cached.rrsets_with_ttl(policy.stale_ttl(cached.original_ttl)),
+                validation: cached.validation,
+                stale: true,
+                prefetch: false,
+            }
+        } else {
+            self.stats.misses += 1;
+            return None;
+        };
+        entry.hits += 1;
+        self.stats.hits += 1;
+        if answer.stale {
+            self.stats.stale += 1;
+        }
+        Some(answer)
+    }
+
+    //lookups answered by the entry since it was stored
+    pub fn hits(&self, key: &QueryKey) -> u64 {
+        self.entries.get(key).map_or(0, |entry| entry.hits)
+    }
+
+    //live entries the prefetch policy wants refreshed, together with hits()
+    //this is what a resolver needs to prefetch popular records before they
+    //expire
+    pub fn expiring_soon(&self, now: SystemTime) -> impl Iterator<Item = &QueryKey> {
+        let policy = &self.policy;
+        self.entries
+            .iter()
+            .filter(move |(_, entry)| {
+                let cached = &entry.cached;
+                policy.should_prefetch(cached.original_ttl, cached.remaining_ttl(now))
+            })
+            .map(|(key, _)| key)
+    }
+
+    pub fn remove(&mut self, key: &QueryKey) -> Option<CacheEntry> {
+        self.take(key).map(|entry| entry.cached)
+    }
+
+    //drops entries that can't be served anymore, not even stale
+    pub fn remove_expired(&mut self, now: SystemTime) -> usize {
+        let expired: Vec<QueryKey> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| self.is_dead(&entry.cached, now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.take(key);
+        }
+        self.stats.expired += expired.len() as u64;
+        expired.len()
+    }
+
+    //magic, version and entry count, then per entry the time it was stored
+    //in unix seconds, the original ttl, the validation status and the proof
+    //count, followed by the rrset and the proofs. Each of those is written
+    //as its rrset and rrsig rrset, in wire format without name compression
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut buf = OutputBuffer::new(512);
+        buf.write_bytes(SNAPSHOT_MAGIC);
+        buf.write_u8(SNAPSHOT_VERSION);
+        buf.write_u32(self.entries.len() as u32);
+        for entry in self.entries.values() {
+            let cached = &entry.cached;
+            buf.write_bytes(&unix_secs(cached.stored).to_be_bytes());
+            buf.write_u32(cached.original_ttl);
+            buf.write_u8(validation_to_u8(cached.validation));
+            buf.write_u16(cached.proofs.len() as u16);
+            write_signed(&mut buf, &cached.data);
+            for proof in &cached.proofs {
+                write_signed(&mut buf, proof);
+            }
+        }
+        buf.take_data()
+    }
+
+    //entries the policy no longer allows to serve are skipped
+    pub fn restore(data: &[u8], policy: CachePolicy, now: SystemTime) -> Result<Self> {
+        let mut buf = InputBuffer::new(data);
+        if buf.read_bytes(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            return Err(DNSError::InvalidCacheSnapshot("bad magic".to_string()).into());
+        }
+        let version = buf.read_u8()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(
+                DNSError::InvalidCacheSnapshot(format!("unknown version {}", version)).into(),
+            );
+        }
+
+        let mut cache = Cache::with_policy(policy);
+        let count = buf.read_u32()?;
+        for _ in 0..count {
+            let secs = u64::from_be_bytes(buf.read_bytes(8)?.try_into().unwrap());
+            let original_ttl = buf.read_u32()?;
+            let stored = UNIX_EPOCH
+                .checked_add(Duration::from_secs(secs))
+                .filter(|stored| {
+                    stored
+                        .checked_add(Duration::from_secs(u64::from(original_ttl)))
+                        .is_some()
+                })
+                .ok_or_else(|| DNSError::InvalidCacheSnapshot("expiry out of range".to_string()))?;
+            let validation = validation_from_u8(buf.read_u8()?)?;
+            let proof_count = buf.read_u16()?;
+            let data = read_signed(&mut buf)?;
+            let mut proofs = Vec::with_capacity(usize::from(proof_count));
+            for _ in 0..proof_count {
+                proofs.push(read_signed(&mut buf)?);
+            }
+            let cached = CacheEntry {
+                data,
+                validation,
+                proofs,
+                original_ttl,
+                stored,
+            };
+            if !cache.is_dead(&cached, now) {
+                cache.add(cached);
+            }
+        }
+        if buf.position() != buf.len() {
+            return Err(DNSError::InvalidCacheSnapshot("trailing data".to_string()).into());
+        }
+        Ok(cache)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rrset::RRTtl;
+    use std::str::FromStr;
+
+    fn rrset(s: &str) -> RRset {
+        RRset::from_str(s).unwrap()
+    }
+
+    fn entry(s: &str, now: SystemTime) -> CacheEntry {
+        CacheEntry::new(SignedRRset::new(rrset(s)), ValidationStatus::Insecure, now)
+    }
+
+    fn key(name: &str, typ: RRType) -> QueryKey {
+        QueryKey::new(Name::new(name).unwrap(), typ)
+    }
+
+    #[test]
+    fn test_cache_get() {
+        let now = SystemTime::now();
+        let mut cache = Cache::with_policy(CachePolicy::strict());
+        cache.insert(entry("example.com. 300 IN A 192.0.2.1", now));
+        cache.insert(entry("example.com. 0 IN AAAA 2001:db8::1", now));
+        assert_eq!(cache.len(), 1);
+
+        let key = key("EXAMPLE.com.", RRType::A);
+        let later = now + Duration::from_secs(100);
+        let answer = cache.get(&key, 0, later).unwrap();
+        assert_eq!(answer.rrsets[0].ttl, RRTtl(200));
+        assert_eq!(answer.validation, ValidationStatus::Insecure);
+        assert!(!answer.stale);
+        assert!(cache.get(&key, 0, now + Duration::from_secs(300)).is_none());
+        assert!(cache.is_empty());
+
+        cache.insert(entry("example.com. 300 IN A 192.0.2.1", now));
+        cache.insert(entry("example.org. 30 IN A 192.0.2.2", now));
+        assert_eq!(cache.remove_expired(later), 1);
+        assert!(cache.remove(&key).is_some());
+    }
+
+    #[test]
+    fn test_cache_serve_stale() {
+        let now = SystemTime::now();
+        let mut cache = Cache::new();
+        cache.insert(entry("example.com. 300 IN A 192.0.2.1", now));
+        let key = key("example.com.", RRType::A);
+
+        let expired = now + Duration::from_secs(400);
+        let answer = cache.get(&key, 0, expired).unwrap();
+        assert!(answer.stale);
+        assert_eq!(answer.rrsets[0].ttl, RRTtl(30));
+        assert_eq!(cache.stats().stale, 1);
+        //the response already holds as many stale rrsets as allowed
+        assert!(cache.get(&key, 4, expired).is_none());
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.remove_expired(expired), 0);
+
+        let dead = now + Duration::from_secs(300 + 86401);
+        assert!(cache.get(&key, 0, dead).is_none());
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().expired, 1);
+    }
+
+    #[test]
+    fn test_cache_snapshot() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let mut cache = Cache::new();
+        let mut mx = rrset("example.com. 300 IN MX 10 mail.example.com.");
+        mx.rdatas
+            .append(&mut rrset("example.com. 300 IN MX 20 backup.example.com.").rdatas);
+        let rrsig = rrset(
+            "example.com. 300 IN RRSIG MX 13 2 300 20300101000000 20200101000000 1 example.com. AQID",
+        );
+        let proof = rrset("example.com. 300 IN NSEC a.example.com. MX RRSIG NSEC");
+        let signed = SignedRRset::group(vec![mx.clone(), rrsig]).remove(0);
+        cache.insert(
+            CacheEntry::new(signed, ValidationStatus::Secure, now)
+                .with_proofs(vec![SignedRRset::new(proof)]),
+        );
+        cache.insert(entry("example.org. 30 IN A 192.0.2.2", now));
+
+        let data = cache.snapshot();
+        assert_eq!(&data[..5], b"R53C\x02");
+
+        let later = now + Duration::from_secs(60);
+        let mut restored = Cache::restore(&data, CachePolicy::strict(), later).unwrap();
+        assert_eq!(restored.len(), 1);
+        let key = key("example.com.", RRType::MX);
+        let cached = restored.get(&key, 0, later).unwrap();
+        assert_eq!(cached.validation, ValidationStatus::Secure);
+        assert_eq!(cached.rrsets.len(), 3);
+        assert_eq!(cached.rrsets[0].rdatas, mx.rdatas);
+        assert_eq!(cached.rrsets[1].typ, RRType::RRSIG);
+        assert_eq!(cached.rrsets[2].typ, RRType::NSEC);
+        assert!(cached.rrsets.iter().all(|rrset| rrset.ttl == RRTtl(240)));
+        //served stale after a restart under the default policy
+        assert_eq!(
+            Cache::restore(&data, CachePolicy::default(), later)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        assert!(Cache::restore(b"R53C\x01\x00\x00\x00\x00", CachePolicy::default(), now).is_err());
+        assert!(Cache::restore(&data[..data.len() - 1], CachePolicy::default(), now).is_err());
+        assert!(Cache::restore(b"XXXX", CachePolicy::default(), now).is_err());
+
+        //an expiry past what SystemTime can hold
+        let mut overflow = restored.snapshot();
+        overflow[9..17].copy_from_slice(&[0xff; 8]);
+        let err = Cache::restore(&overflow, CachePolicy::default(), now).unwrap_err();
+        assert!(err.to_string().contains("expiry out of range"));
+    }
+
+    #[test]
+    fn test_cache_budget() {
+        let now = SystemTime::now();
+        let a = entry("a.example.com. 300 IN A 192.0.2.1", now);
+        let size = entry_size(&a);
+        assert!(a.data.rrset.heap_size() >= a.data.rrset.name.len() + 4);
+
+        let mut cache = Cache::with_budget(size * 2);
+        cache.set_policy(CachePolicy::strict());
+        cache.insert(a.clone());
+        assert_eq!(cache.memory(), size);
+        cache.insert(a);
+        assert_eq!(cache.memory(), size);
+
+        cache.insert(entry("b.example.com. 100 IN A 192.0.2.2", now));
+        cache.insert(entry("c.example.com. 200 IN A 192.0.2.3", now));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.memory() <= size * 2);
+        assert_eq!(cache.stats().evicted, 1);
+        assert!(cache
+            .get(&key("b.example.com.", RRType::A), 0, now)
+            .is_none());
+
+        let later = now + Duration::from_secs(250);
+        cache.set_budget(Some(size), later);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.stats().expired, 1);
+        assert_eq!(cache.stats().evicted, 1);
+        cache.set_budget(None, later);
+        assert_eq!(cache.budget(), None);
+    }
+
+    #[test]
+    fn test_cache_expiring_soon() {
+        let now = SystemTime::now();
+        let mut cache = Cache::new();
+        cache.insert(entry("a.example.com. 30 IN A 192.0.2.1", now));
+        cache.insert(entry("b.example.com. 300 IN A 192.0.2.2", now));
+        cache.insert(entry("c.example.com. 5 IN A 192.0.2.3", now));
+
+        let key = key("a.example.com.", RRType::A);
+        cache.get(&key, 0, now);
+        cache.get(&key, 0, now);
+        let missing = QueryKey::new(Name::new("d.example.com.").unwrap(), RRType::A);
+        assert!(cache.get(&missing, 0, now).is_none());
+        assert_eq!(cache.hits(&key), 2);
+        assert_eq!(cache.hits(&missing), 0);
+        assert_eq!(cache.stats().hits, 2);
+        assert_eq!(cache.stats().misses, 1);
+
+        //c is below the minimum prefetch ttl
+        let later = now + Duration::from_secs(28);
+        let soon: Vec<&QueryKey> = cache.expiring_soon(later).collect();
+        assert_eq!(soon, vec![&key]);
+        assert!(cache.get(&key, 0, later).unwrap().prefetch);
+        cache.set_policy(CachePolicy::strict());
+        assert_eq!(cache.expiring_soon(later).count(), 0);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::lookup_result::ValidationStatus;
+use crate::rr_type::RRType;
+use crate::rrset::{RRTtl, RRset};
+use crate::signed_rrset::SignedRRset;
+use std::time::{Duration, SystemTime};
+
+//one cached rrset with what validation said about it, the rrsigs and the
+//denial proofs are kept along so answers from cache can carry the ad bit
+//and the signatures, and nsec records can be reused per rfc8198
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CacheEntry {
+    pub data: SignedRRset,
+    pub validation: ValidationStatus,
+    //nsec or nsec3 rrsets with their rrsigs
+    pub proofs: Vec<SignedRRset>,
+    pub original_ttl: u32,
+    //wall clock time, the cache snapshots it across restarts
+    pub stored: SystemTime,
+}
+
+impl CacheEntry {
+    pub fn new(data: SignedRRset, validation: ValidationStatus, now: SystemTime) -> Self {
+        CacheEntry {
+            original_ttl: data.rrset.ttl.0,
+            data,
+            validation,
+            proofs: Vec::new(),
+            stored: now,
+        }
+    }
+
+    pub fn with_proofs(mut self, proofs: Vec<SignedRRset>) -> Self {
+        self.proofs = proofs;
+        self
+    }
+
+    fn age(&self, now: SystemTime) -> u64 {
+        now.duration_since(self.stored)
+            .map_or(0, |age| age.as_secs())
+    }
+
+    pub fn remaining_ttl(&self, now: SystemTime) -> u32 {
+        u64::from(self.original_ttl).saturating_sub(self.age(now)) as u32
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.remaining_ttl(now) == 0
+    }
+
+    //how long ago the entry expired, for deciding on serving it stale
+    pub fn expired_for(&self, now: SystemTime) -> Duration {
+        Duration::from_secs(self.age(now).saturating_sub(u64::from(self.original_ttl)))
+    }
+
+    pub fn sets_ad(&self) -> bool {
+        self.validation == ValidationStatus::Secure
+    }
+
+    //bogus data is kept only so the failed validation isn't repeated
+    pub fn is_servable(&self) -> bool {
+        self.validation != ValidationStatus::Bogus
+    }
+
+    //secure signed nsec or nsec3 records that may answer other names
+    pub fn has_denial_proof(&self) -> bool {
+        self.sets_ad()
+            && !self.proofs.is_empty()
+            && self.proofs.iter().all(|proof| {
+                proof.is_signed() && matches!(proof.rrset.typ, RRType::NSEC | RRType::NSEC3)
+            })
+    }
+
+    //the rrsets with their rrsigs and then the proofs, ttls counted down
+    pub fn to_rrsets(&self, now: SystemTime) -> Vec<RRset> {
+        self.rrsets_with_ttl(self.remaining_ttl(now))
+    }
+
+    //the same with every ttl capped at ttl, stale answers use this
+    pub fn rrsets_with_ttl(&self, ttl: u32) -> Vec<RRset> {
+        let mut rrsets = self.data.clone().into_rrsets();
+        for proof in &self.proofs {
+            rrsets.extend(proof.clone().into_rrsets());
+        }
+        for rrset in &mut rrsets {
+            rrset.ttl = RRTtl(rrset.ttl.0.min(ttl));
+        }
+        rrsets
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn signed(rrset: &str, covered: &str) -> SignedRRset {
+        let rrset = RRset::from_str(rrset).unwrap();
+        let rrsig = RRset::from_str(&format!(
+            "{} 300 IN RRSIG {} 13 2 300 20300101000000 20200101000000 1 example.com. AQID",
+            rrset.name, covered
+        ))
+        .unwrap();
+        SignedRRset::group(vec![rrset, rrsig]).remove(0)
+    }
+
+    #[test]
+    fn test_cache_entry() {
+        let now = SystemTime::now();
+        let data = signed("www.example.com. 300 IN A 192.0.2.1", "A");
+        let entry = CacheEntry::new(data, ValidationStatus::Secure, now);
+        assert!(entry.sets_ad());
+        assert!(entry.is_servable());
+        assert!(!entry.has_denial_proof());
+
+        let later = now + Duration::from_secs(100);
+        assert_eq!(entry.remaining_ttl(later), 200);
+        let rrsets = entry.to_rrsets(later);
+        assert_eq!(rrsets.len(), 2);
+        assert!(rrsets.iter().all(|rrset| rrset.ttl == RRTtl(200)));
+        assert_eq!(rrsets[1].typ, RRType::RRSIG);
+
+        let expired = now + Duration::from_secs(330);
+        assert!(entry.is_expired(expired));
+        assert_eq!(entry.expired_for(expired), Duration::from_secs(30));
+        assert_eq!(entry.expired_for(later), Duration::from_secs(0));
+
+        let proof = signed(
+            "example.com. 300 IN NSEC a.example.com. SOA NS NSEC RRSIG",
+            "NSEC",
+        );
+        let soa = signed(
+            "example.com. 300 IN SOA ns.example.com. root.example.com. 1 2 3 4 300",
+            "SOA",
+        );
+        let negative = CacheEntry::new(soa, ValidationStatus::Secure, now).with_proofs(vec![proof]);
+        assert!(negative.has_denial_proof());
+        assert_eq!(negative.to_rrsets(now).len(), 4);
+
+        let bogus = CacheEntry::new(negative.data.clone(), ValidationStatus::Bogus, now);
+        assert!(!bogus.is_servable());
+        assert!(!bogus.sets_ad());
+        let unsigned =
+            CacheEntry::new(negative.data, ValidationStatus::Secure, now).with_proofs(vec![
+                SignedRRset::new(
+                    RRset::from_str("example.com. 300 IN NSEC a.example.com. SOA").unwrap(),
+                ),
+            ]);
+        assert!(!unsigned.has_denial_proof());
+    }
+}
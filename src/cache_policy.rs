@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+//rfc8767 section 5, stale data should be kept 1 to 3 days and served with
+//a ttl of 30 seconds
+const DEFAULT_SERVE_STALE_MAX_AGE: Duration = Duration::from_secs(86400);
+const DEFAULT_STALE_ANSWER_TTL: u32 = 30;
+const DEFAULT_PREFETCH_PERCENT: u8 = 10;
+const DEFAULT_MAX_STALE_PER_QUERY: usize = 4;
+//records with a shorter ttl expire before a prefetch is worth it
+const DEFAULT_MIN_PREFETCH_TTL: u32 = 10;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CachePolicy {
+    //how long after expiry a record may still be served, zero disables
+    //serve stale
+    pub serve_stale_max_age: Duration,
+    pub stale_answer_ttl: u32,
+    //refresh once the remaining ttl drops to this percentage of the
+    //original ttl, zero disables prefetch
+    pub prefetch_percent: u8,
+    pub min_prefetch_ttl: u32,
+    //stale rrsets a single response may be built from
+    pub max_stale_per_query: usize,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        CachePolicy {
+            serve_stale_max_age: DEFAULT_SERVE_STALE_MAX_AGE,
+            stale_answer_ttl: DEFAULT_STALE_ANSWER_TTL,
+            prefetch_percent: DEFAULT_PREFETCH_PERCENT,
+            min_prefetch_ttl: DEFAULT_MIN_PREFETCH_TTL,
+            max_stale_per_query: DEFAULT_MAX_STALE_PER_QUERY,
+        }
+    }
+}
+
+impl CachePolicy {
+    pub fn strict() -> Self {
+        CachePolicy {
+            serve_stale_max_age: Duration::from_secs(0),
+            prefetch_percent: 0,
+            max_stale_per_query: 0,
+            ..Default::default()
+        }
+    }
+
+    pub fn serves_stale(&self) -> bool {
+        self.serve_stale_max_age > Duration::from_secs(0) && self.max_stale_per_query > 0
+    }
+
+    pub fn can_serve_stale(&self, expired_for: Duration, stale_in_response: usize) -> bool {
+        self.serves_stale()
+            && expired_for <= self.serve_stale_max_age
+            && stale_in_response < self.max_stale_per_query
+    }
+
+    pub fn should_prefetch(&self, original_ttl: u32, remaining_ttl: u32) -> bool {
+        self.prefetch_percent > 0
+            && original_ttl >= self.min_prefetch_ttl
+            && remaining_ttl > 0
+            && u64::from(remaining_ttl) * 100
+                <= u64::from(original_ttl) * u64::from(self.prefetch_percent)
+    }
+
+    //stale answers never claim more than the stale ttl
+    pub fn stale_ttl(&self, original_ttl: u32) -> u32 {
+        original_ttl.min(self.stale_answer_ttl)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cache_policy() {
+        let policy = CachePolicy::default();
+        assert!(policy.serves_stale());
+        assert!(policy.can_serve_stale(Duration::from_secs(3600), 0));
+        assert!(!policy.can_serve_stale(Duration::from_secs(86401), 0));
+        assert!(!policy.can_serve_stale(Duration::from_secs(1), 4));
+        assert_eq!(policy.stale_ttl(300), 30);
+        assert_eq!(policy.stale_ttl(5), 5);
+
+        assert!(!policy.should_prefetch(300, 31));
+        assert!(policy.should_prefetch(300, 30));
+        assert!(!policy.should_prefetch(300, 0));
+        assert!(!policy.should_prefetch(5, 0));
+        assert!(policy.should_prefetch(u32::MAX, 1));
+
+        let strict = CachePolicy::strict();
+        assert!(!strict.serves_stale());
+        assert!(!strict.can_serve_stale(Duration::from_secs(0), 0));
+        assert!(!strict.should_prefetch(300, 1));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::algorithm::Algorithm;
+use crate::edns;
+use crate::rdata;
+use crate::rr_type::RRType;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Capabilities {
+    pub version: &'static str,
+    pub rr_types: Vec<RRType>,
+    pub edns_options: Vec<u16>,
+    pub dnssec_algorithms: Vec<Algorithm>,
+    pub features: Vec<&'static str>,
+}
+
+fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "psl") {
+        features.push("psl");
+    }
+    if cfg!(feature = "zeroize") {
+        features.push("zeroize");
+    }
+    features
+}
+
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        rr_types: rdata::SUPPORTED_TYPES.to_vec(),
+        edns_options: edns::SUPPORTED_OPTIONS.to_vec(),
+        dnssec_algorithms: (0..=u8::MAX)
+            .map(Algorithm::new)
+            .filter(|alg| !matches!(alg, Algorithm::Unknown(_)))
+            .collect(),
+        features: enabled_features(),
+    }
+}
+
+impl Capabilities {
+    pub fn supports_type(&self, typ: RRType) -> bool {
+        self.rr_types.contains(&typ)
+    }
+
+    pub fn supports_edns_option(&self, code: u16) -> bool {
+        self.edns_options.contains(&code)
+    }
+
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.contains(&feature)
+    }
+
+    pub fn to_string(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "version: {}", self.version).unwrap();
+        let types: Vec<&str> = self.rr_types.iter().map(|typ| typ.to_str()).collect();
+        writeln!(out, "rr types: {}", types.join(" ")).unwrap();
+        let options: Vec<String> = self.edns_options.iter().map(u16::to_string).collect();
+        writeln!(out, "edns options: {}", options.join(" ")).unwrap();
+        let algorithms: Vec<&str> = self
+            .dnssec_algorithms
+            .iter()
+            .map(|alg| alg.to_str())
+            .collect();
+        writeln!(out, "dnssec algorithms: {}", algorithms.join(" ")).unwrap();
+        writeln!(out, "features: {}", self.features.join(" ")).unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::edns::OPTION_COOKIE;
+
+    #[test]
+    fn test_capabilities() {
+        let caps = capabilities();
+        assert_eq!(caps.version, "0.1.0");
+        assert!(caps.supports_type(RRType::SOA));
+        assert!(!caps.supports_type(RRType::AXFR));
+        assert!(caps.supports_edns_option(OPTION_COOKIE));
+        assert!(!caps.supports_edns_option(12));
+        assert!(caps.dnssec_algorithms.contains(&Algorithm::ED25519));
+        assert!(!caps.dnssec_algorithms.is_empty());
+        assert_eq!(caps.has_feature("psl"), cfg!(feature = "psl"));
+        assert!(!caps.has_feature("unknown"));
+
+        let report = caps.to_string();
+        assert!(report.starts_with("version: 0.1.0\nrr types: A AAAA NS CNAME"));
+        assert!(report.contains("edns options: 3 8 10 11 15\n"));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::name::Name;
+use crate::rdata_dnskey::DNSKEY;
+use crate::rdata_ds::{ds_from_dnskey, DS};
+use failure::Result;
+
+//rfc7344 section 4.1, only keys that may sign the dnskey rrset are
+//published to the parent and a revoked key is on its way out
+fn active_ksks(keys: &[DNSKEY]) -> impl Iterator<Item = &DNSKEY> {
+    keys.iter().filter(|key| key.is_ksk() && !key.is_revoked())
+}
+
+pub fn expected_cdnskey(keys: &[DNSKEY]) -> Vec<DNSKEY> {
+    active_ksks(keys).cloned().collect()
+}
+
+pub fn expected_cds(owner: &Name, keys: &[DNSKEY], digest_alg: u8) -> Result<Vec<DS>> {
+    active_ksks(keys)
+        .map(|key| ds_from_dnskey(owner, key, digest_alg))
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ParentSync {
+    //ds records the parent still has to publish
+    pub add: Vec<DS>,
+    //ds records the parent serves that no active ksk matches
+    pub remove: Vec<DS>,
+}
+
+impl ParentSync {
+    pub fn is_in_sync(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty()
+    }
+}
+
+//compares the cds set the child wants with the ds set the parent serves
+pub fn check_parent_sync(expected: &[DS], parent: &[DS]) -> ParentSync {
+    ParentSync {
+        add: expected
+            .iter()
+            .filter(|ds| !parent.contains(ds))
+            .cloned()
+            .collect(),
+        remove: parent
+            .iter()
+            .filter(|ds| !expected.contains(ds))
+            .cloned()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rdata::RData;
+    use crate::rdata_ds::DIGEST_SHA256;
+    use crate::rr_type::RRType;
+
+    fn rdata(typ: RRType, s: &str) -> RData {
+        RData::from_str(typ, s).unwrap()
+    }
+
+    fn dnskey(s: &str) -> DNSKEY {
+        match rdata(RRType::DNSKEY, s) {
+            RData::DNSKEY(key) => *key,
+            _ => unreachable!(),
+        }
+    }
+
+    fn ds(s: &str) -> DS {
+        match rdata(RRType::DS, s) {
+            RData::DS(ds) => *ds,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parent_sync() {
+        let owner = Name::new("example.com.").unwrap();
+        let ksk = dnskey("257 3 13 AQID");
+        let keys = vec![
+            ksk.clone(),
+            dnskey("256 3 13 BAUG"),
+            //revoked ksk
+            dnskey("385 3 13 BwgJ"),
+        ];
+        assert_eq!(expected_cdnskey(&keys), vec![ksk.clone()]);
+
+        let cds = expected_cds(&owner, &keys, DIGEST_SHA256).unwrap();
+        assert_eq!(
+            cds,
+            vec![ds_from_dnskey(&owner, &ksk, DIGEST_SHA256).unwrap()]
+        );
+        assert!(expected_cds(&owner, &keys, 3).is_err());
+        assert!(check_parent_sync(&cds, &cds).is_in_sync());
+
+        let stale = ds("12345 8 2 0102");
+        let sync = check_parent_sync(&cds, &[stale.clone()]);
+        assert!(!sync.is_in_sync());
+        assert_eq!(sync.add, cds);
+        assert_eq!(sync.remove, vec![stale]);
+
+        let sync = check_parent_sync(&cds, &[]);
+        assert_eq!(sync.add, cds);
+        assert!(sync.remove.is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::error::DNSError;
+use crate::header_flag::HeaderFlag;
+use crate::message::{Message, SectionType};
+use crate::message_builder::MessageBuilder;
+use crate::name::Name;
+use crate::opcode::Opcode;
+use crate::rdata::RData;
+use crate::rr_class::RRClass;
+use crate::rr_type::RRType;
+use crate::rrset::{RRTtl, RRset};
+use failure::Result;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ChangeRequest {
+    //fails if the rrset already exists
+    Create(RRset),
+    Delete(Name, RRType),
+    Replace(RRset),
+}
+
+impl ChangeRequest {
+    pub fn name(&self) -> &Name {
+        match self {
+            ChangeRequest::Create(rrset) | ChangeRequest::Replace(rrset) => &rrset.name,
+            ChangeRequest::Delete(name, _) => name,
+        }
+    }
+
+    pub fn typ(&self) -> RRType {
+        match self {
+            ChangeRequest::Create(rrset) | ChangeRequest::Replace(rrset) => rrset.typ,
+            ChangeRequest::Delete(_, typ) => *typ,
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            ChangeRequest::Create(rrset) => format!("create {} {}", rrset.name, rrset.typ),
+            ChangeRequest::Delete(name, typ) => format!("delete {} {}", name, typ),
+            ChangeRequest::Replace(rrset) => format!("replace {} {}", rrset.name, rrset.typ),
+        }
+    }
+}
+
+fn empty_rrset(name: &Name, typ: RRType, class: RRClass) -> RRset {
+    RRset {
+        name: name.clone(),
+        typ,
+        class,
+        ttl: RRTtl(0),
+        rdatas: Vec::new(),
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ChangeSet {
+    pub zone: Name,
+    //compare and swap, the changes only apply while the zone still has
+    //this soa, which carries the expected serial
+    pub expected_soa: Option<RRset>,
+    pub changes: Vec<ChangeRequest>,
+}
+
+impl ChangeSet {
+    pub fn new(zone: Name) -> Self {
+        ChangeSet {
+            zone,
+            expected_soa: None,
+            changes: Vec::new(),
+        }
+    }
+
+    //rfc2136 section 3.2.1, a prerequisite with a non-zero ttl is a format
+    //error, so the zone ttl isn't kept
+    pub fn compare_soa(&mut self, mut soa: RRset) -> Result<&mut Self> {
+        if soa.typ != RRType::SOA || soa.rdatas.len() != 1 || !soa.name.eq(&self.zone) {
+            return Err(DNSError::InvalidChangeSet.into());
+        }
+        soa.ttl = RRTtl(0);
+        self.expected_soa = Some(soa);
+        Ok(self)
+    }
+
+    pub fn expected_serial(&self) -> Option<u32> {
+        match self.expected_soa.as_ref()?.rdatas.first() {
+            Some(RData::SOA(ref soa)) => Some(soa.serial),
+            _ => None,
+        }
+    }
+
+    pub fn add(&mut self, change: ChangeRequest) -> Result<&mut Self> {
+        if !change.name().is_subdomain(&self.zone) {
+            return Err(DNSError::OutOfZone.into());
+        }
+        if let ChangeRequest::Create(ref rrset) | ChangeRequest::Replace(ref rrset) = change {
+            if rrset.rdatas.is_empty() {
+                return Err(DNSError::InvalidChangeSet.into());
+            }
+        }
+        self.changes.push(change);
+        Ok(self)
+    }
+
+    //rfc2136: the zone goes to the question, prerequisites to the answer
+    //section and updates to the authority section
+    pub fn to_update(&self) -> Message {
+        let mut msg = Message::with_query(self.zone.clone(), RRType::SOA);
+        let mut builder = MessageBuilder::new(&mut msg);
+        builder
+            .opcode(Opcode::Update)
+            .clear_flag(HeaderFlag::RecursionDesired);
+        if let Some(ref soa) = self.expected_soa {
+            builder.add_answer(soa.clone());
+        }
+        for change in &self.changes {
+            match change {
+                ChangeRequest::Create(rrset) => {
+                    builder
+                        .add_answer(empty_rrset(&rrset.name, rrset.typ, RRClass::NONE))
+                        .add_auth(rrset.clone());
+                }
+                ChangeRequest::Delete(name, typ) => {
+                    builder.add_auth(empty_rrset(name, *typ, RRClass::ANY));
+                }
+                ChangeRequest::Replace(rrset) => {
+                    builder
+                        .add_auth(empty_rrset(&rrset.name, rrset.typ, RRClass::ANY))
+                        .add_auth(rrset.clone());
+                }
+            }
+        }
+        builder.done();
+        msg
+    }
+
+    pub fn from_update(msg: &Message) -> Result<Self> {
+        let zone = match msg.question {
+            Some(ref q) if msg.header.opcode == Opcode::Update && q.typ == RRType::SOA => {
+                q.name.clone()
+            }
+            _ => return Err(DNSError::InvalidChangeSet.into()),
+        };
+
+        let mut change_set = ChangeSet::new(zone);
+        let mut not_exist = Vec::new();
+        for prereq in msg.section(SectionType::Answer).into_iter().flatten() {
+            if prereq.ttl.0 != 0 {
+                return Err(DNSError::InvalidChangeSet.into());
+            }
+            match prereq.class {
+                RRClass::IN if prereq.typ == RRType::SOA => {
+                    change_set.compare_soa(prereq.clone())?;
+                }
+                RRClass::NONE if prereq.rdatas.is_empty() => {
+                    not_exist.push((prereq.name.clone(), prereq.typ))
+                }
+                _ => return Err(DNSError::InvalidChangeSet.into()),
+            }
+        }
+
+        let updates = msg
+            .section(SectionType::Authority)
+            .map_or(&[][..], |u| &u[..]);
+        let mut i = 0;
+        while i < updates.len() {
+            let update = &updates[i];
+            let change = match update.class {
+                RRClass::ANY if update.rdatas.is_empty() => match updates.get(i + 1) {
+                    Some(next)
+                        if next.class == RRClass::IN
+                            && next.typ == update.typ
+                            && next.name.eq(&update.name) =>
+                    {
+                        i += 1;
+                        ChangeRequest::Replace(next.clone())
+                    }
+                    _ => ChangeRequest::Delete(update.name.clone(), update.typ),
+                },
+                RRClass::IN => {
+                    let key = (update.name.clone(), update.typ);
+                    match not_exist.iter().position(|k| *k == key) {
+                        Some(index) => {
+                            not_exist.remove(index);
+                            ChangeRequest::Create(update.clone())
+                        }
+                        None => return Err(DNSError::InvalidChangeSet.into()),
+                    }
+                }
+                _ => return Err(DNSError::InvalidChangeSet.into()),
+            };
+            change_set.add(change)?;
+            i += 1;
+        }
+        if !not_exist.is_empty() {
+            return Err(DNSError::InvalidChangeSet.into());
+        }
+        Ok(change_set)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message_render::MessageRender;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_change_set_update() {
+        let zone = Name::new("example.com.").unwrap();
+        let soa = RRset::from_str("example.com. 3600 IN SOA ns.example.com. root.example.com. 2019070101 7200 3600 1209600 300").unwrap();
+
+        let mut change_set = ChangeSet::new(zone.clone());
+        change_set
+            .compare_soa(soa.clone())
+            .unwrap()
+            .add(ChangeRequest::Create(
+                RRset::from_str("www.example.com. 300 IN A 192.0.2.1").unwrap(),
+            ))
+            .unwrap()
+            .add(ChangeRequest::Delete(
+                Name::new("old.example.com.").unwrap(),
+                RRType::CNAME,
+            ))
+            .unwrap()
+            .add(ChangeRequest::Replace(
+                RRset::from_str("mail.example.com. 300 IN MX 10 mx.example.com.").unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(change_set.expected_serial(), Some(2019070101));
+        assert!(change_set
+            .add(ChangeRequest::Delete(
+                Name::new("example.org.").unwrap(),
+                RRType::A
+            ))
+            .is_err());
+        assert_eq!(
+            change_set.changes[1].to_string(),
+            "delete old.example.com. CNAME"
+        );
+
+        let update = change_set.to_update();
+        assert_eq!(update.header.opcode, Opcode::Update);
+        assert_eq!(update.header.an_count, 2);
+        assert_eq!(update.header.ns_count, 4);
+        let prereqs = update.section(SectionType::Answer).unwrap();
+        assert!(prereqs.iter().all(|prereq| prereq.ttl == RRTtl(0)));
+
+        let mut render = MessageRender::new();
+        update.rend(&mut render);
+        let parsed = Message::from_wire(render.data()).unwrap();
+        assert_eq!(ChangeSet::from_update(&parsed).unwrap(), change_set);
+
+        let mut bad_ttl = update.clone();
+        bad_ttl.section_mut(SectionType::Answer).unwrap()[0].ttl = RRTtl(3600);
+        assert!(ChangeSet::from_update(&bad_ttl).is_err());
+
+        let query = Message::with_query(zone, RRType::SOA);
+        assert!(ChangeSet::from_update(&query).is_err());
+    }
+}
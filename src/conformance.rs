@@ -0,0 +1,46 @@
+use crate::message_render::MessageRender;
+use crate::rdata::RData;
+use crate::rr_type::RRType;
+use crate::util::hex::from_hex;
+use crate::util::{InputBuffer, OutputBuffer};
+
+//rdata_conformance!{MX, "10 mail.example.com.", "000a046d61696c..."; ...}
+//checks every vector and evaluates to the list of checked types
+macro_rules! rdata_conformance {
+    ($($typ:ident, $text:expr, $hex:expr);+ $(;)?) => {{
+        let mut checked = Vec::new();
+        $(
+            let typ = $crate::rr_type::RRType::$typ;
+            $crate::conformance::check_vector(typ, $text, $hex);
+            checked.push(typ);
+        )+
+        checked
+    }};
+}
+
+//the wire vector is uncompressed, rendered output may use compression so
+//it is only checked by parsing it back
+pub fn check_vector(typ: RRType, text: &str, hex: &str) {
+    let raw = from_hex(hex).unwrap();
+    let rdata = RData::from_wire(typ, &mut InputBuffer::new(&raw), raw.len() as u16)
+        .unwrap_or_else(|e| panic!("{} wire {}: {}", typ, hex, e));
+    assert_eq!(rdata.typ(), typ);
+    assert_eq!(rdata.to_string(), text, "{} to_string", typ);
+    let parsed =
+        RData::from_str(typ, text).unwrap_or_else(|e| panic!("{} text {}: {}", typ, text, e));
+    assert_eq!(parsed, rdata, "{} from_str", typ);
+
+    let mut buf = OutputBuffer::new(raw.len());
+    rdata.to_wire(&mut buf);
+    assert_eq!(buf.data(), raw.as_slice(), "{} to_wire", typ);
+
+    let mut render = MessageRender::new();
+    rdata.rend(&mut render);
+    let rendered = render.data();
+    assert_eq!(
+        RData::from_wire(typ, &mut InputBuffer::new(rendered), rendered.len() as u16).unwrap(),
+        rdata,
+        "{} rend",
+        typ
+    );
+}
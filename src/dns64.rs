@@ -0,0 +1,376 @@
+use crate::error::DNSError;
+use crate::message::{Message, Section, SectionType};
+use crate::name::Name;
+use crate::question::Question;
+use crate::rcode::Rcode;
+use crate::rdata::RData;
+use crate::rdata_aaaa::AAAA;
+use crate::reverse::{ipv4_to_reverse_name, parse_ip6_arpa};
+use crate::rr_type::RRType;
+use crate::rrset::{RRTtl, RRset};
+use crate::util::prefix_match;
+use failure::Result;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+const VALID_PREFIX_LEN: [u8; 6] = [32, 40, 48, 56, 64, 96];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Nat64Prefix {
+    addr: Ipv6Addr,
+    len: u8,
+}
+
+impl Nat64Prefix {
+    pub fn new(addr: Ipv6Addr, len: u8) -> Result<Self> {
+        if !VALID_PREFIX_LEN.contains(&len) {
+            return Err(DNSError::InvalidPrefixLen(len).into());
+        }
+        //rfc6052: bits 64 to 71 of the address are reserved and must be zero
+        if len != 96 && addr.octets()[8] != 0 {
+            return Err(DNSError::InvalidNat64Prefix.into());
+        }
+        Ok(Nat64Prefix { addr, len })
+    }
+
+    pub fn well_known() -> Self {
+        Nat64Prefix {
+            addr: Ipv6Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0, 0),
+            len: 96,
+        }
+    }
+
+    pub fn addr(&self) -> Ipv6Addr {
+        self.addr
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    pub fn is_well_known(&self) -> bool {
+        *self == Self::well_known()
+    }
+
+    //rfc6052 section 2.2, octet 8 is skipped for every prefix shorter than 96
+    pub fn embed(&self, v4: Ipv4Addr) -> Ipv6Addr {
+        let mut octets = self.addr.octets();
+        let prefix_octets = (self.len / 8) as usize;
+        for o in &mut octets[prefix_octets..] {
+            *o = 0;
+        }
+        let mut pos = prefix_octets;
+        for b in v4.octets().iter() {
+            if pos == 8 {
+                pos += 1;
+            }
+            octets[pos] = *b;
+            pos += 1;
+        }
+        Ipv6Addr::from(octets)
+    }
+
+    pub fn extract(&self, v6: Ipv6Addr) -> Option<Ipv4Addr> {
+        let octets = v6.octets();
+        let prefix_octets = (self.len / 8) as usize;
+        if octets[..prefix_octets] != self.addr.octets()[..prefix_octets] {
+            return None;
+        }
+        let mut v4 = [0u8; 4];
+        let mut pos = prefix_octets;
+        for b in v4.iter_mut() {
+            if pos == 8 {
+                pos += 1;
+            }
+            *b = octets[pos];
+            pos += 1;
+        }
+        Some(Ipv4Addr::from(v4))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dns64Config {
+    pub prefix: Nat64Prefix,
+    pub exclude_v4: Vec<(Ipv4Addr, u8)>,
+    pub exclude_v6: Vec<(Ipv6Addr, u8)>,
+}
+
+impl Dns64Config {
+    pub fn new(prefix: Nat64Prefix) -> Self {
+        let mut exclude_v4 = vec![
+            (Ipv4Addr::new(0, 0, 0, 0), 8),
+            (Ipv4Addr::new(127, 0, 0, 0), 8),
+            (Ipv4Addr::new(169, 254, 0, 0), 16),
+            (Ipv4Addr::new(255, 255, 255, 255), 32),
+        ];
+        //rfc6052 section 3.1, the well-known prefix must not be used to
+        //represent non-global ipv4 addresses
+        if prefix.is_well_known() {
+            exclude_v4.push((Ipv4Addr::new(10, 0, 0, 0), 8));
+            exclude_v4.push((Ipv4Addr::new(172, 16, 0, 0), 12));
+            exclude_v4.push((Ipv4Addr::new(192, 168, 0, 0), 16));
+        }
+        Dns64Config {
+            prefix,
+            exclude_v4,
+            exclude_v6: vec![(Ipv6Addr::new(0, 0, 0, 0, 0, 0xffff, 0, 0), 96)],
+        }
+    }
+
+    fn is_excluded_v4(&self, addr: Ipv4Addr) -> bool {
+        self.exclude_v4
+            .iter()
+            .any(|(net, len)| prefix_match(IpAddr::V4(addr), IpAddr::V4(*net), *len))
+    }
+
+    fn is_excluded_v6(&self, addr: Ipv6Addr) -> bool {
+        self.exclude_v6
+            .iter()
+            .any(|(net, len)| prefix_match(IpAddr::V6(addr), IpAddr::V6(*net), *len))
+    }
+
+    //synthesis is needed when the aaaa response carries no usable address,
+    //only rcode NOERROR qualifies, NXDOMAIN is returned as is
+    pub fn needs_synthesis(&self, aaaa_response: &Message) -> bool {
+        if aaaa_response.header.rcode != Rcode::NoError {
+            return false;
+        }
+
+        let answers = match aaaa_response.section(SectionType::Answer) {
+            Some(rrsets) => rrsets,
+            None => return true,
+        };
+        !answers.iter().filter(|r| r.typ == RRType::AAAA).any(|r| {
+            r.rdatas.iter().any(|rdata| match rdata {
+                RData::AAAA(ref aaaa) => !self.is_excluded_v6(aaaa.host),
+                _ => false,
+            })
+        })
+    }
+}
+
+pub fn synthesize_rrset(rrset: &RRset, config: &Dns64Config, max_ttl: Option<u32>) -> RRset {
+    let rdatas = rrset
+        .rdatas
+        .iter()
+        .filter_map(|rdata| match rdata {
+            RData::A(ref a) if !config.is_excluded_v4(a.host) => Some(RData::AAAA(AAAA {
+                host: config.prefix.embed(a.host),
+            })),
+            _ => None,
+        })
+        .collect();
+    RRset {
+        name: rrset.name.clone(),
+        typ: RRType::AAAA,
+        class: rrset.class,
+        ttl: RRTtl(max_ttl.map_or(rrset.ttl.0, |ttl| ttl.min(rrset.ttl.0))),
+        rdatas,
+    }
+}
+
+//turn the response of the a query issued for an aaaa query into the aaaa
+//response, None means no a record could be synthesized
+pub fn synthesize(
+    a_response: &Message,
+    config: &Dns64Config,
+    max_ttl: Option<u32>,
+) -> Option<Message> {
+    let answers = a_response.section(SectionType::Answer)?;
+    let mut synthesized = false;
+    let answers: Vec<RRset> = answers
+        .iter()
+        .filter_map(|rrset| {
+            if rrset.typ == RRType::A {
+                let aaaa = synthesize_rrset(rrset, config, max_ttl);
+                if aaaa.rdatas.is_empty() {
+                    None
+                } else {
+                    synthesized = true;
+                    Some(aaaa)
+                }
+            } else {
+                Some(rrset.clone())
+            }
+        })
+        .collect();
+    if !synthesized {
+        return None;
+    }
+
+    let mut response = a_response.clone();
+    response.question = a_response.question.as_ref().map(|q| Question {
+        name: q.name.clone(),
+        typ: RRType::AAAA,
+        class: q.class,
+    });
+    response.sections[SectionType::Answer as usize] = Section(Some(answers));
+    response.recalculate_header();
+    Some(response)
+}
+
+pub fn reverse_v4_name(qname: &Name, config: &Dns64Config) -> Option<Name> {
+    match parse_ip6_arpa(qname) {
+        Ok((v6, 128)) => config
+            .prefix
+            .extract(v6)
+            .filter(|v4| !config.is_excluded_v4(*v4))
+            .map(ipv4_to_reverse_name),
+        _ => None,
+    }
+}
+
+//map a ptr query under ip6.arpa inside the nat64 prefix to the in-addr.arpa
+//query which should be sent instead
+pub fn reverse_v4_query(query: &Message, config: &Dns64Config) -> Option<Message> {
+    let question = query.question.as_ref()?;
+    if question.typ != RRType::PTR {
+        return None;
+    }
+    let name = reverse_v4_name(&question.name, config)?;
+    let mut v4_query = query.clone();
+    v4_query.question = Some(Question {
+        name,
+        typ: question.typ,
+        class: question.class,
+    });
+    Some(v4_query)
+}
+
+//rename the in-addr.arpa owners in the response of the mapped query back to
+//the ip6.arpa name of the original query
+pub fn rewrite_reverse_response(v4_response: &Message, query: &Message) -> Message {
+    let mut response = v4_response.clone();
+    response.header.id = query.header.id;
+    response.question = query.question.clone();
+    if let (Some(v4_question), Some(question)) =
+        (v4_response.question.as_ref(), query.question.as_ref())
+    {
+        if let Some(answers) = response.section_mut(SectionType::Answer) {
+            for rrset in answers.iter_mut() {
+                if rrset.name == v4_question.name {
+                    rrset.name = question.name.clone();
+                }
+            }
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message_builder::MessageBuilder;
+    use crate::reverse::ipv6_to_reverse_name;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_embed_extract() {
+        let v4 = Ipv4Addr::new(192, 0, 2, 33);
+        //examples from rfc6052 section 2.4
+        let cases = vec![
+            ("2001:db8::", 32, "2001:db8:c000:221::"),
+            ("2001:db8:100::", 40, "2001:db8:1c0:2:21::"),
+            ("2001:db8:122::", 48, "2001:db8:122:c000:2:2100::"),
+            ("2001:db8:122:300::", 56, "2001:db8:122:3c0:0:221::"),
+            ("2001:db8:122:344::", 64, "2001:db8:122:344:c0:2:2100:0"),
+            ("2001:db8:122:344::", 96, "2001:db8:122:344::192.0.2.33"),
+        ];
+        for (prefix, len, desired) in cases {
+            let prefix = Nat64Prefix::new(prefix.parse().unwrap(), len).unwrap();
+            let v6 = prefix.embed(v4);
+            assert_eq!(v6, desired.parse::<Ipv6Addr>().unwrap());
+            assert_eq!(prefix.extract(v6), Some(v4));
+        }
+        assert_eq!(
+            Nat64Prefix::well_known().embed(v4),
+            "64:ff9b::192.0.2.33".parse::<Ipv6Addr>().unwrap()
+        );
+        assert!(Nat64Prefix::new("2001:db8::".parse().unwrap(), 33).is_err());
+        assert!(Nat64Prefix::well_known()
+            .extract("2001:db8::1".parse().unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn test_synthesize() {
+        let config = Dns64Config::new(Nat64Prefix::well_known());
+        let mut a_response = Message::with_query(Name::new("www.example.com.").unwrap(), RRType::A);
+        MessageBuilder::new(&mut a_response)
+            .make_response()
+            .add_answer(RRset::from_str("www.example.com. 600 IN CNAME web.example.com.").unwrap())
+            .add_answer(RRset::from_str("web.example.com. 600 IN A 192.0.2.1").unwrap())
+            .add_answer(RRset::from_str("web.example.com. 600 IN A 10.0.0.1").unwrap())
+            .done();
+
+        let response = synthesize(&a_response, &config, Some(300)).unwrap();
+        assert_eq!(response.question.as_ref().unwrap().typ, RRType::AAAA);
+        assert_eq!(response.header.an_count, 2);
+        let answers = response.section(SectionType::Answer).unwrap();
+        assert_eq!(answers[0].typ, RRType::CNAME);
+        assert_eq!(
+            answers[1],
+            RRset::from_str("web.example.com. 300 IN AAAA 64:ff9b::192.0.2.1").unwrap()
+        );
+
+        let mut a_response = Message::with_query(Name::new("lo.example.com.").unwrap(), RRType::A);
+        MessageBuilder::new(&mut a_response)
+            .make_response()
+            .add_answer(RRset::from_str("lo.example.com. 600 IN A 127.0.0.1").unwrap())
+            .done();
+        assert!(synthesize(&a_response, &config, None).is_none());
+
+        let mut aaaa_response =
+            Message::with_query(Name::new("www.example.com.").unwrap(), RRType::AAAA);
+        MessageBuilder::new(&mut aaaa_response)
+            .make_response()
+            .done();
+        assert!(config.needs_synthesis(&aaaa_response));
+        MessageBuilder::new(&mut aaaa_response)
+            .add_answer(RRset::from_str("www.example.com. 600 IN AAAA ::ffff:192.0.2.1").unwrap())
+            .done();
+        assert!(config.needs_synthesis(&aaaa_response));
+        MessageBuilder::new(&mut aaaa_response)
+            .add_answer(RRset::from_str("www.example.com. 600 IN AAAA 2001:db8::1").unwrap())
+            .done();
+        assert!(!config.needs_synthesis(&aaaa_response));
+        MessageBuilder::new(&mut aaaa_response)
+            .rcode(Rcode::NXDomain)
+            .done();
+        assert!(!config.needs_synthesis(&aaaa_response));
+    }
+
+    #[test]
+    fn test_reverse_mapping() {
+        let config = Dns64Config::new(Nat64Prefix::well_known());
+        let ip6_name = ipv6_to_reverse_name("64:ff9b::192.0.2.1".parse().unwrap());
+        let query = Message::with_query(ip6_name.clone(), RRType::PTR);
+        let v4_query = reverse_v4_query(&query, &config).unwrap();
+        let v4_name = Name::new("1.2.0.192.in-addr.arpa.").unwrap();
+        assert_eq!(v4_query.question.as_ref().unwrap().name, v4_name);
+        assert_eq!(v4_query.header.id, query.header.id);
+
+        let mut v4_response = v4_query.clone();
+        MessageBuilder::new(&mut v4_response)
+            .id(query.header.id.wrapping_add(1))
+            .make_response()
+            .add_answer(
+                RRset::from_str("1.2.0.192.in-addr.arpa. 600 IN PTR host.example.").unwrap(),
+            )
+            .done();
+        let response = rewrite_reverse_response(&v4_response, &query);
+        assert_eq!(response.header.id, query.header.id);
+        assert_eq!(response.question, query.question);
+        let answers = response.section(SectionType::Answer).unwrap();
+        assert_eq!(answers[0].name, ip6_name);
+        assert_eq!(answers[0].typ, RRType::PTR);
+
+        let excluded = ipv6_to_reverse_name("64:ff9b::10.0.0.1".parse().unwrap());
+        assert!(reverse_v4_name(&excluded, &config).is_none());
+        let outside = ipv6_to_reverse_name("2001:db8::1".parse().unwrap());
+        assert!(reverse_v4_name(&outside, &config).is_none());
+        let prefix_only = Name::new("b.9.f.f.4.6.0.0.ip6.arpa.").unwrap();
+        assert!(reverse_v4_name(&prefix_only, &config).is_none());
+        assert!(reverse_v4_query(&Message::with_query(ip6_name, RRType::A), &config).is_none());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::algorithm::{Algorithm, Requirement};
+use crate::edns::EdnsOption;
+use crate::error::DNSError;
+use crate::header_flag::HeaderFlag;
+use crate::lookup_result::{LookupResult, ValidationStatus};
+use crate::message::{Message, SectionType};
+use crate::rdata::RData;
+use crate::rdata_nsec3::NSEC3;
+use crate::rr_type::RRType;
+use failure::Result;
+
+const SIGNER_RSA_KEY_BITS: u16 = 2048;
+const VALIDATOR_RSA_KEY_BITS: u16 = 1024;
+//rfc9276 section 3.1, signers should use no extra iterations and no salt
+const SIGNER_NSEC3_ITERATIONS: u16 = 0;
+//rfc9276 section 3.2, validators may treat more iterations as insecure
+const VALIDATOR_NSEC3_ITERATIONS: u16 = 100;
+//rfc8914 section 4.28
+const EDE_UNSUPPORTED_NSEC3_ITERATIONS: u16 = 27;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnssecPolicy {
+    pub allowed_algorithms: Vec<Algorithm>,
+    pub min_rsa_key_bits: u16,
+    pub max_nsec3_iterations: u16,
+    pub max_nsec3_salt_len: usize,
+    //what a secure answer over the nsec3 limits becomes, insecure to
+    //downgrade it or bogus to reject it
+    pub nsec3_limit_status: ValidationStatus,
+}
+
+impl DnssecPolicy {
+    pub fn signer() -> Self {
+        DnssecPolicy {
+            allowed_algorithms: Self::algorithms_at_least(
+                Algorithm::signing_requirement,
+                Requirement::May,
+            ),
+            min_rsa_key_bits: SIGNER_RSA_KEY_BITS,
+            max_nsec3_iterations: SIGNER_NSEC3_ITERATIONS,
+            max_nsec3_salt_len: 0,
+            nsec3_limit_status: ValidationStatus::Bogus,
+        }
+    }
+
+    pub fn validator() -> Self {
+        DnssecPolicy {
+            allowed_algorithms: Self::algorithms_at_least(
+                Algorithm::validation_requirement,
+                Requirement::Recommended,
+            ),
+            min_rsa_key_bits: VALIDATOR_RSA_KEY_BITS,
+            max_nsec3_iterations: VALIDATOR_NSEC3_ITERATIONS,
+            max_nsec3_salt_len: 255,
+            nsec3_limit_status: ValidationStatus::Insecure,
+        }
+    }
+
+    fn algorithms_at_least(
+        requirement: fn(Algorithm) -> Requirement,
+        min: Requirement,
+    ) -> Vec<Algorithm> {
+        (1..=255)
+            .map(Algorithm::new)
+            .filter(|alg| requirement(*alg) >= min)
+            .collect()
+    }
+
+    pub fn is_allowed(&self, alg: Algorithm) -> bool {
+        self.allowed_algorithms.contains(&alg)
+    }
+
+    pub fn check_key(&self, alg: Algorithm, key_bits: u16) -> Result<()> {
+        if !self.is_allowed(alg) {
+            return Err(DNSError::AlgorithmNotAllowed(alg.to_u8()).into());
+        }
+        if alg.is_rsa() && key_bits < self.min_rsa_key_bits {
+            return Err(DNSError::KeyTooShort(key_bits, self.min_rsa_key_bits).into());
+        }
+        Ok(())
+    }
+
+    pub fn check_nsec3(&self, iterations: u16, salt_len: usize) -> bool {
+        iterations <= self.max_nsec3_iterations && salt_len <= self.max_nsec3_salt_len
+    }
+
+    //the first nsec3 record in the authority section over the limits
+    pub fn nsec3_over_limit<'a>(&self, response: &'a Message) -> Option<&'a NSEC3> {
+        response
+            .section(SectionType::Authority)?
+            .iter()
+            .filter(|rrset| rrset.typ == RRType::NSEC3)
+            .flat_map(|rrset| rrset.rdatas.iter())
+            .find_map(|rdata| match rdata {
+                RData::NSEC3(nsec3) if !self.check_nsec3(nsec3.iterations, nsec3.salt.len()) => {
+                    Some(nsec3.as_ref())
+                }
+                _ => None,
+            })
+    }
+
+    //rfc9276 section 3.2, a bogus result stays bogus. The ad flag is
+    //cleared and, when the response has edns, the reason attached
+    pub fn enforce_nsec3(&self, response: &mut Message, result: &mut LookupResult) -> bool {
+        let iterations = match self.nsec3_over_limit(response) {
+            Some(nsec3) => nsec3.iterations,
+            None => return false,
+        };
+        if result.validation != ValidationStatus::Bogus {
+            result.validation = self.nsec3_limit_status;
+        }
+        response.header.set_flag(HeaderFlag::AuthenticData, false);
+        if let Some(edns) = response.edns.as_mut() {
+            edns.add_option(EdnsOption::ExtendedError(
+                EDE_UNSUPPORTED_NSEC3_ITERATIONS,
+                format!("nsec3 iterations {}", iterations),
+            ));
+        }
+        true
+    }
+}
+
+impl Default for DnssecPolicy {
+    fn default() -> Self {
+        Self::validator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::edns::{EdnsConfig, OPTION_EXTENDED_ERROR};
+    use crate::lookup_result::LookupSource;
+    use crate::message_builder::MessageBuilder;
+    use crate::name::Name;
+    use crate::rrset::RRset;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_dnssec_policy() {
+        let signer = DnssecPolicy::signer();
+        assert_eq!(
+            signer.allowed_algorithms,
+            vec![
+                Algorithm::RSASHA256,
+                Algorithm::ECDSAP256SHA256,
+                Algorithm::ECDSAP384SHA384,
+                Algorithm::ED25519,
+                Algorithm::ED448,
+            ]
+        );
+        assert!(signer.check_key(Algorithm::RSASHA256, 2048).is_ok());
+        assert!(signer.check_key(Algorithm::RSASHA256, 1024).is_err());
+        assert!(signer.check_key(Algorithm::RSASHA1, 2048).is_err());
+        assert!(signer.check_key(Algorithm::ED25519, 256).is_ok());
+        assert!(signer.check_nsec3(0, 0));
+        assert!(!signer.check_nsec3(1, 0));
+        assert!(!signer.check_nsec3(0, 8));
+
+        let validator = DnssecPolicy::default();
+        assert!(validator.is_allowed(Algorithm::RSASHA1));
+        assert!(validator.is_allowed(Algorithm::ED448));
+        assert!(!validator.is_allowed(Algorithm::ECCGOST));
+        assert!(!validator.is_allowed(Algorithm::RSAMD5));
+        assert!(validator.check_key(Algorithm::RSASHA1, 1024).is_ok());
+        assert!(validator.check_nsec3(100, 8));
+        assert!(!validator.check_nsec3(101, 8));
+    }
+
+    #[test]
+    fn test_enforce_nsec3() {
+        let qname = Name::new("missing.example.").unwrap();
+        let nsec3 = |iterations: u16| {
+            RRset::from_str(&format!(
+                "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example. 3600 IN NSEC3 1 0 {} aabb 2t7b4g4vsa5smi47k61mv5bv1a22bojr A RRSIG",
+                iterations
+            ))
+            .unwrap()
+        };
+        let response_with = |nsec3: RRset| {
+            let mut response = Message::with_query(qname.clone(), RRType::A);
+            MessageBuilder::new(&mut response)
+                .make_response()
+                .set_flag(HeaderFlag::AuthenticData)
+                .add_auth(nsec3)
+                .edns(EdnsConfig::default().to_edns())
+                .done();
+            response
+        };
+
+        let validator = DnssecPolicy::validator();
+        let mut response = response_with(nsec3(100));
+        let mut result = LookupResult::from_message(&response, LookupSource::Upstream).unwrap();
+        assert!(validator.nsec3_over_limit(&response).is_none());
+        assert!(!validator.enforce_nsec3(&mut response, &mut result));
+        assert_eq!(result.validation, ValidationStatus::Secure);
+
+        let mut response = response_with(nsec3(150));
+        let mut result = LookupResult::from_message(&response, LookupSource::Upstream).unwrap();
+        assert_eq!(
+            validator.nsec3_over_limit(&response).unwrap().iterations,
+            150
+        );
+        assert!(validator.enforce_nsec3(&mut response, &mut result));
+        assert_eq!(result.validation, ValidationStatus::Insecure);
+        assert!(!response.header.is_flag_set(HeaderFlag::AuthenticData));
+        assert_eq!(
+            response
+                .edns
+                .as_ref()
+                .unwrap()
+                .option(OPTION_EXTENDED_ERROR),
+            Some(&EdnsOption::ExtendedError(
+                27,
+                "nsec3 iterations 150".to_string()
+            ))
+        );
+
+        let strict = DnssecPolicy {
+            nsec3_limit_status: ValidationStatus::Bogus,
+            ..DnssecPolicy::validator()
+        };
+        let mut response = response_with(nsec3(150));
+        let mut result = LookupResult::from_message(&response, LookupSource::Upstream).unwrap();
+        assert!(strict.enforce_nsec3(&mut response, &mut result));
+        assert_eq!(result.validation, ValidationStatus::Bogus);
+    }
+}
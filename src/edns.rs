@@ -1,15 +1,43 @@
+use crate::error::DNSError;
 use crate::message_render::MessageRender;
+use crate::rdata::RData;
 use crate::rr_class::RRClass;
 use crate::rr_type::RRType;
 use crate::rrset::{RRTtl, RRset};
-use crate::util::OutputBuffer;
+use crate::util::{ct_eq, hex::to_hex, mask_addr, InputBuffer, OutputBuffer};
+use failure::Result;
 use std::fmt::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
 
 const VERSION_SHIFT: u32 = 16;
 const EXTRCODE_SHIFT: u32 = 24;
 const VERSION_MASK: u32 = 0x00ff_0000;
 const EXTFLAG_DO: u32 = 0x0000_8000;
 
+pub const OPTION_NSID: u16 = 3;
+pub const OPTION_CLIENT_SUBNET: u16 = 8;
+pub const OPTION_COOKIE: u16 = 10;
+pub const OPTION_TCP_KEEPALIVE: u16 = 11;
+pub const OPTION_PADDING: u16 = 12;
+pub const OPTION_EXTENDED_ERROR: u16 = 15;
+
+//options parsed into their own EdnsOption variant
+pub const SUPPORTED_OPTIONS: &[u16] = &[
+    OPTION_NSID,
+    OPTION_CLIENT_SUBNET,
+    OPTION_COOKIE,
+    OPTION_TCP_KEEPALIVE,
+    OPTION_EXTENDED_ERROR,
+];
+
+const CLIENT_COOKIE_LEN: usize = 8;
+const MIN_SERVER_COOKIE_LEN: usize = 8;
+const MAX_SERVER_COOKIE_LEN: usize = 32;
+
+const FAMILY_IPV4: u16 = 1;
+const FAMILY_IPV6: u16 = 2;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Edns {
     pub versoin: u8,
@@ -20,22 +48,311 @@ pub struct Edns {
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct EdnsOption {}
+pub enum EdnsOption {
+    Nsid(Vec<u8>),
+    ClientSubnet(ClientSubnet),
+    //client cookie and the optional server cookie
+    Cookie(Vec<u8>, Option<Vec<u8>>),
+    //idle timeout in units of 100 milliseconds, absent in queries
+    TcpKeepalive(Option<u16>),
+    ExtendedError(u16, String),
+    Unknown(u16, Vec<u8>),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ClientSubnet {
+    pub addr: IpAddr,
+    pub source_prefix: u8,
+    pub scope_prefix: u8,
+}
+
+fn prefix_octets(prefix_len: u8) -> usize {
+    (usize::from(prefix_len) + 7) / 8
+}
+
+impl ClientSubnet {
+    pub fn new(addr: IpAddr, source_prefix: u8) -> Self {
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+        let source_prefix = source_prefix.min(max_len);
+        ClientSubnet {
+            addr: mask_addr(addr, source_prefix),
+            source_prefix,
+            scope_prefix: 0,
+        }
+    }
+
+    //rfc7871 section 6, the address is truncated to the source prefix and
+    //bits beyond it must be zero
+    fn from_wire(data: &[u8]) -> Result<Self> {
+        if data.len() < 4 {
+            return Err(DNSError::InvalidEdnsOption(OPTION_CLIENT_SUBNET).into());
+        }
+        let family = (u16::from(data[0]) << 8) | u16::from(data[1]);
+        let source_prefix = data[2];
+        let scope_prefix = data[3];
+        let addr_data = &data[4..];
+        if addr_data.len() != prefix_octets(source_prefix) {
+            return Err(DNSError::InvalidEdnsOption(OPTION_CLIENT_SUBNET).into());
+        }
+        let addr = match family {
+            FAMILY_IPV4 if source_prefix <= 32 && scope_prefix <= 32 => {
+                let mut octets = [0; 4];
+                octets[..addr_data.len()].copy_from_slice(addr_data);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            FAMILY_IPV6 if source_prefix <= 128 && scope_prefix <= 128 => {
+                let mut octets = [0; 16];
+                octets[..addr_data.len()].copy_from_slice(addr_data);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return Err(DNSError::InvalidEdnsOption(OPTION_CLIENT_SUBNET).into()),
+        };
+        if mask_addr(addr, source_prefix) != addr {
+            return Err(DNSError::InvalidEdnsOption(OPTION_CLIENT_SUBNET).into());
+        }
+        Ok(ClientSubnet {
+            addr,
+            source_prefix,
+            scope_prefix,
+        })
+    }
+
+    fn max_prefix(&self) -> u8 {
+        if self.addr.is_ipv4() {
+            32
+        } else {
+            128
+        }
+    }
+
+    fn data_len(&self) -> usize {
+        4 + prefix_octets(self.source_prefix.min(self.max_prefix()))
+    }
+
+    //the fields are public, so the prefixes are clamped to the family and
+    //the bits past the source prefix cleared as rfc7871 section 6 requires
+    fn to_wire(&self, buf: &mut OutputBuffer) {
+        let source_prefix = self.source_prefix.min(self.max_prefix());
+        let addr_len = prefix_octets(source_prefix);
+        let (family, octets) = match mask_addr(self.addr, source_prefix) {
+            IpAddr::V4(v4) => (FAMILY_IPV4, v4.octets().to_vec()),
+            IpAddr::V6(v6) => (FAMILY_IPV6, v6.octets().to_vec()),
+        };
+        buf.write_u16(family);
+        buf.write_u8(source_prefix);
+        buf.write_u8(self.scope_prefix.min(self.max_prefix()));
+        buf.write_bytes(&octets[..addr_len]);
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("{}/{}/{}", self.addr, self.source_prefix, self.scope_prefix)
+    }
+}
+
+impl EdnsOption {
+    //only a truncated option is an error, a payload that doesn't decode
+    //is kept as Unknown so one bad option doesn't cost the whole message
+    pub fn from_wire(buf: &mut InputBuffer) -> Result<Self> {
+        let code = buf.read_u16()?;
+        let len = buf.read_u16()? as usize;
+        let data = buf.read_bytes(len)?;
+        Ok(Self::decode(code, data).unwrap_or_else(|| EdnsOption::Unknown(code, data.to_vec())))
+    }
+
+    fn decode(code: u16, data: &[u8]) -> Option<Self> {
+        let len = data.len();
+        match code {
+            OPTION_NSID => Some(EdnsOption::Nsid(data.to_vec())),
+            OPTION_CLIENT_SUBNET => ClientSubnet::from_wire(data)
+                .ok()
+                .map(EdnsOption::ClientSubnet),
+            OPTION_COOKIE => {
+                if len == CLIENT_COOKIE_LEN {
+                    Some(EdnsOption::Cookie(data.to_vec(), None))
+                } else if (CLIENT_COOKIE_LEN + MIN_SERVER_COOKIE_LEN
+                    ..=CLIENT_COOKIE_LEN + MAX_SERVER_COOKIE_LEN)
+                    .contains(&len)
+                {
+                    Some(EdnsOption::Cookie(
+                        data[..CLIENT_COOKIE_LEN].to_vec(),
+                        Some(data[CLIENT_COOKIE_LEN..].to_vec()),
+                    ))
+                } else {
+                    None
+                }
+            }
+            OPTION_TCP_KEEPALIVE => match len {
+                0 => Some(EdnsOption::TcpKeepalive(None)),
+                2 => Some(EdnsOption::TcpKeepalive(Some(
+                    (u16::from(data[0]) << 8) | u16::from(data[1]),
+                ))),
+                _ => None,
+            },
+            OPTION_EXTENDED_ERROR if len >= 2 => {
+                let info_code = (u16::from(data[0]) << 8) | u16::from(data[1]);
+                let text = String::from_utf8_lossy(&data[2..]).into_owned();
+                Some(EdnsOption::ExtendedError(info_code, text))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> u16 {
+        match *self {
+            EdnsOption::Nsid(_) => OPTION_NSID,
+            EdnsOption::ClientSubnet(_) => OPTION_CLIENT_SUBNET,
+            EdnsOption::Cookie(..) => OPTION_COOKIE,
+            EdnsOption::TcpKeepalive(_) => OPTION_TCP_KEEPALIVE,
+            EdnsOption::ExtendedError(..) => OPTION_EXTENDED_ERROR,
+            EdnsOption::Unknown(code, _) => code,
+        }
+    }
+
+    fn data_len(&self) -> usize {
+        match *self {
+            EdnsOption::Nsid(ref data) => data.len(),
+            EdnsOption::ClientSubnet(ref subnet) => subnet.data_len(),
+            EdnsOption::Cookie(ref client, ref server) => {
+                client.len() + server.as_ref().map_or(0, |s| s.len())
+            }
+            EdnsOption::TcpKeepalive(None) => 0,
+            EdnsOption::TcpKeepalive(Some(_)) => 2,
+            EdnsOption::ExtendedError(_, ref text) => 2 + text.len(),
+            EdnsOption::Unknown(_, ref data) => data.len(),
+        }
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u16(self.code());
+        buf.write_u16(self.data_len() as u16);
+        match *self {
+            EdnsOption::Nsid(ref data) | EdnsOption::Unknown(_, ref data) => {
+                buf.write_bytes(data.as_slice())
+            }
+            EdnsOption::ClientSubnet(ref subnet) => subnet.to_wire(buf),
+            EdnsOption::Cookie(ref client, ref server) => {
+                buf.write_bytes(client.as_slice());
+                if let Some(server) = server.as_ref() {
+                    buf.write_bytes(server.as_slice());
+                }
+            }
+            EdnsOption::TcpKeepalive(None) => {}
+            EdnsOption::TcpKeepalive(Some(timeout)) => buf.write_u16(timeout),
+            EdnsOption::ExtendedError(info_code, ref text) => {
+                buf.write_u16(info_code);
+                buf.write_bytes(text.as_bytes());
+            }
+        }
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        let mut buf = OutputBuffer::new(self.data_len() + 4);
+        self.to_wire(&mut buf);
+        render.write_bytes(buf.data());
+    }
+
+    pub fn to_string(&self) -> String {
+        match *self {
+            EdnsOption::Nsid(ref data) => format!("; NSID: {}", to_hex(data)),
+            EdnsOption::ClientSubnet(ref subnet) => {
+                format!("; CLIENT-SUBNET: {}", subnet.to_string())
+            }
+            EdnsOption::Cookie(ref client, ref server) => format!(
+                "; COOKIE: {}{}",
+                to_hex(client),
+                server.as_ref().map_or(String::new(), |s| to_hex(s))
+            ),
+            EdnsOption::TcpKeepalive(None) => "; TCP KEEPALIVE".to_string(),
+            EdnsOption::TcpKeepalive(Some(timeout)) => {
+                format!("; TCP KEEPALIVE: {}.{} secs", timeout / 10, timeout % 10)
+            }
+            EdnsOption::ExtendedError(info_code, ref text) => {
+                format!("; EDE: {} ({})", info_code, text)
+            }
+            EdnsOption::Unknown(code, ref data) => format!("; OPT={}: {}", code, to_hex(data)),
+        }
+    }
+}
 
 impl Edns {
+    //a truncated option ends the option list, what came before is kept
     pub fn from_rrset(rrset: &RRset) -> Edns {
         assert!(rrset.typ == RRType::OPT);
 
+        let mut options = Vec::new();
+        if let Some(RData::OPT(ref opt)) = rrset.rdatas.first() {
+            let mut buf = InputBuffer::new(opt.data.as_slice());
+            while buf.position() < buf.len() {
+                match EdnsOption::from_wire(&mut buf) {
+                    Ok(option) => options.push(option),
+                    Err(_) => break,
+                }
+            }
+        }
+
         let flags = rrset.ttl.0;
         Edns {
             versoin: ((flags & VERSION_MASK) >> VERSION_SHIFT) as u8,
             udp_size: rrset.class.to_u16(),
             extened_rcode: (flags >> EXTRCODE_SHIFT) as u8,
             dnssec_aware: (flags & EXTFLAG_DO) != 0,
-            options: None,
+            options: if options.is_empty() {
+                None
+            } else {
+                Some(options)
+            },
+        }
+    }
+
+    pub fn option(&self, code: u16) -> Option<&EdnsOption> {
+        self.options
+            .as_ref()
+            .and_then(|options| options.iter().find(|o| o.code() == code))
+    }
+
+    pub fn options_of(&self, code: u16) -> impl Iterator<Item = &EdnsOption> {
+        self.options
+            .iter()
+            .flat_map(|options| options.iter())
+            .filter(move |o| o.code() == code)
+    }
+
+    //extended errors may repeat, any other option replaces the old one
+    pub fn add_option(&mut self, option: EdnsOption) {
+        let code = option.code();
+        let options = self.options.get_or_insert_with(Vec::new);
+        if code != OPTION_EXTENDED_ERROR {
+            options.retain(|o| o.code() != code);
+        }
+        options.push(option);
+    }
+
+    pub fn remove_option(&mut self, code: u16) {
+        if let Some(options) = self.options.as_mut() {
+            options.retain(|o| o.code() != code);
+            if options.is_empty() {
+                self.options = None;
+            }
         }
     }
 
+    pub fn tcp_keepalive_timeout(&self) -> Option<Duration> {
+        match self.option(OPTION_TCP_KEEPALIVE) {
+            Some(EdnsOption::TcpKeepalive(Some(timeout))) => {
+                Some(Duration::from_millis(u64::from(*timeout) * 100))
+            }
+            _ => None,
+        }
+    }
+
+    fn options_len(&self) -> usize {
+        self.options.as_ref().map_or(0, |options| {
+            options
+                .iter()
+                .fold(0, |len, option| len + 4 + option.data_len())
+        })
+    }
+
     pub fn to_string(&self) -> String {
         let mut edns_str = String::new();
         write!(&mut edns_str, "; EDNS: version: {}, ", self.versoin).unwrap();
@@ -43,6 +360,11 @@ impl Edns {
             write!(&mut edns_str, "flags: do; ").unwrap();
         }
         writeln!(&mut edns_str, "udp: {}", self.udp_size).unwrap();
+        if let Some(options) = self.options.as_ref() {
+            for option in options {
+                writeln!(&mut edns_str, "{}", option.to_string()).unwrap();
+            }
+        }
         edns_str
     }
 
@@ -57,7 +379,10 @@ impl Edns {
         RRType::OPT.rend(render);
         RRClass::Unknown(self.udp_size).rend(render);
         RRTtl(flags).rend(render);
-        render.write_u16(0);
+        render.write_u16(self.options_len() as u16);
+        if let Some(options) = self.options.as_ref() {
+            options.iter().for_each(|option| option.rend(render));
+        }
     }
 
     pub fn to_wire(&self, buf: &mut OutputBuffer) {
@@ -71,13 +396,75 @@ impl Edns {
         RRType::OPT.to_wire(buf);
         RRClass::Unknown(self.udp_size).to_wire(buf);
         RRTtl(flags).to_wire(buf);
-        buf.write_u16(0);
+        buf.write_u16(self.options_len() as u16);
+        if let Some(options) = self.options.as_ref() {
+            options.iter().for_each(|option| option.to_wire(buf));
+        }
     }
 
     pub fn rr_count(&self) -> usize {
-        match self.options {
-            Some(ref options) => options.len(),
-            None => 1,
+        1
+    }
+}
+
+//rfc7828: the timeout is only sent over tcp and only when the client
+//signalled support with an empty option
+pub fn negotiate_tcp_keepalive(
+    query_edns: Option<&Edns>,
+    over_tcp: bool,
+    idle_timeout: Duration,
+) -> Option<EdnsOption> {
+    if !over_tcp {
+        return None;
+    }
+    match query_edns.and_then(|edns| edns.option(OPTION_TCP_KEEPALIVE)) {
+        Some(EdnsOption::TcpKeepalive(None)) => {
+            let timeout = (idle_timeout.as_millis() / 100).min(u128::from(u16::MAX));
+            Some(EdnsOption::TcpKeepalive(Some(timeout as u16)))
+        }
+        _ => None,
+    }
+}
+
+//rfc7873 section 5.3, a response without a cookie is handled as coming
+//from a server without cookie support, while one echoing a different
+//client cookie must be discarded
+pub fn verify_client_cookie(sent: &[u8], response_edns: Option<&Edns>) -> bool {
+    match response_edns.and_then(|edns| edns.option(OPTION_COOKIE)) {
+        Some(EdnsOption::Cookie(client, _)) => ct_eq(client, sent),
+        _ => true,
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EdnsConfig {
+    pub udp_size: u16,
+    pub dnssec_aware: bool,
+    pub options: Vec<EdnsOption>,
+}
+
+impl Default for EdnsConfig {
+    fn default() -> Self {
+        EdnsConfig {
+            udp_size: 1232,
+            dnssec_aware: false,
+            options: Vec::new(),
+        }
+    }
+}
+
+impl EdnsConfig {
+    pub fn to_edns(&self) -> Edns {
+        Edns {
+            versoin: 0,
+            extened_rcode: 0,
+            udp_size: self.udp_size,
+            dnssec_aware: self.dnssec_aware,
+            options: if self.options.is_empty() {
+                None
+            } else {
+                Some(self.options.clone())
+            },
         }
     }
 }
@@ -85,7 +472,7 @@ impl Edns {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::util::{hex::from_hex, InputBuffer};
+    use crate::util::hex::from_hex;
 
     #[test]
     fn test_edns_to_wire() {
@@ -106,4 +493,111 @@ mod test {
         desired_edns.rend(&mut render);
         assert_eq!(raw.as_slice(), render.data());
     }
+
+    #[test]
+    fn test_edns_tcp_keepalive() {
+        let raw = from_hex("000029100000000000000b000b0000000c0003abcdef").unwrap();
+        let mut buf = InputBuffer::new(raw.as_slice());
+        let rrset = RRset::from_wire(&mut buf).unwrap();
+        let query_edns = Edns::from_rrset(&rrset);
+        assert_eq!(
+            query_edns.options,
+            Some(vec![
+                EdnsOption::TcpKeepalive(None),
+                EdnsOption::Unknown(12, vec![0xab, 0xcd, 0xef]),
+            ])
+        );
+        assert!(query_edns.tcp_keepalive_timeout().is_none());
+        let mut render = MessageRender::new();
+        query_edns.rend(&mut render);
+        assert_eq!(raw.as_slice(), render.data());
+
+        let idle = Duration::from_secs(30);
+        assert!(negotiate_tcp_keepalive(Some(&query_edns), false, idle).is_none());
+        assert!(negotiate_tcp_keepalive(None, true, idle).is_none());
+        let option = negotiate_tcp_keepalive(Some(&query_edns), true, idle).unwrap();
+        assert_eq!(option, EdnsOption::TcpKeepalive(Some(300)));
+
+        let mut response_edns = query_edns.clone();
+        response_edns.remove_option(12);
+        response_edns.add_option(option);
+        assert_eq!(response_edns.tcp_keepalive_timeout(), Some(idle));
+        let mut buf = OutputBuffer::new(32);
+        response_edns.to_wire(&mut buf);
+        assert_eq!(
+            buf.data(),
+            from_hex("0000291000000000000006000b0002012c")
+                .unwrap()
+                .as_slice()
+        );
+
+        //a keepalive of one byte, a short cookie and a short ede are kept
+        //as unknown, a truncated last option is dropped
+        let raw = from_hex(
+            "000029100000000000001b000b000101000a0003010203000f0001ff000300010000030004ab",
+        )
+        .unwrap();
+        let rrset = RRset::from_wire(&mut InputBuffer::new(raw.as_slice())).unwrap();
+        assert_eq!(
+            Edns::from_rrset(&rrset).options,
+            Some(vec![
+                EdnsOption::Unknown(OPTION_TCP_KEEPALIVE, vec![1]),
+                EdnsOption::Unknown(OPTION_COOKIE, vec![1, 2, 3]),
+                EdnsOption::Unknown(OPTION_EXTENDED_ERROR, vec![0xff]),
+                EdnsOption::Nsid(vec![0]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_client_subnet_wire() {
+        let option_wire = |subnet: ClientSubnet| {
+            let mut buf = OutputBuffer::new(16);
+            EdnsOption::ClientSubnet(subnet).to_wire(&mut buf);
+            buf.data().to_vec()
+        };
+        let addr: IpAddr = "10.1.2.3".parse().unwrap();
+        assert_eq!(
+            option_wire(ClientSubnet {
+                addr,
+                source_prefix: 16,
+                scope_prefix: 0,
+            }),
+            from_hex("00080006000110000a01").unwrap()
+        );
+        assert_eq!(
+            option_wire(ClientSubnet {
+                addr,
+                source_prefix: 40,
+                scope_prefix: 40,
+            }),
+            from_hex("00080008000120200a010203").unwrap()
+        );
+
+        //an unknown family is kept as unknown
+        let raw = from_hex("000029100000000000000b00080007000318000a0000").unwrap();
+        let rrset = RRset::from_wire(&mut InputBuffer::new(raw.as_slice())).unwrap();
+        assert_eq!(
+            Edns::from_rrset(&rrset).options,
+            Some(vec![EdnsOption::Unknown(
+                OPTION_CLIENT_SUBNET,
+                vec![0, 3, 0x18, 0, 0x0a, 0, 0]
+            )])
+        );
+    }
+
+    #[test]
+    fn test_verify_client_cookie() {
+        let client = vec![1u8; 8];
+        let mut edns = EdnsConfig::default().to_edns();
+        assert!(verify_client_cookie(&client, None));
+        assert!(verify_client_cookie(&client, Some(&edns)));
+
+        edns.add_option(EdnsOption::Cookie(client.clone(), Some(vec![2; 16])));
+        assert!(verify_client_cookie(&client, Some(&edns)));
+        assert!(!verify_client_cookie(
+            &[1, 1, 1, 1, 1, 1, 1, 2],
+            Some(&edns)
+        ));
+    }
 }
@@ -0,0 +1,158 @@
+use crate::forwarder::Transport;
+use crate::header_flag::HeaderFlag;
+use crate::message::Message;
+use crate::query_state::EdnsStep;
+use crate::rcode::Rcode;
+
+#[derive(Debug, Copy, Clone)]
+pub enum QueryOutcome<'a> {
+    Response(&'a Message),
+    Timeout,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FallbackAction {
+    Accept,
+    Downgrade(EdnsStep),
+    RetryOverTcp,
+    GiveUp,
+}
+
+//rfc6891 section 6.2.5, 7 and rfc8906, a server that doesn't know edns
+//answers formerr without an opt record, so edns is dropped at once. A lost
+//udp query gives up a step of edns per timeout since the path may choke on
+//big or dnssec answers. A plain query that still fails over udp goes to tcp,
+//and a failure over tcp is final
+pub fn edns_fallback(
+    outcome: QueryOutcome,
+    step: EdnsStep,
+    transport: Transport,
+) -> FallbackAction {
+    let retry = |next: Option<EdnsStep>| match (next, transport) {
+        (Some(next), _) => FallbackAction::Downgrade(next),
+        (None, Transport::Udp) => FallbackAction::RetryOverTcp,
+        (None, Transport::Tcp) => FallbackAction::GiveUp,
+    };
+
+    match outcome {
+        QueryOutcome::Timeout if transport == Transport::Udp => retry(step.next()),
+        QueryOutcome::Timeout => FallbackAction::GiveUp,
+        QueryOutcome::Response(response) => {
+            if transport == Transport::Udp && response.header.is_flag_set(HeaderFlag::Truncation) {
+                FallbackAction::RetryOverTcp
+            } else if response.header.rcode != Rcode::FormErr {
+                FallbackAction::Accept
+            } else if step == EdnsStep::Disabled {
+                retry(None)
+            } else if response.edns.is_none() {
+                retry(Some(EdnsStep::Disabled))
+            } else {
+                FallbackAction::Accept
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::edns::EdnsConfig;
+    use crate::message_builder::MessageBuilder;
+    use crate::name::Name;
+    use crate::rr_type::RRType;
+
+    fn response(rcode: Rcode, with_edns: bool, truncated: bool) -> Message {
+        let mut msg = Message::with_query(Name::new("example.com.").unwrap(), RRType::A);
+        {
+            let mut builder = MessageBuilder::new(&mut msg);
+            builder.make_response().rcode(rcode);
+            if with_edns {
+                builder.edns(EdnsConfig::default().to_edns());
+            }
+            if truncated {
+                builder.set_flag(HeaderFlag::Truncation);
+            }
+            builder.done();
+        }
+        msg
+    }
+
+    #[test]
+    fn test_edns_fallback() {
+        use FallbackAction::*;
+        use Transport::*;
+
+        let formerr = response(Rcode::FormErr, false, false);
+        let formerr_with_opt = response(Rcode::FormErr, true, false);
+        let truncated = response(Rcode::NoError, true, true);
+        let answer = response(Rcode::NoError, true, false);
+        let cases = vec![
+            (
+                QueryOutcome::Timeout,
+                EdnsStep::Full,
+                Udp,
+                Downgrade(EdnsStep::NoDnssec),
+            ),
+            (
+                QueryOutcome::Timeout,
+                EdnsStep::MinimalBuffer,
+                Udp,
+                Downgrade(EdnsStep::Disabled),
+            ),
+            (QueryOutcome::Timeout, EdnsStep::Disabled, Udp, RetryOverTcp),
+            (QueryOutcome::Timeout, EdnsStep::Full, Tcp, GiveUp),
+            (
+                QueryOutcome::Response(&formerr),
+                EdnsStep::Full,
+                Udp,
+                Downgrade(EdnsStep::Disabled),
+            ),
+            (
+                QueryOutcome::Response(&formerr),
+                EdnsStep::NoDnssec,
+                Tcp,
+                Downgrade(EdnsStep::Disabled),
+            ),
+            (
+                QueryOutcome::Response(&formerr),
+                EdnsStep::Disabled,
+                Udp,
+                RetryOverTcp,
+            ),
+            (
+                QueryOutcome::Response(&formerr),
+                EdnsStep::Disabled,
+                Tcp,
+                GiveUp,
+            ),
+            //the server understood edns, the formerr is about something else
+            (
+                QueryOutcome::Response(&formerr_with_opt),
+                EdnsStep::Full,
+                Udp,
+                Accept,
+            ),
+            (
+                QueryOutcome::Response(&truncated),
+                EdnsStep::Full,
+                Udp,
+                RetryOverTcp,
+            ),
+            (
+                QueryOutcome::Response(&truncated),
+                EdnsStep::Full,
+                Tcp,
+                Accept,
+            ),
+            (QueryOutcome::Response(&answer), EdnsStep::Full, Udp, Accept),
+        ];
+        for (i, (outcome, step, transport, action)) in cases.into_iter().enumerate() {
+            assert_eq!(
+                edns_fallback(outcome, step, transport),
+                action,
+                "case {}",
+                i
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use failure::Fail;
 
+pub const UNKNOWN_ERROR_CODE: &str = "UNKNOWN";
+
 #[derive(Debug, Fail)]
 pub enum DNSError {
     #[fail(display = "wire data is incomplete")]
@@ -66,4 +68,204 @@ pub enum DNSError {
         display = "label seqences in concat_all, the last is absolute and others are not absolute"
     )]
     InvalidLabelSequnceConcatParam,
+
+    #[fail(display = "dname can't be applied to the name")]
+    DNameNotApplicable,
+
+    #[fail(display = "prefix length {} isn't valid", _0)]
+    InvalidPrefixLen(u8),
+
+    #[fail(display = "name isn't a valid reverse name")]
+    InvalidReverseName,
+
+    #[fail(display = "nat64 prefix isn't valid")]
+    InvalidNat64Prefix,
+
+    #[fail(display = "edns option {} isn't valid", _0)]
+    InvalidEdnsOption(u16),
+
+    #[fail(display = "no message id is available")]
+    MessageIdExhausted,
+
+    #[fail(display = "pool member must be an A or AAAA rdata")]
+    InvalidPoolMember,
+
+    #[fail(display = "aname can't be flattened for the type")]
+    ANameNotApplicable,
+
+    #[fail(display = "name is out of zone")]
+    OutOfZone,
+
+    #[fail(display = "update message isn't a valid change set")]
+    InvalidChangeSet,
+
+    #[fail(display = "$GENERATE directive isn't valid: {}", _0)]
+    InvalidGenerateDirective(String),
+
+    #[fail(display = "unknown dnssec algorithm {}", _0)]
+    UnknownAlgorithm(String),
+
+    #[fail(display = "dnssec algorithm {} isn't allowed", _0)]
+    AlgorithmNotAllowed(u8),
+
+    #[fail(display = "key size {} is smaller than {}", _0, _1)]
+    KeyTooShort(u16, u16),
+
+    #[fail(display = "cname chain loops")]
+    CNameLoop,
+
+    #[fail(display = "escape sequence isn't complete")]
+    InCompleteEscape,
+
+    #[fail(display = "rewrite rule {} isn't valid", _0)]
+    InvalidRewriteRule(String),
+
+    #[fail(display = "cname chain is longer than {}", _0)]
+    CNameChainTooLong(usize),
+
+    #[fail(display = "name has too many labels")]
+    TooManyLabels,
+
+    #[fail(display = "label sequence isn't absolute")]
+    RelativeName,
+
+    #[fail(display = "character string of {} bytes is longer than 255", _0)]
+    TooLongCharString(usize),
+
+    #[fail(display = "type bitmap isn't valid")]
+    InvalidTypeBitmap,
+
+    #[fail(display = "timestamp {} isn't valid", _0)]
+    InvalidTimestamp(String),
+
+    #[fail(display = "svc param isn't valid: {}", _0)]
+    InvalidSvcParam(String),
+
+    #[fail(display = "ds digest type {} isn't supported", _0)]
+    UnknownDigestType(u8),
+
+    #[fail(display = "cache snapshot isn't valid: {}", _0)]
+    InvalidCacheSnapshot(String),
+
+    #[fail(display = "zone change isn't valid: {}", _0)]
+    InvalidZoneChange(String),
+
+    #[fail(display = "key file isn't valid: {}", _0)]
+    InvalidKeyFile(String),
+
+    #[fail(display = "key state change isn't allowed: {}", _0)]
+    InvalidKeyTransition(String),
+
+    #[fail(display = "multi-signer key sets don't match: {}", _0)]
+    InvalidMultiSigner(String),
+}
+
+impl DNSError {
+    //codes are part of the api, keep them unchanged when the display text
+    //is reworded
+    pub fn code(&self) -> &'static str {
+        match self {
+            DNSError::InCompleteWire => "INCOMPLETE_WIRE",
+            DNSError::TooLongName => "NAME_TOO_LONG",
+            DNSError::TooLongLabel => "LABEL_TOO_LONG",
+            DNSError::InvalidDecimalFormat => "INVALID_DECIMAL",
+            DNSError::NoneTerminateLabel => "UNTERMINATED_LABEL",
+            DNSError::DuplicatePeriod => "DUPLICATE_PERIOD",
+            DNSError::UnknownRRType(_) => "UNKNOWN_RRTYPE",
+            DNSError::InvalidLabelCharacter => "INVALID_LABEL_CHARACTER",
+            DNSError::BadCompressPointer => "BAD_COMPRESS_POINTER",
+            DNSError::InCompleteName => "INCOMPLETE_NAME",
+            DNSError::RdataLenIsNotCorrect => "BAD_RDATA_LENGTH",
+            DNSError::InvalidIPv4Address => "INVALID_IPV4",
+            DNSError::InvalidIPv6Address => "INVALID_IPV6",
+            DNSError::ShortOfQuestion => "NO_QUESTION",
+            DNSError::InvalidLabelIndex => "INVALID_LABEL_INDEX",
+            DNSError::InvalidRRsetString => "INVALID_RRSET_STRING",
+            DNSError::InvalidTtlString => "INVALID_TTL_STRING",
+            DNSError::InvalidClassString => "INVALID_CLASS_STRING",
+            DNSError::RRTypeIsNotSupport => "RRTYPE_NOT_SUPPORTED",
+            DNSError::InvalidRdataString(..) => "INVALID_RDATA_STRING",
+            DNSError::InvalidLabelSequnceConcatParam => "INVALID_CONCAT_PARAM",
+            DNSError::DNameNotApplicable => "DNAME_NOT_APPLICABLE",
+            DNSError::InvalidPrefixLen(_) => "INVALID_PREFIX_LEN",
+            DNSError::InvalidReverseName => "INVALID_REVERSE_NAME",
+            DNSError::InvalidNat64Prefix => "INVALID_NAT64_PREFIX",
+            DNSError::InvalidEdnsOption(_) => "INVALID_EDNS_OPTION",
+            DNSError::MessageIdExhausted => "MESSAGE_ID_EXHAUSTED",
+            DNSError::InvalidPoolMember => "INVALID_POOL_MEMBER",
+            DNSError::ANameNotApplicable => "ANAME_NOT_APPLICABLE",
+            DNSError::OutOfZone => "OUT_OF_ZONE",
+            DNSError::InvalidChangeSet => "INVALID_CHANGE_SET",
+            DNSError::InvalidGenerateDirective(_) => "INVALID_GENERATE",
+            DNSError::UnknownAlgorithm(_) => "UNKNOWN_ALGORITHM",
+            DNSError::AlgorithmNotAllowed(_) => "ALGORITHM_NOT_ALLOWED",
+            DNSError::KeyTooShort(..) => "KEY_TOO_SHORT",
+            DNSError::CNameLoop => "CNAME_LOOP",
+            DNSError::InCompleteEscape => "INCOMPLETE_ESCAPE",
+            DNSError::InvalidRewriteRule(_) => "INVALID_REWRITE_RULE",
+            DNSError::CNameChainTooLong(_) => "CNAME_CHAIN_TOO_LONG",
+            DNSError::TooManyLabels => "TOO_MANY_LABELS",
+            DNSError::RelativeName => "RELATIVE_NAME",
+            DNSError::TooLongCharString(_) => "CHAR_STRING_TOO_LONG",
+            DNSError::InvalidTypeBitmap => "INVALID_TYPE_BITMAP",
+            DNSError::InvalidTimestamp(_) => "INVALID_TIMESTAMP",
+            DNSError::InvalidSvcParam(_) => "INVALID_SVC_PARAM",
+            DNSError::UnknownDigestType(_) => "UNKNOWN_DIGEST_TYPE",
+            DNSError::InvalidCacheSnapshot(_) => "INVALID_CACHE_SNAPSHOT",
+            DNSError::InvalidZoneChange(_) => "INVALID_ZONE_CHANGE",
+            DNSError::InvalidKeyFile(_) => "INVALID_KEY_FILE",
+            DNSError::InvalidKeyTransition(_) => "INVALID_KEY_TRANSITION",
+            DNSError::InvalidMultiSigner(_) => "INVALID_MULTI_SIGNER",
+        }
+    }
+
+    //the operation may succeed if retried later
+    pub fn is_transient(&self) -> bool {
+        matches!(self, DNSError::MessageIdExhausted)
+    }
+
+    //caused by configuration or arguments rather than by the data being
+    //processed, so it persists until the caller changes them
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            DNSError::InvalidLabelIndex
+                | DNSError::InvalidLabelSequnceConcatParam
+                | DNSError::InvalidPrefixLen(_)
+                | DNSError::InvalidNat64Prefix
+                | DNSError::InvalidPoolMember
+                | DNSError::InvalidGenerateDirective(_)
+                | DNSError::UnknownAlgorithm(_)
+                | DNSError::AlgorithmNotAllowed(_)
+                | DNSError::KeyTooShort(..)
+                | DNSError::InvalidRewriteRule(_)
+                | DNSError::UnknownDigestType(_)
+        )
+    }
+}
+
+pub fn error_code(err: &failure::Error) -> &'static str {
+    err.downcast_ref::<DNSError>()
+        .map_or(UNKNOWN_ERROR_CODE, |e| e.code())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::name::Name;
+
+    #[test]
+    fn test_error_code() {
+        let err = Name::new("a..b").unwrap_err();
+        assert_eq!(error_code(&err), "DUPLICATE_PERIOD");
+        assert_eq!(err.to_string(), "period is duplicate");
+        assert_eq!(error_code(&failure::err_msg("other")), UNKNOWN_ERROR_CODE);
+
+        assert_eq!(DNSError::KeyTooShort(512, 1024).code(), "KEY_TOO_SHORT");
+        assert!(DNSError::KeyTooShort(512, 1024).is_fatal());
+        assert!(!DNSError::KeyTooShort(512, 1024).is_transient());
+        assert!(DNSError::MessageIdExhausted.is_transient());
+        assert!(!DNSError::InCompleteWire.is_fatal());
+        assert!(!DNSError::InCompleteWire.is_transient());
+    }
 }
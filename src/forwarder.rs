@@ -0,0 +1,366 @@
+use crate::edns_fallback::{edns_fallback, FallbackAction, QueryOutcome};
+use crate::message::Message;
+use crate::query_state::EdnsStep;
+use crate::rcode::Rcode;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+const INIT_SRTT: Duration = Duration::from_millis(50);
+const MAX_SRTT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+#[derive(Debug, Clone)]
+pub struct Upstream {
+    pub addr: SocketAddr,
+    pub srtt: Duration,
+    pub failures: u32,
+}
+
+impl Upstream {
+    fn new(addr: SocketAddr) -> Self {
+        Upstream {
+            addr,
+            srtt: INIT_SRTT,
+            failures: 0,
+        }
+    }
+
+    //smooth like bind does, new sample weights 3/10
+    fn update_rtt(&mut self, rtt: Duration) {
+        let srtt = (self.srtt * 7 + rtt * 3) / 10;
+        self.srtt = srtt.min(MAX_SRTT);
+        self.failures = 0;
+    }
+
+    fn penalize(&mut self) {
+        self.srtt = (self.srtt * 2).min(MAX_SRTT);
+        self.failures += 1;
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ForwarderConfig {
+    pub attempt_timeout: Duration,
+    pub max_attempts: usize,
+    pub budget: Duration,
+}
+
+impl Default for ForwarderConfig {
+    fn default() -> Self {
+        ForwarderConfig {
+            attempt_timeout: Duration::from_millis(1500),
+            max_attempts: 4,
+            budget: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Forwarder {
+    upstreams: Vec<Upstream>,
+    config: ForwarderConfig,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ForwardTarget {
+    pub upstream: usize,
+    pub addr: SocketAddr,
+    pub transport: Transport,
+    //how much of the query's edns to keep, see EdnsStep::apply
+    pub edns_step: EdnsStep,
+    pub timeout: Duration,
+}
+
+#[derive(Debug)]
+pub enum ForwardAction {
+    Send(ForwardTarget),
+    Answer(Message),
+    GiveUp,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ForwardState {
+    attempts: usize,
+    spent: Duration,
+    tried: Vec<usize>,
+    //upstreams that have fallen back from a full edns query over udp
+    fallbacks: Vec<(usize, EdnsStep, Transport)>,
+    given_up: Vec<usize>,
+    current: Option<ForwardTarget>,
+}
+
+impl Forwarder {
+    pub fn new(addrs: Vec<SocketAddr>, config: ForwarderConfig) -> Self {
+        Forwarder {
+            upstreams: addrs.into_iter().map(Upstream::new).collect(),
+            config,
+        }
+    }
+
+    pub fn upstreams(&self) -> &[Upstream] {
+        self.upstreams.as_slice()
+    }
+
+    pub fn select(&self, exclude: &[usize]) -> Option<usize> {
+        let best = |candidates: &mut dyn Iterator<Item = (usize, &Upstream)>| {
+            candidates
+                .min_by_key(|(_, upstream)| upstream.srtt)
+                .map(|(index, _)| index)
+        };
+
+        best(
+            &mut self
+                .upstreams
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !exclude.contains(index)),
+        )
+        .or_else(|| best(&mut self.upstreams.iter().enumerate()))
+    }
+
+    pub fn start(&self) -> (ForwardState, ForwardAction) {
+        let mut state = ForwardState::default();
+        let action = state.next_attempt(self, None);
+        (state, action)
+    }
+
+    pub fn on_response(
+        &mut self,
+        state: &mut ForwardState,
+        response: Message,
+        rtt: Duration,
+    ) -> ForwardAction {
+        let target = match state.current.take() {
+            Some(target) => target,
+            None => return ForwardAction::GiveUp,
+        };
+        state.spent += rtt;
+        self.upstreams[target.upstream].update_rtt(rtt);
+
+        let outcome = QueryOutcome::Response(&response);
+        match edns_fallback(outcome, target.edns_step, target.transport) {
+            FallbackAction::Accept => {}
+            //the upstream is alive, so go on with it right away
+            action @ FallbackAction::Downgrade(_) | action @ FallbackAction::RetryOverTcp => {
+                state.fall_back(&target, action);
+                return state.next_attempt(self, Some(target.upstream));
+            }
+            FallbackAction::GiveUp => {
+                self.upstreams[target.upstream].failures += 1;
+                state.given_up.push(target.upstream);
+                return state.next_attempt(self, None);
+            }
+        }
+
+        match response.header.rcode {
+            Rcode::ServFail | Rcode::Refused | Rcode::NotImp => {
+                self.upstreams[target.upstream].failures += 1;
+                state.next_attempt(self, None)
+            }
+            _ => ForwardAction::Answer(response),
+        }
+    }
+
+    //a timeout may as well mean the upstream is down, the fallback is kept
+    //for the next time it is picked and the other upstreams go first
+    pub fn on_timeout(&mut self, state: &mut ForwardState) -> ForwardAction {
+        let target = match state.current.take() {
+            Some(target) => target,
+            None => return ForwardAction::GiveUp,
+        };
+        state.spent += target.timeout;
+        self.upstreams[target.upstream].penalize();
+        match edns_fallback(QueryOutcome::Timeout, target.edns_step, target.transport) {
+            FallbackAction::GiveUp => state.given_up.push(target.upstream),
+            action => state.fall_back(&target, action),
+        }
+        state.next_attempt(self, None)
+    }
+}
+
+impl ForwardState {
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    pub fn spent(&self) -> Duration {
+        self.spent
+    }
+
+    pub fn current(&self) -> Option<&ForwardTarget> {
+        self.current.as_ref()
+    }
+
+    fn fall_back(&mut self, target: &ForwardTarget, action: FallbackAction) {
+        let (edns_step, transport) = match action {
+            FallbackAction::Downgrade(step) => (step, target.transport),
+            FallbackAction::RetryOverTcp => (target.edns_step, Transport::Tcp),
+            FallbackAction::Accept | FallbackAction::GiveUp => return,
+        };
+        self.fallbacks
+            .retain(|(upstream, _, _)| *upstream != target.upstream);
+        self.fallbacks.push((target.upstream, edns_step, transport));
+    }
+
+    fn next_attempt(&mut self, forwarder: &Forwarder, retry: Option<usize>) -> ForwardAction {
+        let config = &forwarder.config;
+        if self.attempts >= config.max_attempts || self.spent >= config.budget {
+            return ForwardAction::GiveUp;
+        }
+
+        let upstream = match retry {
+            Some(upstream) => upstream,
+            None => {
+                if self.given_up.len() >= forwarder.upstreams.len() {
+                    return ForwardAction::GiveUp;
+                }
+                //untried upstreams go first, select falls back to every
+                //upstream once all are excluded
+                let mut exclude = self.tried.clone();
+                exclude.extend(self.given_up.iter().copied());
+                match forwarder.select(&exclude) {
+                    Some(index) if !self.given_up.contains(&index) => index,
+                    _ => forwarder.select(&self.given_up).unwrap(),
+                }
+            }
+        };
+        let (edns_step, transport) = self
+            .fallbacks
+            .iter()
+            .find(|(index, _, _)| *index == upstream)
+            .map_or((EdnsStep::Full, Transport::Udp), |(_, step, transport)| {
+                (*step, *transport)
+            });
+
+        let timeout = config.attempt_timeout.min(config.budget - self.spent);
+        let target = ForwardTarget {
+            upstream,
+            addr: forwarder.upstreams[upstream].addr,
+            transport,
+            edns_step,
+            timeout,
+        };
+        self.attempts += 1;
+        if !self.tried.contains(&upstream) {
+            self.tried.push(upstream);
+        }
+        self.current = Some(target);
+        ForwardAction::Send(target)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::header_flag::HeaderFlag;
+    use crate::message_builder::MessageBuilder;
+    use crate::name::Name;
+    use crate::rr_type::RRType;
+
+    fn response(rcode: Rcode, truncated: bool) -> Message {
+        let mut msg = Message::with_query(Name::new("example.com.").unwrap(), RRType::A);
+        let mut builder = MessageBuilder::new(&mut msg);
+        builder.make_response().rcode(rcode);
+        if truncated {
+            builder.set_flag(HeaderFlag::Truncation);
+        }
+        builder.done();
+        msg
+    }
+
+    fn sent(action: ForwardAction) -> ForwardTarget {
+        match action {
+            ForwardAction::Send(target) => target,
+            _ => panic!("expect send action"),
+        }
+    }
+
+    #[test]
+    fn test_forwarder_state_machine() {
+        let addrs = vec!["1.1.1.1:53".parse().unwrap(), "8.8.8.8:53".parse().unwrap()];
+        let mut forwarder = Forwarder::new(addrs, ForwarderConfig::default());
+
+        let (mut state, action) = forwarder.start();
+        let target = sent(action);
+        assert_eq!(target.upstream, 0);
+        assert_eq!(target.transport, Transport::Udp);
+
+        let target = sent(forwarder.on_timeout(&mut state));
+        assert_eq!(target.upstream, 1);
+
+        let target = sent(forwarder.on_response(
+            &mut state,
+            response(Rcode::NoError, true),
+            Duration::from_millis(20),
+        ));
+        assert_eq!(target.upstream, 1);
+        assert_eq!(target.transport, Transport::Tcp);
+
+        match forwarder.on_response(
+            &mut state,
+            response(Rcode::NXDomain, false),
+            Duration::from_millis(30),
+        ) {
+            ForwardAction::Answer(msg) => assert_eq!(msg.header.rcode, Rcode::NXDomain),
+            _ => panic!("expect answer"),
+        }
+        assert_eq!(state.attempts(), 3);
+        assert!(forwarder.upstreams()[1].srtt < forwarder.upstreams()[0].srtt);
+
+        let (mut state, action) = forwarder.start();
+        assert_eq!(sent(action).upstream, 1);
+        let target = sent(forwarder.on_response(
+            &mut state,
+            response(Rcode::ServFail, false),
+            Duration::from_millis(30),
+        ));
+        assert_eq!(target.upstream, 0);
+        sent(forwarder.on_timeout(&mut state));
+        sent(forwarder.on_timeout(&mut state));
+        match forwarder.on_timeout(&mut state) {
+            ForwardAction::GiveUp => {}
+            _ => panic!("attempts should be exhausted"),
+        }
+    }
+
+    #[test]
+    fn test_forwarder_edns_fallback() {
+        let addrs = vec!["1.1.1.1:53".parse().unwrap(), "8.8.8.8:53".parse().unwrap()];
+        let config = ForwarderConfig {
+            max_attempts: 5,
+            ..Default::default()
+        };
+        let mut forwarder = Forwarder::new(addrs, config);
+
+        let (mut state, action) = forwarder.start();
+        let target = sent(action);
+        assert_eq!(target.edns_step, EdnsStep::Full);
+
+        let rtt = Duration::from_millis(20);
+        let target = sent(forwarder.on_response(&mut state, response(Rcode::FormErr, false), rtt));
+        assert_eq!(target.upstream, 0);
+        assert_eq!(target.edns_step, EdnsStep::Disabled);
+        assert_eq!(target.transport, Transport::Udp);
+
+        let target = sent(forwarder.on_response(&mut state, response(Rcode::FormErr, false), rtt));
+        assert_eq!(target.upstream, 0);
+        assert_eq!(target.transport, Transport::Tcp);
+
+        let target = sent(forwarder.on_response(&mut state, response(Rcode::FormErr, false), rtt));
+        assert_eq!(target.upstream, 1);
+        assert_eq!(target.edns_step, EdnsStep::Full);
+
+        let target = sent(forwarder.on_timeout(&mut state));
+        assert_eq!(target.upstream, 1);
+        assert_eq!(target.edns_step, EdnsStep::NoDnssec);
+        match forwarder.on_response(&mut state, response(Rcode::NoError, false), rtt) {
+            ForwardAction::Answer(msg) => assert_eq!(msg.header.rcode, Rcode::NoError),
+            _ => panic!("expect answer"),
+        }
+    }
+}
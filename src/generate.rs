@@ -0,0 +1,306 @@
+use crate::error::DNSError;
+use crate::name::Name;
+use crate::rr_class::RRClass;
+use crate::rr_type::RRType;
+use crate::rrset::{RRTtl, RRset};
+use failure::Result;
+use std::str::FromStr;
+
+const DIRECTIVE: &str = "$GENERATE";
+//one directive expands to at most this many records
+pub const MAX_RECORDS: u64 = 65536;
+//no name or field gets longer than a name
+const MAX_WIDTH: usize = 255;
+
+fn invalid(reason: &str) -> failure::Error {
+    DNSError::InvalidGenerateDirective(reason.to_string()).into()
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Generate {
+    pub start: u32,
+    pub stop: u32,
+    pub step: u32,
+    pub lhs: String,
+    pub ttl: Option<RRTtl>,
+    pub class: Option<RRClass>,
+    pub typ: RRType,
+    pub rhs: String,
+}
+
+impl FromStr for Generate {
+    type Err = failure::Error;
+
+    //$GENERATE range lhs [ttl] [class] type rhs, the leading keyword is
+    //optional
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let mut fields = s.split_whitespace().peekable();
+        if fields
+            .peek()
+            .map(|f| f.eq_ignore_ascii_case(DIRECTIVE))
+            .unwrap_or(false)
+        {
+            fields.next();
+        }
+
+        let range = fields.next().ok_or_else(|| invalid("range is missing"))?;
+        let (start, stop, step) = parse_range(range)?;
+        let lhs = fields
+            .next()
+            .ok_or_else(|| invalid("lhs is missing"))?
+            .to_string();
+
+        let mut ttl = None;
+        let mut class = None;
+        let typ = loop {
+            let field = fields.next().ok_or_else(|| invalid("type is missing"))?;
+            if ttl.is_none() && class.is_none() {
+                if let Ok(t) = RRTtl::from_str(field) {
+                    ttl = Some(t);
+                    continue;
+                }
+            }
+            if class.is_none() {
+                if let Ok(c) = RRClass::from_str(field) {
+                    class = Some(c);
+                    continue;
+                }
+            }
+            break RRType::from_str(field).map_err(|_| invalid("type isn't valid"))?;
+        };
+        match typ {
+            RRType::A | RRType::AAAA | RRType::NS | RRType::CNAME | RRType::DNAME | RRType::PTR => {
+            }
+            _ => return Err(invalid("type isn't supported")),
+        }
+
+        let rhs = fields.collect::<Vec<&str>>().join(" ");
+        if rhs.is_empty() {
+            return Err(invalid("rhs is missing"));
+        }
+
+        Ok(Generate {
+            start,
+            stop,
+            step,
+            lhs,
+            ttl,
+            class,
+            typ,
+            rhs,
+        })
+    }
+}
+
+impl Generate {
+    pub fn record_count(&self) -> usize {
+        range_len(self.start, self.stop, self.step) as usize
+    }
+
+    //records are produced lazily, one per value in the range
+    pub fn iter<'a>(
+        &'a self,
+        origin: &'a Name,
+        default_ttl: RRTtl,
+    ) -> impl Iterator<Item = Result<RRset>> + 'a {
+        (self.start..=self.stop)
+            .step_by(self.step as usize)
+            .map(move |value| self.record(value, origin, default_ttl))
+    }
+
+    pub fn expand(&self, origin: &Name, default_ttl: RRTtl) -> Result<Vec<RRset>> {
+        self.iter(origin, default_ttl).collect()
+    }
+
+    fn record(&self, value: u32, origin: &Name, default_ttl: RRTtl) -> Result<RRset> {
+        let owner = absolute(&substitute(&self.lhs, value)?, origin);
+        let mut rdata = substitute(&self.rhs, value)?;
+        if self.typ != RRType::A && self.typ != RRType::AAAA {
+            rdata = absolute(&rdata, origin);
+        }
+        RRset::from_str(&format!(
+            "{} {} {} {} {}",
+            owner,
+            self.ttl.unwrap_or(default_ttl).0,
+            self.class.unwrap_or(RRClass::IN),
+            self.typ,
+            rdata
+        ))
+    }
+}
+
+fn parse_range(range: &str) -> Result<(u32, u32, u32)> {
+    let (range, step) = match range.find('/') {
+        Some(pos) => (&range[..pos], &range[pos + 1..]),
+        None => (range, "1"),
+    };
+    let pos = range
+        .find('-')
+        .ok_or_else(|| invalid("range isn't valid"))?;
+    let start = range[..pos].parse::<u32>();
+    let stop = range[pos + 1..].parse::<u32>();
+    let step = step.parse::<u32>();
+    match (start, stop, step) {
+        (Ok(start), Ok(stop), Ok(step)) if start <= stop && step > 0 => {
+            if range_len(start, stop, step) > MAX_RECORDS {
+                return Err(invalid("range generates too many records"));
+            }
+            Ok((start, stop, step))
+        }
+        _ => Err(invalid("range isn't valid")),
+    }
+}
+
+fn range_len(start: u32, stop: u32, step: u32) -> u64 {
+    (u64::from(stop) - u64::from(start)) / u64::from(step) + 1
+}
+
+fn absolute(name: &str, origin: &Name) -> String {
+    if name == "@" {
+        origin.to_string()
+    } else if name.ends_with('.') {
+        name.to_string()
+    } else if origin.is_root() {
+        format!("{}.", name)
+    } else {
+        format!("{}.{}", name, origin)
+    }
+}
+
+//$ is replaced by the value, ${offset[,width[,base]]} adjusts it and
+//\$ is a literal dollar sign
+fn substitute(template: &str, value: u32) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) => {
+                    if next != '$' {
+                        out.push('\\');
+                    }
+                    out.push(next);
+                }
+                None => out.push('\\'),
+            },
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let modifier: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                out.push_str(&format_value(value, &modifier)?);
+            }
+            '$' => out.push_str(&value.to_string()),
+            _ => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+fn format_value(value: u32, modifier: &str) -> Result<String> {
+    let mut parts = modifier.split(',');
+    let offset = match parts.next() {
+        Some(offset) if !offset.is_empty() => offset
+            .parse::<i64>()
+            .map_err(|_| invalid("offset isn't valid"))?,
+        _ => 0,
+    };
+    let width = match parts.next() {
+        Some(width) => width
+            .parse::<usize>()
+            .ok()
+            .filter(|width| *width <= MAX_WIDTH)
+            .ok_or_else(|| invalid("width isn't valid"))?,
+        None => 0,
+    };
+    let value = i64::from(value) + offset;
+    if value < 0 {
+        return Err(invalid("offset makes the value negative"));
+    }
+    match parts.next().unwrap_or("d") {
+        "d" => Ok(format!("{:0width$}", value, width = width)),
+        "o" => Ok(format!("{:0width$o}", value, width = width)),
+        "x" => Ok(format!("{:0width$x}", value, width = width)),
+        "X" => Ok(format!("{:0width$X}", value, width = width)),
+        _ => Err(invalid("base isn't supported")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let origin = Name::new("2.0.192.in-addr.arpa.").unwrap();
+        let generate =
+            Generate::from_str("$GENERATE 1-10/3 $ 600 PTR host-${0,3,d}.example.com.").unwrap();
+        assert_eq!(generate.record_count(), 4);
+        assert_eq!(generate.ttl, Some(RRTtl(600)));
+        assert_eq!(generate.class, None);
+
+        let rrsets = generate.expand(&origin, RRTtl(3600)).unwrap();
+        let expected = [
+            "1.2.0.192.in-addr.arpa. 600 IN PTR host-001.example.com.",
+            "4.2.0.192.in-addr.arpa. 600 IN PTR host-004.example.com.",
+            "7.2.0.192.in-addr.arpa. 600 IN PTR host-007.example.com.",
+            "10.2.0.192.in-addr.arpa. 600 IN PTR host-010.example.com.",
+        ];
+        for (rrset, expected) in rrsets.iter().zip(expected.iter()) {
+            assert_eq!(rrset, &RRset::from_str(expected).unwrap());
+        }
+
+        let origin = Name::new("example.com.").unwrap();
+        let generate = Generate::from_str("0-1 node${10,2,x}\\$ IN A 10.0.0.${1}").unwrap();
+        let mut records = generate.iter(&origin, RRTtl(300));
+        assert_eq!(
+            records.next().unwrap().unwrap(),
+            RRset::from_str("node0a$.example.com. 300 IN A 10.0.0.1").unwrap()
+        );
+        assert_eq!(
+            records.next().unwrap().unwrap(),
+            RRset::from_str("node0b$.example.com. 300 IN A 10.0.0.2").unwrap()
+        );
+        assert!(records.next().is_none());
+
+        let generate = Generate::from_str("1-2 web$ CNAME @").unwrap();
+        assert_eq!(
+            generate.expand(&origin, RRTtl(300)).unwrap()[1],
+            RRset::from_str("web2.example.com. 300 IN CNAME example.com.").unwrap()
+        );
+
+        assert!(Generate::from_str("5-1 $ PTR host$.").is_err());
+        assert!(Generate::from_str("1-5/0 $ PTR host$.").is_err());
+        assert!(Generate::from_str("1-5 $ MX 10 mx$.").is_err());
+        assert!(Generate::from_str("1-5 $ PTR").is_err());
+        assert!(Generate::from_str("0-1 $ A 10.0.0.${-1}")
+            .unwrap()
+            .expand(&origin, RRTtl(300))
+            .is_err());
+        assert!(Generate::from_str("0-1 $ A 10.0.0.${0,256}")
+            .unwrap()
+            .expand(&origin, RRTtl(300))
+            .is_err());
+
+        assert!(Generate::from_str("0-4294967295 h$ A 10.0.0.1").is_err());
+        assert!(Generate::from_str("0-65536 h$ A 10.0.0.1").is_err());
+        assert_eq!(
+            Generate::from_str("0-65535 h$ A 10.0.0.1")
+                .unwrap()
+                .record_count(),
+            65536
+        );
+        let generate = Generate::from_str("0-4294967295/65536 h$ A 10.0.0.1").unwrap();
+        assert_eq!(generate.record_count(), 65536);
+        assert_eq!(
+            generate
+                .iter(&origin, RRTtl(300))
+                .last()
+                .unwrap()
+                .unwrap()
+                .name,
+            Name::new("h4294901760.example.com.").unwrap()
+        );
+        let generate = Generate::from_str("4294967295-4294967295 h$ A 10.0.0.1").unwrap();
+        assert_eq!(generate.record_count(), 1);
+        assert_eq!(generate.expand(&origin, RRTtl(300)).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::message::{Message, SectionType};
+use crate::name::Name;
+use crate::probe::{Probe, ProbeFailure};
+use crate::rdata::RData;
+use crate::rr_type::RRType;
+use failure::Result;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum HijackVerdict {
+    Clean,
+    Hijacked {
+        signature: Option<String>,
+        rdatas: Vec<RData>,
+    },
+    Inconclusive(ProbeFailure),
+}
+
+impl HijackVerdict {
+    pub fn is_hijacked(&self) -> bool {
+        matches!(self, HijackVerdict::Hijacked { .. })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HijackDetector {
+    signatures: Vec<(String, RData)>,
+}
+
+impl HijackDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_signature(&mut self, label: &str, rdata: RData) -> &mut Self {
+        self.signatures.push((label.to_string(), rdata));
+        self
+    }
+
+    pub fn signature_count(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn probes(&self, zone: &Name, count: usize) -> Result<Vec<Probe>> {
+        (0..count).map(|_| Probe::nxdomain(zone.clone())).collect()
+    }
+
+    pub fn analyze(&self, probe: &Probe, response: &Message) -> HijackVerdict {
+        match probe.evaluate(response) {
+            Ok(()) => HijackVerdict::Clean,
+            Err(ProbeFailure::UnexpectedAnswer) => {
+                let rdatas: Vec<RData> = response
+                    .section(SectionType::Answer)
+                    .into_iter()
+                    .flatten()
+                    .filter(|rrset| {
+                        rrset.typ == RRType::A
+                            || rrset.typ == RRType::AAAA
+                            || rrset.typ == RRType::CNAME
+                    })
+                    .flat_map(|rrset| rrset.rdatas.iter().cloned())
+                    .collect();
+                //noerror without address records is nodata, not a rewrite
+                if rdatas.is_empty() {
+                    return HijackVerdict::Clean;
+                }
+                let signature = self
+                    .signatures
+                    .iter()
+                    .find(|(_, rdata)| rdatas.contains(rdata))
+                    .map(|(label, _)| label.clone());
+                HijackVerdict::Hijacked { signature, rdatas }
+            }
+            Err(failure) => HijackVerdict::Inconclusive(failure),
+        }
+    }
+
+    pub fn detect<'a, I>(&self, exchanges: I) -> HijackVerdict
+    where
+        I: IntoIterator<Item = (&'a Probe, &'a Message)>,
+    {
+        let mut verdict = None;
+        for (probe, response) in exchanges {
+            match self.analyze(probe, response) {
+                v @ HijackVerdict::Hijacked { .. } => return v,
+                HijackVerdict::Clean => verdict = Some(HijackVerdict::Clean),
+                v @ HijackVerdict::Inconclusive(_) => {
+                    if verdict.is_none() {
+                        verdict = Some(v);
+                    }
+                }
+            }
+        }
+        verdict.unwrap_or(HijackVerdict::Inconclusive(ProbeFailure::NoAnswer))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message_builder::MessageBuilder;
+    use crate::rcode::Rcode;
+    use crate::rrset::RRset;
+    use std::str::FromStr;
+
+    fn respond(probe: &Probe, rcode: Rcode, answer: Option<&str>) -> Message {
+        let mut response = probe.query().clone();
+        {
+            let mut builder = MessageBuilder::new(&mut response);
+            builder.make_response().rcode(rcode);
+            if let Some(rr) = answer {
+                builder.add_answer(RRset::from_str(rr).unwrap());
+            }
+            builder.done();
+        }
+        response
+    }
+
+    #[test]
+    fn test_detect_hijack() {
+        let ad_server = RRset::from_str("ads.isp.net. 60 IN A 198.51.100.7")
+            .unwrap()
+            .rdatas
+            .remove(0);
+        let mut detector = HijackDetector::new();
+        detector.add_signature("isp-ads", ad_server.clone());
+        assert_eq!(detector.signature_count(), 1);
+
+        let zone = Name::new("example.com.").unwrap();
+        let probes = detector.probes(&zone, 2).unwrap();
+        assert_eq!(probes.len(), 2);
+
+        let clean = respond(&probes[0], Rcode::NXDomain, None);
+        assert_eq!(detector.analyze(&probes[0], &clean), HijackVerdict::Clean);
+
+        let qname = probes[1].query().question.as_ref().unwrap().name.clone();
+        let hijacked = respond(
+            &probes[1],
+            Rcode::NoError,
+            Some(format!("{} 60 IN A 198.51.100.7", qname).as_ref()),
+        );
+        let verdict = detector.analyze(&probes[1], &hijacked);
+        assert!(verdict.is_hijacked());
+        assert_eq!(
+            verdict,
+            HijackVerdict::Hijacked {
+                signature: Some("isp-ads".to_string()),
+                rdatas: vec![ad_server],
+            }
+        );
+        assert!(detector
+            .detect(vec![(&probes[0], &clean), (&probes[1], &hijacked)])
+            .is_hijacked());
+
+        let unknown = respond(
+            &probes[1],
+            Rcode::NoError,
+            Some(format!("{} 60 IN A 203.0.113.9", qname).as_ref()),
+        );
+        match detector.analyze(&probes[1], &unknown) {
+            HijackVerdict::Hijacked { signature, .. } => assert_eq!(signature, None),
+            v => panic!("unexpected verdict {:?}", v),
+        }
+
+        let servfail = respond(&probes[0], Rcode::ServFail, None);
+        assert_eq!(
+            detector.detect(vec![(&probes[0], &servfail), (&probes[0], &clean)]),
+            HijackVerdict::Clean
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use crate::algorithm::Algorithm;
+use crate::error::DNSError;
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::rdata_dnskey::DNSKEY;
+use crate::rr_class::RRClass;
+use crate::rr_type::RRType;
+use crate::util::base64::{from_base64, to_base64};
+use crate::util::time::{format_timestamp, parse_timestamp};
+use crate::util::SecretKey;
+use failure::Result;
+use std::str::FromStr;
+
+const PRIVATE_KEY_FORMAT: &str = "v1.3";
+
+fn invalid(reason: &str) -> failure::Error {
+    DNSError::InvalidKeyFile(reason.to_string()).into()
+}
+
+//the file name shared by the .key and .private files, without the suffix
+pub fn key_file_base(name: &Name, dnskey: &DNSKEY) -> String {
+    let mut name = name.clone();
+    name.to_lowercase();
+    format!(
+        "K{}+{:03}+{:05}",
+        name.to_string(),
+        dnskey.algorithm,
+        dnskey.key_tag()
+    )
+}
+
+//the public half, a single DNSKEY record with optional ttl and class,
+//lines starting with ';' are comments
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct KeyFile {
+    pub name: Name,
+    pub ttl: Option<u32>,
+    pub dnskey: DNSKEY,
+}
+
+impl KeyFile {
+    pub fn new(name: Name, dnskey: DNSKEY) -> Self {
+        KeyFile {
+            name,
+            ttl: None,
+            dnskey,
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut records = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with(';'));
+        let line = records.next().ok_or_else(|| invalid("no dnskey record"))?;
+        if records.next().is_some() {
+            return Err(invalid("more than one record"));
+        }
+
+        let mut fields = line.split_whitespace();
+        let name = Name::from_str(fields.next().unwrap())?;
+        let mut field = fields.next();
+        let ttl = match field.and_then(|s| s.parse::<u32>().ok()) {
+            Some(ttl) => {
+                field = fields.next();
+                Some(ttl)
+            }
+            None => None,
+        };
+        if let Some(class) = field {
+            if let Ok(class) = RRClass::from_str(class) {
+                if class != RRClass::IN {
+                    return Err(invalid("class isn't IN"));
+                }
+                field = fields.next();
+            }
+        }
+        if field.map_or(true, |typ| !typ.eq_ignore_ascii_case("DNSKEY")) {
+            return Err(invalid("record isn't a DNSKEY"));
+        }
+
+        let rdata = fields.collect::<Vec<&str>>().join(" ");
+        let dnskey = match RData::from_str(RRType::DNSKEY, &rdata)? {
+            RData::DNSKEY(dnskey) => *dnskey,
+            _ => unreachable!(),
+        };
+        Ok(KeyFile { name, ttl, dnskey })
+    }
+
+    pub fn file_base(&self) -> String {
+        key_file_base(&self.name, &self.dnskey)
+    }
+
+    pub fn to_string(&self) -> String {
+        let mut fields = vec![self.name.to_string()];
+        if let Some(ttl) = self.ttl {
+            fields.push(ttl.to_string());
+        }
+        fields.push("IN DNSKEY".to_string());
+        fields.push(self.dnskey.to_string());
+        fields.join(" ") + "\n"
+    }
+}
+
+//the times a key enters each state, missing ones are not scheduled
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct KeyTiming {
+    pub created: Option<u32>,
+    pub publish: Option<u32>,
+    pub activate: Option<u32>,
+    pub revoke: Option<u32>,
+    pub inactive: Option<u32>,
+    pub delete: Option<u32>,
+}
+
+impl KeyTiming {
+    fn field_mut(&mut self, key: &str) -> Option<&mut Option<u32>> {
+        match key {
+            "Created" => Some(&mut self.created),
+            "Publish" => Some(&mut self.publish),
+            "Activate" => Some(&mut self.activate),
+            "Revoke" => Some(&mut self.revoke),
+            "Inactive" => Some(&mut self.inactive),
+            "Delete" => Some(&mut self.delete),
+            _ => None,
+        }
+    }
+
+    fn fields(&self) -> [(&'static str, Option<u32>); 6] {
+        [
+            ("Created", self.created),
+            ("Publish", self.publish),
+            ("Activate", self.activate),
+            ("Revoke", self.revoke),
+            ("Inactive", self.inactive),
+            ("Delete", self.delete),
+        ]
+    }
+}
+
+//the private half in the v1.x "key: value" format, key material fields
+//are base64 and kept in file order
+#[derive(Debug, Clone)]
+pub struct PrivateKeyFile {
+    pub format: String,
+    pub algorithm: u8,
+    pub fields: Vec<(String, SecretKey)>,
+    pub timing: KeyTiming,
+}
+
+impl PrivateKeyFile {
+    pub fn new(algorithm: u8, fields: Vec<(String, SecretKey)>) -> Self {
+        PrivateKeyFile {
+            format: PRIVATE_KEY_FORMAT.to_string(),
+            algorithm,
+            fields,
+            timing: KeyTiming::default(),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut format = None;
+        let mut algorithm = None;
+        let mut fields = Vec::new();
+        let mut timing = KeyTiming::default();
+        for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (key, value) = match line.find(':') {
+                Some(i) => (&line[..i], line[i + 1..].trim()),
+                None => return Err(invalid("line isn't key: value")),
+            };
+            match key {
+                "Private-key-format" => {
+                    if !value.starts_with("v1.") {
+                        return Err(invalid("unsupported private key format"));
+                    }
+                    format = Some(value.to_string());
+                }
+                "Algorithm" => {
+                    let number = value.split_whitespace().next().unwrap_or("");
+                    let number = number
+                        .parse::<u8>()
+                        .map_err(|_| invalid("algorithm isn't a number"))?;
+                    algorithm = Some(number);
+                }
+                _ => {
+                    if let Some(time) = timing.field_mut(key) {
+                        *time = Some(parse_timestamp(value)?);
+                    } else {
+                        let data = from_base64(value)
+                            .ok_or_else(|| invalid("key material isn't base64"))?;
+                        fields.push((key.to_string(), SecretKey::new(data)));
+                    }
+                }
+            }
+        }
+
+        Ok(PrivateKeyFile {
+            format: format.ok_or_else(|| invalid("no private key format"))?,
+            algorithm: algorithm.ok_or_else(|| invalid("no algorithm"))?,
+            fields,
+            timing,
+        })
+    }
+
+    pub fn field(&self, key: &str) -> Option<&SecretKey> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn to_string(&self) -> String {
+        let mut s = format!(
+            "Private-key-format: {}\nAlgorithm: {} ({})\n",
+            self.format,
+            self.algorithm,
+            Algorithm::new(self.algorithm).to_str()
+        );
+        for (key, value) in &self.fields {
+            s.push_str(&format!("{}: {}\n", key, to_base64(value.as_bytes())));
+        }
+        for (key, time) in self.timing.fields().iter() {
+            if let Some(time) = time {
+                s.push_str(&format!("{}: {}\n", key, format_timestamp(*time)));
+            }
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEY: &str = "; This is a key-signing key, keyid 31589, for example.com.
+example.com. 3600 IN DNSKEY 257 3 13 mdsswUyr3DPW132mOi8V9xESWE8jTo0dxCjjnopKl+GqJxpVXckHAeF+KkxLbxILfDLUT0rAK9iUzy1L53eKGQ==
+";
+
+    const PRIVATE: &str = "Private-key-format: v1.3
+Algorithm: 13 (ECDSAP256SHA256)
+PrivateKey: GU6SnQ/Ou+xC5RumuIUIuJZteXT2z0O/ok1s38Et6mQ=
+Created: 20200101000000
+Publish: 20200101000000
+Activate: 20200108000000
+";
+
+    #[test]
+    fn test_key_file() {
+        let key = KeyFile::parse(KEY).unwrap();
+        assert_eq!(key.name, Name::new("example.com.").unwrap());
+        assert_eq!(key.ttl, Some(3600));
+        assert!(key.dnskey.is_ksk());
+        assert_eq!(
+            key.file_base(),
+            format!("Kexample.com.+013+{:05}", key.dnskey.key_tag())
+        );
+        assert_eq!(KeyFile::parse(&key.to_string()).unwrap(), key);
+
+        let short = KeyFile::parse("Example.COM. DNSKEY 256 3 13 AQID").unwrap();
+        assert_eq!(short.ttl, None);
+        assert!(short.dnskey.is_zsk());
+        assert!(short.file_base().starts_with("Kexample.com.+013+"));
+
+        assert!(KeyFile::parse("; only a comment\n").is_err());
+        assert!(KeyFile::parse("example.com. IN DS 1 13 2 AABB").is_err());
+        assert!(KeyFile::parse("example.com. CH DNSKEY 256 3 13 AQID").is_err());
+        assert!(KeyFile::parse(&format!("{}{}", KEY, KEY)).is_err());
+    }
+
+    #[test]
+    fn test_private_key_file() {
+        let private = PrivateKeyFile::parse(PRIVATE).unwrap();
+        assert_eq!(private.format, "v1.3");
+        assert_eq!(private.algorithm, 13);
+        assert_eq!(private.field("PrivateKey").unwrap().len(), 32);
+        assert_eq!(
+            private.timing.activate,
+            Some(parse_timestamp("20200108000000").unwrap())
+        );
+        assert_eq!(private.timing.inactive, None);
+        assert_eq!(private.to_string(), PRIVATE);
+
+        assert!(PrivateKeyFile::parse("Algorithm: 13\nPrivateKey: AQID\n").is_err());
+        assert!(PrivateKeyFile::parse("Private-key-format: v2.0\nAlgorithm: 13\n").is_err());
+        assert!(
+            PrivateKeyFile::parse("Private-key-format: v1.3\nAlgorithm: 13\nPrivateKey: !!\n")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use crate::error::DNSError;
+use crate::key_file::KeyTiming;
+use crate::rdata_dnskey::DNSKEY;
+use crate::util::time::serial_le;
+use failure::Result;
+
+const FLAG_REVOKE: u16 = 0x0080;
+
+//the life of a key through the rollovers of rfc6781 section 4.1, a ksk
+//may be revoked before removal as rfc5011 requires
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum KeyState {
+    Generated,
+    Published,
+    Active,
+    Revoked,
+    Retired,
+    Removed,
+}
+
+impl KeyState {
+    pub fn can_transition(self, to: KeyState, is_ksk: bool) -> bool {
+        matches!(
+            (self, to),
+            (KeyState::Generated, KeyState::Published)
+                | (KeyState::Published, KeyState::Active)
+                | (KeyState::Active, KeyState::Retired)
+                | (KeyState::Retired, KeyState::Removed)
+                | (KeyState::Revoked, KeyState::Removed)
+        ) || (is_ksk && self == KeyState::Active && to == KeyState::Revoked)
+    }
+
+    pub fn transition(self, to: KeyState, is_ksk: bool) -> Result<KeyState> {
+        if self.can_transition(to, is_ksk) {
+            Ok(to)
+        } else {
+            Err(DNSError::InvalidKeyTransition(format!("{:?} to {:?}", self, to)).into())
+        }
+    }
+
+    //in the dnskey rrset, a revoked key stays there with the revoke bit
+    //set until it's removed
+    pub fn is_published(self) -> bool {
+        matches!(
+            self,
+            KeyState::Published | KeyState::Active | KeyState::Revoked | KeyState::Retired
+        )
+    }
+
+    pub fn is_signing(self) -> bool {
+        self == KeyState::Active
+    }
+}
+
+impl KeyTiming {
+    pub fn state_at(&self, now: u32) -> KeyState {
+        let reached = |time: Option<u32>| time.map_or(false, |time| serial_le(time, now));
+        if reached(self.delete) {
+            KeyState::Removed
+        } else if reached(self.revoke) {
+            KeyState::Revoked
+        } else if reached(self.inactive) {
+            KeyState::Retired
+        } else if reached(self.activate) {
+            KeyState::Active
+        } else if reached(self.publish) {
+            KeyState::Published
+        } else {
+            KeyState::Generated
+        }
+    }
+
+    //records the time a key moves on from the state it's in at that time
+    pub fn advance(&mut self, to: KeyState, at: u32, is_ksk: bool) -> Result<()> {
+        self.state_at(at).transition(to, is_ksk)?;
+        let time = match to {
+            KeyState::Published => &mut self.publish,
+            KeyState::Active => &mut self.activate,
+            KeyState::Revoked => &mut self.revoke,
+            KeyState::Retired => &mut self.inactive,
+            KeyState::Removed => &mut self.delete,
+            KeyState::Generated => unreachable!(),
+        };
+        *time = Some(at);
+        Ok(())
+    }
+
+    //scheduled times have to follow the order of the states
+    pub fn is_ordered(&self) -> bool {
+        let times = [self.publish, self.activate, self.inactive, self.delete];
+        let mut times = times.iter().flatten();
+        let mut last = match times.next() {
+            Some(time) => *time,
+            None => return true,
+        };
+        times.all(|time| {
+            let ordered = serial_le(last, *time);
+            last = *time;
+            ordered
+        })
+    }
+}
+
+//the dnskey as it belongs in the zone at the time, if it belongs there
+pub fn published_dnskey(dnskey: &DNSKEY, timing: &KeyTiming, now: u32) -> Option<DNSKEY> {
+    match timing.state_at(now) {
+        KeyState::Revoked => {
+            let mut dnskey = dnskey.clone();
+            dnskey.flags |= FLAG_REVOKE;
+            Some(dnskey)
+        }
+        state if state.is_published() => Some(dnskey.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::error_code;
+
+    fn ksk() -> DNSKEY {
+        DNSKEY {
+            flags: 257,
+            protocol: 3,
+            algorithm: 13,
+            public_key: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn test_key_transition() {
+        assert_eq!(
+            KeyState::Generated
+                .transition(KeyState::Published, false)
+                .unwrap(),
+            KeyState::Published
+        );
+        let err = KeyState::Generated
+            .transition(KeyState::Active, false)
+            .unwrap_err();
+        assert_eq!(error_code(&err), "INVALID_KEY_TRANSITION");
+        assert!(KeyState::Active.can_transition(KeyState::Revoked, true));
+        assert!(!KeyState::Active.can_transition(KeyState::Revoked, false));
+        assert!(!KeyState::Removed.can_transition(KeyState::Published, false));
+        assert!(KeyState::Retired.is_published());
+        assert!(!KeyState::Retired.is_signing());
+    }
+
+    #[test]
+    fn test_key_rollover() {
+        let mut old = KeyTiming::default();
+        old.advance(KeyState::Published, 100, true).unwrap();
+        old.advance(KeyState::Active, 200, true).unwrap();
+        assert_eq!(old.state_at(150), KeyState::Published);
+        assert_eq!(old.state_at(200), KeyState::Active);
+        assert!(old.advance(KeyState::Removed, 300, true).is_err());
+
+        let mut new = KeyTiming::default();
+        new.advance(KeyState::Published, 300, true).unwrap();
+        new.advance(KeyState::Active, 400, true).unwrap();
+        old.advance(KeyState::Revoked, 400, true).unwrap();
+        old.advance(KeyState::Removed, 500, true).unwrap();
+        assert!(old.is_ordered());
+        assert!(new.is_ordered());
+
+        let key = ksk();
+        let revoked = published_dnskey(&key, &old, 450).unwrap();
+        assert!(revoked.is_revoked());
+        assert_ne!(revoked.key_tag(), key.key_tag());
+        assert_eq!(published_dnskey(&key, &old, 250), Some(key.clone()));
+        assert_eq!(published_dnskey(&key, &old, 500), None);
+        assert_eq!(published_dnskey(&key, &new, 299), None);
+
+        let timing = KeyTiming {
+            publish: Some(300),
+            activate: Some(200),
+            ..Default::default()
+        };
+        assert!(!timing.is_ordered());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::error::DNSError;
 use crate::label_slice::LabelSlice;
-use crate::name::{self, string_parse, Name};
+use crate::name::{self, check_labels, string_parse, Name};
 use failure::{self, Result};
 use std::{
     cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd},
@@ -16,9 +16,22 @@ pub struct LabelSequence {
 
 impl LabelSequence {
     pub fn new(data: Vec<u8>, offsets: Vec<u8>) -> LabelSequence {
+        debug_assert!(check_labels(&data, &offsets).is_ok());
         LabelSequence { data, offsets }
     }
 
+    pub fn into_name(self) -> Result<Name> {
+        if self.is_absolute() {
+            Ok(Name::from_raw_unchecked(self.data, self.offsets))
+        } else {
+            Err(DNSError::RelativeName.into())
+        }
+    }
+
+    pub fn as_slice(&self) -> LabelSlice<'_> {
+        LabelSlice::from_label_sequence(self)
+    }
+
     pub fn data(&self) -> &[u8] {
         self.data.as_slice()
     }
@@ -90,7 +103,10 @@ impl LabelSequence {
     pub fn concat_all(&self, suffixes: &[&LabelSequence]) -> Result<Name> {
         if self.is_absolute() {
             if suffixes.len() == 0 {
-                return Ok(Name::from_raw(self.data.clone(), self.offsets.clone()));
+                return Ok(Name::from_raw_unchecked(
+                    self.data.clone(),
+                    self.offsets.clone(),
+                ));
             } else {
                 return Err(DNSError::InvalidLabelSequnceConcatParam.into());
             }
@@ -136,7 +152,7 @@ impl LabelSequence {
             offset_pos = offset_pos + data[offset_pos] as usize + 1;
         }
 
-        Ok(Name::from_raw(data, offsets))
+        Ok(Name::from_raw_unchecked(data, offsets))
     }
 
     pub fn to_string(&self) -> String {
@@ -148,6 +164,13 @@ impl LabelSequence {
     }
 }
 
+impl From<Name> for LabelSequence {
+    fn from(name: Name) -> Self {
+        let last_label = name.label_count() - 1;
+        name.into_label_sequence(0, last_label)
+    }
+}
+
 impl PartialEq for LabelSequence {
     fn eq(&self, other: &LabelSequence) -> bool {
         self.equals(other, false)
@@ -200,6 +223,36 @@ mod test {
     use crate::name::Name;
     use std::str::FromStr;
 
+    #[test]
+    fn test_label_sequence_conversion() {
+        let name = Name::new("www.Example.com.").unwrap();
+        let seq = LabelSequence::from(name.clone());
+        assert_eq!(seq.label_count(), 4);
+        assert!(seq.as_slice().equals(&name.as_slice(), true));
+        assert_eq!(seq.clone().into_name().unwrap(), name);
+
+        let mut slice = name.as_slice();
+        slice.strip_left(1);
+        assert_eq!(slice.to_name().unwrap(), Name::new("example.com.").unwrap());
+        slice.strip_right(1);
+        let relative = slice.to_label_sequence();
+        assert_eq!(relative.to_string(), "Example.com");
+        assert_eq!(relative.offsets(), [0, 8]);
+        assert!(relative.into_name().is_err());
+
+        assert_eq!(
+            Name::from_raw(name.raw_data().to_vec(), name.offsets().to_vec()).unwrap(),
+            name
+        );
+        assert!(Name::from_raw(vec![1, b'a', 0], vec![0, 2]).is_ok());
+        assert!(Name::from_raw(vec![1, b'a'], vec![0]).is_err());
+        assert!(Name::from_raw(vec![1, b'a', 0], vec![0]).is_err());
+        assert!(Name::from_raw(vec![1, b'a', 0], vec![0, 1]).is_err());
+        assert!(Name::from_raw(vec![0, 1, b'a', 0], vec![0, 1, 3]).is_err());
+        assert!(Name::from_raw(vec![2, b'a', 0], vec![0, 2]).is_err());
+        assert!(Name::from_raw(Vec::new(), Vec::new()).is_err());
+    }
+
     #[test]
     fn test_label_sequence_split() {
         let www_google_com_cn = Name::new("www.google.com.cn.").unwrap();
@@ -3,8 +3,36 @@ use crate::name::lower_case;
 use crate::name::Name;
 use crate::name::NameComparisonResult;
 use crate::name::NameRelation;
+use crate::presentation;
+use failure::Result;
 use std::{cmp, fmt};
 
+//rfc4343: only ascii A-Z fold to a-z, octets above 0x7f are never folded
+//since their case mapping depends on an unknown character set
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CaseFolding {
+    Sensitive,
+    Ascii,
+}
+
+impl CaseFolding {
+    pub fn from_case_sensitive(case_sensitive: bool) -> Self {
+        if case_sensitive {
+            CaseFolding::Sensitive
+        } else {
+            CaseFolding::Ascii
+        }
+    }
+
+    #[inline]
+    pub fn fold(self, c: u8) -> u8 {
+        match self {
+            CaseFolding::Sensitive => c,
+            CaseFolding::Ascii => lower_case(c as usize),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct LabelSlice<'a> {
     data: &'a [u8],
@@ -65,16 +93,25 @@ impl<'a> LabelSlice<'a> {
     }
 
     pub fn equals(&self, other: &LabelSlice, case_sensitive: bool) -> bool {
+        self.equals_with(other, CaseFolding::from_case_sensitive(case_sensitive))
+    }
+
+    pub fn equals_with(&self, other: &LabelSlice, folding: CaseFolding) -> bool {
         if self.len() != other.len() {
             false
-        } else if case_sensitive {
-            self.data() == other.data()
         } else {
-            self.data().eq_ignore_ascii_case(other.data())
+            match folding {
+                CaseFolding::Sensitive => self.data() == other.data(),
+                CaseFolding::Ascii => self.data().eq_ignore_ascii_case(other.data()),
+            }
         }
     }
 
     pub fn compare(&self, other: &LabelSlice, case_sensitive: bool) -> NameComparisonResult {
+        self.compare_with(other, CaseFolding::from_case_sensitive(case_sensitive))
+    }
+
+    pub fn compare_with(&self, other: &LabelSlice, folding: CaseFolding) -> NameComparisonResult {
         let mut nlabels: usize = 0;
         let mut l1: usize = self.label_count();
         let mut l2: usize = other.label_count();
@@ -95,14 +132,15 @@ impl<'a> LabelSlice<'a> {
             let mut count = cmp::min(count1, count2);
 
             while count > 0 {
-                let mut label1: u8 = self.data[pos1];
-                let mut label2: u8 = other.data[pos2];
-                let chdiff: i8;
-                if !case_sensitive {
-                    label1 = lower_case(label1 as usize);
-                    label2 = lower_case(label2 as usize);
-                }
-                chdiff = (label1) as i8 - (label2) as i8;
+                let label1 = folding.fold(self.data[pos1]);
+                let label2 = folding.fold(other.data[pos2]);
+                //octets are unsigned, a signed difference would misorder
+                //and overflow for values above 0x7f
+                let chdiff: i8 = match label1.cmp(&label2) {
+                    cmp::Ordering::Less => -1,
+                    cmp::Ordering::Equal => 0,
+                    cmp::Ordering::Greater => 1,
+                };
                 if chdiff != 0 {
                     return NameComparisonResult {
                         order: chdiff,
@@ -157,43 +195,36 @@ impl<'a> LabelSlice<'a> {
         self.last_label -= index;
     }
 
+    pub fn to_label_sequence(&self) -> LabelSequence {
+        let first_offset = self.offsets()[0];
+        LabelSequence::new(
+            self.data().to_vec(),
+            self.offsets().iter().map(|o| o - first_offset).collect(),
+        )
+    }
+
+    pub fn to_name(&self) -> Result<Name> {
+        self.to_label_sequence().into_name()
+    }
+
     pub fn to_string(&self) -> String {
-        let mut buf = Vec::with_capacity(self.len());
-        let special_char: Vec<u8> = vec![0x22, 0x28, 0x29, 0x2E, 0x3B, 0x5C, 0x40, 0x24]; //" ( ) . ; \\ @ $
-        let mut i = 0;
+        let mut result = String::with_capacity(self.len());
         let data = self.data();
-        while i < self.len() {
-            let mut count = data[i as usize];
+        let mut i = 0;
+        while i < data.len() {
+            let count = data[i] as usize;
             i += 1;
-
             if count == 0 {
-                buf.push(b'.');
+                result.push('.');
                 break;
             }
-
-            if !buf.is_empty() {
-                buf.push(b'.');
-            }
-
-            while count > 0 {
-                count -= 1;
-                let c: u8 = data[i as usize];
-                i += 1;
-                if special_char.contains(&c) {
-                    buf.push(b'\\');
-                    buf.push(c);
-                } else if c > 0x20 && c < 0x7f {
-                    buf.push(c);
-                } else {
-                    buf.push(0x5c);
-                    buf.push(0x30 + ((c / 100) % 10));
-                    buf.push(0x30 + ((c / 10) % 10));
-                    buf.push(0x30 + (c % 10));
-                }
+            if !result.is_empty() {
+                result.push('.');
             }
+            presentation::escape_label(&data[i..i + count], &mut result);
+            i += count;
         }
-
-        unsafe { String::from_utf8_unchecked(buf) }
+        result
     }
 }
 
@@ -207,6 +238,8 @@ impl<'a> fmt::Display for LabelSlice<'a> {
 mod test {
     use super::*;
     use crate::name::{root, Name};
+    use crate::util::InputBuffer;
+
     #[test]
     fn test_label_slice_new() {
         //0377777705626169647503636f6d00
@@ -307,6 +340,44 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_label_slice_case_folding() {
+        let name = |label: &[u8]| {
+            let mut raw = vec![label.len() as u8];
+            raw.extend_from_slice(label);
+            raw.extend_from_slice(b"\x07example\x00");
+            Name::from_wire(&mut InputBuffer::new(raw.as_slice())).unwrap()
+        };
+
+        //0xc4 and 0xe4 are upper and lower a-diaeresis in latin-1
+        let upper = name(b"x\xc4B");
+        let lower = name(b"x\xe4b");
+        let ls_upper = LabelSlice::from_name(&upper);
+        let ls_lower = LabelSlice::from_name(&lower);
+        assert!(!ls_upper.equals_with(&ls_lower, CaseFolding::Ascii));
+        assert!(ls_upper.compare_with(&ls_lower, CaseFolding::Ascii).order < 0);
+        assert_ne!(upper, lower);
+
+        let ascii = name(b"x\xc4b");
+        let ls_ascii = LabelSlice::from_name(&ascii);
+        assert!(ls_upper.equals_with(&ls_ascii, CaseFolding::Ascii));
+        assert!(!ls_upper.equals_with(&ls_ascii, CaseFolding::Sensitive));
+        assert_eq!(
+            ls_upper
+                .compare_with(&ls_ascii, CaseFolding::Ascii)
+                .relation,
+            NameRelation::Equal
+        );
+        assert_eq!(upper, ascii);
+
+        let high = name(b"x\xff");
+        let low = name(b"x\x7f");
+        let ls_high = LabelSlice::from_name(&high);
+        let ls_low = LabelSlice::from_name(&low);
+        assert!(ls_high.compare_with(&ls_low, CaseFolding::Sensitive).order > 0);
+        assert!(ls_low.compare(&ls_high, false).order < 0);
+    }
+
     #[test]
     fn test_label_slice_root() {
         let n1 = Name::new(".").unwrap();
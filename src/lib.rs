@@ -1,36 +1,97 @@
+//test vectors macro, declared first so every rdata module can use it
+#[cfg(test)]
+#[macro_use]
+mod conformance;
+
+pub mod algorithm;
+pub mod answer_pool;
+pub mod answer_selector;
+pub mod cache;
+pub mod cache_entry;
+pub mod cache_policy;
+pub mod capabilities;
+pub mod cds;
+pub mod change_request;
+pub mod dns64;
+pub mod dnssec_policy;
 pub mod edns;
+pub mod edns_fallback;
 pub mod error;
+pub mod forwarder;
+pub mod generate;
 pub mod header;
 pub mod header_flag;
+pub mod hijack;
+pub mod key_file;
+pub mod key_state;
 pub mod label_sequence;
 pub mod label_slice;
+pub mod lookup_result;
+pub mod malformed;
 pub mod message;
 pub mod message_builder;
 pub mod message_render;
+pub mod middleware;
+pub mod multi_signer;
 pub mod name;
+pub mod name_matcher;
+pub mod negative_trust_anchor;
 pub mod opcode;
+pub mod pipeline;
+pub mod presentation;
+pub mod privacy;
+pub mod probe;
+#[cfg(feature = "psl")]
+pub mod psl;
+pub mod qname_rewrite;
+pub mod query_features;
+pub mod query_state;
 pub mod question;
 pub mod rand_name_generator;
 pub mod rcode;
 pub mod rdata;
 pub mod rdata_a;
 pub mod rdata_aaaa;
+pub mod rdata_aname;
 pub mod rdata_cname;
 pub mod rdata_dname;
+pub mod rdata_dnskey;
+pub mod rdata_ds;
 pub mod rdata_mx;
 pub mod rdata_naptr;
 pub mod rdata_ns;
+pub mod rdata_nsec;
+pub mod rdata_nsec3;
+pub mod rdata_nsec3param;
 pub mod rdata_opt;
 pub mod rdata_ptr;
+pub mod rdata_rp;
+pub mod rdata_rrsig;
 pub mod rdata_soa;
 pub mod rdata_srv;
+pub mod rdata_svcb;
+pub mod rdata_tlsa;
 pub mod rdata_txt;
+pub mod rdata_zonemd;
 mod rdatafield_string_parser;
+pub mod resign;
+pub mod response_class;
+pub mod response_meta;
+pub mod response_validation;
+pub mod reverse;
 pub mod rr_class;
 pub mod rr_type;
 pub mod rrset;
+pub mod server;
+pub mod service;
+pub mod signed_rrset;
+pub mod type_bitmap;
 pub mod util;
+pub mod views;
+pub mod zone;
+pub mod zonemd;
 
+pub use capabilities::capabilities;
 pub use header::Header;
 pub use header_flag::HeaderFlag;
 pub use label_sequence::LabelSequence;
@@ -40,6 +101,7 @@ pub use message_builder::MessageBuilder;
 pub use message_render::MessageRender;
 pub use name::Name;
 pub use name::NameRelation;
+pub use name_matcher::NameMatcher;
 pub use opcode::Opcode;
 pub use rand_name_generator::RandNameGenerator;
 pub use rcode::Rcode;
@@ -48,13 +110,22 @@ pub use rdata_a::A;
 pub use rdata_aaaa::AAAA;
 pub use rdata_cname::CName;
 pub use rdata_dname::DName;
+pub use rdata_dnskey::DNSKEY;
+pub use rdata_ds::DS;
 pub use rdata_mx::MX;
 pub use rdata_naptr::NAPTR;
 pub use rdata_ns::NS;
+pub use rdata_nsec::NSEC;
+pub use rdata_nsec3::NSEC3;
+pub use rdata_nsec3param::NSEC3PARAM;
 pub use rdata_opt::OPT;
 pub use rdata_ptr::PTR;
+pub use rdata_rp::RP;
+pub use rdata_rrsig::RRSIG;
 pub use rdata_soa::SOA;
 pub use rdata_srv::SRV;
+pub use rdata_svcb::SVCB;
+pub use rdata_tlsa::TLSA;
 pub use rr_class::RRClass;
 pub use rr_type::RRType;
 pub use rrset::RRTtl;
@@ -0,0 +1,350 @@
+use crate::error::DNSError;
+use crate::header_flag::HeaderFlag;
+use crate::message::{Message, SectionType};
+use crate::name::Name;
+use crate::rcode::Rcode;
+use crate::rdata::RData;
+use crate::rdata_dname::{synthesize_cname, DNameSynthesis};
+use crate::response_class::ResponseClass;
+use crate::rr_type::RRType;
+use crate::rrset::RRset;
+use failure::Result;
+use std::fmt::Write;
+
+pub const MAX_CNAME_CHAIN: usize = 16;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ValidationStatus {
+    Secure,
+    Insecure,
+    Bogus,
+    Indeterminate,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LookupSource {
+    Cache,
+    Upstream,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LookupResult {
+    pub qname: Name,
+    pub qtype: RRType,
+    pub rcode: Rcode,
+    pub class: ResponseClass,
+    pub cname_chain: Vec<RRset>,
+    pub answers: Vec<RRset>,
+    pub validation: ValidationStatus,
+    pub source: LookupSource,
+}
+
+impl LookupResult {
+    pub fn from_message(msg: &Message, source: LookupSource) -> Result<Self> {
+        let question = msg.question.as_ref().ok_or(DNSError::ShortOfQuestion)?;
+        Self::from_messages(&question.name, question.typ, &[msg], source)
+    }
+
+    //a chain may be split over several responses when the resolver follows
+    //cnames itself, the rcode and class come from the last response
+    pub fn from_messages(
+        qname: &Name,
+        qtype: RRType,
+        msgs: &[&Message],
+        source: LookupSource,
+    ) -> Result<Self> {
+        Self::from_messages_with_limit(qname, qtype, msgs, source, MAX_CNAME_CHAIN)
+    }
+
+    //cnames synthesized from a dname count against max_chain like the
+    //ones in the answer
+    pub fn from_messages_with_limit(
+        qname: &Name,
+        qtype: RRType,
+        msgs: &[&Message],
+        source: LookupSource,
+        max_chain: usize,
+    ) -> Result<Self> {
+        let last = msgs.last().ok_or(DNSError::ShortOfQuestion)?;
+        let rrsets: Vec<&RRset> = msgs
+            .iter()
+            .flat_map(|msg| msg.section(SectionType::Answer).into_iter().flatten())
+            .collect();
+
+        let mut current = qname.clone();
+        let mut cname_chain: Vec<RRset> = Vec::new();
+        let answers = loop {
+            let answers: Vec<RRset> = rrsets
+                .iter()
+                .filter(|rrset| rrset.typ == qtype && rrset.name.eq(&current))
+                .map(|rrset| (*rrset).clone())
+                .collect();
+            if !answers.is_empty() || qtype == RRType::CNAME {
+                break answers;
+            }
+
+            let cname = match next_cname(&rrsets, &current)? {
+                Some(cname) => cname,
+                None => break answers,
+            };
+            let target = match cname.rdatas.first() {
+                Some(RData::CName(ref cname)) => cname.name.clone(),
+                _ => break answers,
+            };
+            if target.eq(qname) || cname_chain.iter().any(|rrset| rrset.name.eq(&target)) {
+                return Err(DNSError::CNameLoop.into());
+            }
+            if cname_chain.len() == max_chain {
+                return Err(DNSError::CNameChainTooLong(max_chain).into());
+            }
+            cname_chain.push(cname);
+            current = target;
+        };
+
+        let validation = if msgs
+            .iter()
+            .all(|msg| msg.header.is_flag_set(HeaderFlag::AuthenticData))
+        {
+            ValidationStatus::Secure
+        } else {
+            ValidationStatus::Indeterminate
+        };
+
+        Ok(LookupResult {
+            qname: qname.clone(),
+            qtype,
+            rcode: last.header.rcode,
+            class: ResponseClass::from_message(last),
+            cname_chain,
+            answers,
+            validation,
+            source,
+        })
+    }
+
+    pub fn canonical_name(&self) -> &Name {
+        match self
+            .cname_chain
+            .last()
+            .and_then(|rrset| rrset.rdatas.first())
+        {
+            Some(RData::CName(ref cname)) => &cname.name,
+            _ => &self.qname,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+
+    pub fn rdatas(&self) -> impl Iterator<Item = &RData> {
+        self.answers.iter().flat_map(|rrset| rrset.rdatas.iter())
+    }
+
+    pub fn to_string(&self) -> String {
+        let mut result = String::new();
+        writeln!(
+            &mut result,
+            ";; {} {} {} {:?} {:?}",
+            self.qname, self.qtype, self.rcode, self.validation, self.source
+        )
+        .unwrap();
+        for rrset in self.cname_chain.iter().chain(self.answers.iter()) {
+            write!(&mut result, "{}", rrset.to_string()).unwrap();
+        }
+        result
+    }
+}
+
+//the cname owned by name, or one synthesized from the closest dname above
+//it when the server left the cname out
+fn next_cname(rrsets: &[&RRset], name: &Name) -> Result<Option<RRset>> {
+    if let Some(cname) = rrsets
+        .iter()
+        .find(|rrset| rrset.typ == RRType::CNAME && rrset.name.eq(name))
+    {
+        return Ok(Some((*cname).clone()));
+    }
+
+    let dname = rrsets
+        .iter()
+        .filter(|rrset| {
+            rrset.typ == RRType::DNAME
+                && rrset.name.label_count() < name.label_count()
+                && name.is_subdomain(&rrset.name)
+        })
+        .max_by_key(|rrset| rrset.name.label_count());
+    match dname {
+        Some(dname) => match synthesize_cname(dname, name)? {
+            DNameSynthesis::CName(cname) => Ok(Some(cname)),
+            DNameSynthesis::YXDomain => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message_builder::MessageBuilder;
+    use std::str::FromStr;
+
+    fn response(qname: &str, answer: &[&str], ad: bool) -> Message {
+        let mut msg = Message::with_query(Name::new(qname).unwrap(), RRType::A);
+        {
+            let mut builder = MessageBuilder::new(&mut msg);
+            builder.make_response();
+            if ad {
+                builder.set_flag(HeaderFlag::AuthenticData);
+            }
+            for rr in answer {
+                builder.add_answer(RRset::from_str(rr).unwrap());
+            }
+            builder.done();
+        }
+        msg
+    }
+
+    #[test]
+    fn test_lookup_result() {
+        let first = response(
+            "www.example.com.",
+            &[
+                "www.example.com. 300 IN CNAME web.example.net.",
+                "web.example.net. 300 IN CNAME edge.cdn.example.",
+            ],
+            true,
+        );
+        let second = response(
+            "edge.cdn.example.",
+            &[
+                "edge.cdn.example. 60 IN A 192.0.2.1",
+                "edge.cdn.example. 60 IN A 192.0.2.2",
+            ],
+            false,
+        );
+
+        let qname = Name::new("www.example.com.").unwrap();
+        let result = LookupResult::from_messages(
+            &qname,
+            RRType::A,
+            &[&first, &second],
+            LookupSource::Upstream,
+        )
+        .unwrap();
+        assert_eq!(result.cname_chain.len(), 2);
+        assert_eq!(
+            result.canonical_name(),
+            &Name::new("edge.cdn.example.").unwrap()
+        );
+        assert_eq!(result.rdatas().count(), 2);
+        assert_eq!(result.class, ResponseClass::Positive);
+        assert_eq!(result.validation, ValidationStatus::Indeterminate);
+
+        let result = LookupResult::from_message(&first, LookupSource::Cache).unwrap();
+        assert!(result.is_empty());
+        assert_eq!(result.cname_chain.len(), 2);
+        assert_eq!(result.validation, ValidationStatus::Secure);
+        assert_eq!(
+            result.to_string().lines().next().unwrap(),
+            ";; www.example.com. A NOERROR Secure Cache"
+        );
+
+        let looped = response(
+            "a.example.",
+            &[
+                "a.example. 300 IN CNAME b.example.",
+                "b.example. 300 IN CNAME a.example.",
+            ],
+            false,
+        );
+        assert!(LookupResult::from_message(&looped, LookupSource::Upstream).is_err());
+    }
+
+    fn error_of(result: Result<LookupResult>) -> &'static str {
+        crate::error::error_code(&result.unwrap_err())
+    }
+
+    #[test]
+    fn test_cname_chain_guards() {
+        let self_loop = response("a.example.", &["a.example. 300 IN CNAME A.example."], false);
+        assert_eq!(
+            error_of(LookupResult::from_message(
+                &self_loop,
+                LookupSource::Upstream
+            )),
+            "CNAME_LOOP"
+        );
+
+        let inner_loop = response(
+            "a.example.",
+            &[
+                "a.example. 300 IN CNAME b.example.",
+                "b.example. 300 IN CNAME c.example.",
+                "c.example. 300 IN CNAME b.example.",
+            ],
+            false,
+        );
+        assert_eq!(
+            error_of(LookupResult::from_message(
+                &inner_loop,
+                LookupSource::Upstream
+            )),
+            "CNAME_LOOP"
+        );
+
+        let chain: Vec<String> = (0..20)
+            .map(|i| format!("n{}.example. 300 IN CNAME n{}.example.", i, i + 1))
+            .collect();
+        let chain: Vec<&str> = chain.iter().map(|rr| rr.as_str()).collect();
+        let long = response("n0.example.", &chain, false);
+        assert_eq!(
+            error_of(LookupResult::from_message(&long, LookupSource::Upstream)),
+            "CNAME_CHAIN_TOO_LONG"
+        );
+        let qname = Name::new("n0.example.").unwrap();
+        let result = LookupResult::from_messages_with_limit(
+            &qname,
+            RRType::A,
+            &[&long],
+            LookupSource::Upstream,
+            20,
+        )
+        .unwrap();
+        assert_eq!(result.canonical_name(), &Name::new("n20.example.").unwrap());
+        assert!(LookupResult::from_messages_with_limit(
+            &qname,
+            RRType::A,
+            &[&long],
+            LookupSource::Upstream,
+            19
+        )
+        .is_err());
+
+        let dname = response(
+            "www.example.com.",
+            &[
+                "example.com. 300 IN DNAME example.net.",
+                "www.example.net. 300 IN A 192.0.2.1",
+            ],
+            false,
+        );
+        let result = LookupResult::from_message(&dname, LookupSource::Upstream).unwrap();
+        assert_eq!(result.cname_chain.len(), 1);
+        assert_eq!(result.rdatas().count(), 1);
+
+        //every hop moves the name one level deeper under the dname owner
+        let expanding = response(
+            "www.example.",
+            &["example. 300 IN DNAME sub.example."],
+            false,
+        );
+        assert_eq!(
+            error_of(LookupResult::from_message(
+                &expanding,
+                LookupSource::Upstream
+            )),
+            "CNAME_CHAIN_TOO_LONG"
+        );
+    }
+}
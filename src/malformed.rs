@@ -0,0 +1,265 @@
+use crate::error::DNSError;
+use crate::util::hex::to_hex;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+const HEADER_LEN: usize = 12;
+const REDACTED_BYTE: u8 = b'x';
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MalformedKind {
+    ShortHeader,
+    Truncated,
+    BadName,
+    BadRdata,
+    BadEdns,
+    Other,
+}
+
+impl MalformedKind {
+    pub fn classify(raw: &[u8], err: &failure::Error) -> Self {
+        if raw.len() < HEADER_LEN {
+            return MalformedKind::ShortHeader;
+        }
+        match err.downcast_ref::<DNSError>() {
+            Some(DNSError::InCompleteWire) => MalformedKind::Truncated,
+            Some(DNSError::TooLongName)
+            | Some(DNSError::TooLongLabel)
+            | Some(DNSError::TooManyLabels)
+            | Some(DNSError::BadCompressPointer)
+            | Some(DNSError::InCompleteName)
+            | Some(DNSError::InvalidLabelCharacter)
+            | Some(DNSError::NoneTerminateLabel)
+            | Some(DNSError::DuplicatePeriod) => MalformedKind::BadName,
+            Some(DNSError::RdataLenIsNotCorrect)
+            | Some(DNSError::UnknownRRType(_))
+            | Some(DNSError::RRTypeIsNotSupport)
+            | Some(DNSError::InvalidIPv4Address)
+            | Some(DNSError::InvalidIPv6Address) => MalformedKind::BadRdata,
+            Some(DNSError::InvalidEdnsOption(_)) => MalformedKind::BadEdns,
+            _ => MalformedKind::Other,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            MalformedKind::ShortHeader => "short-header",
+            MalformedKind::Truncated => "truncated",
+            MalformedKind::BadName => "bad-name",
+            MalformedKind::BadRdata => "bad-rdata",
+            MalformedKind::BadEdns => "bad-edns",
+            MalformedKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for MalformedKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+//overwrite the label bytes of the question name but keep the length
+//octets, so the structure of the packet can still be diagnosed
+pub fn redact_question_name(raw: &mut [u8]) {
+    if raw.len() <= HEADER_LEN || (raw[4] == 0 && raw[5] == 0) {
+        return;
+    }
+
+    let mut pos = HEADER_LEN;
+    while pos < raw.len() {
+        let len = raw[pos] as usize;
+        if len == 0 || len & 0xc0 != 0 {
+            break;
+        }
+        let end = (pos + 1 + len).min(raw.len());
+        raw[pos + 1..end]
+            .iter_mut()
+            .for_each(|b| *b = REDACTED_BYTE);
+        pos = end;
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MalformedEvent {
+    pub kind: MalformedKind,
+    pub error: String,
+    pub peer: Option<SocketAddr>,
+    pub len: usize,
+    pub bytes: Vec<u8>,
+    //events dropped by the rate limit since the previous one
+    pub suppressed: u64,
+}
+
+impl MalformedEvent {
+    pub fn new(
+        raw: &[u8],
+        err: &failure::Error,
+        peer: Option<SocketAddr>,
+        config: &MalformedConfig,
+    ) -> Self {
+        let mut bytes = raw[..raw.len().min(config.max_bytes)].to_vec();
+        if config.redact_qname {
+            redact_question_name(&mut bytes);
+        }
+        MalformedEvent {
+            kind: MalformedKind::classify(raw, err),
+            error: err.to_string(),
+            peer,
+            len: raw.len(),
+            bytes,
+            suppressed: 0,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.bytes.len() == self.len
+    }
+
+    pub fn to_string(&self) -> String {
+        format!(
+            "malformed {} from {}: {}, len {}, suppressed {}, data {}{}",
+            self.kind,
+            self.peer
+                .map_or("unknown".to_string(), |peer| peer.to_string()),
+            self.error,
+            self.len,
+            self.suppressed,
+            to_hex(&self.bytes),
+            if self.is_complete() { "" } else { "..." },
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MalformedConfig {
+    pub redact_qname: bool,
+    pub max_bytes: usize,
+    pub max_events: u32,
+    pub window: Duration,
+}
+
+impl Default for MalformedConfig {
+    fn default() -> Self {
+        MalformedConfig {
+            redact_qname: true,
+            max_bytes: 512,
+            max_events: 10,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MalformedLogger {
+    config: MalformedConfig,
+    window_start: Option<Instant>,
+    logged: u32,
+    suppressed: u64,
+}
+
+impl MalformedLogger {
+    pub fn new(config: MalformedConfig) -> Self {
+        MalformedLogger {
+            config,
+            window_start: None,
+            logged: 0,
+            suppressed: 0,
+        }
+    }
+
+    pub fn config(&self) -> &MalformedConfig {
+        &self.config
+    }
+
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    pub fn record(
+        &mut self,
+        raw: &[u8],
+        err: &failure::Error,
+        peer: Option<SocketAddr>,
+    ) -> Option<MalformedEvent> {
+        self.record_at(raw, err, peer, Instant::now())
+    }
+
+    pub fn record_at(
+        &mut self,
+        raw: &[u8],
+        err: &failure::Error,
+        peer: Option<SocketAddr>,
+        now: Instant,
+    ) -> Option<MalformedEvent> {
+        match self.window_start {
+            Some(start) if now.saturating_duration_since(start) < self.config.window => {}
+            _ => {
+                self.window_start = Some(now);
+                self.logged = 0;
+            }
+        }
+
+        if self.logged >= self.config.max_events {
+            self.suppressed += 1;
+            return None;
+        }
+
+        self.logged += 1;
+        let mut event = MalformedEvent::new(raw, err, peer, &self.config);
+        event.suppressed = self.suppressed;
+        self.suppressed = 0;
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message::Message;
+    use crate::util::hex::from_hex;
+
+    #[test]
+    fn test_malformed_logger() {
+        //query for www.example.com. cut in the middle of the question type
+        let raw = from_hex("04b00100000100000000000003777777076578616d706c6503636f6d0000").unwrap();
+        let err = Message::from_wire(raw.as_slice()).unwrap_err();
+        let peer = Some("192.0.2.1:5353".parse().unwrap());
+
+        let config = MalformedConfig {
+            max_bytes: 16,
+            max_events: 2,
+            window: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let mut logger = MalformedLogger::new(config);
+        let now = Instant::now();
+        let event = logger.record_at(&raw, &err, peer, now).unwrap();
+        assert_eq!(event.kind, MalformedKind::Truncated);
+        assert_eq!(event.len, raw.len());
+        assert_eq!(to_hex(&event.bytes), "04b00100000100000000000003787878");
+        assert!(!event.is_complete());
+        assert_eq!(
+            event.to_string(),
+            "malformed truncated from 192.0.2.1:5353: wire data is incomplete, len 30, suppressed 0, data 04b00100000100000000000003787878..."
+        );
+
+        assert!(logger.record_at(&raw, &err, None, now).is_some());
+        assert!(logger.record_at(&raw, &err, None, now).is_none());
+        assert!(logger.record_at(&raw, &err, None, now).is_none());
+        assert_eq!(logger.suppressed(), 2);
+
+        let later = now + Duration::from_secs(10);
+        let event = logger.record_at(&raw[..8], &err, None, later).unwrap();
+        assert_eq!(event.kind, MalformedKind::ShortHeader);
+        assert_eq!(event.suppressed, 2);
+        assert_eq!(logger.suppressed(), 0);
+
+        let mut raw = from_hex("04b0010000010000000000000377777707657861").unwrap();
+        let err = Message::from_wire(raw.as_slice()).unwrap_err();
+        assert_eq!(MalformedKind::classify(&raw, &err), MalformedKind::BadName);
+        redact_question_name(&mut raw);
+        assert_eq!(to_hex(&raw), "04b0010000010000000000000378787807787878");
+    }
+}
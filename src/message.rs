@@ -1,12 +1,14 @@
-use crate::edns::Edns;
+use crate::edns::{Edns, EdnsConfig};
 use crate::header::Header;
 use crate::header_flag::HeaderFlag;
 use crate::message_render::MessageRender;
 use crate::name::Name;
 use crate::question::Question;
+use crate::rcode::Rcode;
 use crate::rr_class::RRClass;
 use crate::rr_type::RRType;
 use crate::rrset::RRset;
+use crate::signed_rrset::SignedRRset;
 use crate::util::{InputBuffer, OutputBuffer};
 use failure::Result;
 use rand;
@@ -31,26 +33,69 @@ impl Section {
         })
     }
 
+    //consecutive records of the same rrset are merged into one
+    fn add_rr(rrsets: &mut Vec<RRset>, mut rrset: RRset) {
+        if let Some(last_rrset) = rrsets.last_mut() {
+            if rrset.is_same_rrset(last_rrset)
+                && !rrset.rdatas.is_empty()
+                && !last_rrset.rdatas.is_empty()
+            {
+                last_rrset.rdatas.push(rrset.rdatas.remove(0));
+                return;
+            }
+        }
+        rrsets.push(rrset);
+    }
+
     pub fn from_wire(buf: &mut InputBuffer, rr_count: u16) -> Result<Self> {
         if rr_count == 0 {
             return Ok(Section(None));
         }
 
         let mut rrsets = Vec::with_capacity(rr_count as usize);
-        let mut last_rrset = RRset::from_wire(buf)?;
-        for _ in 1..rr_count {
-            let mut rrset = RRset::from_wire(buf)?;
-            if rrset.is_same_rrset(&last_rrset) {
-                last_rrset.rdatas.push(rrset.rdatas.remove(0));
-            } else {
-                rrsets.push(last_rrset);
-                last_rrset = rrset;
-            }
+        for _ in 0..rr_count {
+            Section::add_rr(&mut rrsets, RRset::from_wire(buf)?);
         }
-        rrsets.push(last_rrset);
         Ok(Section(Some(rrsets)))
     }
 
+    //a record whose rdata doesn't parse is skipped by its rdlength, only a
+    //record whose boundary can't be found ends the parse, the returned flag
+    //tells whether the buffer is still at a record boundary
+    fn from_wire_lenient(
+        buf: &mut InputBuffer,
+        rr_count: u16,
+        section: SectionType,
+        issues: &mut Vec<ParseIssue>,
+    ) -> (Self, bool) {
+        let mut rrsets = Vec::new();
+        let mut synced = true;
+        for index in 0..rr_count as usize {
+            let start = buf.position();
+            match RRset::from_wire(buf) {
+                Ok(rrset) => Section::add_rr(&mut rrsets, rrset),
+                Err(error) => {
+                    buf.set_position(start);
+                    synced = RRset::skip_wire(buf).is_ok();
+                    issues.push(ParseIssue {
+                        section,
+                        index,
+                        error,
+                    });
+                    if !synced {
+                        break;
+                    }
+                }
+            }
+        }
+        let section = if rrsets.is_empty() {
+            Section(None)
+        } else {
+            Section(Some(rrsets))
+        };
+        (section, synced)
+    }
+
     pub fn rend(&self, render: &mut MessageRender) {
         if let Some(rrsets) = self.0.as_ref() {
             rrsets.iter().for_each(|rrset| rrset.rend(render));
@@ -73,10 +118,21 @@ impl Section {
     }
 }
 
+//a record that lenient parsing had to leave out
+#[derive(Debug)]
+pub struct ParseIssue {
+    pub section: SectionType,
+    pub index: usize,
+    pub error: failure::Error,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Message {
     pub header: Header,
     pub question: Option<Question>,
+    //questions after the first, only mdns and odd traffic carry them, go
+    //through questions() and add_question()
+    pub(crate) extra_questions: Vec<Question>,
     pub sections: [Section; 3],
     pub edns: Option<Edns>,
 }
@@ -93,6 +149,7 @@ impl Message {
                 typ: qtype,
                 class: RRClass::IN,
             }),
+            extra_questions: Vec::new(),
             sections: [Section(None), Section(None), Section(None)],
             edns: None,
         }
@@ -101,34 +158,199 @@ impl Message {
     pub fn from_wire(raw: &[u8]) -> Result<Self> {
         let buf = &mut InputBuffer::new(raw);
         let header = Header::from_wire(buf)?;
-        let question = if header.qd_count == 1 {
-            Some(Question::from_wire(buf)?)
-        } else {
-            None
-        };
+        let (question, extra_questions) = Message::questions_from_wire(buf, header.qd_count)?;
 
         let answer = Section::from_wire(buf, header.an_count)?;
         let auth = Section::from_wire(buf, header.ns_count)?;
         let mut additional = Section::from_wire(buf, header.ar_count)?;
 
+        let edns = Message::split_edns(&mut additional);
+        Ok(Message {
+            header,
+            question,
+            extra_questions,
+            sections: [answer, auth, additional],
+            edns,
+        })
+    }
+
+    fn questions_from_wire(
+        buf: &mut InputBuffer,
+        qd_count: u16,
+    ) -> Result<(Option<Question>, Vec<Question>)> {
+        if qd_count == 0 {
+            return Ok((None, Vec::new()));
+        }
+        let question = Question::from_wire(buf)?;
+        let mut extra_questions = Vec::with_capacity(qd_count as usize - 1);
+        for _ in 1..qd_count {
+            extra_questions.push(Question::from_wire(buf)?);
+        }
+        Ok((Some(question), extra_questions))
+    }
+
+    pub fn questions(&self) -> impl Iterator<Item = &Question> {
+        self.question.iter().chain(self.extra_questions.iter())
+    }
+
+    pub fn add_question(&mut self, question: Question) {
+        if self.question.is_none() {
+            self.question = Some(question);
+        } else {
+            self.extra_questions.push(question);
+        }
+        self.recalculate_header();
+    }
+
+    //the opt record is expected last in the additional section
+    fn split_edns(additional: &mut Section) -> Option<Edns> {
+        let rrsets = additional.0.as_mut()?;
         let mut edns = None;
-        if header.ar_count > 0 {
-            let rrsets = additional.0.as_mut().unwrap();
-            if rrsets[rrsets.len() - 1].typ == RRType::OPT {
-                edns = Some(Edns::from_rrset(&rrsets.pop().unwrap()));
+        if rrsets[rrsets.len() - 1].typ == RRType::OPT {
+            edns = Some(Edns::from_rrset(&rrsets.pop().unwrap()));
+        }
+        if rrsets.is_empty() {
+            *additional = Section(None);
+        }
+        edns
+    }
+
+    //only the header and question have to parse. A broken record is left
+    //out and reported, so a valid answer or nxdomain survives garbage in a
+    //later section. The header counts are recalculated to what was kept
+    pub fn from_wire_lenient(raw: &[u8]) -> Result<(Self, Vec<ParseIssue>)> {
+        let buf = &mut InputBuffer::new(raw);
+        let header = Header::from_wire(buf)?;
+        let (question, extra_questions) = Message::questions_from_wire(buf, header.qd_count)?;
+
+        let mut issues = Vec::new();
+        let mut sections = [Section(None), Section(None), Section(None)];
+        let counts = [header.an_count, header.ns_count, header.ar_count];
+        for (i, section_type) in [
+            SectionType::Answer,
+            SectionType::Authority,
+            SectionType::Additional,
+        ]
+        .iter()
+        .enumerate()
+        {
+            let (section, synced) =
+                Section::from_wire_lenient(buf, counts[i], *section_type, &mut issues);
+            sections[i] = section;
+            if !synced {
+                break;
             }
         }
 
-        Ok(Message {
+        let edns = Message::split_edns(&mut sections[2]);
+        let mut message = Message {
             header,
             question,
-            sections: [answer, auth, additional],
+            extra_questions,
+            sections,
             edns,
-        })
+        };
+        if !issues.is_empty() {
+            message.recalculate_header();
+        }
+        Ok((message, issues))
+    }
+
+    //the answer to a packet that can't be handled, built from the header
+    //and question only. Nothing comes back for a packet shorter than a
+    //header or one that is itself a response, to not feed a loop
+    pub fn formerr_for_raw(raw: &[u8]) -> Option<Self> {
+        Message::error_for_raw(raw, Rcode::FormErr)
+    }
+
+    //rcode is used when the header and the one question parse, anything
+    //else is answered with formerr
+    pub fn error_for_raw(raw: &[u8], rcode: Rcode) -> Option<Self> {
+        let buf = &mut InputBuffer::new(raw);
+        let query_header = Header::from_wire(buf).ok()?;
+        if query_header.is_flag_set(HeaderFlag::QueryRespone) {
+            return None;
+        }
+
+        let question = if query_header.qd_count == 1 {
+            Question::from_wire(buf).ok()
+        } else {
+            None
+        };
+        let mut header = Header {
+            id: query_header.id,
+            opcode: query_header.opcode,
+            rcode: if question.is_some() {
+                rcode
+            } else {
+                Rcode::FormErr
+            },
+            ..Default::default()
+        };
+        header.set_flag(HeaderFlag::QueryRespone, true);
+        header.set_flag(
+            HeaderFlag::RecursionDesired,
+            query_header.is_flag_set(HeaderFlag::RecursionDesired),
+        );
+        let mut msg = Message {
+            header,
+            question,
+            extra_questions: Vec::new(),
+            sections: [Section(None), Section(None), Section(None)],
+            edns: None,
+        };
+        msg.recalculate_header();
+        Some(msg)
+    }
+
+    //the query part only, response flags, rcode and records are dropped
+    pub fn clone_for_retry(&self, new_id: u16) -> Self {
+        let mut header = self.header.clone();
+        header.id = new_id;
+        header.rcode = Rcode::NoError;
+        for flag in &[
+            HeaderFlag::QueryRespone,
+            HeaderFlag::AuthAnswer,
+            HeaderFlag::Truncation,
+            HeaderFlag::RecursionAvailable,
+        ] {
+            header.set_flag(*flag, false);
+        }
+        let mut msg = Message {
+            header,
+            question: self.question.clone(),
+            extra_questions: self.extra_questions.clone(),
+            sections: [Section(None), Section(None), Section(None)],
+            edns: self.edns.clone().map(|mut edns| {
+                edns.extened_rcode = 0;
+                edns
+            }),
+        };
+        msg.recalculate_header();
+        msg
+    }
+
+    //setting the do bit on a message without edns adds a default opt record
+    pub fn with_do_bit(mut self, dnssec_aware: bool) -> Self {
+        match self.edns.as_mut() {
+            Some(edns) => edns.dnssec_aware = dnssec_aware,
+            None if dnssec_aware => {
+                self.edns = Some(
+                    EdnsConfig {
+                        dnssec_aware,
+                        ..Default::default()
+                    }
+                    .to_edns(),
+                );
+                self.recalculate_header();
+            }
+            None => {}
+        }
+        self
     }
 
     pub fn recalculate_header(&mut self) {
-        self.header.qd_count = 1;
+        self.header.qd_count = self.questions().count() as u16;
         self.header.an_count = self.sections[0].rr_count() as u16;
         self.header.ns_count = self.sections[1].rr_count() as u16;
         self.header.ar_count = self.sections[2].rr_count() as u16;
@@ -137,7 +359,7 @@ impl Message {
 
     pub fn rend(&self, render: &mut MessageRender) {
         self.header.rend(render);
-        self.question.as_ref().map(|q| q.rend(render));
+        self.questions().for_each(|q| q.rend(render));
         self.sections
             .iter()
             .for_each(|section| section.rend(render));
@@ -148,7 +370,7 @@ impl Message {
 
     pub fn to_wire(&self, buf: &mut OutputBuffer) {
         self.header.to_wire(buf);
-        self.question.as_ref().map(|q| q.to_wire(buf));
+        self.questions().for_each(|q| q.to_wire(buf));
         self.sections
             .iter()
             .for_each(|section| section.to_wire(buf));
@@ -167,9 +389,10 @@ impl Message {
         write!(
             message_str,
             ";; QUESTION SECTION:\n{}\n",
-            self.question
-                .as_ref()
-                .map_or("".to_string(), |q| q.to_string()),
+            self.questions()
+                .map(|q| q.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
         )
         .unwrap();
 
@@ -210,14 +433,45 @@ impl Message {
         self.sections[section as usize].0.as_ref()
     }
 
+    //the section with every rrsig moved next to the rrset it covers
+    pub fn signed_rrsets(&self, section: SectionType) -> Vec<SignedRRset> {
+        self.section(section)
+            .map_or(Vec::new(), |rrsets| SignedRRset::group(rrsets.clone()))
+    }
+
     pub fn take_section(&mut self, section: SectionType) -> Option<Vec<RRset>> {
         self.sections[section as usize].0.take()
     }
+
+    pub fn sanitize(&mut self, bailiwick: &Name) -> Vec<(SectionType, RRset)> {
+        let mut removed = Vec::new();
+        for section in &[
+            SectionType::Answer,
+            SectionType::Authority,
+            SectionType::Additional,
+        ] {
+            if let Some(rrsets) = self.take_section(*section) {
+                let (kept, dropped): (Vec<RRset>, Vec<RRset>) = rrsets
+                    .into_iter()
+                    .partition(|rrset| rrset.name.is_subdomain(bailiwick));
+                removed.extend(dropped.into_iter().map(|rrset| (*section, rrset)));
+                if !kept.is_empty() {
+                    self.sections[*section as usize] = Section(Some(kept));
+                }
+            }
+        }
+
+        if !removed.is_empty() {
+            self.recalculate_header();
+        }
+        removed
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::edns::{EdnsOption, OPTION_TCP_KEEPALIVE};
     use crate::header_flag::HeaderFlag;
     use crate::message_builder::MessageBuilder;
     use crate::name::Name;
@@ -282,4 +536,227 @@ mod test {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn test_message_sanitize() {
+        let mut msg = build_desired_message();
+        {
+            let mut builder = MessageBuilder::new(&mut msg);
+            builder
+                .add_auth(RRset::from_str("com. 3600 IN NS a.gtld-servers.net.").unwrap())
+                .add_additional(RRset::from_str("a.gtld-servers.net. 3600 IN A 1.1.1.1").unwrap())
+                .done();
+        }
+        assert_eq!(msg.header.ns_count, 2);
+        assert_eq!(msg.header.ar_count, 3);
+
+        let removed = msg.sanitize(&Name::new("example.com.").unwrap());
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[0].1.name, Name::new("com.").unwrap());
+        assert_eq!(removed[1].1.name, Name::new("a.gtld-servers.net.").unwrap());
+        assert_eq!(msg.header.an_count, 2);
+        assert_eq!(msg.header.ns_count, 1);
+        assert_eq!(msg.header.ar_count, 2);
+
+        assert!(msg.sanitize(&Name::new("example.com.").unwrap()).is_empty());
+        let removed = msg.sanitize(&Name::new("test.example.com.").unwrap());
+        assert_eq!(removed.len(), 2);
+        assert!(msg.section(SectionType::Authority).is_none());
+        assert!(msg.section(SectionType::Additional).is_none());
+        assert_eq!(msg.header.ar_count, 1);
+    }
+
+    #[test]
+    fn test_message_with_malformed_edns_option() {
+        let mut msg = Message::with_query(Name::new("example.com.").unwrap(), RRType::A);
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        let mut raw = render.take_data();
+        raw[11] = 1;
+        //opt with a one byte tcp keepalive
+        raw.extend_from_slice(&from_hex("0000291000000000000005000b000101").unwrap());
+        let parsed = Message::from_wire(&raw).unwrap();
+        let edns = parsed.edns.unwrap();
+        assert_eq!(edns.udp_size, 4096);
+        assert_eq!(
+            edns.options,
+            Some(vec![EdnsOption::Unknown(OPTION_TCP_KEEPALIVE, vec![1])])
+        );
+        msg.header.ar_count = 1;
+        msg.edns = Some(edns);
+        assert_eq!(parsed.header, msg.header);
+    }
+
+    #[test]
+    fn test_clone_for_retry() {
+        let mut response = build_desired_message();
+        response.header.rcode = Rcode::ServFail;
+        response.header.set_flag(HeaderFlag::CheckDisable, true);
+        let retry = response.clone_for_retry(4321);
+        assert_eq!(retry.header.id, 4321);
+        assert_eq!(retry.header.rcode, Rcode::NoError);
+        assert_eq!(
+            retry.header.setted_flags(),
+            vec![HeaderFlag::RecursionDesired, HeaderFlag::CheckDisable]
+        );
+        assert_eq!(retry.question, response.question);
+        assert!(retry.section(SectionType::Answer).is_none());
+        assert_eq!(
+            (
+                retry.header.qd_count,
+                retry.header.an_count,
+                retry.header.ns_count,
+                retry.header.ar_count
+            ),
+            (1, 0, 0, 1)
+        );
+
+        let probe = retry.with_do_bit(true);
+        assert!(probe.edns.as_ref().unwrap().dnssec_aware);
+        assert_eq!(probe.edns.as_ref().unwrap().udp_size, 4096);
+        let query = Message::with_query(Name::new("example.com.").unwrap(), RRType::A);
+        assert!(query.clone().with_do_bit(false).edns.is_none());
+        let query = query.with_do_bit(true);
+        assert!(query.edns.unwrap().dnssec_aware);
+        assert_eq!(query.header.ar_count, 1);
+    }
+
+    #[test]
+    fn test_message_from_wire_lenient() {
+        let mut msg = Message::with_query(Name::new("nx.example.com.").unwrap(), RRType::A);
+        MessageBuilder::new(&mut msg)
+            .make_response()
+            .rcode(Rcode::NXDomain)
+            .add_auth(
+                RRset::from_str(
+                    "example.com. 300 IN SOA ns.example.com. root.example.com. 1 2 3 4 5",
+                )
+                .unwrap(),
+            )
+            .done();
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        let mut raw = render.take_data();
+        raw[11] = 2;
+        //an a record with a 3 byte address, then a good one
+        raw.extend_from_slice(&from_hex("c00c000100010000012c0003010203").unwrap());
+        raw.extend_from_slice(&from_hex("c00c000100010000012c000401020304").unwrap());
+        assert!(Message::from_wire(&raw).is_err());
+
+        let (lenient, issues) = Message::from_wire_lenient(&raw).unwrap();
+        assert_eq!(lenient.header.rcode, Rcode::NXDomain);
+        assert_eq!(lenient.sections[1], msg.sections[1]);
+        assert_eq!(
+            lenient.section(SectionType::Additional).unwrap()[0],
+            RRset::from_str("nx.example.com. 300 IN A 1.2.3.4").unwrap()
+        );
+        assert_eq!(lenient.header.ar_count, 1);
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(issues[0].section, SectionType::Additional));
+        assert_eq!(issues[0].index, 0);
+
+        //a record cut short leaves nothing to resync on
+        raw.truncate(raw.len() - 3);
+        let (lenient, issues) = Message::from_wire_lenient(&raw).unwrap();
+        assert_eq!(lenient.sections[1], msg.sections[1]);
+        assert!(lenient.section(SectionType::Additional).is_none());
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[1].index, 1);
+
+        let raw = from_hex("04b0850000010002").unwrap();
+        assert!(Message::from_wire_lenient(&raw).is_err());
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        let (lenient, issues) = Message::from_wire_lenient(render.data()).unwrap();
+        assert!(issues.is_empty());
+        assert_eq!(lenient, msg);
+    }
+
+    #[test]
+    fn test_message_question_count() {
+        //a notify with no question
+        let raw = from_hex("04d224000000000000000000").unwrap();
+        let msg = Message::from_wire(&raw).unwrap();
+        assert!(msg.question.is_none());
+        assert_eq!(msg.questions().count(), 0);
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        assert_eq!(render.data(), raw.as_slice());
+        let mut recalculated = msg.clone();
+        recalculated.recalculate_header();
+        assert_eq!(recalculated.header, msg.header);
+
+        //an mdns probe asking two questions, the second with the qu bit
+        let raw = from_hex("000000000002000000000000056c6f63616c00000100010474657374c00c00ff8001")
+            .unwrap();
+        let msg = Message::from_wire(&raw).unwrap();
+        assert_eq!(
+            msg.question.as_ref().unwrap().name,
+            Name::new("local.").unwrap()
+        );
+        let questions: Vec<&Question> = msg.questions().collect();
+        assert_eq!(questions.len(), 2);
+        assert_eq!(questions[1].name, Name::new("test.local.").unwrap());
+        assert_eq!(questions[1].typ, RRType::ANY);
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        assert_eq!(render.data(), raw.as_slice());
+        let mut recalculated = msg.clone();
+        recalculated.recalculate_header();
+        assert_eq!(recalculated.header.qd_count, 2);
+        assert!(Message::from_wire(&raw[..raw.len() - 4]).is_err());
+
+        let mut built = msg.clone();
+        built.question = None;
+        built.extra_questions.clear();
+        for question in msg.questions() {
+            built.add_question(question.clone());
+        }
+        assert_eq!(built, msg);
+    }
+
+    #[test]
+    fn test_formerr_for_raw() {
+        let mut query = Message::with_query(Name::new("example.com.").unwrap(), RRType::A);
+        query.header.id = 1234;
+        let mut render = MessageRender::new();
+        query.rend(&mut render);
+        let raw = render.take_data();
+
+        let response = Message::error_for_raw(&raw, Rcode::ServFail).unwrap();
+        assert_eq!(response.header.rcode, Rcode::ServFail);
+        assert_eq!(response.question, query.question);
+        let response = Message::formerr_for_raw(&raw).unwrap();
+        assert_eq!(response.header.rcode, Rcode::FormErr);
+
+        //claims an answer that isn't there, only the header and question
+        //are looked at
+        let mut broken = raw.clone();
+        broken[7] = 1;
+        let response = Message::error_for_raw(&broken, Rcode::ServFail).unwrap();
+        assert_eq!(response.header.rcode, Rcode::ServFail);
+        let response = Message::formerr_for_raw(&broken).unwrap();
+        assert_eq!(response.header.id, 1234);
+        assert_eq!(response.header.rcode, Rcode::FormErr);
+        assert_eq!(
+            response.header.setted_flags(),
+            vec![HeaderFlag::QueryRespone, HeaderFlag::RecursionDesired]
+        );
+        assert_eq!(response.question, query.question);
+        assert_eq!(response.header.qd_count, 1);
+        assert_eq!(response.header.an_count, 0);
+
+        //garbage after the header
+        let response = Message::error_for_raw(&broken[..16], Rcode::ServFail).unwrap();
+        assert_eq!(response.header.rcode, Rcode::FormErr);
+        assert!(response.question.is_none());
+        assert_eq!(response.header.qd_count, 0);
+        let mut render = MessageRender::new();
+        response.rend(&mut render);
+        assert_eq!(render.len(), 12);
+
+        assert!(Message::formerr_for_raw(&raw[..11]).is_none());
+        broken[2] |= 0x80;
+        assert!(Message::formerr_for_raw(&broken).is_none());
+    }
 }
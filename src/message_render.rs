@@ -343,4 +343,52 @@ mod test {
         msg.rend(&mut render);
         assert_eq!(raw.as_slice(), render.data());
     }
+
+    #[test]
+    fn test_compress_label_suffix() {
+        let mut render = MessageRender::new();
+        for name in &[
+            "a.b.example.com",
+            "b.example.com",
+            "x.example.com",
+            "com",
+            "mail.a.b.example.com",
+        ] {
+            render.write_name(&Name::new(name).unwrap(), true);
+        }
+        //later names point into the middle of earlier ones, and into names
+        //that themselves end with a pointer
+        let raw =
+            from_hex("01610162076578616d706c6503636f6d00c0020178c004c00c046d61696cc000").unwrap();
+        assert_eq!(raw.as_slice(), render.data());
+        render.write_name(&Name::new("y.x.example.com").unwrap(), true);
+        assert_eq!(
+            &render.data()[raw.len()..],
+            from_hex("0179c013").unwrap().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_deterministic_render() {
+        let names: Vec<Name> = (0..200)
+            .map(|i| Name::new(&format!("n{}.example.com", i % 50)).unwrap())
+            .collect();
+        let rend = || {
+            let mut render = MessageRender::new();
+            for name in &names {
+                render.write_name(name, true);
+            }
+            render.take_data()
+        };
+        let data = rend();
+        assert_eq!(data, rend());
+        //a repeated name points at its first occurrence
+        let n0 = Name::new("n0.example.com").unwrap();
+        let mut render = MessageRender::new();
+        for name in &names {
+            render.write_name(name, true);
+        }
+        render.write_name(&n0, true);
+        assert_eq!(&render.data()[data.len()..], &[0xc0, 0]);
+    }
 }
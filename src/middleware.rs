@@ -0,0 +1,229 @@
+use crate::edns::{EdnsConfig, EdnsOption, OPTION_PADDING};
+use crate::header_flag::HeaderFlag;
+use crate::message::{Message, Section};
+use crate::message_render::MessageRender;
+use crate::name::Name;
+
+const MIN_UDP_SIZE: u16 = 512;
+const OPTION_HEADER_LEN: usize = 4;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Context {
+    pub over_tcp: bool,
+    //the payload size the client can receive over udp
+    pub max_udp_size: u16,
+    pub query_has_edns: bool,
+    pub bailiwick: Option<Name>,
+    //set by a stage to stop the chain and send nothing
+    pub drop: bool,
+}
+
+impl Context {
+    pub fn new(query: &Message, over_tcp: bool) -> Self {
+        Context {
+            over_tcp,
+            max_udp_size: query
+                .edns
+                .as_ref()
+                .map_or(MIN_UDP_SIZE, |edns| edns.udp_size.max(MIN_UDP_SIZE)),
+            query_has_edns: query.edns.is_some(),
+            bailiwick: None,
+            drop: false,
+        }
+    }
+}
+
+pub type Stage = Box<dyn Fn(&mut Message, &mut Context) + Send + Sync>;
+
+#[derive(Default)]
+pub struct Middleware {
+    stages: Vec<Stage>,
+}
+
+impl Middleware {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add<F>(&mut self, stage: F) -> &mut Self
+    where
+        F: Fn(&mut Message, &mut Context) + Send + Sync + 'static,
+    {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    //stages run in the order they were added, false means the response
+    //was dropped
+    pub fn apply(&self, response: &mut Message, ctx: &mut Context) -> bool {
+        for stage in &self.stages {
+            if ctx.drop {
+                break;
+            }
+            stage(response, ctx);
+        }
+        !ctx.drop
+    }
+}
+
+pub fn rendered_len(msg: &Message) -> usize {
+    let mut render = MessageRender::new();
+    msg.rend(&mut render);
+    render.len()
+}
+
+//removes records outside the bailiwick of the context
+pub fn sanitize(response: &mut Message, ctx: &mut Context) {
+    if let Some(bailiwick) = ctx.bailiwick.as_ref() {
+        response.sanitize(bailiwick);
+    }
+}
+
+//edns is only added to the response when the query had it
+pub fn extended_error(info_code: u16, text: &str) -> impl Fn(&mut Message, &mut Context) {
+    let text = text.to_string();
+    move |response: &mut Message, ctx: &mut Context| {
+        if response.edns.is_none() {
+            if !ctx.query_has_edns {
+                return;
+            }
+            response.edns = Some(EdnsConfig::default().to_edns());
+        }
+        response
+            .edns
+            .as_mut()
+            .unwrap()
+            .add_option(EdnsOption::ExtendedError(info_code, text.clone()));
+        response.recalculate_header();
+    }
+}
+
+//rfc7830 padding to a multiple of block_size, rfc8467 recommends 468 for
+//responses
+pub fn pad(block_size: usize) -> impl Fn(&mut Message, &mut Context) {
+    move |response: &mut Message, _ctx: &mut Context| {
+        if block_size == 0 {
+            return;
+        }
+        match response.edns.as_mut() {
+            Some(edns) => edns.remove_option(OPTION_PADDING),
+            None => return,
+        }
+        let len = rendered_len(response) + OPTION_HEADER_LEN;
+        let padding = (block_size - len % block_size) % block_size;
+        response
+            .edns
+            .as_mut()
+            .unwrap()
+            .add_option(EdnsOption::Unknown(OPTION_PADDING, vec![0; padding]));
+    }
+}
+
+//responses too large for the client over udp lose all records and get
+//the tc flag, so the client retries over tcp
+pub fn truncate(response: &mut Message, ctx: &mut Context) {
+    if ctx.over_tcp || rendered_len(response) <= ctx.max_udp_size as usize {
+        return;
+    }
+    response.sections = [Section(None), Section(None), Section(None)];
+    if let Some(edns) = response.edns.as_mut() {
+        edns.remove_option(OPTION_PADDING);
+    }
+    response.header.set_flag(HeaderFlag::Truncation, true);
+    response.recalculate_header();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::edns::OPTION_EXTENDED_ERROR;
+    use crate::message::SectionType;
+    use crate::message_builder::MessageBuilder;
+    use crate::rr_type::RRType;
+    use crate::rrset::RRset;
+    use std::str::FromStr;
+
+    fn response(query: &Message, count: usize) -> Message {
+        let mut response = query.clone();
+        {
+            let mut builder = MessageBuilder::new(&mut response);
+            builder.make_response();
+            for i in 0..count {
+                let rr = format!("www.example.com. 300 IN A 192.0.2.{}", i + 1);
+                builder.add_answer(RRset::from_str(&rr).unwrap());
+            }
+            builder
+                .add_additional(RRset::from_str("ns.example.org. 300 IN A 192.0.2.53").unwrap())
+                .done();
+        }
+        response
+    }
+
+    #[test]
+    fn test_middleware() {
+        let mut query = Message::with_query(Name::new("www.example.com.").unwrap(), RRType::A);
+        query.edns = Some(EdnsConfig::default().to_edns());
+
+        let mut middleware = Middleware::new();
+        middleware
+            .add(sanitize)
+            .add(extended_error(18, "prohibited"))
+            .add(pad(128))
+            .add(truncate);
+        assert_eq!(middleware.len(), 4);
+
+        let mut ctx = Context::new(&query, false);
+        ctx.bailiwick = Some(Name::new("example.com.").unwrap());
+        assert_eq!(ctx.max_udp_size, 1232);
+        let mut msg = response(&query, 2);
+        assert!(middleware.apply(&mut msg, &mut ctx));
+        assert!(msg.section(SectionType::Additional).is_none());
+        assert_eq!(msg.header.an_count, 2);
+        let edns = msg.edns.as_ref().unwrap();
+        assert_eq!(
+            edns.option(OPTION_EXTENDED_ERROR),
+            Some(&EdnsOption::ExtendedError(18, "prohibited".to_string()))
+        );
+        assert!(edns.option(OPTION_PADDING).is_some());
+        assert_eq!(rendered_len(&msg) % 128, 0);
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        assert_eq!(Message::from_wire(render.data()).unwrap(), msg);
+
+        let mut ctx = Context::new(&query, false);
+        ctx.max_udp_size = 512;
+        let mut msg = response(&query, 40);
+        assert!(middleware.apply(&mut msg, &mut ctx));
+        assert!(msg.header.is_flag_set(HeaderFlag::Truncation));
+        assert_eq!(msg.header.an_count, 0);
+        assert!(rendered_len(&msg) <= 512);
+
+        let mut ctx = Context::new(&query, true);
+        let mut msg = response(&query, 100);
+        assert!(middleware.apply(&mut msg, &mut ctx));
+        assert_eq!(msg.header.an_count, 100);
+
+        let mut middleware = Middleware::new();
+        middleware
+            .add(|_: &mut Message, ctx: &mut Context| ctx.drop = true)
+            .add(truncate);
+        let mut ctx = Context::new(&query, false);
+        assert!(!middleware.apply(&mut msg, &mut ctx));
+        assert_eq!(msg.header.an_count, 100);
+
+        let query = Message::with_query(Name::new("www.example.com.").unwrap(), RRType::A);
+        let mut msg = response(&query, 1);
+        let mut ctx = Context::new(&query, false);
+        extended_error(18, "prohibited")(&mut msg, &mut ctx);
+        pad(128)(&mut msg, &mut ctx);
+        assert!(msg.edns.is_none());
+    }
+}
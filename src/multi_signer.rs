@@ -0,0 +1,215 @@
+use crate::error::DNSError;
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::rdata_dnskey::DNSKEY;
+use crate::rr_type::RRType;
+use crate::rrset::{RRTtl, RRset};
+use crate::signed_rrset::SignedRRset;
+use failure::Result;
+
+//rfc8901 model 2, every provider signs with its own keys and publishes the
+//zsks of the others so resolvers can validate whichever provider answers
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SignatureProblem {
+    Unsigned,
+    WrongSigner,
+    OutsideValidity,
+    //no key in the combined set has the tag and algorithm
+    UnknownKey,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SignatureIssue {
+    pub name: Name,
+    pub typ: RRType,
+    pub key_tag: Option<u16>,
+    pub problem: SignatureProblem,
+}
+
+fn invalid(reason: String) -> failure::Error {
+    DNSError::InvalidMultiSigner(reason).into()
+}
+
+pub fn dnskeys(rrset: &RRset) -> Vec<DNSKEY> {
+    rrset
+        .rdatas
+        .iter()
+        .filter_map(|rdata| match rdata {
+            RData::DNSKEY(dnskey) => Some(dnskey.as_ref().clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn check_key_sets(sets: &[&RRset]) -> Result<()> {
+    let first = sets
+        .first()
+        .ok_or_else(|| invalid("no key set".to_string()))?;
+    for set in sets {
+        if set.typ != RRType::DNSKEY {
+            return Err(invalid(format!("{} isn't a DNSKEY rrset", set.typ)));
+        }
+        if set.name != first.name || set.class != first.class {
+            return Err(invalid(format!("{} isn't the same zone", set.name)));
+        }
+    }
+    Ok(())
+}
+
+//the union of the providers' keys with duplicates removed, the ttl is the
+//lowest one so no provider's keys are cached longer than it asked for
+pub fn merge_dnskeys(sets: &[RRset]) -> Result<RRset> {
+    check_key_sets(&sets.iter().collect::<Vec<&RRset>>())?;
+    let mut merged = sets[0].clone();
+    for set in &sets[1..] {
+        merged.ttl = RRTtl(merged.ttl.0.min(set.ttl.0));
+        merged.rdatas.extend(set.rdatas.iter().cloned());
+    }
+    merged.sort_rdatas();
+    Ok(merged)
+}
+
+//the provider's own key set plus the zsks of the other providers, their
+//ksks stay with them since only the parent's ds points to those
+pub fn import_zsks(own: &RRset, others: &[RRset]) -> Result<RRset> {
+    let mut sets = vec![own];
+    sets.extend(others.iter());
+    check_key_sets(&sets)?;
+    let mut imported = own.clone();
+    for other in others {
+        imported.rdatas.extend(
+            dnskeys(other)
+                .into_iter()
+                .filter(|key| key.is_zsk() && !key.is_revoked())
+                .map(|key| RData::DNSKEY(Box::new(key))),
+        );
+    }
+    imported.sort_rdatas();
+    Ok(imported)
+}
+
+//every rrset a provider serves has to carry a signature a resolver can
+//match to the combined key set. Signature bytes aren't verified, a key
+//with the right tag and algorithm has to exist
+pub fn check_signatures(combined: &RRset, signed: &[SignedRRset], now: u32) -> Vec<SignatureIssue> {
+    let keys = dnskeys(combined);
+    let mut issues = Vec::new();
+    for signed in signed {
+        let issue = |key_tag: Option<u16>, problem: SignatureProblem| SignatureIssue {
+            name: signed.rrset.name.clone(),
+            typ: signed.rrset.typ,
+            key_tag,
+            problem,
+        };
+        if !signed.is_signed() {
+            issues.push(issue(None, SignatureProblem::Unsigned));
+            continue;
+        }
+        for rrsig in &signed.signatures {
+            let problem = if rrsig.signer != combined.name {
+                SignatureProblem::WrongSigner
+            } else if !rrsig.is_valid_at(now) {
+                SignatureProblem::OutsideValidity
+            } else if rrsig.candidate_keys(&keys).is_empty() {
+                SignatureProblem::UnknownKey
+            } else {
+                continue;
+            };
+            issues.push(issue(Some(rrsig.key_tag), problem));
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn key_set(ttl: u32, keys: &[&str]) -> RRset {
+        let mut rrset =
+            RRset::from_str(&format!("example.com. {} IN DNSKEY {}", ttl, keys[0])).unwrap();
+        for key in &keys[1..] {
+            rrset
+                .rdatas
+                .push(RData::from_str(RRType::DNSKEY, key).unwrap());
+        }
+        rrset
+    }
+
+    #[test]
+    fn test_merge_dnskeys() {
+        let a = key_set(3600, &["257 3 13 AQID", "256 3 13 BAUG"]);
+        let b = key_set(600, &["257 3 13 BwgJ", "256 3 13 CgsM", "256 3 13 BAUG"]);
+        let merged = merge_dnskeys(&[a.clone(), b.clone()]).unwrap();
+        assert_eq!(merged.ttl.0, 600);
+        assert_eq!(merged.rdatas.len(), 4);
+
+        let imported = import_zsks(&a, &[b.clone()]).unwrap();
+        let keys = dnskeys(&imported);
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys.iter().filter(|key| key.is_ksk()).count(), 1);
+
+        let other = RRset::from_str("example.org. 3600 IN DNSKEY 256 3 13 AQID").unwrap();
+        assert!(merge_dnskeys(&[a.clone(), other]).is_err());
+        let ds = RRset::from_str("example.com. 3600 IN DS 1 13 2 AABB").unwrap();
+        assert!(import_zsks(&a, &[ds]).is_err());
+        assert!(merge_dnskeys(&[]).is_err());
+    }
+
+    #[test]
+    fn test_check_signatures() {
+        let a = key_set(3600, &["257 3 13 AQID", "256 3 13 BAUG"]);
+        let b = key_set(3600, &["257 3 13 BwgJ", "256 3 13 CgsM"]);
+        let combined = merge_dnskeys(&[a, b.clone()]).unwrap();
+        let zsk = dnskeys(&b)[1].key_tag();
+
+        let rrsig = |signer: &str, key_tag: u16| {
+            let rdata = RData::from_str(
+                RRType::RRSIG,
+                &format!(
+                    "A 13 3 3600 20300101000000 20200101000000 {} {} AQID",
+                    key_tag, signer
+                ),
+            )
+            .unwrap();
+            match rdata {
+                RData::RRSIG(rrsig) => *rrsig,
+                _ => unreachable!(),
+            }
+        };
+        let signed = |signatures| SignedRRset {
+            rrset: RRset::from_str("www.example.com. 3600 IN A 192.0.2.1").unwrap(),
+            signatures,
+        };
+
+        let now = 1_700_000_000;
+        let good = signed(vec![rrsig("example.com.", zsk)]);
+        assert!(check_signatures(&combined, &[good], now).is_empty());
+
+        let issues = check_signatures(
+            &combined,
+            &[
+                signed(Vec::new()),
+                signed(vec![rrsig("example.org.", zsk)]),
+                signed(vec![rrsig("example.com.", zsk.wrapping_add(1))]),
+            ],
+            now,
+        );
+        let problems: Vec<SignatureProblem> = issues.iter().map(|issue| issue.problem).collect();
+        assert_eq!(
+            problems,
+            vec![
+                SignatureProblem::Unsigned,
+                SignatureProblem::WrongSigner,
+                SignatureProblem::UnknownKey
+            ]
+        );
+        assert_eq!(issues[2].key_tag, Some(zsk.wrapping_add(1)));
+
+        let good = signed(vec![rrsig("example.com.", zsk)]);
+        let issues = check_signatures(&combined, &[good], 2_000_000_000);
+        assert_eq!(issues[0].problem, SignatureProblem::OutsideValidity);
+    }
+}
@@ -2,6 +2,7 @@ use crate::error::DNSError;
 use crate::label_sequence::LabelSequence;
 use crate::label_slice::LabelSlice;
 use crate::message_render::MessageRender;
+use crate::presentation;
 use crate::util::{InputBuffer, OutputBuffer};
 use failure::{self, Result};
 use std::{
@@ -46,16 +47,6 @@ pub struct NameComparisonResult {
     pub relation: NameRelation,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-enum FtStat {
-    Init,
-    Start,
-    Ordinary,
-    Initialescape,
-    Escape,
-    Escdecimal,
-}
-
 #[derive(Eq, PartialEq)]
 enum FwStat {
     Start,
@@ -63,33 +54,6 @@ enum FwStat {
     NewCurrent,
 }
 
-#[inline]
-fn is_digit(c: char) -> bool {
-    c >= '0' && c <= '9'
-}
-
-static DIGITAL_VALUES: &'static [i8] = &[
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, // 16
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, // 32
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, // 48
-    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, -1, -1, -1, -1, -1, -1, // 64
-    -1, 10, 11, 12, 13, 14, 15, -1, -1, -1, -1, -1, -1, -1, -1, -1, // 80
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, // 96
-    -1, 10, 11, 12, 13, 14, 15, -1, -1, -1, -1, -1, -1, -1, -1, -1, // 112
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, // 128
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-    -1, -1, -1, -1, -1, -1, -1, -1,
-];
-
-#[inline]
-fn digitvalue(c: usize) -> i8 {
-    DIGITAL_VALUES[c]
-}
-
 pub static MAP_TO_LOWER: &'static [u8] = &[
     0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
     0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
@@ -114,143 +78,100 @@ pub fn lower_case(c: usize) -> u8 {
     MAP_TO_LOWER[c]
 }
 
+//the layout shared by Name and LabelSequence, only the last label may be
+//empty and it's the root label of an absolute name
+pub(crate) fn check_labels(data: &[u8], offsets: &[u8]) -> Result<()> {
+    if offsets.is_empty() {
+        return Err(DNSError::InvalidLabelIndex.into());
+    } else if offsets.len() > MAX_LABEL_COUNT as usize {
+        return Err(DNSError::TooManyLabels.into());
+    } else if data.len() > MAX_WIRE_LEN {
+        return Err(DNSError::TooLongName.into());
+    }
+
+    let mut pos = 0;
+    for (i, &offset) in offsets.iter().enumerate() {
+        if offset as usize != pos || pos >= data.len() {
+            return Err(DNSError::InvalidLabelIndex.into());
+        }
+        let len = data[pos];
+        if len > MAX_LABEL_LEN {
+            return Err(DNSError::TooLongLabel.into());
+        } else if len == 0 && i + 1 != offsets.len() {
+            return Err(DNSError::DuplicatePeriod.into());
+        }
+        pos += len as usize + 1;
+    }
+    if pos != data.len() {
+        return Err(DNSError::InvalidLabelIndex.into());
+    }
+    Ok(())
+}
+
 pub fn string_parse(
     name_raw: &[u8],
     start_pos: usize,
     end: usize,
     as_absolute: bool,
 ) -> Result<(Vec<u8>, Vec<u8>)> {
-    let mut start = start_pos;
-    let mut data: Vec<u8> = Vec::with_capacity(end - start + 1);
-    let mut offsets: Vec<u8> = Vec::new();
-    let mut count = 0;
-    let mut digits = 0;
-    let mut value: i32 = 0;
-    let mut done = false;
-    let mut is_root = false;
-    let mut state = FtStat::Init;
-    let mut next_u8 = true;
-    let mut c: char = 0 as char;
-
-    offsets.push(0);
-    loop {
-        if next_u8 {
-            if data.len() >= MAX_WIRE_LEN || start == end || done {
-                break;
+    let raw = &name_raw[start_pos..end];
+    if raw.is_empty() {
+        return Err(DNSError::InCompleteName.into());
+    }
+    if raw == b"." || raw == b"@" {
+        return Ok((vec![0], vec![0]));
+    }
+    if raw[0] == b'.' {
+        return Err(DNSError::NoneTerminateLabel.into());
+    }
+
+    let mut data: Vec<u8> = Vec::with_capacity(raw.len() + 2);
+    let mut offsets: Vec<u8> = vec![0];
+    let mut count: u8 = 0;
+    let mut pos = 0;
+    data.push(0);
+    while pos < raw.len() {
+        if raw[pos] == b'.' {
+            if count == 0 {
+                return Err(DNSError::DuplicatePeriod.into());
             }
-            c = name_raw[start] as char;
-            start += 1;
-        }
-
-        if state == FtStat::Init {
-            if c == '.' {
-                if start != end {
-                    return Err(DNSError::NoneTerminateLabel.into());
-                } else {
-                    is_root = true;
-                }
-            } else if c == '@' && start == end {
-                is_root = true;
+            data[offsets[offsets.len() - 1] as usize] = count;
+            if data.len() >= MAX_WIRE_LEN {
+                return Err(DNSError::TooLongName.into());
             }
-
-            if is_root {
-                data.push(0);
-                done = true;
-                break;
-            }
-            state = FtStat::Start;
-            next_u8 = false;
-        } else if state == FtStat::Start {
+            offsets.push(data.len() as u8);
             data.push(0);
             count = 0;
-            if c == '\\' {
-                state = FtStat::Initialescape;
-                break;
-            }
-            state = FtStat::Ordinary;
-            next_u8 = false;
-        } else if state == FtStat::Ordinary {
-            if c == '.' {
-                if count == 0 {
-                    return Err(DNSError::DuplicatePeriod.into());
-                }
-                data[offsets[offsets.len() - 1] as usize] = count;
-                offsets.push(data.len() as u8);
-                if start == end {
-                    data.push(0);
-                    done = true;
-                }
-                state = FtStat::Start;
-            } else if c == '\\' {
-                state = FtStat::Escape;
-            } else {
-                count += 1;
-                if count > MAX_LABEL_LEN {
-                    return Err(DNSError::TooLongLabel.into());
-                }
-                data.push(c as u8);
-            }
-            next_u8 = true;
-        } else if state == FtStat::Initialescape {
-            if c == '[' {
-                return Err(DNSError::InvalidLabelCharacter.into());
-            }
-            state = FtStat::Escape;
-            next_u8 = false;
-        } else if state == FtStat::Escape {
-            if !is_digit(c) {
-                count += 1;
-                if count > MAX_LABEL_LEN {
-                    return Err(DNSError::TooLongLabel.into());
-                }
-                data.push(c as u8);
-                state = FtStat::Ordinary;
-                break;
-            }
-            digits = 0;
-            value = 0;
-            state = FtStat::Escdecimal;
-            next_u8 = false;
-        } else if state == FtStat::Escdecimal {
-            if !is_digit(c) {
-                return Err(DNSError::InvalidDecimalFormat.into());
-            }
-            value *= 10;
-            value += i32::from(digitvalue(c as usize));
-            digits += 1;
-            if digits == 3 {
-                if value > 255 {
-                    return Err(DNSError::InvalidDecimalFormat.into());
-                }
-                count += 1;
-                if count > MAX_LABEL_LEN {
-                    return Err(DNSError::TooLongLabel.into());
-                }
-                data.push(c as u8);
-                state = FtStat::Ordinary;
-            }
-            next_u8 = true;
-        } else {
-            panic!("impossible state");
+            pos += 1;
+            continue;
         }
-    }
 
-    if !done {
-        if data.len() == MAX_WIRE_LEN {
-            return Err(DNSError::TooLongName.into());
+        //bitstring labels of rfc2673 are not supported
+        if count == 0 && raw[pos] == b'\\' && raw.get(pos + 1) == Some(&b'[') {
+            return Err(DNSError::InvalidLabelCharacter.into());
         }
-        assert!(start == end);
-        if state != FtStat::Ordinary {
-            return Err(DNSError::InCompleteName.into());
-        } else {
-            assert!(count != 0);
-            data[offsets[offsets.len() - 1] as usize] = count as u8;
-            if as_absolute {
-                offsets.push(data.len() as u8);
-                data.push(0);
+        let (c, next) = presentation::unescape_next(raw, pos)?;
+        count += 1;
+        if count > MAX_LABEL_LEN {
+            return Err(DNSError::TooLongLabel.into());
+        }
+        data.push(c);
+        pos = next;
+    }
+
+    if count != 0 {
+        data[offsets[offsets.len() - 1] as usize] = count;
+        if as_absolute {
+            if data.len() >= MAX_WIRE_LEN {
+                return Err(DNSError::TooLongName.into());
             }
+            offsets.push(data.len() as u8);
+            data.push(0);
         }
     }
+    if data.len() > MAX_WIRE_LEN {
+        return Err(DNSError::TooLongName.into());
+    }
 
     Ok((data, offsets))
 }
@@ -264,7 +185,18 @@ impl Name {
         }
     }
 
-    pub(crate) fn from_raw(raw: Vec<u8>, offsets: Vec<u8>) -> Self {
+    //raw is the uncompressed wire form and offsets the position of each
+    //length octet in it, root label included
+    pub fn from_raw(raw: Vec<u8>, offsets: Vec<u8>) -> Result<Self> {
+        check_labels(&raw, &offsets)?;
+        if raw.last() != Some(&0) {
+            return Err(DNSError::RelativeName.into());
+        }
+        Ok(Name { raw, offsets })
+    }
+
+    pub(crate) fn from_raw_unchecked(raw: Vec<u8>, offsets: Vec<u8>) -> Self {
+        debug_assert!(check_labels(&raw, &offsets).is_ok() && raw.last() == Some(&0));
         Name { raw, offsets }
     }
 
@@ -291,10 +223,15 @@ impl Name {
 
             if state == FwStat::Start {
                 if c <= MAX_LABEL_LEN {
-                    offsets.push(nused as u8);
+                    //checked per label, so names assembled from pointers
+                    //can't exceed the limit either. A label other than the
+                    //root takes at least two bytes, so the wire length
+                    //already caps a name at 127 labels plus the root
+                    debug_assert!(offsets.len() < MAX_LABEL_COUNT as usize);
                     if nused + (c as usize) + 1 > MAX_WIRE_LEN {
                         return Err(DNSError::TooLongName.into());
                     }
+                    offsets.push(nused as u8);
 
                     nused += (c as usize) + 1;
                     data.push(c);
@@ -386,6 +323,10 @@ impl Name {
         LabelSequence::new(self.raw, self.offsets)
     }
 
+    pub fn as_slice(&self) -> LabelSlice<'_> {
+        LabelSlice::from_name(self)
+    }
+
     pub fn get_relation(&self, other: &Name) -> NameComparisonResult {
         LabelSlice::from_name(self).compare(&LabelSlice::from_name(other), false)
     }
@@ -711,6 +652,7 @@ impl Hash for Name {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::error::error_code;
 
     #[test]
     fn test_name_concat() {
@@ -898,4 +840,90 @@ mod test {
         let name = Name::new("a.a.a").unwrap();
         assert!(name.parent(3).unwrap().is_root());
     }
+
+    #[test]
+    fn test_name_escape() {
+        let cases = vec![
+            ("a\\.b.example.", vec![3, b'a', b'.', b'b']),
+            ("\\065bc.example.", vec![3, b'A', b'b', b'c']),
+            ("\\\\.example.", vec![1, b'\\']),
+            ("a\\032b.example.", vec![3, b'a', b' ', b'b']),
+            ("\\000\\255.example.", vec![2, 0, 255]),
+            ("\\@\\$\\;.example.", vec![3, b'@', b'$', b';']),
+        ];
+        for (s, first_label) in cases {
+            let name = Name::new(s).unwrap();
+            assert_eq!(name.label_count(), 3);
+            assert_eq!(
+                &name.raw_data()[..first_label.len()],
+                first_label.as_slice()
+            );
+            assert_eq!(
+                Name::new(&name.to_string()).unwrap().raw_data(),
+                name.raw_data()
+            );
+        }
+        assert_eq!(Name::new("\\065\\066.").unwrap().to_string(), "AB.");
+        assert_eq!(
+            Name::new("a\\.b\\032c.").unwrap().to_string(),
+            "a\\.b\\032c."
+        );
+
+        for c in 0..=255u8 {
+            let mut raw = vec![2, c, b'x', 0];
+            let name = Name::from_wire(&mut InputBuffer::new(raw.as_slice())).unwrap();
+            let parsed = Name::new(&name.to_string()).unwrap();
+            raw.truncate(4);
+            assert_eq!(parsed.raw_data(), raw.as_slice());
+        }
+
+        assert!(Name::new("\\256.example.").is_err());
+        assert!(Name::new("\\25.example.").is_err());
+        assert!(Name::new("a\\").is_err());
+        assert!(Name::new("\\[x].example.").is_err());
+    }
+
+    //each segment is a one byte label followed by a pointer to the previous
+    //segment, so the name at the last segment has one label per segment
+    fn pointer_chain(segments: usize) -> (Vec<u8>, usize) {
+        let mut wire = vec![1, b'a', 0];
+        let mut prev = 0;
+        for _ in 1..segments {
+            let pos = wire.len();
+            wire.extend_from_slice(&[1, b'a', 0xc0 | (prev >> 8) as u8, prev as u8]);
+            prev = pos;
+        }
+        (wire, prev)
+    }
+
+    fn name_at(wire: &[u8], pos: usize) -> Result<Name> {
+        let mut buf = InputBuffer::new(wire);
+        buf.set_position(pos);
+        Name::from_wire(&mut buf)
+    }
+
+    #[test]
+    fn test_from_wire_limits() {
+        let (wire, pos) = pointer_chain(127);
+        let name = name_at(&wire, pos).unwrap();
+        assert_eq!(name.len(), MAX_WIRE_LEN);
+        assert_eq!(name.label_count(), MAX_LABEL_COUNT as usize);
+
+        let (wire, pos) = pointer_chain(128);
+        assert_eq!(
+            error_code(&name_at(&wire, pos).unwrap_err()),
+            "NAME_TOO_LONG"
+        );
+
+        let mut wire = vec![63];
+        wire.extend_from_slice(&[b'a'; 63]);
+        for _ in 0..3 {
+            wire.push(63);
+            wire.extend_from_slice(&[b'b'; 63]);
+        }
+        wire.push(0);
+        assert_eq!(error_code(&name_at(&wire, 0).unwrap_err()), "NAME_TOO_LONG");
+        let name = name_at(&wire[64..], 0).unwrap();
+        assert_eq!(name.len(), 193);
+    }
 }
@@ -0,0 +1,155 @@
+use crate::label_slice::LabelSlice;
+use crate::name::{lower_case, Name};
+use failure::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MatchKind {
+    Exact,
+    Suffix,
+    Wildcard,
+}
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    name: Name,
+    kind: MatchKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NameMatcher {
+    patterns: HashMap<u64, Vec<Pattern>>,
+}
+
+fn slice_hash(slice: &LabelSlice) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for c in slice.data() {
+        hasher.write_u8(lower_case(*c as usize));
+    }
+    hasher.finish()
+}
+
+impl NameMatcher {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add(&mut self, name: Name, kind: MatchKind) -> &mut Self {
+        let hash = slice_hash(&LabelSlice::from_name(&name));
+        let patterns = self.patterns.entry(hash).or_default();
+        if !patterns.iter().any(|p| p.kind == kind && p.name == name) {
+            patterns.push(Pattern { name, kind });
+        }
+        self
+    }
+
+    pub fn add_exact(&mut self, name: Name) -> &mut Self {
+        self.add(name, MatchKind::Exact)
+    }
+
+    pub fn add_suffix(&mut self, name: Name) -> &mut Self {
+        self.add(name, MatchKind::Suffix)
+    }
+
+    pub fn add_wildcard(&mut self, name: Name) -> &mut Self {
+        self.add(name, MatchKind::Wildcard)
+    }
+
+    //"*.example.com" matches names below example.com, anything else is an
+    //exact match
+    pub fn add_pattern(&mut self, pattern: &str) -> Result<&mut Self> {
+        let name = Name::new(pattern)?;
+        if name.is_wildcard() {
+            Ok(self.add_wildcard(name.strip_left(1)))
+        } else {
+            Ok(self.add_exact(name))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.values().map(|patterns| patterns.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn find(&self, name: &Name) -> Option<(&Name, MatchKind)> {
+        let mut slice = LabelSlice::from_name(name);
+        let mut depth = 0;
+        loop {
+            if let Some(patterns) = self.patterns.get(&slice_hash(&slice)) {
+                for pattern in patterns {
+                    let matched = match pattern.kind {
+                        MatchKind::Exact => depth == 0,
+                        MatchKind::Suffix => true,
+                        MatchKind::Wildcard => depth > 0,
+                    };
+                    if matched && slice.equals(&LabelSlice::from_name(&pattern.name), false) {
+                        return Some((&pattern.name, pattern.kind));
+                    }
+                }
+            }
+
+            if slice.label_count() == 1 {
+                return None;
+            }
+            slice.strip_left(1);
+            depth += 1;
+        }
+    }
+
+    pub fn is_match(&self, name: &Name) -> bool {
+        self.find(name).is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_name_matcher() {
+        let mut matcher = NameMatcher::new();
+        matcher
+            .add_pattern("*.internal.example.com")
+            .unwrap()
+            .add_pattern("www.example.com")
+            .unwrap()
+            .add_suffix(Name::new("example.org").unwrap());
+        assert_eq!(matcher.len(), 3);
+
+        let matched = vec![
+            "a.internal.example.com",
+            "a.b.INTERNAL.example.com",
+            "WWW.example.com",
+            "example.org",
+            "a.b.example.org",
+        ];
+        for name in matched {
+            assert!(matcher.is_match(&Name::new(name).unwrap()), name);
+        }
+
+        let not_matched = vec![
+            "internal.example.com",
+            "a.www.example.com",
+            "example.com",
+            "org",
+            "example.net",
+        ];
+        for name in not_matched {
+            assert!(!matcher.is_match(&Name::new(name).unwrap()), name);
+        }
+
+        let (name, kind) = matcher
+            .find(&Name::new("x.internal.example.com").unwrap())
+            .unwrap();
+        assert_eq!(name, &Name::new("internal.example.com").unwrap());
+        assert_eq!(kind, MatchKind::Wildcard);
+
+        matcher.add_suffix(Name::new(".").unwrap());
+        assert!(matcher.is_match(&Name::new("example.com").unwrap()));
+    }
+}
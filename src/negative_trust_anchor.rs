@@ -0,0 +1,153 @@
+use crate::edns::EdnsOption;
+use crate::header_flag::HeaderFlag;
+use crate::lookup_result::{LookupResult, ValidationStatus};
+use crate::message::Message;
+use crate::name::Name;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+//rfc8914 has no dedicated code, "other" with the anchor in the text
+const EDE_OTHER: u16 = 0;
+
+//rfc7646, names at or below an anchor are treated as insecure until the
+//anchor expires
+#[derive(Debug, Clone, Default)]
+pub struct NegativeTrustAnchors {
+    anchors: HashMap<Name, Instant>,
+}
+
+impl NegativeTrustAnchors {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    //an anchor added again for the same name gets the new expiry
+    pub fn add(&mut self, name: Name, expires: Instant) {
+        self.anchors.insert(name, expires);
+    }
+
+    pub fn add_for(&mut self, name: Name, lifetime: Duration, now: Instant) {
+        self.add(name, now + lifetime);
+    }
+
+    pub fn remove(&mut self, name: &Name) -> bool {
+        self.anchors.remove(name).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.anchors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+
+    pub fn expires(&self, name: &Name) -> Option<Instant> {
+        self.anchors.get(name).cloned()
+    }
+
+    //the closest unexpired anchor at or above name
+    pub fn covering(&self, name: &Name, now: Instant) -> Option<&Name> {
+        (0..name.label_count()).find_map(|level| {
+            let ancestor = name.strip_left(level);
+            self.anchors
+                .get_key_value(&ancestor)
+                .filter(|(_, &expires)| now < expires)
+                .map(|(anchor, _)| anchor)
+        })
+    }
+
+    pub fn is_suspended(&self, name: &Name, now: Instant) -> bool {
+        self.covering(name, now).is_some()
+    }
+
+    pub fn purge(&mut self, now: Instant) {
+        self.anchors.retain(|_, expires| now < *expires);
+    }
+
+    //clears the ad flag and, when the response has edns, explains why
+    pub fn annotate(&self, response: &mut Message, now: Instant) -> bool {
+        let anchor = match response
+            .question
+            .as_ref()
+            .and_then(|question| self.covering(&question.name, now))
+        {
+            Some(anchor) => anchor,
+            None => return false,
+        };
+        response.header.set_flag(HeaderFlag::AuthenticData, false);
+        if let Some(edns) = response.edns.as_mut() {
+            edns.add_option(EdnsOption::ExtendedError(
+                EDE_OTHER,
+                format!("negative trust anchor {}", anchor),
+            ));
+        }
+        true
+    }
+
+    pub fn apply(&self, result: &mut LookupResult, now: Instant) -> bool {
+        if self.is_suspended(&result.qname, now) {
+            result.validation = ValidationStatus::Insecure;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::edns::{EdnsConfig, OPTION_EXTENDED_ERROR};
+    use crate::lookup_result::LookupSource;
+    use crate::message_builder::MessageBuilder;
+    use crate::rr_type::RRType;
+
+    #[test]
+    fn test_negative_trust_anchors() {
+        let now = Instant::now();
+        let mut ntas = NegativeTrustAnchors::new();
+        ntas.add_for(
+            Name::new("broken.example.").unwrap(),
+            Duration::from_secs(3600),
+            now,
+        );
+        ntas.add(Name::new("expired.example.").unwrap(), now);
+        assert_eq!(ntas.len(), 2);
+
+        let qname = Name::new("www.Broken.example.").unwrap();
+        assert_eq!(
+            ntas.covering(&qname, now),
+            Some(&Name::new("broken.example.").unwrap())
+        );
+        assert!(!ntas.is_suspended(&Name::new("example.").unwrap(), now));
+        assert!(!ntas.is_suspended(&Name::new("www.expired.example.").unwrap(), now));
+        assert!(!ntas.is_suspended(&qname, now + Duration::from_secs(3600)));
+
+        let mut response = Message::with_query(qname.clone(), RRType::A);
+        MessageBuilder::new(&mut response)
+            .make_response()
+            .set_flag(HeaderFlag::AuthenticData)
+            .edns(EdnsConfig::default().to_edns())
+            .done();
+        let mut result = LookupResult::from_message(&response, LookupSource::Upstream).unwrap();
+        assert_eq!(result.validation, ValidationStatus::Secure);
+        assert!(ntas.apply(&mut result, now));
+        assert_eq!(result.validation, ValidationStatus::Insecure);
+
+        assert!(ntas.annotate(&mut response, now));
+        assert!(!response.header.is_flag_set(HeaderFlag::AuthenticData));
+        assert_eq!(
+            response.edns.unwrap().option(OPTION_EXTENDED_ERROR),
+            Some(&EdnsOption::ExtendedError(
+                0,
+                "negative trust anchor broken.example.".to_string()
+            ))
+        );
+
+        ntas.purge(now);
+        assert_eq!(ntas.len(), 1);
+        assert!(ntas.remove(&Name::new("broken.example.").unwrap()));
+        assert!(ntas.is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::error::DNSError;
+use crate::header_flag::HeaderFlag;
+use crate::message::Message;
+use crate::question::Question;
+use failure::Result;
+use std::collections::{HashMap, VecDeque};
+
+const ID_SPACE: u32 = 65536;
+
+struct Pending<T> {
+    question: Option<Question>,
+    context: T,
+}
+
+pub struct Pipeline<T> {
+    in_flight: HashMap<u16, Pending<T>>,
+    free_ids: VecDeque<u16>,
+    issued: u32,
+    start: u16,
+    step: u16,
+    max_in_flight: usize,
+}
+
+impl<T> Pipeline<T> {
+    pub fn new(max_in_flight: usize) -> Self {
+        //an odd step walks the whole id space in a random looking order
+        //without keeping a shuffled table around
+        Pipeline {
+            in_flight: HashMap::new(),
+            free_ids: VecDeque::new(),
+            issued: 0,
+            start: rand::random::<u16>(),
+            step: rand::random::<u16>() | 1,
+            max_in_flight: max_in_flight.min(ID_SPACE as usize),
+        }
+    }
+
+    fn next_id(&mut self) -> Option<u16> {
+        if self.issued < ID_SPACE {
+            let id = self
+                .start
+                .wrapping_add(self.step.wrapping_mul(self.issued as u16));
+            self.issued += 1;
+            Some(id)
+        } else {
+            self.free_ids.pop_front()
+        }
+    }
+
+    pub fn send(&mut self, query: &mut Message, context: T) -> Result<u16> {
+        if self.is_exhausted() {
+            return Err(DNSError::MessageIdExhausted.into());
+        }
+        let id = self.next_id().ok_or(DNSError::MessageIdExhausted)?;
+        query.header.id = id;
+        self.in_flight.insert(
+            id,
+            Pending {
+                question: query.question.clone(),
+                context,
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn receive(&mut self, response: &Message) -> Option<T> {
+        if !response.header.is_flag_set(HeaderFlag::QueryRespone) {
+            return None;
+        }
+        let id = response.header.id;
+        let matched = match self.in_flight.get(&id) {
+            Some(pending) => response.question.is_none() || response.question == pending.question,
+            None => false,
+        };
+        if matched {
+            self.release(id)
+        } else {
+            None
+        }
+    }
+
+    pub fn cancel(&mut self, id: u16) -> Option<T> {
+        self.release(id)
+    }
+
+    fn release(&mut self, id: u16) -> Option<T> {
+        self.in_flight.remove(&id).map(|pending| {
+            self.free_ids.push_back(id);
+            pending.context
+        })
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.in_flight.len() >= self.max_in_flight
+    }
+
+    pub fn drain(&mut self) -> Vec<(u16, T)> {
+        let ids: Vec<u16> = self.in_flight.keys().cloned().collect();
+        ids.into_iter()
+            .filter_map(|id| self.release(id).map(|context| (id, context)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message_builder::MessageBuilder;
+    use crate::name::Name;
+    use crate::rr_type::RRType;
+    use std::collections::HashSet;
+
+    fn response_for(query: &Message) -> Message {
+        let mut response = query.clone();
+        MessageBuilder::new(&mut response).make_response().done();
+        response
+    }
+
+    #[test]
+    fn test_pipeline_match() {
+        let mut pipeline = Pipeline::new(16);
+        let mut q1 = Message::with_query(Name::new("a.example.com.").unwrap(), RRType::A);
+        let mut q2 = Message::with_query(Name::new("b.example.com.").unwrap(), RRType::AAAA);
+        let id1 = pipeline.send(&mut q1, 1).unwrap();
+        let id2 = pipeline.send(&mut q2, 2).unwrap();
+        assert_ne!(id1, id2);
+        assert_eq!(q1.header.id, id1);
+        assert_eq!(pipeline.in_flight(), 2);
+
+        assert!(pipeline.receive(&q2).is_none());
+        let mut spoofed = response_for(&q2);
+        spoofed.question = q1.question.clone();
+        assert!(pipeline.receive(&spoofed).is_none());
+
+        assert_eq!(pipeline.receive(&response_for(&q2)), Some(2));
+        assert_eq!(pipeline.receive(&response_for(&q2)), None);
+        assert_eq!(pipeline.receive(&response_for(&q1)), Some(1));
+        assert_eq!(pipeline.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_pipeline_exhaustion() {
+        let mut pipeline = Pipeline::new(2);
+        let mut query = Message::with_query(Name::new("example.com.").unwrap(), RRType::A);
+        let id = pipeline.send(&mut query, ()).unwrap();
+        pipeline.send(&mut query, ()).unwrap();
+        assert!(pipeline.is_exhausted());
+        assert!(pipeline.send(&mut query, ()).is_err());
+        assert_eq!(pipeline.cancel(id), Some(()));
+        assert!(pipeline.send(&mut query, ()).is_ok());
+        assert_eq!(pipeline.drain().len(), 2);
+
+        let mut pipeline = Pipeline::new(usize::MAX);
+        let mut ids = HashSet::new();
+        for _ in 0..ID_SPACE {
+            ids.insert(pipeline.send(&mut query, ()).unwrap());
+        }
+        assert_eq!(ids.len(), ID_SPACE as usize);
+        assert!(pipeline.send(&mut query, ()).is_err());
+        pipeline.cancel(query.header.id);
+        assert_eq!(pipeline.send(&mut query, ()).unwrap(), query.header.id);
+    }
+}
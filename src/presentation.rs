@@ -0,0 +1,121 @@
+use crate::error::DNSError;
+use failure::Result;
+
+//" ( ) . ; \ @ $ have a meaning in master files and are always escaped
+//inside labels
+const LABEL_SPECIAL_CHARS: &[u8] = b"\"().;\\@$";
+//inside a quoted character string only the quote and backslash need it
+const STRING_SPECIAL_CHARS: &[u8] = b"\"\\";
+
+fn push_decimal_escape(c: u8, out: &mut String) {
+    out.push('\\');
+    out.push((b'0' + c / 100) as char);
+    out.push((b'0' + (c / 10) % 10) as char);
+    out.push((b'0' + c % 10) as char);
+}
+
+pub fn escape_label(label: &[u8], out: &mut String) {
+    for &c in label {
+        if LABEL_SPECIAL_CHARS.contains(&c) {
+            out.push('\\');
+            out.push(c as char);
+        } else if c > 0x20 && c < 0x7f {
+            out.push(c as char);
+        } else {
+            push_decimal_escape(c, out);
+        }
+    }
+}
+
+//the result is meant to be wrapped in quotes, so spaces stay as they are
+pub fn escape_char_string(data: &[u8], out: &mut String) {
+    for &c in data {
+        if STRING_SPECIAL_CHARS.contains(&c) {
+            out.push('\\');
+            out.push(c as char);
+        } else if (0x20..0x7f).contains(&c) {
+            out.push(c as char);
+        } else {
+            push_decimal_escape(c, out);
+        }
+    }
+}
+
+pub fn quote_char_string(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() + 2);
+    out.push('"');
+    escape_char_string(data, &mut out);
+    out.push('"');
+    out
+}
+
+//returns the octet at pos and the position after it, \X stands for X and
+//\DDD for the octet with decimal value DDD
+pub fn unescape_next(raw: &[u8], pos: usize) -> Result<(u8, usize)> {
+    if raw[pos] != b'\\' {
+        return Ok((raw[pos], pos + 1));
+    }
+    match raw.get(pos + 1) {
+        None => Err(DNSError::InCompleteEscape.into()),
+        Some(c) if c.is_ascii_digit() => {
+            let digits = raw
+                .get(pos + 1..pos + 4)
+                .filter(|digits| digits.iter().all(u8::is_ascii_digit))
+                .ok_or(DNSError::InvalidDecimalFormat)?;
+            let value = digits
+                .iter()
+                .fold(0u32, |value, d| value * 10 + u32::from(d - b'0'));
+            if value > 255 {
+                return Err(DNSError::InvalidDecimalFormat.into());
+            }
+            Ok((value as u8, pos + 4))
+        }
+        Some(c) => Ok((*c, pos + 2)),
+    }
+}
+
+pub fn unescape(raw: &[u8]) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(raw.len());
+    let mut pos = 0;
+    while pos < raw.len() {
+        let (c, next) = unescape_next(raw, pos)?;
+        data.push(c);
+        pos = next;
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_escape_round_trip() {
+        for c in 0..=255u8 {
+            let mut label = String::new();
+            escape_label(&[c, b'a', c], &mut label);
+            assert_eq!(unescape(label.as_bytes()).unwrap(), vec![c, b'a', c]);
+
+            let mut string = String::new();
+            escape_char_string(&[c], &mut string);
+            assert_eq!(unescape(string.as_bytes()).unwrap(), vec![c]);
+
+            let decimal = format!("\\{:03}", c);
+            assert_eq!(unescape(decimal.as_bytes()).unwrap(), vec![c]);
+        }
+
+        let mut label = String::new();
+        escape_label(b"a.b c\\\x00@", &mut label);
+        assert_eq!(label, "a\\.b\\032c\\\\\\000\\@");
+        assert_eq!(
+            quote_char_string(b"say \"hi\"\n"),
+            "\"say \\\"hi\\\"\\010\""
+        );
+
+        assert_eq!(unescape(b"\\065\\066C\\.\\\\").unwrap(), b"ABC.\\".to_vec());
+        assert!(unescape(b"\\256").is_err());
+        assert!(unescape(b"\\25").is_err());
+        assert!(unescape(b"\\2a5").is_err());
+        assert!(unescape(b"abc\\").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::edns::ClientSubnet;
+use crate::name::Name;
+#[cfg(feature = "psl")]
+use crate::psl::PslSet;
+use crate::util::digest::hmac_sha256;
+use crate::util::hex::to_hex;
+use crate::util::SecretKey;
+use std::net::IpAddr;
+#[cfg(feature = "psl")]
+use std::sync::Arc;
+
+const HASHED_NAME_LEN: usize = 16;
+
+//keep the rightmost labels of the name, root excluded
+pub fn keep_labels(name: &Name, label_count: usize) -> Name {
+    let labels = name.label_count() - 1;
+    if labels <= label_count {
+        name.clone()
+    } else {
+        name.strip_left(labels - label_count)
+    }
+}
+
+//hash of the lower cased wire form, so names differing only in case
+//produce the same value
+pub fn hash_name(name: &Name, key: &[u8]) -> String {
+    let mut name = name.clone();
+    name.to_lowercase();
+    to_hex(&hmac_sha256(key, name.raw_data())[..HASHED_NAME_LEN])
+}
+
+pub fn truncate_addr(addr: IpAddr, ipv4_prefix: u8, ipv6_prefix: u8) -> IpAddr {
+    let prefix = if addr.is_ipv4() {
+        ipv4_prefix
+    } else {
+        ipv6_prefix
+    };
+    ClientSubnet::new(addr, prefix).addr
+}
+
+pub fn truncate_client_subnet(
+    subnet: &ClientSubnet,
+    ipv4_prefix: u8,
+    ipv6_prefix: u8,
+) -> ClientSubnet {
+    let max_prefix = if subnet.addr.is_ipv4() {
+        ipv4_prefix
+    } else {
+        ipv6_prefix
+    };
+    if subnet.source_prefix <= max_prefix {
+        subnet.clone()
+    } else {
+        ClientSubnet::new(subnet.addr, max_prefix)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum QnameRedaction {
+    Keep,
+    KeepLabels(usize),
+    Hmac(SecretKey),
+    #[cfg(feature = "psl")]
+    RegistrableDomain(Arc<PslSet>),
+}
+
+impl QnameRedaction {
+    pub fn redact(&self, name: &Name) -> String {
+        match self {
+            QnameRedaction::Keep => name.to_string(),
+            QnameRedaction::KeepLabels(count) => keep_labels(name, *count).to_string(),
+            QnameRedaction::Hmac(key) => hash_name(name, key.as_bytes()),
+            #[cfg(feature = "psl")]
+            QnameRedaction::RegistrableDomain(psl) => name
+                .registrable_domain(psl)
+                .unwrap_or_else(|| name.clone())
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PrivacyPolicy {
+    pub qname: QnameRedaction,
+    pub ipv4_prefix: u8,
+    pub ipv6_prefix: u8,
+}
+
+impl Default for PrivacyPolicy {
+    fn default() -> Self {
+        PrivacyPolicy {
+            qname: QnameRedaction::Keep,
+            ipv4_prefix: 24,
+            ipv6_prefix: 56,
+        }
+    }
+}
+
+impl PrivacyPolicy {
+    pub fn redact_name(&self, name: &Name) -> String {
+        self.qname.redact(name)
+    }
+
+    pub fn redact_addr(&self, addr: IpAddr) -> IpAddr {
+        truncate_addr(addr, self.ipv4_prefix, self.ipv6_prefix)
+    }
+
+    pub fn redact_client_subnet(&self, subnet: &ClientSubnet) -> ClientSubnet {
+        truncate_client_subnet(subnet, self.ipv4_prefix, self.ipv6_prefix)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_privacy_policy() {
+        let name = Name::new("a.b.www.example.com.").unwrap();
+        assert_eq!(keep_labels(&name, 2).to_string(), "example.com.");
+        assert_eq!(keep_labels(&name, 5), name);
+        assert_eq!(keep_labels(&name, 9), name);
+        assert!(keep_labels(&name, 0).is_root());
+
+        let upper = Name::new("A.B.WWW.Example.COM.").unwrap();
+        let hashed = hash_name(&name, b"secret");
+        assert_eq!(hashed.len(), HASHED_NAME_LEN * 2);
+        assert_eq!(hashed, hash_name(&upper, b"secret"));
+        assert_ne!(hashed, hash_name(&name, b"other"));
+
+        let mut policy = PrivacyPolicy::default();
+        assert_eq!(policy.redact_name(&name), "a.b.www.example.com.");
+        policy.qname = QnameRedaction::KeepLabels(3);
+        assert_eq!(policy.redact_name(&name), "www.example.com.");
+        policy.qname = QnameRedaction::Hmac(SecretKey::from(&b"secret"[..]));
+        assert_eq!(policy.redact_name(&upper), hashed);
+        #[cfg(feature = "psl")]
+        {
+            let psl: PslSet = "com\nuk\nco.uk".parse().unwrap();
+            policy.qname = QnameRedaction::RegistrableDomain(Arc::new(psl));
+            assert_eq!(policy.redact_name(&name), "example.com.");
+            let name = Name::new("co.uk.").unwrap();
+            assert_eq!(policy.redact_name(&name), "co.uk.");
+        }
+
+        assert_eq!(
+            policy.redact_addr("192.0.2.129".parse().unwrap()),
+            "192.0.2.0".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            policy.redact_addr("2001:db8:1:2:3::1".parse().unwrap()),
+            "2001:db8:1::".parse::<IpAddr>().unwrap()
+        );
+
+        let subnet = ClientSubnet::new("198.51.100.77".parse().unwrap(), 32);
+        let redacted = policy.redact_client_subnet(&subnet);
+        assert_eq!(redacted.to_string(), "198.51.100.0/24/0");
+        let subnet = ClientSubnet::new("198.51.100.77".parse().unwrap(), 16);
+        assert_eq!(policy.redact_client_subnet(&subnet), subnet);
+    }
+}
@@ -0,0 +1,318 @@
+use crate::edns::Edns;
+use crate::edns_fallback::{edns_fallback, FallbackAction, QueryOutcome};
+use crate::forwarder::Transport;
+use crate::header_flag::HeaderFlag;
+use crate::message::{Message, SectionType};
+use crate::name::{self, Name};
+use crate::query_state::EdnsStep;
+use crate::rcode::Rcode;
+use crate::response_class::ResponseClass;
+use crate::rr_type::RRType;
+use failure::Result;
+use rand::Rng;
+use std::fmt;
+
+const RANDOM_LABEL_LEN: usize = 16;
+const RANDOM_LABEL_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ProbeKind {
+    ZoneSoa(Name),
+    RootNs,
+    NxDomain(Name),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ProbeFailure {
+    NotResponse,
+    IdMismatch,
+    QuestionMismatch,
+    //the probe should be sent again as Probe::fall_back says
+    Fallback(FallbackAction),
+    Rcode(Rcode),
+    NoAnswer,
+    UnexpectedAnswer,
+}
+
+impl fmt::Display for ProbeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProbeFailure::NotResponse => f.write_str("qr flag isn't set"),
+            ProbeFailure::IdMismatch => f.write_str("id doesn't match the query"),
+            ProbeFailure::QuestionMismatch => f.write_str("question doesn't match the query"),
+            ProbeFailure::Fallback(FallbackAction::Downgrade(_)) => {
+                f.write_str("server can't handle the query's edns")
+            }
+            ProbeFailure::Fallback(FallbackAction::RetryOverTcp) => {
+                f.write_str("query has to go over tcp")
+            }
+            ProbeFailure::Fallback(_) => f.write_str("server rejects the query"),
+            ProbeFailure::Rcode(rcode) => write!(f, "unexpected rcode {}", rcode),
+            ProbeFailure::NoAnswer => f.write_str("expected records are missing"),
+            ProbeFailure::UnexpectedAnswer => f.write_str("nonexistent name is answered"),
+        }
+    }
+}
+
+pub type ProbeResult = std::result::Result<(), ProbeFailure>;
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+    kind: ProbeKind,
+    query: Message,
+    edns_step: EdnsStep,
+    transport: Transport,
+}
+
+pub fn random_label() -> String {
+    let mut rng = rand::thread_rng();
+    (0..RANDOM_LABEL_LEN)
+        .map(|_| RANDOM_LABEL_CHARS[rng.gen_range(0, RANDOM_LABEL_CHARS.len())] as char)
+        .collect()
+}
+
+impl Probe {
+    pub fn zone_soa(zone: Name) -> Self {
+        let query = Message::with_query(zone.clone(), RRType::SOA);
+        Self::new(ProbeKind::ZoneSoa(zone), query)
+    }
+
+    pub fn root_ns() -> Self {
+        Self::new(
+            ProbeKind::RootNs,
+            Message::with_query(name::root(), RRType::NS),
+        )
+    }
+
+    pub fn nxdomain(zone: Name) -> Result<Self> {
+        let qname = Name::new(&random_label())?.concat(&zone)?;
+        Ok(Self::new(
+            ProbeKind::NxDomain(zone),
+            Message::with_query(qname, RRType::A),
+        ))
+    }
+
+    fn new(kind: ProbeKind, query: Message) -> Self {
+        let edns_step = if query.edns.is_some() {
+            EdnsStep::Full
+        } else {
+            EdnsStep::Disabled
+        };
+        Probe {
+            kind,
+            query,
+            edns_step,
+            transport: Transport::Udp,
+        }
+    }
+
+    pub fn with_edns(mut self, edns: Edns) -> Self {
+        self.query.edns = Some(edns);
+        self.query.recalculate_header();
+        self.edns_step = EdnsStep::Full;
+        self
+    }
+
+    pub fn kind(&self) -> &ProbeKind {
+        &self.kind
+    }
+
+    pub fn query(&self) -> &Message {
+        &self.query
+    }
+
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    pub fn on_timeout(&self) -> FallbackAction {
+        edns_fallback(QueryOutcome::Timeout, self.edns_step, self.transport)
+    }
+
+    //reshapes the probe for the retry edns_fallback asked for, false
+    //means there is nothing left to try
+    pub fn fall_back(&mut self, action: FallbackAction) -> bool {
+        match action {
+            FallbackAction::Downgrade(step) => {
+                self.edns_step = step;
+                step.apply(&mut self.query);
+                true
+            }
+            FallbackAction::RetryOverTcp => {
+                self.transport = Transport::Tcp;
+                true
+            }
+            FallbackAction::Accept | FallbackAction::GiveUp => false,
+        }
+    }
+
+    pub fn evaluate(&self, response: &Message) -> ProbeResult {
+        if !response.header.is_flag_set(HeaderFlag::QueryRespone) {
+            return Err(ProbeFailure::NotResponse);
+        }
+        if response.header.id != self.query.header.id {
+            return Err(ProbeFailure::IdMismatch);
+        }
+        if response.question != self.query.question {
+            return Err(ProbeFailure::QuestionMismatch);
+        }
+        let outcome = QueryOutcome::Response(response);
+        match edns_fallback(outcome, self.edns_step, self.transport) {
+            FallbackAction::Accept => {}
+            action => return Err(ProbeFailure::Fallback(action)),
+        }
+
+        let class = ResponseClass::from_message(response);
+        match self.kind {
+            ProbeKind::ZoneSoa(_) | ProbeKind::RootNs => {
+                if let ResponseClass::Error(rcode) = class {
+                    return Err(ProbeFailure::Rcode(rcode));
+                }
+                let question = self.query.question.as_ref().unwrap();
+                let answered = response
+                    .section(SectionType::Answer)
+                    .into_iter()
+                    .flatten()
+                    .any(|rrset| rrset.typ == question.typ && rrset.name == question.name);
+                if answered {
+                    Ok(())
+                } else if response.header.rcode != Rcode::NoError {
+                    Err(ProbeFailure::Rcode(response.header.rcode))
+                } else {
+                    Err(ProbeFailure::NoAnswer)
+                }
+            }
+            ProbeKind::NxDomain(_) => match response.header.rcode {
+                Rcode::NXDomain => Ok(()),
+                Rcode::NoError => Err(ProbeFailure::UnexpectedAnswer),
+                rcode => Err(ProbeFailure::Rcode(rcode)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::edns::EdnsConfig;
+    use crate::message_builder::MessageBuilder;
+    use crate::rrset::RRset;
+    use std::str::FromStr;
+
+    fn respond(probe: &Probe, rcode: Rcode, answer: Option<&str>) -> Message {
+        let mut response = probe.query().clone();
+        {
+            let mut builder = MessageBuilder::new(&mut response);
+            builder.make_response().rcode(rcode);
+            if let Some(rr) = answer {
+                builder.add_answer(RRset::from_str(rr).unwrap());
+            }
+            builder.done();
+        }
+        response
+    }
+
+    #[test]
+    fn test_soa_probe() {
+        let probe = Probe::zone_soa(Name::new("example.com.").unwrap());
+        let soa = "example.com. 300 IN SOA ns.example.com. root.example.com. 1 2 3 4 5";
+        assert_eq!(
+            probe.evaluate(&respond(&probe, Rcode::NoError, Some(soa))),
+            Ok(())
+        );
+        assert_eq!(
+            probe.evaluate(&respond(&probe, Rcode::NoError, None)),
+            Err(ProbeFailure::NoAnswer)
+        );
+        assert_eq!(
+            probe.evaluate(&respond(&probe, Rcode::ServFail, None)),
+            Err(ProbeFailure::Rcode(Rcode::ServFail))
+        );
+        assert_eq!(
+            probe.evaluate(probe.query()),
+            Err(ProbeFailure::NotResponse)
+        );
+
+        let mut response = respond(&probe, Rcode::NoError, Some(soa));
+        response.header.id = response.header.id.wrapping_add(1);
+        assert_eq!(probe.evaluate(&response), Err(ProbeFailure::IdMismatch));
+
+        let probe = Probe::root_ns();
+        let ns = ". 518400 IN NS a.root-servers.net.";
+        assert_eq!(
+            probe.evaluate(&respond(&probe, Rcode::NoError, Some(ns))),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_nxdomain_probe() {
+        let zone = Name::new("example.com.").unwrap();
+        let probe = Probe::nxdomain(zone.clone()).unwrap();
+        let qname = &probe.query().question.as_ref().unwrap().name;
+        assert!(qname.is_subdomain(&zone));
+        assert_eq!(qname.label_count(), zone.label_count() + 1);
+        assert_ne!(
+            qname,
+            &Probe::nxdomain(zone)
+                .unwrap()
+                .query()
+                .question
+                .as_ref()
+                .unwrap()
+                .name
+        );
+
+        assert_eq!(
+            probe.evaluate(&respond(&probe, Rcode::NXDomain, None)),
+            Ok(())
+        );
+        let hijacked = format!("{} 60 IN A 198.51.100.1", qname);
+        assert_eq!(
+            probe.evaluate(&respond(&probe, Rcode::NoError, Some(hijacked.as_ref()))),
+            Err(ProbeFailure::UnexpectedAnswer)
+        );
+        assert_eq!(
+            ProbeFailure::UnexpectedAnswer.to_string(),
+            "nonexistent name is answered"
+        );
+    }
+
+    #[test]
+    fn test_probe_fallback() {
+        let mut probe = Probe::root_ns().with_edns(EdnsConfig::default().to_edns());
+        assert_eq!(
+            probe.on_timeout(),
+            FallbackAction::Downgrade(EdnsStep::NoDnssec)
+        );
+
+        //a server without edns doesn't echo the opt record
+        let mut response = respond(&probe, Rcode::FormErr, None);
+        response.edns = None;
+        response.recalculate_header();
+        let failure = probe.evaluate(&response).unwrap_err();
+        assert_eq!(
+            failure,
+            ProbeFailure::Fallback(FallbackAction::Downgrade(EdnsStep::Disabled))
+        );
+        match failure {
+            ProbeFailure::Fallback(action) => assert!(probe.fall_back(action)),
+            _ => unreachable!(),
+        }
+        assert!(probe.query().edns.is_none());
+
+        let mut response = respond(&probe, Rcode::NoError, None);
+        response.header.set_flag(HeaderFlag::Truncation, true);
+        assert_eq!(
+            probe.evaluate(&response),
+            Err(ProbeFailure::Fallback(FallbackAction::RetryOverTcp))
+        );
+        assert!(probe.fall_back(FallbackAction::RetryOverTcp));
+        assert_eq!(probe.transport(), Transport::Tcp);
+        assert_eq!(
+            probe.evaluate(&respond(&probe, Rcode::FormErr, None)),
+            Err(ProbeFailure::Fallback(FallbackAction::GiveUp))
+        );
+        assert!(!probe.fall_back(FallbackAction::GiveUp));
+    }
+}
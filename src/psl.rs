@@ -0,0 +1,167 @@
+use crate::name::Name;
+use failure::{self, Result};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PslSet {
+    rules: HashSet<Vec<u8>>,
+    //parents of "*." rules
+    wildcards: HashSet<Vec<u8>>,
+    exceptions: HashSet<Vec<u8>>,
+}
+
+fn rule_key(name: &Name) -> Vec<u8> {
+    let mut name = name.clone();
+    name.to_lowercase();
+    name.raw_data().to_vec()
+}
+
+impl PslSet {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add_rule(&mut self, rule: &str) -> Result<()> {
+        if let Some(exception) = rule.strip_prefix('!') {
+            let name = Name::new(exception)?;
+            self.exceptions.insert(rule_key(&name));
+        } else {
+            let name = Name::new(rule)?;
+            if name.is_wildcard() {
+                self.wildcards.insert(rule_key(&name.strip_left(1)));
+            } else {
+                self.rules.insert(rule_key(&name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len() + self.wildcards.len() + self.exceptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //label count of the public suffix, the longest matching rule wins
+    //except that an exception rule overrides a wildcard, and an unlisted
+    //tld is a public suffix on its own
+    pub fn public_suffix_len(&self, name: &Name) -> usize {
+        let labels = name.label_count() - 1;
+        if labels == 0 {
+            return 0;
+        }
+
+        let key = rule_key(name);
+        let offsets = name.offsets();
+        for count in (1..=labels).rev() {
+            let suffix = &key[offsets[labels - count] as usize..];
+            if self.exceptions.contains(suffix) {
+                return count - 1;
+            }
+            let parent = &suffix[suffix[0] as usize + 1..];
+            if self.rules.contains(suffix) || self.wildcards.contains(parent) {
+                return count;
+            }
+        }
+        1
+    }
+
+    pub fn public_suffix(&self, name: &Name) -> Name {
+        let labels = name.label_count() - 1;
+        name.strip_left(labels - self.public_suffix_len(name))
+    }
+
+    pub fn is_public_suffix(&self, name: &Name) -> bool {
+        self.public_suffix_len(name) == name.label_count() - 1
+    }
+}
+
+//the public suffix list format: one rule per line, "//" starts a comment
+//and only the text before the first whitespace is read
+impl FromStr for PslSet {
+    type Err = failure::Error;
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let mut psl = PslSet::new();
+        for line in s.lines() {
+            let rule = match line.split_whitespace().next() {
+                Some(rule) if !rule.starts_with("//") => rule,
+                _ => continue,
+            };
+            psl.add_rule(rule)?;
+        }
+        Ok(psl)
+    }
+}
+
+impl Name {
+    pub fn registrable_domain(&self, psl: &PslSet) -> Option<Name> {
+        let labels = self.label_count() - 1;
+        let suffix_len = psl.public_suffix_len(self);
+        if labels <= suffix_len {
+            None
+        } else {
+            Some(self.strip_left(labels - suffix_len - 1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_registrable_domain() {
+        let psl = PslSet::from_str(
+            "// comment line\n\
+             com\n\
+             \n\
+             uk\n\
+             co.uk\n\
+             jp\n\
+             *.kawasaki.jp\n\
+             !city.kawasaki.jp\n\
+             *.ck\n\
+             !www.ck   trailing text\n",
+        )
+        .unwrap();
+        assert_eq!(psl.len(), 8);
+
+        let cases = vec![
+            ("www.example.com.", Some("example.com.")),
+            ("Example.COM.", Some("Example.COM.")),
+            ("com.", None),
+            ("a.b.example.co.uk.", Some("example.co.uk.")),
+            ("co.uk.", None),
+            ("foo.bar.kawasaki.jp.", Some("foo.bar.kawasaki.jp.")),
+            ("bar.kawasaki.jp.", None),
+            ("kawasaki.jp.", Some("kawasaki.jp.")),
+            ("www.city.kawasaki.jp.", Some("city.kawasaki.jp.")),
+            ("city.kawasaki.jp.", Some("city.kawasaki.jp.")),
+            ("www.ck.", Some("www.ck.")),
+            ("a.www.ck.", Some("www.ck.")),
+            ("foo.ck.", None),
+            ("a.foo.ck.", Some("a.foo.ck.")),
+            ("www.example.unlisted.", Some("example.unlisted.")),
+            ("unlisted.", None),
+            (".", None),
+        ];
+        for (name, registrable) in cases {
+            let name = Name::new(name).unwrap();
+            assert_eq!(
+                name.registrable_domain(&psl),
+                registrable.map(|n| Name::new(n).unwrap()),
+                "{}",
+                name
+            );
+        }
+
+        let name = Name::new("a.b.example.co.uk.").unwrap();
+        assert_eq!(psl.public_suffix(&name), Name::new("co.uk.").unwrap());
+        assert!(psl.is_public_suffix(&Name::new("foo.ck.").unwrap()));
+        assert!(!psl.is_public_suffix(&Name::new("www.ck.").unwrap()));
+        assert!(PslSet::from_str("a..b").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use crate::error::DNSError;
+use crate::message::Message;
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::util::InputBuffer;
+use failure::Result;
+
+const WILDCARD_LABEL: &[u8] = b"*";
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RewriteRule {
+    Exact { from: Name, to: Name },
+    //names under from are moved under to, the labels in front are kept
+    Suffix { from: Name, to: Name },
+    //each "*" label in from matches exactly one label, the "*" labels in to
+    //are filled with the matched labels in order
+    Pattern { from: Name, to: Name },
+}
+
+fn labels(name: &Name) -> Vec<&[u8]> {
+    let raw = name.raw_data();
+    name.offsets()[..name.label_count() - 1]
+        .iter()
+        .map(|&offset| {
+            let start = offset as usize + 1;
+            &raw[start..start + raw[offset as usize] as usize]
+        })
+        .collect()
+}
+
+fn wildcard_count(name: &Name) -> usize {
+    labels(name)
+        .into_iter()
+        .filter(|&label| label == WILDCARD_LABEL)
+        .count()
+}
+
+fn from_labels(labels: &[&[u8]]) -> Result<Name> {
+    let mut raw = Vec::new();
+    for label in labels {
+        raw.push(label.len() as u8);
+        raw.extend_from_slice(label);
+    }
+    raw.push(0);
+    Name::from_wire(&mut InputBuffer::new(&raw))
+}
+
+fn swap_suffix(name: &Name, from: &Name, to: &Name) -> Result<Name> {
+    let prefix = labels(name);
+    let prefix = &prefix[..name.label_count() - from.label_count()];
+    let mut swapped = prefix.to_vec();
+    swapped.extend(labels(to));
+    from_labels(&swapped)
+}
+
+impl RewriteRule {
+    pub fn exact(from: Name, to: Name) -> Self {
+        RewriteRule::Exact { from, to }
+    }
+
+    pub fn suffix(from: Name, to: Name) -> Self {
+        RewriteRule::Suffix { from, to }
+    }
+
+    pub fn pattern(from: Name, to: Name) -> Result<Self> {
+        let captures = wildcard_count(&from);
+        if captures == 0 || wildcard_count(&to) > captures {
+            return Err(DNSError::InvalidRewriteRule(format!("{} -> {}", from, to)).into());
+        }
+        Ok(RewriteRule::Pattern { from, to })
+    }
+
+    //None if the rule doesn't match, an error if the result isn't a valid
+    //name
+    pub fn apply(&self, name: &Name) -> Option<Result<Name>> {
+        match self {
+            RewriteRule::Exact { from, to } => {
+                if name == from {
+                    Some(Ok(to.clone()))
+                } else {
+                    None
+                }
+            }
+            RewriteRule::Suffix { from, to } => {
+                if name.is_subdomain(from) {
+                    Some(swap_suffix(name, from, to))
+                } else {
+                    None
+                }
+            }
+            RewriteRule::Pattern { from, to } => {
+                if name.label_count() != from.label_count() {
+                    return None;
+                }
+                let mut captured = Vec::new();
+                for (label, pattern) in labels(name).into_iter().zip(labels(from)) {
+                    if pattern == WILDCARD_LABEL {
+                        captured.push(label);
+                    } else if !label.eq_ignore_ascii_case(pattern) {
+                        return None;
+                    }
+                }
+                let mut captured = captured.into_iter();
+                let rewritten: Vec<&[u8]> = labels(to)
+                    .into_iter()
+                    .map(|label| {
+                        if label == WILDCARD_LABEL {
+                            captured.next().unwrap()
+                        } else {
+                            label
+                        }
+                    })
+                    .collect();
+                Some(from_labels(&rewritten))
+            }
+        }
+    }
+}
+
+//what the query looked like before rewriting, kept by the caller to map
+//the response back
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rewrite {
+    pub original: Name,
+    pub rewritten: Name,
+    suffix: Option<(Name, Name)>,
+}
+
+impl Rewrite {
+    pub fn restore_name(&self, name: &Name) -> Name {
+        if name == &self.rewritten {
+            return self.original.clone();
+        }
+        if let Some((from, to)) = self.suffix.as_ref() {
+            if name.is_subdomain(to) {
+                if let Ok(restored) = swap_suffix(name, to, from) {
+                    return restored;
+                }
+            }
+        }
+        name.clone()
+    }
+
+    pub fn restore_response(&self, response: &mut Message) {
+        if let Some(question) = response.question.as_mut() {
+            question.name = self.restore_name(&question.name);
+        }
+        for section in response.sections.iter_mut() {
+            for rrset in section.0.iter_mut().flatten() {
+                rrset.name = self.restore_name(&rrset.name);
+                //alias targets are mapped back too, or the chain breaks and
+                //the rewritten name leaks to the client
+                for rdata in rrset.rdatas.iter_mut() {
+                    match rdata {
+                        RData::CName(ref mut cname) => cname.name = self.restore_name(&cname.name),
+                        RData::DName(ref mut dname) => {
+                            dname.target = self.restore_name(&dname.target)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct QnameRewriter {
+    rules: Vec<RewriteRule>,
+}
+
+impl QnameRewriter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add_rule(&mut self, rule: RewriteRule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    //the first matching rule wins
+    pub fn rewrite_name(&self, name: &Name) -> Option<Result<Rewrite>> {
+        self.rules.iter().find_map(|rule| {
+            rule.apply(name).map(|rewritten| {
+                let suffix = match rule {
+                    RewriteRule::Suffix { from, to } => Some((from.clone(), to.clone())),
+                    _ => None,
+                };
+                rewritten.map(|rewritten| Rewrite {
+                    original: name.clone(),
+                    rewritten,
+                    suffix,
+                })
+            })
+        })
+    }
+
+    pub fn rewrite_query(&self, query: &mut Message) -> Result<Option<Rewrite>> {
+        let question = match query.question.as_mut() {
+            Some(question) => question,
+            None => return Ok(None),
+        };
+        match self.rewrite_name(&question.name) {
+            Some(rewrite) => {
+                let rewrite = rewrite?;
+                question.name = rewrite.rewritten.clone();
+                Ok(Some(rewrite))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message::SectionType;
+    use crate::message_builder::MessageBuilder;
+    use crate::rr_type::RRType;
+    use crate::rrset::RRset;
+    use std::str::FromStr;
+
+    fn name(s: &str) -> Name {
+        Name::new(s).unwrap()
+    }
+
+    #[test]
+    fn test_qname_rewrite() {
+        let mut rewriter = QnameRewriter::new();
+        rewriter
+            .add_rule(RewriteRule::exact(
+                name("old.example."),
+                name("new.example."),
+            ))
+            .add_rule(RewriteRule::suffix(
+                name("corp.example."),
+                name("internal.example.net."),
+            ))
+            .add_rule(
+                RewriteRule::pattern(name("*.*.svc.example."), name("*.*.cluster.local.")).unwrap(),
+            );
+        assert_eq!(rewriter.len(), 3);
+        assert!(RewriteRule::pattern(name("a.example."), name("*.example.")).is_err());
+        assert!(RewriteRule::pattern(name("*.example."), name("*.*.example.")).is_err());
+
+        let cases = vec![
+            ("old.example.", Some("new.example.")),
+            ("www.old.example.", None),
+            ("WWW.Corp.example.", Some("WWW.internal.example.net.")),
+            ("corp.example.", Some("internal.example.net.")),
+            ("web.prod.svc.example.", Some("web.prod.cluster.local.")),
+            ("prod.svc.example.", None),
+            ("www.example.", None),
+        ];
+        for (original, rewritten) in cases {
+            let rewrite = rewriter
+                .rewrite_name(&name(original))
+                .map(|rewrite| rewrite.unwrap().rewritten.to_string());
+            assert_eq!(rewrite.as_deref(), rewritten, "{}", original);
+        }
+
+        let long = format!(
+            "{}.{}.corp.example.",
+            vec!["a".repeat(60); 3].join("."),
+            "b".repeat(50)
+        );
+        assert!(rewriter.rewrite_name(&name(&long)).unwrap().is_err());
+
+        let mut query = Message::with_query(name("www.corp.example."), RRType::A);
+        let rewrite = rewriter.rewrite_query(&mut query).unwrap().unwrap();
+        assert_eq!(
+            query.question.as_ref().unwrap().name,
+            name("www.internal.example.net.")
+        );
+
+        let mut response = query.clone();
+        MessageBuilder::new(&mut response)
+            .make_response()
+            .add_answer(
+                RRset::from_str("www.internal.example.net. 300 IN CNAME web.internal.example.net.")
+                    .unwrap(),
+            )
+            .add_answer(RRset::from_str("web.internal.example.net. 300 IN A 192.0.2.1").unwrap())
+            .add_auth(RRset::from_str("example.net. 300 IN NS ns.example.net.").unwrap())
+            .done();
+        rewrite.restore_response(&mut response);
+        assert_eq!(
+            response.question.as_ref().unwrap().name,
+            name("www.corp.example.")
+        );
+        let owners: Vec<String> = response
+            .section(SectionType::Answer)
+            .unwrap()
+            .iter()
+            .map(|rrset| rrset.name.to_string())
+            .collect();
+        assert_eq!(owners, vec!["www.corp.example.", "web.corp.example."]);
+        match response.section(SectionType::Answer).unwrap()[0].rdatas[0] {
+            RData::CName(ref cname) => assert_eq!(cname.name, name("web.corp.example.")),
+            ref other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            response.section(SectionType::Authority).unwrap()[0].name,
+            name("example.net.")
+        );
+
+        let mut query = Message::with_query(name("www.example."), RRType::A);
+        assert!(rewriter.rewrite_query(&mut query).unwrap().is_none());
+    }
+}
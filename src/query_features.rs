@@ -0,0 +1,212 @@
+use crate::message::Message;
+use crate::name::{lower_case, Name};
+use crate::rcode::Rcode;
+use crate::rr_type::RRType;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+//shannon entropy in bits per character of the label bytes, length octets
+//excluded and case folded
+pub fn name_entropy(name: &Name) -> f64 {
+    let mut counts = [0usize; 256];
+    let mut total = 0;
+    let raw = name.raw_data();
+    for &offset in name.offsets() {
+        let start = offset as usize + 1;
+        let end = start + raw[offset as usize] as usize;
+        for &c in &raw[start..end] {
+            counts[lower_case(c as usize) as usize] += 1;
+            total += 1;
+        }
+    }
+    if total == 0 {
+        return 0.0;
+    }
+
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryFeatures {
+    pub qname: Name,
+    pub qtype: RRType,
+    pub label_count: usize,
+    pub name_len: usize,
+    pub longest_label: usize,
+    pub entropy: f64,
+    pub digit_ratio: f64,
+    pub query_size: usize,
+    pub response_size: Option<usize>,
+    pub rcode: Option<Rcode>,
+}
+
+impl QueryFeatures {
+    pub fn new(query: &Message, query_size: usize) -> Option<Self> {
+        let question = query.question.as_ref()?;
+        let name = &question.name;
+        let raw = name.raw_data();
+        let label_lens = name.offsets().iter().map(|&offset| raw[offset as usize]);
+        let chars = raw.len() - name.label_count();
+        let digits = name
+            .offsets()
+            .iter()
+            .flat_map(|&offset| {
+                let start = offset as usize + 1;
+                raw[start..start + raw[offset as usize] as usize].iter()
+            })
+            .filter(|c| c.is_ascii_digit())
+            .count();
+
+        Some(QueryFeatures {
+            qname: name.clone(),
+            qtype: question.typ,
+            label_count: name.label_count() - 1,
+            name_len: name.len(),
+            longest_label: label_lens.max().unwrap_or(0) as usize,
+            entropy: name_entropy(name),
+            digit_ratio: if chars == 0 {
+                0.0
+            } else {
+                digits as f64 / chars as f64
+            },
+            query_size,
+            response_size: None,
+            rcode: None,
+        })
+    }
+
+    pub fn set_response(&mut self, response: &Message, response_size: usize) {
+        self.response_size = Some(response_size);
+        self.rcode = Some(response.header.rcode);
+    }
+
+    //amplification indicator, response bytes per query byte
+    pub fn response_ratio(&self) -> Option<f64> {
+        match self.response_size {
+            Some(size) if self.query_size > 0 => Some(size as f64 / self.query_size as f64),
+            _ => None,
+        }
+    }
+
+    pub fn is_nxdomain(&self) -> bool {
+        self.rcode == Some(Rcode::NXDomain)
+    }
+}
+
+//counts nxdomain responses per key (client address, zone...) in a
+//sliding window
+#[derive(Debug, Clone)]
+pub struct NxDomainBurst<K: Hash + Eq> {
+    window: Duration,
+    threshold: usize,
+    events: HashMap<K, VecDeque<Instant>>,
+}
+
+impl<K: Hash + Eq> NxDomainBurst<K> {
+    pub fn new(window: Duration, threshold: usize) -> Self {
+        NxDomainBurst {
+            window,
+            threshold,
+            events: HashMap::new(),
+        }
+    }
+
+    //returns true once the key reaches the threshold within the window
+    pub fn record(&mut self, key: K, rcode: Rcode, now: Instant) -> bool {
+        if rcode != Rcode::NXDomain {
+            return self.count(&key, now) >= self.threshold;
+        }
+
+        let window = self.window;
+        let events = self.events.entry(key).or_default();
+        events.push_back(now);
+        while let Some(&first) = events.front() {
+            if now.saturating_duration_since(first) < window {
+                break;
+            }
+            events.pop_front();
+        }
+        events.len() >= self.threshold
+    }
+
+    pub fn count(&self, key: &K, now: Instant) -> usize {
+        self.events.get(key).map_or(0, |events| {
+            events
+                .iter()
+                .filter(|&&at| now.saturating_duration_since(at) < self.window)
+                .count()
+        })
+    }
+
+    pub fn purge(&mut self, now: Instant) {
+        let window = self.window;
+        self.events.retain(|_, events| {
+            events.retain(|&at| now.saturating_duration_since(at) < window);
+            !events.is_empty()
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message_builder::MessageBuilder;
+
+    #[test]
+    fn test_query_features() {
+        assert_eq!(name_entropy(&Name::new(".").unwrap()), 0.0);
+        assert_eq!(name_entropy(&Name::new("aaaa.AAAA.").unwrap()), 0.0);
+        assert_eq!(name_entropy(&Name::new("ab.").unwrap()), 1.0);
+        assert_eq!(name_entropy(&Name::new("ab.cd.").unwrap()), 2.0);
+
+        let name = Name::new("x7k2q9.example.com.").unwrap();
+        let mut query = Message::with_query(name.clone(), RRType::TXT);
+        let mut features = QueryFeatures::new(&query, 40).unwrap();
+        assert_eq!(features.qtype, RRType::TXT);
+        assert_eq!(features.label_count, 3);
+        assert_eq!(features.name_len, 20);
+        assert_eq!(features.longest_label, 7);
+        assert_eq!(features.digit_ratio, 3.0 / 16.0);
+        assert_eq!(features.entropy, name_entropy(&name));
+        assert!(features.response_ratio().is_none());
+
+        MessageBuilder::new(&mut query)
+            .make_response()
+            .rcode(Rcode::NXDomain)
+            .done();
+        features.set_response(&query, 400);
+        assert_eq!(features.response_ratio(), Some(10.0));
+        assert!(features.is_nxdomain());
+
+        let mut burst = NxDomainBurst::new(Duration::from_secs(10), 3);
+        let now = Instant::now();
+        assert!(!burst.record("client", Rcode::NXDomain, now));
+        assert!(!burst.record("client", Rcode::NoError, now));
+        assert!(!burst.record("client", Rcode::NXDomain, now));
+        assert!(!burst.record("other", Rcode::NXDomain, now));
+        assert!(burst.record("client", Rcode::NXDomain, now + Duration::from_secs(9)));
+        assert!(!burst.record("client", Rcode::NXDomain, now + Duration::from_secs(12)));
+        assert_eq!(burst.count(&"client", now + Duration::from_secs(12)), 2);
+
+        burst.purge(now + Duration::from_secs(19));
+        assert_eq!(burst.len(), 1);
+        burst.purge(now + Duration::from_secs(30));
+        assert!(burst.is_empty());
+    }
+}
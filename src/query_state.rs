@@ -0,0 +1,259 @@
+use crate::edns::EdnsConfig;
+use crate::edns_fallback::{edns_fallback, FallbackAction, QueryOutcome};
+use crate::forwarder::Transport;
+use crate::message::Message;
+use std::time::{Duration, Instant};
+
+//rfc6891 section 6.2.5, a requestor that sees no answer may retry with a
+//buffer that fits an unfragmented ipv4 packet
+const MIN_UDP_SIZE: u16 = 512;
+
+//each step gives up a little more of the query's edns in the hope the
+//path or the server copes with what is left, edns_fallback decides when
+//to take a step and when to jump straight to Disabled
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum EdnsStep {
+    Full,
+    NoDnssec,
+    MinimalBuffer,
+    Disabled,
+}
+
+impl EdnsStep {
+    pub(crate) fn next(self) -> Option<Self> {
+        match self {
+            EdnsStep::Full => Some(EdnsStep::NoDnssec),
+            EdnsStep::NoDnssec => Some(EdnsStep::MinimalBuffer),
+            EdnsStep::MinimalBuffer => Some(EdnsStep::Disabled),
+            EdnsStep::Disabled => None,
+        }
+    }
+
+    //brings the query's opt record in line with the step, a query sent
+    //without edns is left alone
+    pub fn apply(self, query: &mut Message) {
+        if query.edns.is_none() {
+            return;
+        }
+        match self {
+            EdnsStep::Full => {}
+            EdnsStep::NoDnssec | EdnsStep::MinimalBuffer => {
+                let edns = query.edns.as_mut().unwrap();
+                edns.dnssec_aware = false;
+                if self == EdnsStep::MinimalBuffer {
+                    edns.udp_size = edns.udp_size.min(MIN_UDP_SIZE);
+                }
+            }
+            EdnsStep::Disabled => {
+                query.edns = None;
+                query.recalculate_header();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct QueryStateConfig {
+    pub initial_timeout: Duration,
+    pub max_timeout: Duration,
+    pub max_attempts: usize,
+    //the whole query, all attempts included
+    pub lifetime: Duration,
+}
+
+impl Default for QueryStateConfig {
+    fn default() -> Self {
+        QueryStateConfig {
+            initial_timeout: Duration::from_millis(800),
+            max_timeout: Duration::from_secs(3),
+            max_attempts: 4,
+            lifetime: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryState {
+    config: QueryStateConfig,
+    deadline: Instant,
+    attempts: usize,
+    edns_step: EdnsStep,
+    transport: Transport,
+}
+
+impl QueryState {
+    pub fn new(config: QueryStateConfig, now: Instant) -> Self {
+        QueryState {
+            config,
+            deadline: now + config.lifetime,
+            attempts: 0,
+            edns_step: EdnsStep::Full,
+            transport: Transport::Udp,
+        }
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    pub fn edns_step(&self) -> EdnsStep {
+        self.edns_step
+    }
+
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.deadline.saturating_duration_since(now)
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now >= self.deadline
+    }
+
+    //starts the next attempt and returns how long to wait for it, the
+    //timeout doubles per attempt and never runs past the deadline
+    pub fn next_attempt(&mut self, now: Instant) -> Option<Duration> {
+        if self.attempts >= self.config.max_attempts || self.is_expired(now) {
+            return None;
+        }
+        let backoff = self
+            .config
+            .initial_timeout
+            .checked_mul(1 << self.attempts.min(31) as u32)
+            .unwrap_or(self.config.max_timeout);
+        self.attempts += 1;
+        Some(
+            backoff
+                .min(self.config.max_timeout)
+                .min(self.remaining(now)),
+        )
+    }
+
+    //false once edns is already off
+    pub fn downgrade_edns(&mut self) -> bool {
+        match self.edns_step.next() {
+            Some(step) => {
+                self.edns_step = step;
+                true
+            }
+            None => false,
+        }
+    }
+
+    //feeds the outcome of the last attempt to edns_fallback and moves to
+    //the step or transport it picks for the next one
+    pub fn on_outcome(&mut self, outcome: QueryOutcome) -> FallbackAction {
+        let action = edns_fallback(outcome, self.edns_step, self.transport);
+        match action {
+            FallbackAction::Downgrade(step) => self.edns_step = step,
+            FallbackAction::RetryOverTcp => self.transport = Transport::Tcp,
+            FallbackAction::Accept | FallbackAction::GiveUp => {}
+        }
+        action
+    }
+
+    pub fn apply(&self, query: &mut Message) {
+        self.edns_step.apply(query)
+    }
+
+    //the opt record a fresh query should carry at the current step
+    pub fn edns_config(&self, config: &EdnsConfig) -> Option<EdnsConfig> {
+        match self.edns_step {
+            EdnsStep::Full => Some(config.clone()),
+            EdnsStep::NoDnssec => Some(EdnsConfig {
+                dnssec_aware: false,
+                ..config.clone()
+            }),
+            EdnsStep::MinimalBuffer => Some(EdnsConfig {
+                dnssec_aware: false,
+                udp_size: config.udp_size.min(MIN_UDP_SIZE),
+                ..config.clone()
+            }),
+            EdnsStep::Disabled => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::header_flag::HeaderFlag;
+    use crate::name::Name;
+    use crate::rcode::Rcode;
+    use crate::rr_type::RRType;
+
+    #[test]
+    fn test_query_state() {
+        let now = Instant::now();
+        let mut state = QueryState::new(QueryStateConfig::default(), now);
+        let timeouts: Vec<Duration> = (0..5).filter_map(|_| state.next_attempt(now)).collect();
+        assert_eq!(
+            timeouts,
+            vec![
+                Duration::from_millis(800),
+                Duration::from_millis(1600),
+                Duration::from_secs(3),
+                Duration::from_secs(3),
+            ]
+        );
+        assert_eq!(state.attempts(), 4);
+
+        let mut state = QueryState::new(QueryStateConfig::default(), now);
+        let late = now + Duration::from_millis(9500);
+        assert_eq!(state.next_attempt(late), Some(Duration::from_millis(500)));
+        assert!(state.next_attempt(now + Duration::from_secs(10)).is_none());
+
+        let mut query =
+            Message::with_query(Name::new("example.com.").unwrap(), RRType::A).with_do_bit(true);
+        state.apply(&mut query);
+        assert_eq!(query.edns.as_ref().unwrap().udp_size, 1232);
+        assert!(state.downgrade_edns());
+        state.apply(&mut query);
+        assert!(!query.edns.as_ref().unwrap().dnssec_aware);
+        assert!(state.downgrade_edns());
+        state.apply(&mut query);
+        assert_eq!(query.edns.as_ref().unwrap().udp_size, 512);
+        assert!(state.downgrade_edns());
+        assert_eq!(state.edns_config(&EdnsConfig::default()), None);
+        state.apply(&mut query);
+        assert!(query.edns.is_none());
+        assert_eq!(query.header.ar_count, 0);
+        assert!(!state.downgrade_edns());
+        assert_eq!(state.edns_step(), EdnsStep::Disabled);
+    }
+
+    #[test]
+    fn test_query_state_fallback() {
+        let now = Instant::now();
+        let mut state = QueryState::new(QueryStateConfig::default(), now);
+        assert_eq!(
+            state.on_outcome(QueryOutcome::Timeout),
+            FallbackAction::Downgrade(EdnsStep::NoDnssec)
+        );
+        assert_eq!(state.edns_step(), EdnsStep::NoDnssec);
+
+        let mut formerr = Message::with_query(Name::new("example.com.").unwrap(), RRType::A);
+        formerr.header.rcode = Rcode::FormErr;
+        formerr.header.set_flag(HeaderFlag::QueryRespone, true);
+        assert_eq!(
+            state.on_outcome(QueryOutcome::Response(&formerr)),
+            FallbackAction::Downgrade(EdnsStep::Disabled)
+        );
+        assert_eq!(
+            state.on_outcome(QueryOutcome::Timeout),
+            FallbackAction::RetryOverTcp
+        );
+        assert_eq!(state.transport(), Transport::Tcp);
+        assert_eq!(
+            state.on_outcome(QueryOutcome::Timeout),
+            FallbackAction::GiveUp
+        );
+        assert_eq!(state.edns_step(), EdnsStep::Disabled);
+    }
+}
@@ -1,3 +1,7 @@
+use crate::edns::EdnsConfig;
+use crate::header::Header;
+use crate::header_flag::HeaderFlag;
+use crate::message::{Message, Section};
 use crate::message_render::MessageRender;
 use crate::name::Name;
 use crate::rr_class::RRClass;
@@ -40,12 +44,33 @@ impl Question {
         ]
         .join(" ")
     }
+
+    pub fn to_query_message(&self, flags: &[HeaderFlag], edns: Option<&EdnsConfig>) -> Message {
+        let mut header = Header {
+            id: rand::random::<u16>(),
+            ..Default::default()
+        };
+        for flag in flags {
+            header.set_flag(*flag, true);
+        }
+        let mut msg = Message {
+            header,
+            question: Some(self.clone()),
+            extra_questions: Vec::new(),
+            sections: [Section(None), Section(None), Section(None)],
+            edns: edns.map(EdnsConfig::to_edns),
+        };
+        msg.recalculate_header();
+        msg
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::rrset::RRset;
     use crate::util::hex::from_hex;
+    use std::str::FromStr;
 
     #[test]
     fn test_question_to_wire() {
@@ -63,4 +88,42 @@ mod test {
         desired_q.rend(&mut render);
         assert_eq!(raw.as_slice(), render.data());
     }
+
+    #[test]
+    fn test_question_to_query_message() {
+        let rrset = RRset::from_str("www.example.com. 300 IN A 192.0.2.1").unwrap();
+        let question = rrset.as_question();
+        assert_eq!(
+            question,
+            Question {
+                name: Name::new("www.example.com.").unwrap(),
+                typ: RRType::A,
+                class: RRClass::IN,
+            }
+        );
+
+        let msg = question.to_query_message(&[HeaderFlag::RecursionDesired], None);
+        assert_eq!(msg.question, Some(question.clone()));
+        assert!(msg.header.is_flag_set(HeaderFlag::RecursionDesired));
+        assert!(!msg.header.is_flag_set(HeaderFlag::QueryRespone));
+        assert_eq!(msg.header.qd_count, 1);
+        assert_eq!(msg.header.ar_count, 0);
+        assert!(msg.edns.is_none());
+
+        let config = EdnsConfig {
+            dnssec_aware: true,
+            ..Default::default()
+        };
+        let msg = question.to_query_message(&[HeaderFlag::CheckDisable], Some(&config));
+        assert!(!msg.header.is_flag_set(HeaderFlag::RecursionDesired));
+        assert!(msg.header.is_flag_set(HeaderFlag::CheckDisable));
+        assert_eq!(msg.header.ar_count, 1);
+        let edns = msg.edns.as_ref().unwrap();
+        assert_eq!(edns.udp_size, 1232);
+        assert!(edns.dnssec_aware);
+
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        assert_eq!(Message::from_wire(render.data()).unwrap(), msg);
+    }
 }
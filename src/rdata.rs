@@ -2,117 +2,140 @@ use crate::error::DNSError;
 use crate::message_render::MessageRender;
 use crate::rdata_a;
 use crate::rdata_aaaa;
+use crate::rdata_aname;
 use crate::rdata_cname;
 use crate::rdata_dname;
+use crate::rdata_dnskey;
+use crate::rdata_ds;
 use crate::rdata_mx;
 use crate::rdata_naptr;
 use crate::rdata_ns;
+use crate::rdata_nsec;
+use crate::rdata_nsec3;
+use crate::rdata_nsec3param;
 use crate::rdata_opt;
 use crate::rdata_ptr;
+use crate::rdata_rp;
+use crate::rdata_rrsig;
 use crate::rdata_soa;
 use crate::rdata_srv;
+use crate::rdata_svcb;
+use crate::rdata_tlsa;
 use crate::rdata_txt;
+use crate::rdata_zonemd;
 use crate::rdatafield_string_parser::Parser;
 use crate::rr_type::RRType;
 use crate::util::{InputBuffer, OutputBuffer};
 use failure::Result;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub enum RData {
-    A(rdata_a::A),
-    AAAA(rdata_aaaa::AAAA),
-    NS(Box<rdata_ns::NS>),
-    CName(Box<rdata_cname::CName>),
-    SOA(Box<rdata_soa::SOA>),
-    PTR(Box<rdata_ptr::PTR>),
-    MX(Box<rdata_mx::MX>),
-    NAPTR(Box<rdata_naptr::NAPTR>),
-    DName(Box<rdata_dname::DName>),
-    OPT(Box<rdata_opt::OPT>),
-    SRV(Box<rdata_srv::SRV>),
-    TXT(Box<rdata_txt::TXT>),
-}
+//small rdata is stored inline, everything else boxed to keep RData
+//small. adding a type is one entry here plus its module
+macro_rules! rdata_registry {
+    (
+        inline { $($ityp:ident => $ivariant:ident($irdata:path)),+ $(,)? }
+        boxed { $($btyp:ident => $bvariant:ident($brdata:path)),+ $(,)? }
+    ) => {
+        #[derive(Debug, Clone, Eq, PartialEq)]
+        pub enum RData {
+            $($ivariant($irdata),)+
+            $($bvariant(Box<$brdata>),)+
+        }
 
-impl RData {
-    pub fn from_wire(typ: RRType, buf: &mut InputBuffer, len: u16) -> Result<Self> {
-        let pos = buf.position();
-        let rdata = match typ {
-            RRType::A => rdata_a::A::from_wire(buf, len).map(RData::A),
-            RRType::AAAA => rdata_aaaa::AAAA::from_wire(buf, len).map(RData::AAAA),
-            RRType::NS => rdata_ns::NS::from_wire(buf, len).map(|ns| RData::NS(Box::new(ns))),
-            RRType::CNAME => {
-                rdata_cname::CName::from_wire(buf, len).map(|cname| RData::CName(Box::new(cname)))
+        pub const SUPPORTED_TYPES: &[RRType] = &[$(RRType::$ityp,)+ $(RRType::$btyp,)+];
+
+        impl RData {
+            fn from_wire_unchecked(typ: RRType, buf: &mut InputBuffer, len: u16) -> Result<Self> {
+                match typ {
+                    $(RRType::$ityp => <$irdata>::from_wire(buf, len).map(RData::$ivariant),)+
+                    $(RRType::$btyp => {
+                        <$brdata>::from_wire(buf, len).map(|rdata| RData::$bvariant(Box::new(rdata)))
+                    })+
+                    _ => Err(DNSError::UnknownRRType(typ.to_u16()).into()),
+                }
             }
-            RRType::SOA => rdata_soa::SOA::from_wire(buf, len).map(|soa| RData::SOA(Box::new(soa))),
-            RRType::PTR => rdata_ptr::PTR::from_wire(buf, len).map(|ptr| RData::PTR(Box::new(ptr))),
-            RRType::MX => rdata_mx::MX::from_wire(buf, len).map(|mx| RData::MX(Box::new(mx))),
-            RRType::NAPTR => {
-                rdata_naptr::NAPTR::from_wire(buf, len).map(|naptr| RData::NAPTR(Box::new(naptr)))
+
+            pub fn from_parser<'a>(typ: RRType, rdata_str: &mut Parser<'a>) -> Result<Self> {
+                match typ {
+                    $(RRType::$ityp => <$irdata>::from_str(rdata_str).map(RData::$ivariant),)+
+                    $(RRType::$btyp => {
+                        <$brdata>::from_str(rdata_str).map(|rdata| RData::$bvariant(Box::new(rdata)))
+                    })+
+                    _ => Err(DNSError::RRTypeIsNotSupport.into()),
+                }
             }
-            RRType::DNAME => {
-                rdata_dname::DName::from_wire(buf, len).map(|dname| RData::DName(Box::new(dname)))
+
+            pub fn typ(&self) -> RRType {
+                match *self {
+                    $(RData::$ivariant(_) => RRType::$ityp,)+
+                    $(RData::$bvariant(_) => RRType::$btyp,)+
+                }
             }
-            RRType::OPT => rdata_opt::OPT::from_wire(buf, len).map(|opt| RData::OPT(Box::new(opt))),
-            RRType::SRV => rdata_srv::SRV::from_wire(buf, len).map(|srv| RData::SRV(Box::new(srv))),
-            RRType::TXT => rdata_txt::TXT::from_wire(buf, len).map(|txt| RData::TXT(Box::new(txt))),
-            _ => Err(DNSError::UnknownRRType(typ.to_u16()).into()),
-        };
 
-        if rdata.is_ok() && buf.position() - pos != (len as usize) {
-            Err(DNSError::RdataLenIsNotCorrect.into())
-        } else {
-            rdata
-        }
-    }
+            pub fn rend(&self, render: &mut MessageRender) {
+                match *self {
+                    $(RData::$ivariant(ref rdata) => rdata.rend(render),)+
+                    $(RData::$bvariant(ref rdata) => rdata.rend(render),)+
+                }
+            }
 
-    pub fn rend(&self, render: &mut MessageRender) {
-        match *self {
-            RData::A(ref a) => a.rend(render),
-            RData::AAAA(ref aaaa) => aaaa.rend(render),
-            RData::NS(ref ns) => ns.rend(render),
-            RData::CName(ref cname) => cname.rend(render),
-            RData::SOA(ref soa) => soa.rend(render),
-            RData::PTR(ref ptr) => ptr.rend(render),
-            RData::MX(ref mx) => mx.rend(render),
-            RData::NAPTR(ref naptr) => naptr.rend(render),
-            RData::DName(ref dname) => dname.rend(render),
-            RData::OPT(ref opt) => opt.rend(render),
-            RData::SRV(ref srv) => srv.rend(render),
-            RData::TXT(ref txt) => txt.rend(render),
-        }
-    }
+            pub fn to_wire(&self, buf: &mut OutputBuffer) {
+                match *self {
+                    $(RData::$ivariant(ref rdata) => rdata.to_wire(buf),)+
+                    $(RData::$bvariant(ref rdata) => rdata.to_wire(buf),)+
+                }
+            }
 
-    pub fn to_wire(&self, buf: &mut OutputBuffer) {
-        match *self {
-            RData::A(ref a) => a.to_wire(buf),
-            RData::AAAA(ref aaaa) => aaaa.to_wire(buf),
-            RData::NS(ref ns) => ns.to_wire(buf),
-            RData::CName(ref cname) => cname.to_wire(buf),
-            RData::SOA(ref soa) => soa.to_wire(buf),
-            RData::PTR(ref ptr) => ptr.to_wire(buf),
-            RData::MX(ref mx) => mx.to_wire(buf),
-            RData::NAPTR(ref naptr) => naptr.to_wire(buf),
-            RData::DName(ref dname) => dname.to_wire(buf),
-            RData::OPT(ref opt) => opt.to_wire(buf),
-            RData::SRV(ref srv) => srv.to_wire(buf),
-            RData::TXT(ref txt) => txt.to_wire(buf),
+            pub fn to_string(&self) -> String {
+                match *self {
+                    $(RData::$ivariant(ref rdata) => rdata.to_string(),)+
+                    $(RData::$bvariant(ref rdata) => rdata.to_string(),)+
+                }
+            }
         }
+    };
+}
+
+rdata_registry! {
+    inline {
+        A => A(rdata_a::A),
+        AAAA => AAAA(rdata_aaaa::AAAA),
+    }
+    boxed {
+        NS => NS(rdata_ns::NS),
+        CNAME => CName(rdata_cname::CName),
+        SOA => SOA(rdata_soa::SOA),
+        PTR => PTR(rdata_ptr::PTR),
+        MX => MX(rdata_mx::MX),
+        NAPTR => NAPTR(rdata_naptr::NAPTR),
+        DNAME => DName(rdata_dname::DName),
+        OPT => OPT(rdata_opt::OPT),
+        SRV => SRV(rdata_srv::SRV),
+        TXT => TXT(rdata_txt::TXT),
+        RP => RP(rdata_rp::RP),
+        DS => DS(rdata_ds::DS),
+        DNSKEY => DNSKEY(rdata_dnskey::DNSKEY),
+        RRSIG => RRSIG(rdata_rrsig::RRSIG),
+        NSEC => NSEC(rdata_nsec::NSEC),
+        NSEC3 => NSEC3(rdata_nsec3::NSEC3),
+        NSEC3PARAM => NSEC3PARAM(rdata_nsec3param::NSEC3PARAM),
+        TLSA => TLSA(rdata_tlsa::TLSA),
+        CDS => CDS(rdata_ds::DS),
+        CDNSKEY => CDNSKEY(rdata_dnskey::DNSKEY),
+        SVCB => SVCB(rdata_svcb::SVCB),
+        HTTPS => HTTPS(rdata_svcb::SVCB),
+        ANAME => AName(rdata_aname::AName),
+        ZONEMD => ZONEMD(rdata_zonemd::ZONEMD),
     }
+}
 
-    pub fn to_string(&self) -> String {
-        match *self {
-            RData::A(ref a) => a.to_string(),
-            RData::AAAA(ref aaaa) => aaaa.to_string(),
-            RData::NS(ref ns) => ns.to_string(),
-            RData::CName(ref cname) => cname.to_string(),
-            RData::SOA(ref soa) => soa.to_string(),
-            RData::PTR(ref ptr) => ptr.to_string(),
-            RData::MX(ref mx) => mx.to_string(),
-            RData::NAPTR(ref naptr) => naptr.to_string(),
-            RData::DName(ref dname) => dname.to_string(),
-            RData::OPT(ref opt) => opt.to_string(),
-            RData::SRV(ref srv) => srv.to_string(),
-            RData::TXT(ref txt) => txt.to_string(),
+impl RData {
+    pub fn from_wire(typ: RRType, buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let pos = buf.position();
+        let rdata = Self::from_wire_unchecked(typ, buf, len);
+        if rdata.is_ok() && buf.position() - pos != (len as usize) {
+            Err(DNSError::RdataLenIsNotCorrect.into())
+        } else {
+            rdata
         }
     }
 
@@ -120,28 +143,61 @@ impl RData {
         let mut labels = Parser::new(s.trim());
         Self::from_parser(typ, &mut labels)
     }
+}
 
-    pub fn from_parser<'a>(typ: RRType, rdata_str: &mut Parser<'a>) -> Result<Self> {
-        match typ {
-            RRType::A => rdata_a::A::from_str(rdata_str).map(RData::A),
-            RRType::AAAA => rdata_aaaa::AAAA::from_str(rdata_str).map(RData::AAAA),
-            RRType::NS => rdata_ns::NS::from_str(rdata_str).map(|ns| RData::NS(Box::new(ns))),
-            RRType::CNAME => {
-                rdata_cname::CName::from_str(rdata_str).map(|cname| RData::CName(Box::new(cname)))
-            }
-            RRType::SOA => rdata_soa::SOA::from_str(rdata_str).map(|soa| RData::SOA(Box::new(soa))),
-            RRType::PTR => rdata_ptr::PTR::from_str(rdata_str).map(|ptr| RData::PTR(Box::new(ptr))),
-            RRType::MX => rdata_mx::MX::from_str(rdata_str).map(|mx| RData::MX(Box::new(mx))),
-            RRType::NAPTR => {
-                rdata_naptr::NAPTR::from_str(rdata_str).map(|naptr| RData::NAPTR(Box::new(naptr)))
-            }
-            RRType::DNAME => {
-                rdata_dname::DName::from_str(rdata_str).map(|dname| RData::DName(Box::new(dname)))
-            }
-            RRType::OPT => rdata_opt::OPT::from_str(rdata_str).map(|opt| RData::OPT(Box::new(opt))),
-            RRType::SRV => rdata_srv::SRV::from_str(rdata_str).map(|srv| RData::SRV(Box::new(srv))),
-            RRType::TXT => rdata_txt::TXT::from_str(rdata_str).map(|txt| RData::TXT(Box::new(txt))),
-            _ => Err(DNSError::RRTypeIsNotSupport.into()),
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    //every type RData can parse needs a wire and presentation vector here
+    #[test]
+    fn test_rdata_conformance() {
+        let checked = rdata_conformance! {
+            A, "192.0.2.1", "c0000201";
+            AAAA, "2001:db8::1", "20010db8000000000000000000000001";
+            NS, "ns1.example.com.", "036e7331076578616d706c6503636f6d00";
+            CNAME, "www.example.com.", "03777777076578616d706c6503636f6d00";
+            SOA,
+            "ns.example.com. root.example.com. 1 7200 3600 1209600 300",
+            "026e73076578616d706c6503636f6d0004726f6f74076578616d706c6503636f6d000000000100001c2000000e10001275000000012c";
+            PTR, "host.example.com.", "04686f7374076578616d706c6503636f6d00";
+            MX, "10 mail.example.com.", "000a046d61696c076578616d706c6503636f6d00";
+            NAPTR,
+            "100 10 \"u\" \"E2U+sip\" \"!^.*$!sip:info@example.com!\" .",
+            "0064000a0175074532552b7369701b215e2e2a24217369703a696e666f406578616d706c652e636f6d2100";
+            DNAME, "example.net.", "076578616d706c65036e657400";
+            OPT, "000a00080102030405060708", "000a00080102030405060708";
+            SRV, "0 5 5060 sip.example.com.", "0000000513c403736970076578616d706c6503636f6d00";
+            TXT, "\"v=spf1 -all\"", "0b763d73706631202d616c6c";
+            RP, "admin.example. .", "0561646d696e076578616d706c650000";
+            DS, "60485 5 1 2bb183af5f22588179a53b0a98631fad1a292118", "ec4505012bb183af5f22588179a53b0a98631fad1a292118";
+            DNSKEY, "256 3 13 AQID", "0100030d010203";
+            RRSIG, "NS 8 0 3600 20300101000000 20200101000000 1 . AQID", "0002080000000e1070dbd8805e0be100000100010203";
+            NSEC, "b.example. A NSEC", "0162076578616d706c65000006400000000001";
+            NSEC3, "1 0 10 - 00 A", "0100000a000100000140";
+            NSEC3PARAM, "1 0 10 -", "0100000a00";
+            TLSA, "3 1 1 0102", "0301010102";
+            CDS, "60485 5 2 0102", "ec4505020102";
+            CDNSKEY, "257 3 13 AQID", "0101030d010203";
+            SVCB, "1 svc.example. port=80", "000103737663076578616d706c6500000300020050";
+            HTTPS, "0 svc.example.", "000003737663076578616d706c6500";
+            ANAME, "lb.example.net.", "026c62076578616d706c65036e657400";
+            ZONEMD, "2018031900 1 1 0102", "7848b91c01010102";
+        };
+
+        for typ in SUPPORTED_TYPES {
+            assert!(checked.contains(typ), "{} has no conformance vector", typ);
+        }
+        for value in 0..=u16::MAX {
+            let typ = RRType::new(value);
+            let err = RData::from_wire(typ, &mut InputBuffer::new(&[]), 0).err();
+            let unknown = err.map_or(false, |e| {
+                matches!(
+                    e.downcast_ref::<DNSError>(),
+                    Some(DNSError::UnknownRRType(_))
+                )
+            });
+            assert_eq!(unknown, !SUPPORTED_TYPES.contains(&typ), "{}", typ);
         }
     }
 }
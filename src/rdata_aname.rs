@@ -0,0 +1,122 @@
+use crate::error::DNSError;
+use crate::message_render::MessageRender;
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::rdatafield_string_parser::Parser;
+use crate::rr_type::RRType;
+use crate::rrset::{RRTtl, RRset};
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AName {
+    pub target: Name,
+}
+
+impl AName {
+    pub fn from_wire(buf: &mut InputBuffer, _len: u16) -> Result<Self> {
+        Name::from_wire(buf).map(|name| AName { target: name })
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let target = iter.next_field::<Name>("AName", "Name")?;
+        Ok(AName { target })
+    }
+
+    //new types must not be compressed, rfc3597 section 4
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_name(&self.target, false);
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        self.target.to_wire(buf);
+    }
+
+    pub fn to_string(&self) -> String {
+        self.target.to_string()
+    }
+}
+
+pub trait ANameResolver {
+    fn resolve(&self, target: &Name, typ: RRType) -> Option<RRset>;
+}
+
+//the address rrset of the target is served under the aname owner, with
+//the ttl clamped so the flattened answer never outlives either record
+pub fn flatten<R: ANameResolver + ?Sized>(
+    aname: &RRset,
+    typ: RRType,
+    resolver: &R,
+) -> Result<Option<RRset>> {
+    let target = match aname.rdatas.first() {
+        Some(RData::AName(ref a)) if aname.typ == RRType::ANAME => &a.target,
+        _ => return Err(DNSError::ANameNotApplicable.into()),
+    };
+    if typ != RRType::A && typ != RRType::AAAA {
+        return Err(DNSError::ANameNotApplicable.into());
+    }
+    if target.eq(&aname.name) {
+        return Err(DNSError::CNameLoop.into());
+    }
+
+    let resolved = match resolver.resolve(target, typ) {
+        Some(rrset) if rrset.typ == typ && !rrset.rdatas.is_empty() => rrset,
+        _ => return Ok(None),
+    };
+    Ok(Some(RRset {
+        name: aname.name.clone(),
+        typ,
+        class: aname.class,
+        ttl: RRTtl(aname.ttl.0.min(resolved.ttl.0)),
+        rdatas: resolved.rdatas,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::hex::from_hex;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    struct StaticResolver(HashMap<(Name, u16), RRset>);
+
+    impl ANameResolver for StaticResolver {
+        fn resolve(&self, target: &Name, typ: RRType) -> Option<RRset> {
+            self.0.get(&(target.clone(), typ.to_u16())).cloned()
+        }
+    }
+
+    #[test]
+    fn test_flatten_aname() {
+        let aname = RRset::from_str("example.com. 300 IN ANAME lb.cdn.example.net.").unwrap();
+        assert_eq!(aname.typ, RRType::ANAME);
+
+        let mut render = MessageRender::new();
+        aname.rdatas[0].rend(&mut render);
+        assert_eq!(
+            render.data(),
+            from_hex("026c620363646e076578616d706c65036e657400")
+                .unwrap()
+                .as_slice()
+        );
+
+        let target = Name::new("lb.cdn.example.net.").unwrap();
+        let a = RRset::from_str("lb.cdn.example.net. 60 IN A 192.0.2.1").unwrap();
+        let mut records = HashMap::new();
+        records.insert((target, RRType::A.to_u16()), a.clone());
+        let resolver = StaticResolver(records);
+
+        let flattened = flatten(&aname, RRType::A, &resolver).unwrap().unwrap();
+        assert_eq!(flattened.name, aname.name);
+        assert_eq!(flattened.typ, RRType::A);
+        assert_eq!(flattened.ttl, RRTtl(60));
+        assert_eq!(flattened.rdatas, a.rdatas);
+
+        assert_eq!(flatten(&aname, RRType::AAAA, &resolver).unwrap(), None);
+        assert!(flatten(&aname, RRType::MX, &resolver).is_err());
+        assert!(flatten(&a, RRType::A, &resolver).is_err());
+        let looped = RRset::from_str("example.com. 300 IN ANAME example.com.").unwrap();
+        assert!(flatten(&looped, RRType::A, &resolver).is_err());
+    }
+}
@@ -35,5 +35,10 @@ impl CName {
 #[cfg(test)]
 mod test {
     #[test]
-    fn test_cname_to_wire() {}
+    fn test_cname_to_wire() {
+        rdata_conformance! {
+            CNAME, "www.example.com.", "03777777076578616d706c6503636f6d00";
+            CNAME, "a\\.b.example.", "03612e62076578616d706c6500";
+        };
+    }
 }
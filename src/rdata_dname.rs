@@ -1,6 +1,11 @@
+use crate::error::DNSError;
 use crate::message_render::MessageRender;
 use crate::name::Name;
+use crate::rdata::RData;
+use crate::rdata_cname::CName;
 use crate::rdatafield_string_parser::Parser;
+use crate::rr_type::RRType;
+use crate::rrset::RRset;
 use crate::util::{InputBuffer, OutputBuffer};
 use failure::Result;
 
@@ -9,6 +14,12 @@ pub struct DName {
     pub target: Name,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DNameSynthesis {
+    CName(RRset),
+    YXDomain,
+}
+
 impl DName {
     pub fn from_wire(buf: &mut InputBuffer, _len: u16) -> Result<Self> {
         Name::from_wire(buf).map(|name| DName { target: name })
@@ -31,3 +42,68 @@ impl DName {
         self.target.to_string()
     }
 }
+
+//rfc6672: the synthesized cname replaces the dname owner suffix of qname
+//with the dname target, and inherits the ttl of the dname rrset
+pub fn synthesize_cname(dname: &RRset, qname: &Name) -> Result<DNameSynthesis> {
+    let target = match dname.rdatas.first() {
+        Some(RData::DName(ref d)) if dname.typ == RRType::DNAME => &d.target,
+        _ => return Err(DNSError::DNameNotApplicable.into()),
+    };
+
+    if qname.label_count() <= dname.name.label_count() || !qname.is_subdomain(&dname.name) {
+        return Err(DNSError::DNameNotApplicable.into());
+    }
+
+    let prefix = qname.strip_right(dname.name.label_count() - 1);
+    let name = match prefix.concat(target) {
+        Ok(name) => name,
+        Err(e) => match e.downcast_ref::<DNSError>() {
+            Some(DNSError::TooLongName) | Some(DNSError::TooLongLabel) => {
+                return Ok(DNameSynthesis::YXDomain)
+            }
+            _ => return Err(e),
+        },
+    };
+
+    Ok(DNameSynthesis::CName(RRset {
+        name: qname.clone(),
+        typ: RRType::CNAME,
+        class: dname.class,
+        ttl: dname.ttl,
+        rdatas: vec![RData::CName(Box::new(CName { name }))],
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_synthesize_cname() {
+        let dname = RRset::from_str("example.com. 300 IN DNAME example.net.").unwrap();
+        let qname = Name::new("www.sub.example.com.").unwrap();
+        assert_eq!(
+            synthesize_cname(&dname, &qname).unwrap(),
+            DNameSynthesis::CName(
+                RRset::from_str("www.sub.example.com. 300 IN CNAME www.sub.example.net.").unwrap()
+            )
+        );
+
+        assert!(synthesize_cname(&dname, &Name::new("example.com.").unwrap()).is_err());
+        assert!(synthesize_cname(&dname, &Name::new("www.example.org.").unwrap()).is_err());
+        let cname = RRset::from_str("example.com. 300 IN CNAME example.net.").unwrap();
+        assert!(synthesize_cname(&cname, &qname).is_err());
+
+        let long_label = "a".repeat(63);
+        let long_target = [long_label.as_str(); 3].join(".");
+        let dname =
+            RRset::from_str(&format!("example.com. 300 IN DNAME {}.", long_target)).unwrap();
+        let qname = Name::new(&format!("{}.{}.example.com.", long_label, long_label)).unwrap();
+        assert_eq!(
+            synthesize_cname(&dname, &qname).unwrap(),
+            DNameSynthesis::YXDomain
+        );
+    }
+}
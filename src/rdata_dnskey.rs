@@ -0,0 +1,220 @@
+use crate::message_render::MessageRender;
+use crate::rdatafield_string_parser::Parser;
+use crate::util::base64::to_base64;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+const FLAG_ZONE_KEY: u16 = 0x0100;
+const FLAG_REVOKE: u16 = 0x0080;
+const FLAG_SECURE_ENTRY_POINT: u16 = 0x0001;
+const DNSSEC_PROTOCOL: u8 = 3;
+const ALGORITHM_RSAMD5: u8 = 1;
+
+//rfc4034 section 2
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DNSKEY {
+    pub flags: u16,
+    pub protocol: u8,
+    pub algorithm: u8,
+    pub public_key: Vec<u8>,
+}
+
+impl DNSKEY {
+    pub fn is_zone_key(&self) -> bool {
+        self.flags & FLAG_ZONE_KEY != 0
+    }
+
+    //rfc5011 section 7
+    pub fn is_revoked(&self) -> bool {
+        self.flags & FLAG_REVOKE != 0
+    }
+
+    pub fn is_secure_entry_point(&self) -> bool {
+        self.flags & FLAG_SECURE_ENTRY_POINT != 0
+    }
+
+    //the sep flag is only a hint, rfc4034 section 2.1.1
+    pub fn is_ksk(&self) -> bool {
+        self.is_zone_key() && self.is_secure_entry_point()
+    }
+
+    pub fn is_zsk(&self) -> bool {
+        self.is_zone_key() && !self.is_secure_entry_point()
+    }
+
+    //rfc4034 appendix b, the revoke flag is part of the rdata so a revoked
+    //key gets a new tag
+    pub fn key_tag(&self) -> u16 {
+        if self.algorithm == ALGORITHM_RSAMD5 {
+            let len = self.public_key.len();
+            if len < 3 {
+                return 0;
+            }
+            return u16::from_be_bytes([self.public_key[len - 3], self.public_key[len - 2]]);
+        }
+
+        let mut buf = OutputBuffer::new(4 + self.public_key.len());
+        self.to_wire(&mut buf);
+        let mut ac: u32 = 0;
+        for (i, &b) in buf.data().iter().enumerate() {
+            ac += if i & 1 == 0 {
+                u32::from(b) << 8
+            } else {
+                u32::from(b)
+            };
+        }
+        ac += (ac >> 16) & 0xffff;
+        (ac & 0xffff) as u16
+    }
+
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let flags = buf.read_u16()?;
+        let protocol = buf.read_u8()?;
+        let algorithm = buf.read_u8()?;
+        let public_key = buf.read_bytes((len as usize).saturating_sub(4))?.to_vec();
+        Ok(DNSKEY {
+            flags,
+            protocol,
+            algorithm,
+            public_key,
+        })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_u16(self.flags);
+        render.write_u8(self.protocol);
+        render.write_u8(self.algorithm);
+        render.write_bytes(self.public_key.as_slice());
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u16(self.flags);
+        buf.write_u8(self.protocol);
+        buf.write_u8(self.algorithm);
+        buf.write_bytes(self.public_key.as_slice());
+    }
+
+    pub fn to_string(&self) -> String {
+        [
+            self.flags.to_string(),
+            self.protocol.to_string(),
+            self.algorithm.to_string(),
+            to_base64(&self.public_key),
+        ]
+        .join(" ")
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let flags = iter.next_field::<u16>("DNSKEY", "flags")?;
+        let protocol = iter.next_field::<u8>("DNSKEY", "protocol")?;
+        let algorithm = iter.next_field::<u8>("DNSKEY", "algorithm")?;
+        let public_key = iter.next_base64_rest("DNSKEY", "public_key")?;
+        Ok(DNSKEY {
+            flags,
+            protocol,
+            algorithm,
+            public_key,
+        })
+    }
+}
+
+//the keys which may have made a signature with the key tag and algorithm,
+//tags collide so more than one key can be returned and each has to be tried
+pub fn candidate_keys(keys: &[DNSKEY], key_tag: u16, algorithm: u8) -> Vec<&DNSKEY> {
+    keys.iter()
+        .filter(|key| {
+            key.is_zone_key()
+                && key.protocol == DNSSEC_PROTOCOL
+                && key.algorithm == algorithm
+                && key.key_tag() == key_tag
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rdata::RData;
+    use crate::rr_type::RRType;
+    use crate::util::hex::to_hex;
+
+    #[test]
+    fn test_dnskey_to_wire() {
+        rdata_conformance! {
+            DNSKEY, "257 3 8 AwEAAQ==", "0101030803010001";
+            //rfc8078 section 4
+            CDNSKEY, "0 3 0 AA==", "0000030000";
+        };
+
+        //rfc4034 section 2.3, wrapped the way zone files do
+        let key = RData::from_str(
+            RRType::DNSKEY,
+            "256 3 5 AQPSKmynfzW4kyBv015MUG2DeIQ3 Cbl+BBZH4b/0PY1kxkmvHjcZc8no \
+             kfzj31GajIQKY+5CptLr3buXA10h WqTkF7H6RfoRqXQeogmMHfpftf6z \
+             Mv1LyBUgia7za6ZEzOJBOztyvhjL 742iU/TpPSEDhm2SNKLijfUppn1U aNvv4w==",
+        )
+        .unwrap();
+        let mut buf = OutputBuffer::new(0);
+        key.to_wire(&mut buf);
+        assert_eq!(
+            to_hex(buf.data()),
+            "010003050103d22a6ca77f35b893206fd35e4c506d8378843709b97e041647e1bff43d8d64c649af1e371973c9e891fce3df519a8c840a63ee42a6d2ebddbb97035d215aa4e417b1fa45fa11a9741ea2098c1dfa5fb5feb332fd4bc8152089aef36ba644cce2413b3b72be18cbef8da253f4e93d2103866d9234a2e28df529a67d5468dbefe3"
+        );
+        match key {
+            RData::DNSKEY(ref key) => {
+                let key = key.as_ref();
+                assert!(key.is_zone_key());
+                assert!(!key.is_secure_entry_point());
+                assert!(!key.is_revoked());
+                assert!(key.is_zsk());
+                assert!(!key.is_ksk());
+                //the tag the rrsig in rfc4034 section 3.3 refers to
+                assert_eq!(key.key_tag(), 2642);
+
+                let mut ksk = key.clone();
+                ksk.flags |= FLAG_SECURE_ENTRY_POINT;
+                assert!(ksk.is_ksk());
+                let mut other_algorithm = key.clone();
+                other_algorithm.algorithm = 8;
+                let mut not_zone_key = key.clone();
+                not_zone_key.flags = 0;
+                let keys = vec![
+                    ksk.clone(),
+                    other_algorithm,
+                    not_zone_key,
+                    key.clone(),
+                    key.clone(),
+                ];
+                let candidates = candidate_keys(&keys, 2642, 5);
+                assert_eq!(candidates, vec![key, key]);
+                assert_eq!(candidate_keys(&keys, ksk.key_tag(), 5), vec![&ksk]);
+                assert!(candidate_keys(&keys, 2642, 13).is_empty());
+            }
+            _ => unreachable!(),
+        }
+
+        //rfc4034 section 5.4
+        let key = RData::from_str(
+            RRType::DNSKEY,
+            "256 3 5 AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/ \
+             2pHm822aJ5iI9BMzNXxeYCmZDRD99WYwYqUSdjMmmAphXdvxegXd/ \
+             M5+X7OrzKBaMbCVdFLUUh6DhweJBjEVv5f2wwjM9XzcnOf+EPbtG9DMBmADjFDc2w/rljwvFw==",
+        )
+        .unwrap();
+        match key {
+            RData::DNSKEY(ref key) => assert_eq!(key.key_tag(), 60485),
+            _ => unreachable!(),
+        }
+
+        let md5 = DNSKEY {
+            flags: 257,
+            protocol: 3,
+            algorithm: 1,
+            public_key: vec![1, 2, 3, 4, 5],
+        };
+        assert_eq!(md5.key_tag(), 0x0304);
+
+        assert!(RData::from_str(RRType::DNSKEY, "257 3 8").is_err());
+        assert!(RData::from_str(RRType::DNSKEY, "257 3 8 AwEAAQ=").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::error::DNSError;
+use crate::message_render::MessageRender;
+use crate::name::Name;
+use crate::rdata_dnskey::DNSKEY;
+use crate::rdatafield_string_parser::Parser;
+use crate::util::digest;
+use crate::util::hex::to_hex;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+pub const DIGEST_SHA1: u8 = 1;
+pub const DIGEST_SHA256: u8 = 2;
+pub const DIGEST_SHA384: u8 = 4;
+
+//rfc4034 section 5
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DS {
+    pub key_tag: u16,
+    pub algorithm: u8,
+    pub digest_type: u8,
+    pub digest: Vec<u8>,
+}
+
+impl DS {
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let key_tag = buf.read_u16()?;
+        let algorithm = buf.read_u8()?;
+        let digest_type = buf.read_u8()?;
+        let digest = buf.read_bytes((len as usize).saturating_sub(4))?.to_vec();
+        Ok(DS {
+            key_tag,
+            algorithm,
+            digest_type,
+            digest,
+        })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_u16(self.key_tag);
+        render.write_u8(self.algorithm);
+        render.write_u8(self.digest_type);
+        render.write_bytes(self.digest.as_slice());
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u16(self.key_tag);
+        buf.write_u8(self.algorithm);
+        buf.write_u8(self.digest_type);
+        buf.write_bytes(self.digest.as_slice());
+    }
+
+    pub fn to_string(&self) -> String {
+        [
+            self.key_tag.to_string(),
+            self.algorithm.to_string(),
+            self.digest_type.to_string(),
+            to_hex(&self.digest),
+        ]
+        .join(" ")
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let key_tag = iter.next_field::<u16>("DS", "key_tag")?;
+        let algorithm = iter.next_field::<u8>("DS", "algorithm")?;
+        let digest_type = iter.next_field::<u8>("DS", "digest_type")?;
+        let digest = iter.next_hex_rest("DS", "digest")?;
+        Ok(DS {
+            key_tag,
+            algorithm,
+            digest_type,
+            digest,
+        })
+    }
+}
+
+//rfc4034 section 5.1.4, the digest covers the canonical owner name and
+//the dnskey rdata
+pub fn ds_from_dnskey(owner: &Name, dnskey: &DNSKEY, digest_alg: u8) -> Result<DS> {
+    let mut owner = owner.clone();
+    owner.to_lowercase();
+    let mut buf = OutputBuffer::new(owner.len() + 4 + dnskey.public_key.len());
+    owner.to_wire(&mut buf);
+    dnskey.to_wire(&mut buf);
+
+    let digest = match digest_alg {
+        DIGEST_SHA1 => digest::sha1(buf.data()),
+        DIGEST_SHA256 => digest::sha256(buf.data()).to_vec(),
+        DIGEST_SHA384 => digest::sha384(buf.data()),
+        _ => return Err(DNSError::UnknownDigestType(digest_alg).into()),
+    };
+    Ok(DS {
+        key_tag: dnskey.key_tag(),
+        algorithm: dnskey.algorithm,
+        digest_type: digest_alg,
+        digest,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rdata::RData;
+    use crate::rr_type::RRType;
+
+    #[test]
+    fn test_ds_to_wire() {
+        //rfc4034 section 5.4
+        rdata_conformance! {
+            DS,
+            "60485 5 1 2bb183af5f22588179a53b0a98631fad1a292118",
+            "ec4505012bb183af5f22588179a53b0a98631fad1a292118";
+            //rfc8078 section 4, the cds that asks for the ds to be removed
+            CDS, "0 0 0 00", "0000000000";
+        };
+
+        let ds = RData::from_str(
+            RRType::DS,
+            "60485 5 1 2BB183AF5F22588179A5 3B0A98631FAD1A292118",
+        )
+        .unwrap();
+        assert_eq!(
+            ds.to_string(),
+            "60485 5 1 2bb183af5f22588179a53b0a98631fad1a292118"
+        );
+        assert!(RData::from_str(RRType::DS, "60485 5 1").is_err());
+        assert!(RData::from_str(RRType::DS, "65536 5 1 2b").is_err());
+    }
+
+    fn dnskey(s: &str) -> DNSKEY {
+        match RData::from_str(RRType::DNSKEY, s).unwrap() {
+            RData::DNSKEY(key) => *key,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_ds_from_dnskey() {
+        //rfc4034 section 5.4
+        let owner = Name::new("DSKEY.example.com.").unwrap();
+        let key = dnskey(
+            "256 3 5 AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/2pHm822aJ5iI9BMzNXxeYCmZDRD99WYwYqUSdjMmmAphXdvxegXd/M5+X7OrzKBaMbCVdFLUUh6DhweJBjEVv5f2wwjM9XzcnOf+EPbtG9DMBmADjFDc2w/rljwvFw==",
+        );
+        assert_eq!(
+            ds_from_dnskey(&owner, &key, DIGEST_SHA1)
+                .unwrap()
+                .to_string(),
+            "60485 5 1 2bb183af5f22588179a53b0a98631fad1a292118"
+        );
+        assert_eq!(
+            ds_from_dnskey(&owner, &key, DIGEST_SHA256)
+                .unwrap()
+                .to_string(),
+            "60485 5 2 d4b7d520e7bb5f0f67674a0cceb1e3e0614b93c4f9e99b8383f6a1e4469da50a"
+        );
+
+        //rfc6605 section 6.2
+        let owner = Name::new("example.net.").unwrap();
+        let key = dnskey("257 3 14 xKYaNhWdGOfJ+nPrL8/arkwf2EY3MDJ+SErKivBVSum1w/egsXvSADtNJhyem5RCOpgQ6K8X1DRSEkrbYQ+OB+v8/uX45NBwY8rp65F6Glur8I/mlVNgF6W/qTI37m40");
+        assert_eq!(
+            to_hex(&ds_from_dnskey(&owner, &key, DIGEST_SHA384).unwrap().digest),
+            "72d7b62976ce06438e9c0bf319013cf801f09ecc84b8d7e9495f27e305c6a9b0563a9b5f4d288405c3008a946df983d6"
+        );
+        assert!(ds_from_dnskey(&owner, &key, 3).is_err());
+    }
+}
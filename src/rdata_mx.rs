@@ -41,5 +41,11 @@ impl MX {
 #[cfg(test)]
 mod test {
     #[test]
-    fn test_mx_to_wire() {}
+    fn test_mx_to_wire() {
+        rdata_conformance! {
+            MX, "10 mail.example.com.", "000a046d61696c076578616d706c6503636f6d00";
+            //rfc7505 null mx
+            MX, "0 .", "000000";
+        };
+    }
 }
@@ -1,15 +1,16 @@
 use crate::message_render::MessageRender;
 use crate::name::Name;
 use crate::rdatafield_string_parser::Parser;
-use crate::util::{InputBuffer, OutputBuffer};
+use crate::util::{CharString, InputBuffer, OutputBuffer};
 use failure::Result;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct NAPTR {
     pub order: u16,
     pub preference: u16,
-    pub flags: u16,
-    pub services: u16,
+    pub flags: CharString,
+    pub services: CharString,
+    pub regexp: CharString,
     pub replacement: Name,
 }
 
@@ -17,31 +18,36 @@ impl NAPTR {
     pub fn from_wire(buf: &mut InputBuffer, _len: u16) -> Result<Self> {
         let order = buf.read_u16()?;
         let preference = buf.read_u16()?;
-        let flags = buf.read_u16()?;
-        let services = buf.read_u16()?;
+        let flags = CharString::from_wire(buf)?;
+        let services = CharString::from_wire(buf)?;
+        let regexp = CharString::from_wire(buf)?;
         let replacement = Name::from_wire(buf)?;
         Ok(NAPTR {
             order,
             preference,
             flags,
             services,
+            regexp,
             replacement,
         })
     }
 
+    //the replacement is never compressed, rfc3597 section 4
     pub fn rend(&self, render: &mut MessageRender) {
         render.write_u16(self.order);
         render.write_u16(self.preference);
-        render.write_u16(self.flags);
-        render.write_u16(self.services);
-        render.write_name(&self.replacement, true);
+        self.flags.rend(render);
+        self.services.rend(render);
+        self.regexp.rend(render);
+        render.write_name(&self.replacement, false);
     }
 
     pub fn to_wire(&self, buf: &mut OutputBuffer) {
         buf.write_u16(self.order);
         buf.write_u16(self.preference);
-        buf.write_u16(self.flags);
-        buf.write_u16(self.services);
+        self.flags.to_wire(buf);
+        self.services.to_wire(buf);
+        self.regexp.to_wire(buf);
         self.replacement.to_wire(buf);
     }
 
@@ -51,6 +57,7 @@ impl NAPTR {
             self.preference.to_string(),
             self.flags.to_string(),
             self.services.to_string(),
+            self.regexp.to_string(),
             self.replacement.to_string(),
         ]
         .join(" ")
@@ -59,14 +66,16 @@ impl NAPTR {
     pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
         let order = iter.next_field::<u16>("NAPTR", "order")?;
         let preference = iter.next_field::<u16>("NAPTR", "preference")?;
-        let flags = iter.next_field::<u16>("NAPTR", "flags")?;
-        let services = iter.next_field::<u16>("NAPTR", "services")?;
+        let flags = iter.next_char_string("NAPTR", "flags")?;
+        let services = iter.next_char_string("NAPTR", "services")?;
+        let regexp = iter.next_char_string("NAPTR", "regexp")?;
         let replacement = iter.next_field::<Name>("NAPTR", "replacement")?;
         Ok(NAPTR {
             order,
             preference,
             flags,
             services,
+            regexp,
             replacement,
         })
     }
@@ -75,5 +84,15 @@ impl NAPTR {
 #[cfg(test)]
 mod test {
     #[test]
-    fn test_naptr_to_wire() {}
+    fn test_naptr_to_wire() {
+        //rfc3403 section 6.2 and rfc3761
+        rdata_conformance! {
+            NAPTR,
+            "100 10 \"u\" \"E2U+sip\" \"!^.*$!sip:info@example.com!\" .",
+            "0064000a0175074532552b7369701b215e2e2a24217369703a696e666f406578616d706c652e636f6d2100";
+            NAPTR,
+            "100 50 \"s\" \"http+I2L+I2C+I2R\" \"\" _http._tcp.example.com.",
+            "00640032017310687474702b49324c2b4932432b49325200055f68747470045f746370076578616d706c6503636f6d00";
+        };
+    }
 }
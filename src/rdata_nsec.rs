@@ -0,0 +1,79 @@
+use crate::error::DNSError;
+use crate::message_render::MessageRender;
+use crate::name::Name;
+use crate::rdatafield_string_parser::Parser;
+use crate::type_bitmap::TypeBitmap;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+//rfc4034 section 4
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NSEC {
+    pub next_domain: Name,
+    pub types: TypeBitmap,
+}
+
+impl NSEC {
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let start = buf.position();
+        let next_domain = Name::from_wire(buf)?;
+        let read_len = buf.position() - start;
+        if read_len > len as usize {
+            return Err(DNSError::RdataLenIsNotCorrect.into());
+        }
+        let types = TypeBitmap::from_wire(buf, len - read_len as u16)?;
+        Ok(NSEC { next_domain, types })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        //rfc4034 section 4.1.1, the next domain must not be compressed
+        render.write_name(&self.next_domain, false);
+        self.types.rend(render);
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        self.next_domain.to_wire(buf);
+        self.types.to_wire(buf);
+    }
+
+    pub fn to_string(&self) -> String {
+        let types = self.types.to_string();
+        if types.is_empty() {
+            self.next_domain.to_string()
+        } else {
+            [self.next_domain.to_string(), types].join(" ")
+        }
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let next_domain = iter.next_field::<Name>("NSEC", "next_domain")?;
+        let types = TypeBitmap::from_str(iter)?;
+        Ok(NSEC { next_domain, types })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rdata::RData;
+    use crate::rr_type::RRType;
+    use crate::util::hex::from_hex;
+
+    #[test]
+    fn test_nsec_to_wire() {
+        //rfc4034 section 4.3
+        rdata_conformance! {
+            NSEC,
+            "host.example.com. A MX RRSIG NSEC TYPE1234",
+            "04686f7374076578616d706c6503636f6d000006400100000003041b000000000000000000000000000000000000000000000000000020";
+            NSEC, "example.", "076578616d706c6500";
+        };
+
+        let nsec = RData::from_str(RRType::NSEC, "host.example.com. a mx").unwrap();
+        assert_eq!(nsec.to_string(), "host.example.com. A MX");
+        assert!(RData::from_str(RRType::NSEC, "host.example.com. A BOGUS").is_err());
+
+        let raw = from_hex("04686f737400").unwrap();
+        assert!(NSEC::from_wire(&mut InputBuffer::new(&raw), 3).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::error::DNSError;
+use crate::message_render::MessageRender;
+use crate::rdatafield_string_parser::Parser;
+use crate::type_bitmap::TypeBitmap;
+use crate::util::base32::{from_base32hex, to_base32hex};
+use crate::util::hex::{from_hex, to_hex};
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+const FLAG_OPT_OUT: u8 = 0x01;
+const NO_SALT: &str = "-";
+const MAX_FIELD_LEN: usize = 255;
+
+//the salt is a length octet and the salt, "-" in text when empty.
+//Shared with nsec3param
+pub(crate) fn salt_from_wire(buf: &mut InputBuffer) -> Result<Vec<u8>> {
+    let len = buf.read_u8()?;
+    buf.read_bytes(len as usize).map(|salt| salt.to_vec())
+}
+
+pub(crate) fn salt_to_wire(salt: &[u8], buf: &mut OutputBuffer) {
+    buf.write_u8(salt.len() as u8);
+    buf.write_bytes(salt);
+}
+
+pub(crate) fn rend_salt(salt: &[u8], render: &mut MessageRender) {
+    render.write_u8(salt.len() as u8);
+    render.write_bytes(salt);
+}
+
+pub(crate) fn salt_to_string(salt: &[u8]) -> String {
+    if salt.is_empty() {
+        NO_SALT.to_string()
+    } else {
+        to_hex(salt)
+    }
+}
+
+pub(crate) fn salt_from_str(iter: &mut Parser, rr_type: &'static str) -> Result<Vec<u8>> {
+    let invalid = |reason: &str| DNSError::InvalidRdataString(rr_type, "salt", reason.to_string());
+    match iter.next_string() {
+        Some(NO_SALT) => Ok(Vec::new()),
+        Some(s) => from_hex(s)
+            .filter(|salt| !salt.is_empty() && salt.len() <= MAX_FIELD_LEN)
+            .ok_or_else(|| invalid("invalid hex").into()),
+        None => Err(invalid("empty").into()),
+    }
+}
+
+//rfc5155 section 3
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NSEC3 {
+    pub hash_algorithm: u8,
+    pub flags: u8,
+    pub iterations: u16,
+    pub salt: Vec<u8>,
+    pub next_hashed: Vec<u8>,
+    pub types: TypeBitmap,
+}
+
+impl NSEC3 {
+    pub fn is_opt_out(&self) -> bool {
+        self.flags & FLAG_OPT_OUT != 0
+    }
+
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let start = buf.position();
+        let hash_algorithm = buf.read_u8()?;
+        let flags = buf.read_u8()?;
+        let iterations = buf.read_u16()?;
+        let salt = salt_from_wire(buf)?;
+        let hash_len = buf.read_u8()?;
+        let next_hashed = buf.read_bytes(hash_len as usize)?.to_vec();
+        let read_len = buf.position() - start;
+        if read_len > len as usize {
+            return Err(DNSError::RdataLenIsNotCorrect.into());
+        }
+        let types = TypeBitmap::from_wire(buf, len - read_len as u16)?;
+        Ok(NSEC3 {
+            hash_algorithm,
+            flags,
+            iterations,
+            salt,
+            next_hashed,
+            types,
+        })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_u8(self.hash_algorithm);
+        render.write_u8(self.flags);
+        render.write_u16(self.iterations);
+        rend_salt(&self.salt, render);
+        render.write_u8(self.next_hashed.len() as u8);
+        render.write_bytes(self.next_hashed.as_slice());
+        self.types.rend(render);
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u8(self.hash_algorithm);
+        buf.write_u8(self.flags);
+        buf.write_u16(self.iterations);
+        salt_to_wire(&self.salt, buf);
+        buf.write_u8(self.next_hashed.len() as u8);
+        buf.write_bytes(self.next_hashed.as_slice());
+        self.types.to_wire(buf);
+    }
+
+    pub fn to_string(&self) -> String {
+        let mut fields = vec![
+            self.hash_algorithm.to_string(),
+            self.flags.to_string(),
+            self.iterations.to_string(),
+            salt_to_string(&self.salt),
+            to_base32hex(&self.next_hashed),
+        ];
+        if !self.types.is_empty() {
+            fields.push(self.types.to_string());
+        }
+        fields.join(" ")
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let hash_algorithm = iter.next_field::<u8>("NSEC3", "hash_algorithm")?;
+        let flags = iter.next_field::<u8>("NSEC3", "flags")?;
+        let iterations = iter.next_field::<u16>("NSEC3", "iterations")?;
+        let salt = salt_from_str(iter, "NSEC3")?;
+        let next_hashed = iter
+            .next_string()
+            .and_then(from_base32hex)
+            .filter(|hash| !hash.is_empty() && hash.len() <= MAX_FIELD_LEN)
+            .ok_or_else(|| {
+                DNSError::InvalidRdataString(
+                    "NSEC3",
+                    "next_hashed",
+                    "invalid base32hex".to_string(),
+                )
+            })?;
+        let types = TypeBitmap::from_str(iter)?;
+        Ok(NSEC3 {
+            hash_algorithm,
+            flags,
+            iterations,
+            salt,
+            next_hashed,
+            types,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rdata::RData;
+    use crate::rr_type::RRType;
+
+    #[test]
+    fn test_nsec3_to_wire() {
+        //rfc5155 appendix a
+        rdata_conformance! {
+            NSEC3,
+            "1 1 12 aabbccdd 2t7b4g4vsa5smi47k61mv5bv1a22bojr NS SOA MX RRSIG DNSKEY NSEC3PARAM",
+            "0101000c04aabbccdd14174eb2409fe28bcb4887a1836f957f0a8425e27b000722010000000290";
+            NSEC3, "1 0 0 - 00", "01000000000100";
+        };
+
+        let nsec3 = RData::from_str(
+            RRType::NSEC3,
+            "1 1 12 AABBCCDD 2T7B4G4VSA5SMI47K61MV5BV1A22BOJR mx",
+        )
+        .unwrap();
+        assert_eq!(
+            nsec3.to_string(),
+            "1 1 12 aabbccdd 2t7b4g4vsa5smi47k61mv5bv1a22bojr MX"
+        );
+        match nsec3 {
+            RData::NSEC3(ref nsec3) => assert!(nsec3.is_opt_out()),
+            _ => unreachable!(),
+        }
+
+        for invalid in &[
+            "1 1 12 aabbccdd",
+            "1 1 12 aabbccd 2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+            "1 1 12 aabbccdd 2t7b4g4vsa5smi47k61mv5bv1a22bojw",
+            "1 1 12 aabbccdd 2t7b4g4vsa5smi47k61mv5bv1a22bojr BOGUS",
+        ] {
+            assert!(
+                RData::from_str(RRType::NSEC3, invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::message_render::MessageRender;
+use crate::rdata_nsec3::{rend_salt, salt_from_str, salt_from_wire, salt_to_string, salt_to_wire};
+use crate::rdatafield_string_parser::Parser;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+//rfc5155 section 4
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NSEC3PARAM {
+    pub hash_algorithm: u8,
+    pub flags: u8,
+    pub iterations: u16,
+    pub salt: Vec<u8>,
+}
+
+impl NSEC3PARAM {
+    pub fn from_wire(buf: &mut InputBuffer, _len: u16) -> Result<Self> {
+        let hash_algorithm = buf.read_u8()?;
+        let flags = buf.read_u8()?;
+        let iterations = buf.read_u16()?;
+        let salt = salt_from_wire(buf)?;
+        Ok(NSEC3PARAM {
+            hash_algorithm,
+            flags,
+            iterations,
+            salt,
+        })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_u8(self.hash_algorithm);
+        render.write_u8(self.flags);
+        render.write_u16(self.iterations);
+        rend_salt(&self.salt, render);
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u8(self.hash_algorithm);
+        buf.write_u8(self.flags);
+        buf.write_u16(self.iterations);
+        salt_to_wire(&self.salt, buf);
+    }
+
+    pub fn to_string(&self) -> String {
+        [
+            self.hash_algorithm.to_string(),
+            self.flags.to_string(),
+            self.iterations.to_string(),
+            salt_to_string(&self.salt),
+        ]
+        .join(" ")
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let hash_algorithm = iter.next_field::<u8>("NSEC3PARAM", "hash_algorithm")?;
+        let flags = iter.next_field::<u8>("NSEC3PARAM", "flags")?;
+        let iterations = iter.next_field::<u16>("NSEC3PARAM", "iterations")?;
+        let salt = salt_from_str(iter, "NSEC3PARAM")?;
+        Ok(NSEC3PARAM {
+            hash_algorithm,
+            flags,
+            iterations,
+            salt,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message::Message;
+    use crate::rdata::RData;
+    use crate::rr_type::RRType;
+    use crate::rrset::RRset;
+    use crate::util::hex::from_hex;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_nsec3param_to_wire() {
+        //rfc5155 appendix a
+        rdata_conformance! {
+            NSEC3PARAM, "1 0 12 aabbccdd", "0100000c04aabbccdd";
+            NSEC3PARAM, "1 0 0 -", "0100000000";
+        };
+        assert!(RData::from_str(RRType::NSEC3PARAM, "1 0 12").is_err());
+        assert!(RData::from_str(RRType::NSEC3PARAM, "1 0 12 abc").is_err());
+
+        let rrset = RRset::from_str("example. 3600 IN NSEC3PARAM 1 0 12 aabbccdd").unwrap();
+        let raw = from_hex(
+            "04d285000001000100000000076578616d706c650000330001c00c0033000100000e1000090100000c04aabbccdd",
+        )
+        .unwrap();
+        let msg = Message::from_wire(&raw).unwrap();
+        assert_eq!(msg.sections[0].0.as_ref().unwrap()[0].rdatas, rrset.rdatas);
+    }
+}
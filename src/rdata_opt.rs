@@ -37,5 +37,9 @@ impl OPT {
 #[cfg(test)]
 mod test {
     #[test]
-    fn test_opt_to_wire() {}
+    fn test_opt_to_wire() {
+        rdata_conformance! {
+            OPT, "000a00080102030405060708", "000a00080102030405060708";
+        };
+    }
 }
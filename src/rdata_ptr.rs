@@ -35,5 +35,9 @@ impl PTR {
 #[cfg(test)]
 mod test {
     #[test]
-    fn test_ptr_to_wire() {}
+    fn test_ptr_to_wire() {
+        rdata_conformance! {
+            PTR, "1.2.0.192.in-addr.arpa.", "0131013201300331393207696e2d61646472046172706100";
+        };
+    }
 }
@@ -0,0 +1,75 @@
+use crate::message_render::MessageRender;
+use crate::name::Name;
+use crate::rdatafield_string_parser::Parser;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+//rfc1183 section 2.2, "." stands for a missing mailbox or txt record
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RP {
+    pub mbox: Name,
+    pub txt: Name,
+}
+
+impl RP {
+    pub fn from_wire(buf: &mut InputBuffer, _len: u16) -> Result<Self> {
+        let mbox = Name::from_wire(buf)?;
+        let txt = Name::from_wire(buf)?;
+        Ok(RP { mbox, txt })
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let mbox = iter.next_field::<Name>("RP", "mbox")?;
+        let txt = iter.next_field::<Name>("RP", "txt")?;
+        Ok(RP { mbox, txt })
+    }
+
+    //rfc3597 section 4, rp isn't among the types whose names are compressed
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_name(&self.mbox, false);
+        render.write_name(&self.txt, false);
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        self.mbox.to_wire(buf);
+        self.txt.to_wire(buf);
+    }
+
+    pub fn to_string(&self) -> String {
+        [self.mbox.to_string(), self.txt.to_string()].join(" ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rdata::RData;
+    use crate::rr_type::RRType;
+    use crate::util::hex::from_hex;
+
+    #[test]
+    fn test_rp_to_wire() {
+        rdata_conformance! {
+            //rfc1183 section 2.2
+            RP,
+            "louie.trantor.umd.edu. lam1.people.umd.edu.",
+            "056c6f756965077472616e746f7203756d640365647500046c616d310670656f706c6503756d640365647500";
+            RP, "admin.example.com. .", "0561646d696e076578616d706c6503636f6d0000";
+        };
+
+        //both names stay uncompressed even after the owner is rendered
+        let rp = RData::from_str(RRType::RP, "admin.example. info.example.").unwrap();
+        let mut render = MessageRender::new();
+        render.write_name(&Name::new("example.").unwrap(), true);
+        let start = render.len();
+        rp.rend(&mut render);
+        assert_eq!(
+            &render.data()[start..],
+            from_hex("0561646d696e076578616d706c650004696e666f076578616d706c6500")
+                .unwrap()
+                .as_slice()
+        );
+
+        assert!(RData::from_str(RRType::RP, "admin.example.").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::error::DNSError;
+use crate::message_render::MessageRender;
+use crate::name::Name;
+use crate::rdata_dnskey::{self, DNSKEY};
+use crate::rdatafield_string_parser::Parser;
+use crate::rr_type::RRType;
+use crate::type_bitmap::{type_from_str, type_to_string};
+use crate::util::base64::to_base64;
+use crate::util::time::{format_timestamp, is_valid_period, parse_timestamp};
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+//rfc4034 section 3
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RRSIG {
+    pub type_covered: RRType,
+    pub algorithm: u8,
+    pub labels: u8,
+    pub original_ttl: u32,
+    pub expiration: u32,
+    pub inception: u32,
+    pub key_tag: u16,
+    pub signer: Name,
+    pub signature: Vec<u8>,
+}
+
+fn next_timestamp(iter: &mut Parser, field_name: &'static str) -> Result<u32> {
+    iter.next_string()
+        .ok_or_else(|| {
+            DNSError::InvalidRdataString("RRSIG", field_name, "empty".to_string()).into()
+        })
+        .and_then(parse_timestamp)
+}
+
+impl RRSIG {
+    pub fn is_valid_at(&self, now: u32) -> bool {
+        is_valid_period(self.inception, self.expiration, now)
+    }
+
+    pub fn candidate_keys<'a>(&self, keys: &'a [DNSKEY]) -> Vec<&'a DNSKEY> {
+        rdata_dnskey::candidate_keys(keys, self.key_tag, self.algorithm)
+    }
+
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let start = buf.position();
+        let type_covered = RRType::from_wire(buf)?;
+        let algorithm = buf.read_u8()?;
+        let labels = buf.read_u8()?;
+        let original_ttl = buf.read_u32()?;
+        let expiration = buf.read_u32()?;
+        let inception = buf.read_u32()?;
+        let key_tag = buf.read_u16()?;
+        let signer = Name::from_wire(buf)?;
+        let read_len = buf.position() - start;
+        if read_len > len as usize {
+            return Err(DNSError::RdataLenIsNotCorrect.into());
+        }
+        let signature = buf.read_bytes(len as usize - read_len)?.to_vec();
+        Ok(RRSIG {
+            type_covered,
+            algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            key_tag,
+            signer,
+            signature,
+        })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        self.type_covered.rend(render);
+        render.write_u8(self.algorithm);
+        render.write_u8(self.labels);
+        render.write_u32(self.original_ttl);
+        render.write_u32(self.expiration);
+        render.write_u32(self.inception);
+        render.write_u16(self.key_tag);
+        //rfc4034 section 3.1.7, the signer name must not be compressed
+        render.write_name(&self.signer, false);
+        render.write_bytes(self.signature.as_slice());
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        self.type_covered.to_wire(buf);
+        buf.write_u8(self.algorithm);
+        buf.write_u8(self.labels);
+        buf.write_u32(self.original_ttl);
+        buf.write_u32(self.expiration);
+        buf.write_u32(self.inception);
+        buf.write_u16(self.key_tag);
+        self.signer.to_wire(buf);
+        buf.write_bytes(self.signature.as_slice());
+    }
+
+    pub fn to_string(&self) -> String {
+        [
+            type_to_string(self.type_covered),
+            self.algorithm.to_string(),
+            self.labels.to_string(),
+            self.original_ttl.to_string(),
+            format_timestamp(self.expiration),
+            format_timestamp(self.inception),
+            self.key_tag.to_string(),
+            self.signer.to_string(),
+            to_base64(&self.signature),
+        ]
+        .join(" ")
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let type_covered = iter
+            .next_string()
+            .ok_or_else(|| {
+                DNSError::InvalidRdataString("RRSIG", "type_covered", "empty".to_string()).into()
+            })
+            .and_then(type_from_str)?;
+        let algorithm = iter.next_field::<u8>("RRSIG", "algorithm")?;
+        let labels = iter.next_field::<u8>("RRSIG", "labels")?;
+        let original_ttl = iter.next_field::<u32>("RRSIG", "original_ttl")?;
+        let expiration = next_timestamp(iter, "expiration")?;
+        let inception = next_timestamp(iter, "inception")?;
+        let key_tag = iter.next_field::<u16>("RRSIG", "key_tag")?;
+        let signer = iter.next_field::<Name>("RRSIG", "signer")?;
+        let signature = iter.next_base64_rest("RRSIG", "signature")?;
+        Ok(RRSIG {
+            type_covered,
+            algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            key_tag,
+            signer,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rdata::RData;
+    use crate::util::hex::from_hex;
+
+    #[test]
+    fn test_rrsig_to_wire() {
+        //rfc4034 section 3.3
+        rdata_conformance! {
+            RRSIG,
+            "A 5 3 86400 20030322173103 20030220173103 2642 example.com. oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTrPYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6oB9wfuh3DTJXUAfI/M0zmO/zz8bW0Rznl8O3tGNazPwQKkRN20XPXV6nwwfoXmJQbsLNrLfkGJ5D6fwFm8nN+6pBzeDQfsS3Ap3o=",
+            "00010503000151803e7c9dd73e5510d70a52076578616d706c6503636f6d00a090755ba58d1affa576f4375831b4310920e481218d18a9f164eb3d81afd3b875d3c75428631e0cf2a28d50875f70c329d7dbfafea807dc1fba1dc34c95d401f23f334ce63bfcf3f1b5b44739e5f0eded18d6b33f040a911376d173d757a9f0c1fa1798941bb0b36b2df9062790fa7f0166f2737eea907378341fb12dc0a77a";
+            RRSIG,
+            "TYPE1234 13 2 300 20380119031408 20380119031407 1 example. AQID",
+            "04d20d020000012c800000007fffffff0001076578616d706c6500010203";
+        };
+
+        let rrsig = RData::from_str(
+            RRType::RRSIG,
+            "a 5 3 86400 1048354263 1045762263 2642 example.com. oJB1W6WNGv+ldvQ3 WDG0MQkg5IEh",
+        )
+        .unwrap();
+        match rrsig {
+            RData::RRSIG(ref rrsig) => {
+                assert_eq!(rrsig.type_covered, RRType::A);
+                assert!(rrsig.is_valid_at(1_048_354_263));
+                assert!(!rrsig.is_valid_at(1_048_354_264));
+                assert!(!rrsig.is_valid_at(1_045_762_262));
+            }
+            _ => unreachable!(),
+        }
+
+        for invalid in &[
+            "A 5 3 86400 20030322173103 20030220173103 2642 example.com.",
+            "A 5 3 86400 20030332173103 20030220173103 2642 example.com. AQID",
+            "BOGUS 5 3 86400 20030322173103 20030220173103 2642 example.com. AQID",
+            "A 5 3 86400",
+        ] {
+            assert!(
+                RData::from_str(RRType::RRSIG, invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+
+        let raw = from_hex("0001050300015180").unwrap();
+        assert!(RRSIG::from_wire(&mut InputBuffer::new(&raw), raw.len() as u16).is_err());
+        let raw = from_hex("00010503000151803e7c9dd73e5510d70a5200").unwrap();
+        assert!(RRSIG::from_wire(&mut InputBuffer::new(&raw), 10).is_err());
+    }
+}
@@ -26,11 +26,12 @@ impl SRV {
         })
     }
 
+    //rfc2782, the target must not be compressed
     pub fn rend(&self, render: &mut MessageRender) {
         render.write_u16(self.priority);
         render.write_u16(self.weight);
         render.write_u16(self.port);
-        render.write_name(&self.target, true);
+        render.write_name(&self.target, false);
     }
 
     pub fn to_wire(&self, buf: &mut OutputBuffer) {
@@ -85,5 +86,10 @@ mod test {
         srv.rend(&mut render);
         assert_eq!(raw.as_slice(), render.data());
         assert_eq!(srv.to_string(), "12 10 53 www.baidu.com.");
+
+        let mut render = MessageRender::new();
+        render.write_name(&Name::new("www.baidu.com").unwrap(), true);
+        srv.rend(&mut render);
+        assert_eq!(&render.data()[15..], raw.as_slice());
     }
 }
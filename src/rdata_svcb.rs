@@ -0,0 +1,754 @@
+use crate::error::DNSError;
+use crate::message_render::MessageRender;
+use crate::name::Name;
+use crate::presentation;
+use crate::rdata::RData;
+use crate::rdatafield_string_parser::Parser;
+use crate::rr_type::RRType;
+use crate::rrset::RRset;
+use crate::util::base64::{from_base64, to_base64};
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+use std::convert::TryInto;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::from_utf8;
+
+const GENERIC_KEY_PREFIX: &str = "key";
+
+//rfc9460 section 3 asks for a limit on alias chains without naming one
+pub const MAX_ALIAS_CHAIN: usize = 8;
+
+pub const KEY_MANDATORY: u16 = 0;
+pub const KEY_ALPN: u16 = 1;
+pub const KEY_NO_DEFAULT_ALPN: u16 = 2;
+pub const KEY_PORT: u16 = 3;
+pub const KEY_IPV4HINT: u16 = 4;
+pub const KEY_ECH: u16 = 5;
+pub const KEY_IPV6HINT: u16 = 6;
+
+const KEY_NAMES: &[(u16, &str)] = &[
+    (KEY_MANDATORY, "mandatory"),
+    (KEY_ALPN, "alpn"),
+    (KEY_NO_DEFAULT_ALPN, "no-default-alpn"),
+    (KEY_PORT, "port"),
+    (KEY_IPV4HINT, "ipv4hint"),
+    (KEY_ECH, "ech"),
+    (KEY_IPV6HINT, "ipv6hint"),
+];
+
+fn invalid_param(reason: String) -> failure::Error {
+    DNSError::InvalidSvcParam(reason).into()
+}
+
+pub fn key_to_string(key: u16) -> String {
+    KEY_NAMES.iter().find(|(k, _)| *k == key).map_or_else(
+        || format!("{}{}", GENERIC_KEY_PREFIX, key),
+        |(_, name)| name.to_string(),
+    )
+}
+
+//a mnemonic or the generic keyNNNNN form
+pub fn key_from_str(key: &str) -> Result<u16> {
+    if let Some((k, _)) = KEY_NAMES.iter().find(|(_, name)| *name == key) {
+        return Ok(*k);
+    }
+    key.strip_prefix(GENERIC_KEY_PREFIX)
+        .filter(|num| !num.is_empty() && num.bytes().all(|c| c.is_ascii_digit()))
+        .and_then(|num| num.parse::<u16>().ok())
+        .ok_or_else(|| {
+            DNSError::InvalidRdataString("SVCB", "params", format!("unknown key {}", key)).into()
+        })
+}
+
+//rfc9460 appendix a.1, items are split on commas which a backslash
+//escapes, after the character string itself is unescaped
+fn split_value_list(value: &[u8]) -> Vec<Vec<u8>> {
+    let mut items = vec![Vec::new()];
+    let mut iter = value.iter();
+    while let Some(&c) = iter.next() {
+        match c {
+            b'\\' => {
+                if let Some(&next) = iter.next() {
+                    items.last_mut().unwrap().push(next);
+                }
+            }
+            b',' => items.push(Vec::new()),
+            _ => items.last_mut().unwrap().push(c),
+        }
+    }
+    items
+}
+
+fn join_value_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut value = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            value.push(b',');
+        }
+        for &c in item {
+            if c == b',' || c == b'\\' {
+                value.push(b'\\');
+            }
+            value.push(c);
+        }
+    }
+    value
+}
+
+fn addrs_from_wire<T, const N: usize>(value: &[u8], to_addr: fn([u8; N]) -> T) -> Result<Vec<T>> {
+    if value.is_empty() || value.len() % N != 0 {
+        return Err(invalid_param(format!(
+            "address hint of {} bytes",
+            value.len()
+        )));
+    }
+    Ok(value
+        .chunks(N)
+        .map(|chunk| to_addr(chunk.try_into().unwrap()))
+        .collect())
+}
+
+fn addrs_from_str<T: std::str::FromStr>(value: &[u8]) -> Result<Vec<T>> {
+    split_value_list(value)
+        .iter()
+        .map(|item| {
+            from_utf8(item)
+                .ok()
+                .and_then(|item| item.parse::<T>().ok())
+                .ok_or_else(|| invalid_param("invalid address hint".to_string()))
+        })
+        .collect()
+}
+
+fn addrs_to_string<T: ToString>(addrs: &[T]) -> String {
+    addrs
+        .iter()
+        .map(|addr| addr.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SvcParam {
+    Mandatory(Vec<u16>),
+    Alpn(Vec<Vec<u8>>),
+    NoDefaultAlpn,
+    Port(u16),
+    Ipv4Hint(Vec<Ipv4Addr>),
+    Ech(Vec<u8>),
+    Ipv6Hint(Vec<Ipv6Addr>),
+    Unknown(u16, Vec<u8>),
+}
+
+impl SvcParam {
+    pub fn key(&self) -> u16 {
+        match *self {
+            SvcParam::Mandatory(_) => KEY_MANDATORY,
+            SvcParam::Alpn(_) => KEY_ALPN,
+            SvcParam::NoDefaultAlpn => KEY_NO_DEFAULT_ALPN,
+            SvcParam::Port(_) => KEY_PORT,
+            SvcParam::Ipv4Hint(_) => KEY_IPV4HINT,
+            SvcParam::Ech(_) => KEY_ECH,
+            SvcParam::Ipv6Hint(_) => KEY_IPV6HINT,
+            SvcParam::Unknown(key, _) => key,
+        }
+    }
+
+    //the value of one param in wire format, checked against rfc9460 section 7
+    pub fn from_wire(key: u16, value: &[u8]) -> Result<Self> {
+        match key {
+            KEY_MANDATORY => {
+                if value.is_empty() || value.len() % 2 != 0 {
+                    return Err(invalid_param("mandatory key list".to_string()));
+                }
+                let keys = value
+                    .chunks(2)
+                    .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+                    .collect::<Vec<_>>();
+                if keys.windows(2).any(|pair| pair[0] >= pair[1]) {
+                    return Err(invalid_param("mandatory keys out of order".to_string()));
+                }
+                Ok(SvcParam::Mandatory(keys))
+            }
+            KEY_ALPN => {
+                let mut ids = Vec::new();
+                let mut buf = InputBuffer::new(value);
+                while buf.position() < value.len() {
+                    let len = buf.read_u8()?;
+                    if len == 0 {
+                        return Err(invalid_param("empty alpn id".to_string()));
+                    }
+                    ids.push(buf.read_bytes(len as usize)?.to_vec());
+                }
+                if ids.is_empty() {
+                    return Err(invalid_param("empty alpn".to_string()));
+                }
+                Ok(SvcParam::Alpn(ids))
+            }
+            KEY_NO_DEFAULT_ALPN => {
+                if !value.is_empty() {
+                    return Err(invalid_param("no-default-alpn has a value".to_string()));
+                }
+                Ok(SvcParam::NoDefaultAlpn)
+            }
+            KEY_PORT => {
+                if value.len() != 2 {
+                    return Err(invalid_param(format!("port of {} bytes", value.len())));
+                }
+                Ok(SvcParam::Port(u16::from_be_bytes([value[0], value[1]])))
+            }
+            KEY_IPV4HINT => addrs_from_wire(value, Ipv4Addr::from).map(SvcParam::Ipv4Hint),
+            KEY_ECH => Ok(SvcParam::Ech(value.to_vec())),
+            KEY_IPV6HINT => addrs_from_wire(value, Ipv6Addr::from).map(SvcParam::Ipv6Hint),
+            _ => Ok(SvcParam::Unknown(key, value.to_vec())),
+        }
+    }
+
+    pub fn value_to_wire(&self) -> Vec<u8> {
+        match self {
+            SvcParam::Mandatory(keys) => keys.iter().flat_map(|key| key.to_be_bytes()).collect(),
+            SvcParam::Alpn(ids) => {
+                let mut value = Vec::new();
+                for id in ids {
+                    value.push(id.len() as u8);
+                    value.extend_from_slice(id);
+                }
+                value
+            }
+            SvcParam::NoDefaultAlpn => Vec::new(),
+            SvcParam::Port(port) => port.to_be_bytes().to_vec(),
+            SvcParam::Ipv4Hint(addrs) => addrs.iter().flat_map(|addr| addr.octets()).collect(),
+            SvcParam::Ech(config) => config.clone(),
+            SvcParam::Ipv6Hint(addrs) => addrs.iter().flat_map(|addr| addr.octets()).collect(),
+            SvcParam::Unknown(_, value) => value.clone(),
+        }
+    }
+
+    //value is the unescaped presentation value, None for a bare key
+    pub fn from_presentation(key: u16, value: Option<&[u8]>) -> Result<Self> {
+        let value = match value {
+            None if key == KEY_NO_DEFAULT_ALPN => return Ok(SvcParam::NoDefaultAlpn),
+            None => return SvcParam::from_wire(key, &[]),
+            Some(value) => value,
+        };
+        match key {
+            KEY_MANDATORY => {
+                let mut keys = split_value_list(value)
+                    .iter()
+                    .map(|item| {
+                        from_utf8(item)
+                            .map_err(|_| invalid_param("invalid mandatory key".to_string()))
+                            .and_then(key_from_str)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                keys.sort_unstable();
+                if keys.windows(2).any(|pair| pair[0] == pair[1]) {
+                    return Err(invalid_param("duplicate mandatory key".to_string()));
+                }
+                Ok(SvcParam::Mandatory(keys))
+            }
+            KEY_ALPN => {
+                let ids = split_value_list(value);
+                if ids.iter().any(|id| id.is_empty() || id.len() > 255) {
+                    return Err(invalid_param("invalid alpn id".to_string()));
+                }
+                Ok(SvcParam::Alpn(ids))
+            }
+            KEY_NO_DEFAULT_ALPN if value.is_empty() => Ok(SvcParam::NoDefaultAlpn),
+            KEY_NO_DEFAULT_ALPN => Err(invalid_param("no-default-alpn has a value".to_string())),
+            KEY_PORT => from_utf8(value)
+                .ok()
+                .and_then(|port| port.parse::<u16>().ok())
+                .map(SvcParam::Port)
+                .ok_or_else(|| invalid_param("invalid port".to_string())),
+            KEY_IPV4HINT => addrs_from_str(value).map(SvcParam::Ipv4Hint),
+            KEY_ECH => from_utf8(value)
+                .ok()
+                .and_then(from_base64)
+                .map(SvcParam::Ech)
+                .ok_or_else(|| invalid_param("invalid ech base64".to_string())),
+            KEY_IPV6HINT => addrs_from_str(value).map(SvcParam::Ipv6Hint),
+            _ => Ok(SvcParam::Unknown(key, value.to_vec())),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        let key = key_to_string(self.key());
+        match self {
+            SvcParam::Mandatory(keys) => {
+                let keys = keys.iter().map(|k| key_to_string(*k)).collect::<Vec<_>>();
+                format!("{}={}", key, keys.join(","))
+            }
+            SvcParam::Alpn(ids) => {
+                format!(
+                    "{}={}",
+                    key,
+                    presentation::quote_char_string(&join_value_list(ids))
+                )
+            }
+            SvcParam::NoDefaultAlpn => key,
+            SvcParam::Port(port) => format!("{}={}", key, port),
+            SvcParam::Ipv4Hint(addrs) => format!("{}={}", key, addrs_to_string(addrs)),
+            SvcParam::Ech(config) => format!("{}={}", key, to_base64(config)),
+            SvcParam::Ipv6Hint(addrs) => format!("{}={}", key, addrs_to_string(addrs)),
+            SvcParam::Unknown(_, value) if value.is_empty() => key,
+            SvcParam::Unknown(_, value) => {
+                format!("{}={}", key, presentation::quote_char_string(value))
+            }
+        }
+    }
+}
+
+//rfc9460 section 2, shared by svcb and https. The params are kept in
+//wire order, which is strictly increasing by key
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SVCB {
+    pub priority: u16,
+    pub target: Name,
+    pub params: Vec<SvcParam>,
+}
+
+impl SVCB {
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let start = buf.position();
+        let priority = buf.read_u16()?;
+        let target = Name::from_wire(buf)?;
+        let mut params: Vec<SvcParam> = Vec::new();
+        while buf.position() - start < len as usize {
+            let key = buf.read_u16()?;
+            let value_len = buf.read_u16()?;
+            let value = buf.read_bytes(value_len as usize)?;
+            if params.last().map_or(false, |last| last.key() >= key) {
+                return Err(invalid_param(format!(
+                    "{} isn't in increasing order",
+                    key_to_string(key)
+                )));
+            }
+            params.push(SvcParam::from_wire(key, value)?);
+        }
+        if buf.position() - start != len as usize {
+            return Err(DNSError::RdataLenIsNotCorrect.into());
+        }
+        Ok(SVCB {
+            priority,
+            target,
+            params,
+        })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_u16(self.priority);
+        //rfc9460 section 2.2, the target name is never compressed
+        render.write_name(&self.target, false);
+        for param in &self.params {
+            let value = param.value_to_wire();
+            render.write_u16(param.key());
+            render.write_u16(value.len() as u16);
+            render.write_bytes(&value);
+        }
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u16(self.priority);
+        self.target.to_wire(buf);
+        for param in &self.params {
+            let value = param.value_to_wire();
+            buf.write_u16(param.key());
+            buf.write_u16(value.len() as u16);
+            buf.write_bytes(&value);
+        }
+    }
+
+    pub fn param(&self, key: u16) -> Option<&SvcParam> {
+        self.params.iter().find(|param| param.key() == key)
+    }
+
+    pub fn is_alias_mode(&self) -> bool {
+        self.priority == 0
+    }
+
+    pub fn is_service_mode(&self) -> bool {
+        !self.is_alias_mode()
+    }
+
+    //rfc9460 section 2.5, a "." target means there is no service in alias
+    //mode and the owner name itself in service mode
+    pub fn effective_target<'a>(&'a self, owner: &'a Name) -> Option<&'a Name> {
+        if !self.target.is_root() {
+            Some(&self.target)
+        } else if self.is_service_mode() {
+            Some(owner)
+        } else {
+            None
+        }
+    }
+
+    //rfc9460 section 8, every key in mandatory has to be present and
+    //mandatory can't list itself. Params of an alias record are ignored
+    pub fn validate_params(&self) -> Result<()> {
+        if self.is_alias_mode() {
+            return Ok(());
+        }
+        if let Some(SvcParam::Mandatory(keys)) = self.param(KEY_MANDATORY) {
+            for &key in keys {
+                if key == KEY_MANDATORY {
+                    return Err(invalid_param("mandatory lists itself".to_string()));
+                }
+                if self.param(key).is_none() {
+                    return Err(invalid_param(format!(
+                        "mandatory {} is missing",
+                        key_to_string(key)
+                    )));
+                }
+            }
+        }
+        //section 7.1.1
+        if self.param(KEY_NO_DEFAULT_ALPN).is_some() && self.param(KEY_ALPN).is_none() {
+            return Err(invalid_param("no-default-alpn without alpn".to_string()));
+        }
+        Ok(())
+    }
+
+    //a client skips service records which aren't self consistent or which
+    //make a key it doesn't know mandatory
+    pub fn is_usable(&self) -> bool {
+        let knows_mandatory = match self.param(KEY_MANDATORY) {
+            Some(SvcParam::Mandatory(keys)) => keys.iter().all(|&key| key <= KEY_IPV6HINT),
+            _ => true,
+        };
+        knows_mandatory && self.validate_params().is_ok()
+    }
+
+    pub fn to_string(&self) -> String {
+        let mut fields = vec![self.priority.to_string(), self.target.to_string()];
+        fields.extend(self.params.iter().map(SvcParam::to_string));
+        fields.join(" ")
+    }
+
+    //params may come in any order but each key only once
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let priority = iter.next_field::<u16>("SVCB", "priority")?;
+        let target = iter.next_field::<Name>("SVCB", "target")?;
+        let mut params = Vec::new();
+        while let Some((key, value)) = iter.next_key_value("SVCB", "params")? {
+            let key = key_from_str(key)?;
+            let value = match value {
+                Some(value) => Some(presentation::unescape(value)?),
+                None => None,
+            };
+            params.push(SvcParam::from_presentation(key, value.as_deref())?);
+        }
+        params.sort_by_key(SvcParam::key);
+        if let Some(pair) = params
+            .windows(2)
+            .find(|pair| pair[0].key() == pair[1].key())
+        {
+            return Err(invalid_param(format!(
+                "duplicate key {}",
+                key_to_string(pair[0].key())
+            )));
+        }
+        Ok(SVCB {
+            priority,
+            target,
+            params,
+        })
+    }
+}
+
+pub trait SvcbResolver {
+    fn resolve(&self, name: &Name, typ: RRType) -> Option<RRset>;
+}
+
+//the end of an alias chain, clients connect to the services in order
+//and fall back to the last name with default params when there are none
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SvcbResolution {
+    pub chain: Vec<Name>,
+    pub services: Vec<SVCB>,
+}
+
+impl SvcbResolution {
+    pub fn name(&self) -> &Name {
+        self.chain.last().unwrap()
+    }
+}
+
+fn svcb_records(rrset: &RRset) -> Vec<&SVCB> {
+    rrset
+        .rdatas
+        .iter()
+        .filter_map(|rdata| match rdata {
+            RData::SVCB(ref svcb) | RData::HTTPS(ref svcb) => Some(svcb.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+//rfc9460 section 3, an alias record wins over service records at the
+//same name. None means an alias to "." said the service doesn't exist
+pub fn resolve<R: SvcbResolver + ?Sized>(
+    name: &Name,
+    typ: RRType,
+    resolver: &R,
+    max_chain: usize,
+) -> Result<Option<SvcbResolution>> {
+    let mut chain = vec![name.clone()];
+    loop {
+        let current = chain.last().unwrap();
+        let rrset = match resolver.resolve(current, typ) {
+            Some(rrset) if rrset.typ == typ => rrset,
+            _ => {
+                return Ok(Some(SvcbResolution {
+                    chain,
+                    services: Vec::new(),
+                }))
+            }
+        };
+        let records = svcb_records(&rrset);
+
+        if let Some(alias) = records.iter().find(|svcb| svcb.is_alias_mode()) {
+            let target = match alias.effective_target(current) {
+                Some(target) => target.clone(),
+                None => return Ok(None),
+            };
+            if chain.iter().any(|name| name.eq(&target)) {
+                return Err(DNSError::CNameLoop.into());
+            }
+            if chain.len() > max_chain {
+                return Err(DNSError::CNameChainTooLong(max_chain).into());
+            }
+            chain.push(target);
+            continue;
+        }
+
+        let mut services: Vec<SVCB> = records
+            .into_iter()
+            .filter(|svcb| svcb.is_usable())
+            .cloned()
+            .collect();
+        services.sort_by_key(|svcb| svcb.priority);
+        return Ok(Some(SvcbResolution { chain, services }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::hex::from_hex;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_svcb_to_wire() {
+        //rfc9460 appendix d
+        rdata_conformance! {
+            HTTPS, "0 foo.example.com.", "000003666f6f076578616d706c6503636f6d00";
+            SVCB, "1 .", "000100";
+            SVCB, "16 foo.example.com. port=53", "001003666f6f076578616d706c6503636f6d00000300020035";
+            SVCB,
+            "1 foo.example.com. key667=\"hello\\210qoo\"",
+            "000103666f6f076578616d706c6503636f6d00029b000968656c6c6fd2716f6f";
+            SVCB,
+            "1 foo.example.com. ipv6hint=2001:db8::1,2001:db8::53:1",
+            "000103666f6f076578616d706c6503636f6d000006002020010db800000000000000000000000120010db8000000000000000000530001";
+            SVCB,
+            "16 foo.example.org. mandatory=alpn,ipv4hint alpn=\"h2,h3-19\" ipv4hint=192.0.2.1",
+            "001003666f6f076578616d706c65036f7267000000000400010004000100090268320568332d313900040004c0000201";
+            SVCB,
+            "16 foo.example.org. alpn=\"f\\\\\\\\oo\\\\,bar,h2\"",
+            "001003666f6f076578616d706c65036f7267000001000c08665c6f6f2c626172026832";
+            SVCB, "1 . no-default-alpn ech=AQID key7", "000100000200000005000301020300070000";
+        };
+
+        let svcb = RData::from_str(RRType::SVCB, "1 foo.example.com. key667=hello").unwrap();
+        assert_eq!(svcb.to_string(), "1 foo.example.com. key667=\"hello\"");
+        let generic = RData::from_str(RRType::SVCB, "16 foo.example.com. key3=\"53\"").unwrap();
+        assert_eq!(generic.to_string(), "16 foo.example.com. port=53");
+        let svcb = RData::from_str(
+            RRType::HTTPS,
+            "1 . ipv4hint=192.0.2.2,192.0.2.1 alpn=h2,h3 mandatory=port,alpn port=8443",
+        )
+        .unwrap();
+        assert_eq!(
+            svcb.to_string(),
+            "1 . mandatory=alpn,port alpn=\"h2,h3\" port=8443 ipv4hint=192.0.2.2,192.0.2.1"
+        );
+        if let RData::HTTPS(svcb) = svcb {
+            assert_eq!(
+                svcb.param(KEY_ALPN),
+                Some(&SvcParam::Alpn(vec![b"h2".to_vec(), b"h3".to_vec()]))
+            );
+            assert_eq!(svcb.param(KEY_PORT), Some(&SvcParam::Port(8443)));
+            assert_eq!(svcb.param(KEY_ECH), None);
+        } else {
+            panic!("not https");
+        }
+
+        for invalid in &[
+            "1",
+            "1 . key65536=a",
+            "1 . key1=\"a",
+            "1 . =a",
+            "1 . foo=a",
+            "1 . alpn=h2 alpn=h3",
+            "1 . port=1 key3=2",
+            "1 . alpn=h2,,h3",
+            "1 . alpn",
+            "1 . port=65536",
+            "1 . ipv4hint=2001:db8::1",
+            "1 . ipv6hint=",
+            "1 . mandatory=port,port",
+            "1 . mandatory=foo",
+            "1 . no-default-alpn=x",
+            "1 . ech=AQI",
+        ] {
+            assert!(
+                RData::from_str(RRType::SVCB, invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+
+        for invalid in &[
+            //truncated value
+            "00010000030002",
+            //keys out of order, duplicated
+            "0001000003000200350001000302683200",
+            "000100000300020035000300020035",
+            //malformed known values
+            "000100000300010035",
+            "00010000010000",
+            "0001000001000200 00",
+            "000100000400030102 03",
+            "000100000200 0100",
+            "0001000000000400030001",
+        ] {
+            let raw = from_hex(&invalid.replace(' ', "")).unwrap();
+            assert!(
+                SVCB::from_wire(&mut InputBuffer::new(&raw), raw.len() as u16).is_err(),
+                "{}",
+                invalid
+            );
+        }
+        let raw = from_hex("000100000300020035").unwrap();
+        assert!(SVCB::from_wire(&mut InputBuffer::new(&raw), 7).is_err());
+    }
+
+    fn svcb(s: &str) -> SVCB {
+        match RData::from_str(RRType::HTTPS, s).unwrap() {
+            RData::HTTPS(svcb) => *svcb,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_svcb_modes() {
+        let owner = Name::new("example.com.").unwrap();
+        let alias = svcb("0 pool.svc.example.");
+        assert!(alias.is_alias_mode());
+        assert_eq!(alias.effective_target(&owner), Some(&alias.target));
+        assert_eq!(svcb("0 .").effective_target(&owner), None);
+        assert_eq!(svcb("1 .").effective_target(&owner), Some(&owner));
+        //params of an alias record are ignored
+        assert!(svcb("0 . mandatory=port").validate_params().is_ok());
+
+        assert!(svcb("1 . mandatory=alpn,port alpn=h2 port=443").is_usable());
+        assert!(svcb("1 . alpn=h2 no-default-alpn").is_usable());
+        for invalid in &[
+            "1 . mandatory=alpn,port alpn=h2",
+            "1 . mandatory=mandatory,port port=443",
+            "1 . no-default-alpn",
+        ] {
+            assert!(svcb(invalid).validate_params().is_err(), "{}", invalid);
+            assert!(!svcb(invalid).is_usable(), "{}", invalid);
+        }
+        let unknown = svcb("1 . mandatory=key9 key9=x");
+        assert!(unknown.validate_params().is_ok());
+        assert!(!unknown.is_usable());
+    }
+
+    struct Zone(HashMap<String, Vec<&'static str>>);
+
+    impl SvcbResolver for Zone {
+        fn resolve(&self, name: &Name, typ: RRType) -> Option<RRset> {
+            let rdatas = self.0.get(&name.to_string())?;
+            Some(RRset {
+                name: name.clone(),
+                typ,
+                class: crate::rr_class::RRClass::IN,
+                ttl: crate::rrset::RRTtl(300),
+                rdatas: rdatas
+                    .iter()
+                    .map(|s| RData::from_str(typ, s).unwrap())
+                    .collect(),
+            })
+        }
+    }
+
+    fn zone_of(records: &[(&str, Vec<&'static str>)]) -> Zone {
+        Zone(
+            records
+                .iter()
+                .map(|(name, rdatas)| (name.to_string(), rdatas.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_svcb_resolve() {
+        let name = Name::from_str("example.com.").unwrap();
+        let zone = zone_of(&[
+            ("example.com.", vec!["0 pool.example.net."]),
+            (
+                "pool.example.net.",
+                vec![
+                    "3 . alpn=h2",
+                    "1 h3pool.example.net. alpn=h3 port=8443",
+                    //port is mandatory but missing, so skipped
+                    "2 . mandatory=port alpn=h2",
+                ],
+            ),
+        ]);
+        let resolution = resolve(&name, RRType::HTTPS, &zone, MAX_ALIAS_CHAIN)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolution.name().to_string(), "pool.example.net.");
+        assert_eq!(resolution.chain.len(), 2);
+        let priorities: Vec<u16> = resolution.services.iter().map(|s| s.priority).collect();
+        assert_eq!(priorities, vec![1, 3]);
+        assert_eq!(
+            resolution.services[1].effective_target(resolution.name()),
+            Some(resolution.name())
+        );
+
+        //service records next to an alias are ignored
+        let zone = zone_of(&[("example.com.", vec!["1 . alpn=h2", "0 alias.example.com."])]);
+        let resolution = resolve(&name, RRType::HTTPS, &zone, MAX_ALIAS_CHAIN)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolution.name().to_string(), "alias.example.com.");
+        assert!(resolution.services.is_empty());
+
+        let zone = zone_of(&[("example.com.", vec!["0 ."])]);
+        assert_eq!(
+            resolve(&name, RRType::HTTPS, &zone, MAX_ALIAS_CHAIN).unwrap(),
+            None
+        );
+
+        let zone = zone_of(&[
+            ("example.com.", vec!["0 a.example.com."]),
+            ("a.example.com.", vec!["0 example.com."]),
+        ]);
+        assert!(resolve(&name, RRType::HTTPS, &zone, MAX_ALIAS_CHAIN).is_err());
+
+        let zone = zone_of(&[
+            ("example.com.", vec!["0 a.example.com."]),
+            ("a.example.com.", vec!["0 b.example.com."]),
+            ("b.example.com.", vec!["1 ."]),
+        ]);
+        assert!(resolve(&name, RRType::HTTPS, &zone, 1).is_err());
+        let resolution = resolve(&name, RRType::HTTPS, &zone, 2).unwrap().unwrap();
+        assert_eq!(resolution.services.len(), 1);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::message_render::MessageRender;
+use crate::rdatafield_string_parser::Parser;
+use crate::util::hex::to_hex;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+//rfc6698
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TLSA {
+    pub usage: u8,
+    pub selector: u8,
+    pub matching_type: u8,
+    pub data: Vec<u8>,
+}
+
+impl TLSA {
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let usage = buf.read_u8()?;
+        let selector = buf.read_u8()?;
+        let matching_type = buf.read_u8()?;
+        let data = buf.read_bytes((len as usize).saturating_sub(3))?.to_vec();
+        Ok(TLSA {
+            usage,
+            selector,
+            matching_type,
+            data,
+        })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_u8(self.usage);
+        render.write_u8(self.selector);
+        render.write_u8(self.matching_type);
+        render.write_bytes(self.data.as_slice());
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u8(self.usage);
+        buf.write_u8(self.selector);
+        buf.write_u8(self.matching_type);
+        buf.write_bytes(self.data.as_slice());
+    }
+
+    pub fn to_string(&self) -> String {
+        [
+            self.usage.to_string(),
+            self.selector.to_string(),
+            self.matching_type.to_string(),
+            to_hex(&self.data),
+        ]
+        .join(" ")
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let usage = iter.next_field::<u8>("TLSA", "usage")?;
+        let selector = iter.next_field::<u8>("TLSA", "selector")?;
+        let matching_type = iter.next_field::<u8>("TLSA", "matching_type")?;
+        let data = iter.next_hex_rest("TLSA", "data")?;
+        Ok(TLSA {
+            usage,
+            selector,
+            matching_type,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rdata::RData;
+    use crate::rr_type::RRType;
+
+    #[test]
+    fn test_tlsa_to_wire() {
+        //rfc6698 section 2.3
+        rdata_conformance! {
+            TLSA,
+            "0 0 1 d2abde240d7cd3ee6b4b28c54df034b97983a1d16e8a410e4561cb106618e971",
+            "000001d2abde240d7cd3ee6b4b28c54df034b97983a1d16e8a410e4561cb106618e971";
+            TLSA, "3 1 0 0102", "0301000102";
+        };
+
+        let wrapped =
+            RData::from_str(RRType::TLSA, "3 1 1 D2ABDE240D7CD3EE 6B4B28C54DF034B9").unwrap();
+        assert_eq!(
+            wrapped.to_string(),
+            "3 1 1 d2abde240d7cd3ee6b4b28c54df034b9"
+        );
+        assert!(RData::from_str(RRType::TLSA, "3 1 1").is_err());
+        assert!(RData::from_str(RRType::TLSA, "3 1 1 abc").is_err());
+        assert!(RData::from_str(RRType::TLSA, "256 1 1 ab").is_err());
+    }
+}
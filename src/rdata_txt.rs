@@ -1,12 +1,11 @@
 use crate::message_render::MessageRender;
 use crate::rdatafield_string_parser::Parser;
-use crate::util::{InputBuffer, OutputBuffer};
+use crate::util::{CharString, InputBuffer, OutputBuffer};
 use failure::Result;
-use std::str::from_utf8;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct TXT {
-    pub data: Vec<Vec<u8>>,
+    pub data: Vec<CharString>,
 }
 
 impl TXT {
@@ -14,10 +13,9 @@ impl TXT {
         let mut read_len = 0;
         let mut data = Vec::new();
         while read_len < len {
-            let sl = buf.read_u8()?;
-            let bytes = buf.read_bytes(sl as usize)?;
-            read_len += (sl + 1) as u16;
-            data.push(bytes.to_vec());
+            let string = CharString::from_wire(buf)?;
+            read_len += string.len() as u16 + 1;
+            data.push(string);
         }
         Ok(TXT { data })
     }
@@ -29,30 +27,32 @@ impl TXT {
 
     pub fn rend(&self, render: &mut MessageRender) {
         for data in &self.data {
-            render.write_u8(data.len() as u8);
-            render.write_bytes(data.as_slice());
+            data.rend(render);
         }
     }
 
     pub fn to_wire(&self, buf: &mut OutputBuffer) {
         for data in &self.data {
-            buf.write_u8(data.len() as u8);
-            buf.write_bytes(data.as_slice());
+            data.to_wire(buf);
         }
     }
 
     pub fn to_string(&self) -> String {
-        self.data.iter().fold(String::new(), |mut s, data| {
-            s.push_str("\"");
-            s.push_str(from_utf8(data).unwrap());
-            s.push_str("\" ");
-            s
-        })
+        self.data
+            .iter()
+            .map(|data| data.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
     }
 }
 
 #[cfg(test)]
 mod test {
     #[test]
-    fn test_txt_to_wire() {}
+    fn test_txt_to_wire() {
+        rdata_conformance! {
+            TXT, "\"v=spf1 -all\"", "0b763d73706631202d616c6c";
+            TXT, "\"a \\\"quoted\\\" \\\\ \\000\" \"\"", "0e61202271756f74656422205c200000";
+        };
+    }
 }
@@ -0,0 +1,66 @@
+use crate::message_render::MessageRender;
+use crate::rdatafield_string_parser::Parser;
+use crate::util::hex::to_hex;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+
+//rfc8976 section 2
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ZONEMD {
+    pub serial: u32,
+    pub scheme: u8,
+    pub hash_algorithm: u8,
+    pub digest: Vec<u8>,
+}
+
+impl ZONEMD {
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let serial = buf.read_u32()?;
+        let scheme = buf.read_u8()?;
+        let hash_algorithm = buf.read_u8()?;
+        let digest = buf.read_bytes((len as usize).saturating_sub(6))?.to_vec();
+        Ok(ZONEMD {
+            serial,
+            scheme,
+            hash_algorithm,
+            digest,
+        })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_u32(self.serial);
+        render.write_u8(self.scheme);
+        render.write_u8(self.hash_algorithm);
+        render.write_bytes(self.digest.as_slice());
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u32(self.serial);
+        buf.write_u8(self.scheme);
+        buf.write_u8(self.hash_algorithm);
+        buf.write_bytes(self.digest.as_slice());
+    }
+
+    pub fn to_string(&self) -> String {
+        [
+            self.serial.to_string(),
+            self.scheme.to_string(),
+            self.hash_algorithm.to_string(),
+            to_hex(&self.digest),
+        ]
+        .join(" ")
+    }
+
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let serial = iter.next_field::<u32>("ZONEMD", "serial")?;
+        let scheme = iter.next_field::<u8>("ZONEMD", "scheme")?;
+        let hash_algorithm = iter.next_field::<u8>("ZONEMD", "hash_algorithm")?;
+        let digest = iter.next_hex_rest("ZONEMD", "digest")?;
+        Ok(ZONEMD {
+            serial,
+            scheme,
+            hash_algorithm,
+            digest,
+        })
+    }
+}
@@ -1,9 +1,14 @@
 use crate::error::DNSError;
+use crate::util::base64::from_base64;
 use crate::util::hex::from_hex;
+use crate::util::CharString;
 use failure::Result;
 use std::str::from_utf8;
 use std::str::FromStr;
 
+//a key and its still escaped value, None for a bare key
+pub type KeyValue<'a> = (&'a str, Option<&'a [u8]>);
+
 pub struct Parser<'a> {
     raw: &'a [u8],
     pos: usize,
@@ -41,51 +46,144 @@ impl<'a> Parser<'a> {
         Err(DNSError::InvalidRdataString(rr_type, field_name, "empty".to_string()).into())
     }
 
-    pub fn next_txt(
+    //hex split over the remaining fields, as zone files wrap long digests
+    pub fn next_hex_rest(
+        &mut self,
+        rr_type: &'static str,
+        field_name: &'static str,
+    ) -> Result<Vec<u8>> {
+        let mut hex = String::new();
+        while let Some(s) = self.next_string() {
+            hex.push_str(s);
+        }
+        if hex.is_empty() {
+            return Err(
+                DNSError::InvalidRdataString(rr_type, field_name, "empty".to_string()).into(),
+            );
+        }
+        from_hex(&hex).ok_or_else(|| {
+            DNSError::InvalidRdataString(rr_type, field_name, "invalid hex".to_string()).into()
+        })
+    }
+
+    //base64 split over the remaining fields, as zone files wrap long keys
+    pub fn next_base64_rest(
+        &mut self,
+        rr_type: &'static str,
+        field_name: &'static str,
+    ) -> Result<Vec<u8>> {
+        let mut base64 = String::new();
+        while let Some(s) = self.next_string() {
+            base64.push_str(s);
+        }
+        if base64.is_empty() {
+            return Err(
+                DNSError::InvalidRdataString(rr_type, field_name, "empty".to_string()).into(),
+            );
+        }
+        from_base64(&base64).ok_or_else(|| {
+            DNSError::InvalidRdataString(rr_type, field_name, "invalid base64".to_string()).into()
+        })
+    }
+
+    //a quoted or unquoted character string
+    pub fn next_char_string(
         &mut self,
         rr_type: &'static str,
         field_name: &'static str,
-    ) -> Result<Vec<Vec<u8>>> {
+    ) -> Result<CharString> {
+        let invalid = |reason: String| -> failure::Error {
+            DNSError::InvalidRdataString(rr_type, field_name, reason).into()
+        };
         self.skip_whitespace();
-        let mut data = Vec::new();
-        if self.raw[self.pos] == b'"' {
-            let mut last_pos = self.pos + 1;
-            let mut in_quote = true;
-            let mut start_escape = false;
-            self.pos += 1;
-            while self.is_eos() == false {
-                let c = self.raw[self.pos];
-                if c == b'\\' {
-                    start_escape = true;
-                } else {
-                    if c == b'"' && start_escape == false {
-                        if in_quote {
-                            if self.pos > last_pos {
-                                data.push(self.raw[last_pos..self.pos].to_vec());
-                            }
-                            in_quote = false;
-                        } else {
-                            in_quote = true;
-                            last_pos = self.pos + 1;
-                        }
-                    }
-                    start_escape = false;
-                }
-                self.pos += 1;
+        if self.is_eos() {
+            return Err(invalid("empty".to_string()));
+        }
+        let raw = if self.raw[self.pos] == b'"' {
+            self.next_quoted()
+                .ok_or_else(|| invalid("quote in txt isn't in pair".to_string()))?
+        } else {
+            self.next_string().unwrap().as_bytes()
+        };
+        CharString::from_escaped(raw).map_err(|e| invalid(e.to_string()))
+    }
+
+    //the text between a pair of quotes, the parser must be at the opening one
+    fn next_quoted(&mut self) -> Option<&'a [u8]> {
+        let start = self.pos + 1;
+        let mut end = start;
+        while end < self.raw.len() && self.raw[end] != b'"' {
+            if self.raw[end] == b'\\' {
+                end += 1;
             }
+            end += 1;
+        }
+        if end >= self.raw.len() {
+            return None;
+        }
+        self.pos = end + 1;
+        Some(&self.raw[start..end])
+    }
 
-            if in_quote {
-                return Err(DNSError::InvalidRdataString(
+    //key=value with a quoted or unquoted value, or a bare key
+    pub fn next_key_value(
+        &mut self,
+        rr_type: &'static str,
+        field_name: &'static str,
+    ) -> Result<Option<KeyValue<'a>>> {
+        self.skip_whitespace();
+        if self.is_eos() {
+            return Ok(None);
+        }
+        let start = self.pos;
+        while !self.is_eos()
+            && !self.raw[self.pos].is_ascii_whitespace()
+            && self.raw[self.pos] != b'='
+        {
+            self.pos += 1;
+        }
+        let key = from_utf8(&self.raw[start..self.pos]).unwrap();
+        if key.is_empty() {
+            return Err(
+                DNSError::InvalidRdataString(rr_type, field_name, "empty key".to_string()).into(),
+            );
+        }
+        if self.is_eos() || self.raw[self.pos] != b'=' {
+            return Ok(Some((key, None)));
+        }
+
+        self.pos += 1;
+        if !self.is_eos() && self.raw[self.pos] == b'"' {
+            return match self.next_quoted() {
+                Some(value) => Ok(Some((key, Some(value)))),
+                None => Err(DNSError::InvalidRdataString(
                     rr_type,
                     field_name,
-                    "quote in txt isn't in pair".to_string(),
+                    "quote isn't in pair".to_string(),
                 )
-                .into());
-            }
-        } else {
-            while let Some(s) = self.next_string() {
-                data.push(s.as_bytes().to_vec());
+                .into()),
+            };
+        }
+        let start = self.pos;
+        while !self.is_eos() && !self.raw[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        Ok(Some((key, Some(&self.raw[start..self.pos]))))
+    }
+
+    //quoted and unquoted character strings may be mixed
+    pub fn next_txt(
+        &mut self,
+        rr_type: &'static str,
+        field_name: &'static str,
+    ) -> Result<Vec<CharString>> {
+        let mut data = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.is_eos() {
+                break;
             }
+            data.push(self.next_char_string(rr_type, field_name)?);
         }
 
         if data.is_empty() {
@@ -189,18 +287,18 @@ mod test {
         let s = " abc edf";
         let data = Parser::new(s).next_txt("", "").unwrap();
         assert_eq!(data.len(), 2);
-        assert_eq!(data[0], "abc".as_bytes().to_vec());
-        assert_eq!(data[1], "edf".as_bytes().to_vec());
+        assert_eq!(data[0].as_bytes(), b"abc");
+        assert_eq!(data[1].as_bytes(), b"edf");
 
         let s = " \"abc edf\"";
         let data = Parser::new(s).next_txt("", "").unwrap();
         assert_eq!(data.len(), 1);
-        assert_eq!(data[0], "abc edf".as_bytes().to_vec());
+        assert_eq!(data[0].as_bytes(), b"abc edf");
 
         let s = " \"abc\\\"c\" \"edf\"";
         let data = Parser::new(s).next_txt("", "").unwrap();
         assert_eq!(data.len(), 2);
-        assert_eq!(data[0], "abc\\\"c".as_bytes().to_vec());
-        assert_eq!(data[1], "edf".as_bytes().to_vec());
+        assert_eq!(data[0].as_bytes(), b"abc\"c");
+        assert_eq!(data[1].as_bytes(), b"edf");
     }
 }
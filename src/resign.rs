@@ -0,0 +1,193 @@
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::rr_type::RRType;
+use crate::util::time::serial_le;
+use crate::zone::Zone;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SignatureExpiry {
+    pub name: Name,
+    pub covered: RRType,
+    pub key_tag: u16,
+    pub expiration: u32,
+}
+
+//seconds from now to the timestamp, timestamps wrap so they only order
+//within half the number space around now
+fn remaining(timestamp: u32, now: u32) -> i64 {
+    i64::from(timestamp.wrapping_sub(now) as i32)
+}
+
+//the rrsigs of a zone ordered by expiration, together with the
+//authoritative rrsets no rrsig covers yet
+#[derive(Debug, Clone)]
+pub struct ExpiryIndex {
+    now: u32,
+    signatures: Vec<SignatureExpiry>,
+    unsigned: Vec<(Name, RRType)>,
+}
+
+fn is_covered(zone: &Zone, name: &Name, typ: RRType) -> bool {
+    zone.get(name, RRType::RRSIG).map_or(false, |rrsigs| {
+        rrsigs.rdatas.iter().any(|rdata| match rdata {
+            RData::RRSIG(ref rrsig) => rrsig.type_covered == typ,
+            _ => false,
+        })
+    })
+}
+
+impl ExpiryIndex {
+    pub fn build(zone: &Zone, now: u32) -> Self {
+        let mut signatures = Vec::new();
+        let mut unsigned = Vec::new();
+        for rrset in zone.rrsets() {
+            if rrset.typ == RRType::RRSIG {
+                signatures.extend(rrset.rdatas.iter().filter_map(|rdata| match rdata {
+                    RData::RRSIG(ref rrsig) => Some(SignatureExpiry {
+                        name: rrset.name.clone(),
+                        covered: rrsig.type_covered,
+                        key_tag: rrsig.key_tag,
+                        expiration: rrsig.expiration,
+                    }),
+                    _ => None,
+                }));
+            } else if zone.is_authoritative(&rrset.name, rrset.typ)
+                && !is_covered(zone, &rrset.name, rrset.typ)
+            {
+                unsigned.push((rrset.name.clone(), rrset.typ));
+            }
+        }
+        signatures.sort_by_key(|signature| remaining(signature.expiration, now));
+        ExpiryIndex {
+            now,
+            signatures,
+            unsigned,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    pub fn unsigned(&self) -> &[(Name, RRType)] {
+        &self.unsigned
+    }
+
+    pub fn next_expiration(&self) -> Option<u32> {
+        self.signatures
+            .first()
+            .map(|signature| signature.expiration)
+    }
+
+    //signatures expiring at or before the deadline, soonest first
+    pub fn expiring_by(&self, deadline: u32) -> &[SignatureExpiry] {
+        let limit = remaining(deadline, self.now);
+        let end = self
+            .signatures
+            .iter()
+            .position(|signature| remaining(signature.expiration, self.now) > limit)
+            .unwrap_or(self.signatures.len());
+        &self.signatures[..end]
+    }
+
+    //the rrsets to sign before the deadline, unsigned ones first and each
+    //rrset once however many of its rrsigs expire
+    pub fn due(&self, deadline: u32) -> Vec<(Name, RRType)> {
+        let mut due = self.unsigned.clone();
+        for signature in self.expiring_by(deadline) {
+            let key = (signature.name.clone(), signature.covered);
+            if !due.contains(&key) {
+                due.push(key);
+            }
+        }
+        due
+    }
+
+    //when the next run is needed if rrsigs are refreshed once less than
+    //refresh seconds of validity remain, unsigned data needs one right away
+    pub fn next_run(&self, refresh: u32) -> Option<u32> {
+        if !self.unsigned.is_empty() {
+            return Some(self.now);
+        }
+        let run = self.next_expiration()?.wrapping_sub(refresh);
+        Some(if serial_le(run, self.now) {
+            self.now
+        } else {
+            run
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rrset::RRset;
+    use crate::util::time::parse_timestamp;
+    use std::str::FromStr;
+
+    fn rrsig(owner: &str, covered: &str, expiration: &str, key_tag: u16) -> String {
+        format!(
+            "{} 300 IN RRSIG {} 13 2 300 {} 20240101000000 {} example.com. AQID",
+            owner, covered, expiration, key_tag
+        )
+    }
+
+    #[test]
+    fn test_expiry_index() {
+        let mut zone = Zone::new(Name::new("example.com.").unwrap());
+        let mut records = vec![
+            "example.com. 3600 IN SOA ns1.example.com. root.example.com. 1 3600 900 604800 300"
+                .to_string(),
+            "example.com. 3600 IN NS ns1.example.com.".to_string(),
+            "www.example.com. 300 IN A 192.0.2.1".to_string(),
+            "sub.example.com. 3600 IN NS ns.sub.example.com.".to_string(),
+            "sub.example.com. 3600 IN DS 12345 13 2 0102".to_string(),
+            "ns.sub.example.com. 3600 IN A 192.0.2.54".to_string(),
+        ];
+        records.push(rrsig("example.com.", "SOA", "20250301000000", 1));
+        records.push(rrsig("example.com.", "NS", "20250201000000", 1));
+        records.push(rrsig("www.example.com.", "A", "20250115000000", 1));
+        records.push(rrsig("www.example.com.", "A", "20250110000000", 2));
+        for rr in &records {
+            zone.add_rrset(RRset::from_str(rr).unwrap()).unwrap();
+        }
+
+        let now = parse_timestamp("20250101000000").unwrap();
+        let index = ExpiryIndex::build(&zone, now);
+        assert_eq!(index.len(), 4);
+        //the glue and the delegation ns are the child's
+        let sub = Name::new("sub.example.com.").unwrap();
+        assert_eq!(index.unsigned(), &[(sub.clone(), RRType::DS)][..]);
+        assert_eq!(
+            index.next_expiration(),
+            Some(parse_timestamp("20250110000000").unwrap())
+        );
+        assert_eq!(index.next_run(86400), Some(now));
+
+        let deadline = parse_timestamp("20250120000000").unwrap();
+        assert_eq!(index.expiring_by(deadline).len(), 2);
+        let www = Name::new("www.example.com.").unwrap();
+        assert_eq!(
+            index.due(deadline),
+            vec![(sub, RRType::DS), (www, RRType::A)]
+        );
+        assert_eq!(index.due(now).len(), 1);
+
+        let mut signed = zone.clone();
+        signed
+            .add_rrset(
+                RRset::from_str(&rrsig("sub.example.com.", "DS", "20250401000000", 1)).unwrap(),
+            )
+            .unwrap();
+        let index = ExpiryIndex::build(&signed, now);
+        assert!(index.unsigned().is_empty());
+        assert_eq!(
+            index.next_run(86400),
+            Some(parse_timestamp("20250109000000").unwrap())
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::header_flag::HeaderFlag;
+use crate::message::{Message, SectionType};
+use crate::rcode::Rcode;
+use crate::rr_type::RRType;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ResponseClass {
+    Positive,
+    Negative,
+    Referral,
+    Error(Rcode),
+}
+
+impl ResponseClass {
+    pub fn from_message(msg: &Message) -> Self {
+        match msg.header.rcode {
+            Rcode::NoError => {}
+            Rcode::NXDomain => return ResponseClass::Negative,
+            rcode => return ResponseClass::Error(rcode),
+        }
+
+        let has_answer = msg
+            .section(SectionType::Answer)
+            .into_iter()
+            .any(|rrsets| !rrsets.is_empty());
+        if has_answer {
+            return ResponseClass::Positive;
+        }
+
+        let auth = msg.section(SectionType::Authority);
+        let has_auth_type = |typ: RRType| auth.into_iter().flatten().any(|r| r.typ == typ);
+        if !msg.header.is_flag_set(HeaderFlag::AuthAnswer)
+            && has_auth_type(RRType::NS)
+            && !has_auth_type(RRType::SOA)
+        {
+            ResponseClass::Referral
+        } else {
+            ResponseClass::Negative
+        }
+    }
+
+    pub fn is_error(self) -> bool {
+        matches!(self, ResponseClass::Error(_))
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            ResponseClass::Positive => "POSITIVE",
+            ResponseClass::Negative => "NEGATIVE",
+            ResponseClass::Referral => "REFERRAL",
+            ResponseClass::Error(_) => "ERROR",
+        }
+    }
+}
+
+impl fmt::Display for ResponseClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResponseClass::Error(rcode) => write!(f, "{}({})", self.to_str(), rcode),
+            _ => f.write_str(self.to_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message_builder::MessageBuilder;
+    use crate::name::Name;
+    use crate::rrset::RRset;
+    use crate::util::hex::from_hex;
+    use std::str::FromStr;
+
+    fn response(answer: &[&str], auth: &[&str], aa: bool, rcode: Rcode) -> Message {
+        let mut msg = Message::with_query(Name::new("www.example.com.").unwrap(), RRType::A);
+        {
+            let mut builder = MessageBuilder::new(&mut msg);
+            builder.make_response().rcode(rcode);
+            if aa {
+                builder.set_flag(HeaderFlag::AuthAnswer);
+            }
+            for rr in answer {
+                builder.add_answer(RRset::from_str(rr).unwrap());
+            }
+            for rr in auth {
+                builder.add_auth(RRset::from_str(rr).unwrap());
+            }
+            builder.done();
+        }
+        msg
+    }
+
+    #[test]
+    fn test_response_class() {
+        let soa = "example.com. 300 IN SOA ns.example.com. root.example.com. 1 2 3 4 5";
+        let ns = "example.com. 300 IN NS ns.example.com.";
+        let a = "www.example.com. 300 IN A 192.0.2.1";
+
+        let cases = vec![
+            (
+                response(&[a], &[ns], true, Rcode::NoError),
+                ResponseClass::Positive,
+            ),
+            (
+                response(&[], &[soa], true, Rcode::NoError),
+                ResponseClass::Negative,
+            ),
+            (
+                response(&[], &[soa], true, Rcode::NXDomain),
+                ResponseClass::Negative,
+            ),
+            (
+                response(&[], &[ns], false, Rcode::NoError),
+                ResponseClass::Referral,
+            ),
+            (
+                response(&[], &[ns], true, Rcode::NoError),
+                ResponseClass::Negative,
+            ),
+            (
+                response(&[], &[], false, Rcode::NoError),
+                ResponseClass::Negative,
+            ),
+            (
+                response(&[], &[], false, Rcode::ServFail),
+                ResponseClass::Error(Rcode::ServFail),
+            ),
+        ];
+        for (msg, class) in cases {
+            assert_eq!(ResponseClass::from_message(&msg), class);
+        }
+
+        let packets = vec![
+            ("04b0850000010002000100020474657374076578616d706c6503636f6d0000010001c00c0001000100000e100004c0000202c00c0001000100000e100004c0000201c0110002000100000e100006036e7331c011c04e0001000100000e100004020202020000291000000000000000", ResponseClass::Positive),
+            ("e3808583000100000001000001320131033136380331393207696e2d61646472046172706100000c0001033136380331393207494e2d4144445204415250410000060001000151800017c02a00000000000000708000001c2000093a8000015180", ResponseClass::Negative),
+        ];
+        for (raw, class) in packets {
+            let msg = Message::from_wire(from_hex(raw).unwrap().as_slice()).unwrap();
+            assert_eq!(ResponseClass::from_message(&msg), class);
+        }
+
+        assert_eq!(
+            ResponseClass::Error(Rcode::Refused).to_string(),
+            "ERROR(REFUSED)"
+        );
+        assert!(ResponseClass::Error(Rcode::Refused).is_error());
+    }
+}
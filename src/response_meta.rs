@@ -0,0 +1,88 @@
+use crate::edns::{EdnsOption, OPTION_COOKIE, OPTION_EXTENDED_ERROR, OPTION_NSID};
+use crate::message::Message;
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ResponseMeta {
+    pub nsid: Option<Vec<u8>>,
+    pub server_cookie: Option<Vec<u8>>,
+    pub extended_errors: Vec<(u16, String)>,
+}
+
+impl ResponseMeta {
+    pub fn from_message(msg: &Message) -> Self {
+        let mut meta = ResponseMeta::default();
+        let edns = match msg.edns.as_ref() {
+            Some(edns) => edns,
+            None => return meta,
+        };
+
+        if let Some(EdnsOption::Nsid(ref nsid)) = edns.option(OPTION_NSID) {
+            meta.nsid = Some(nsid.clone());
+        }
+        if let Some(EdnsOption::Cookie(_, Some(ref server))) = edns.option(OPTION_COOKIE) {
+            meta.server_cookie = Some(server.clone());
+        }
+        meta.extended_errors = edns
+            .options_of(OPTION_EXTENDED_ERROR)
+            .filter_map(|option| match option {
+                EdnsOption::ExtendedError(info_code, ref text) => Some((*info_code, text.clone())),
+                _ => None,
+            })
+            .collect();
+        meta
+    }
+
+    //nsid is commonly the ascii host name of the answering instance
+    pub fn nsid_string(&self) -> Option<String> {
+        self.nsid
+            .as_ref()
+            .map(|nsid| String::from_utf8_lossy(nsid).into_owned())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nsid.is_none() && self.server_cookie.is_none() && self.extended_errors.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::edns::Edns;
+    use crate::message_builder::MessageBuilder;
+    use crate::message_render::MessageRender;
+    use crate::name::Name;
+    use crate::rr_type::RRType;
+
+    #[test]
+    fn test_response_meta() {
+        let mut msg = Message::with_query(Name::new("example.com.").unwrap(), RRType::A);
+        assert!(ResponseMeta::from_message(&msg).is_empty());
+
+        let mut edns = Edns {
+            versoin: 0,
+            extened_rcode: 0,
+            udp_size: 1232,
+            dnssec_aware: false,
+            options: None,
+        };
+        edns.add_option(EdnsOption::Nsid(b"ams01".to_vec()));
+        edns.add_option(EdnsOption::Cookie(vec![1; 8], Some(vec![2; 16])));
+        edns.add_option(EdnsOption::ExtendedError(18, "prohibited".to_string()));
+        edns.add_option(EdnsOption::ExtendedError(6, String::new()));
+        MessageBuilder::new(&mut msg)
+            .make_response()
+            .edns(edns)
+            .done();
+
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        let msg = Message::from_wire(render.data()).unwrap();
+        let meta = ResponseMeta::from_message(&msg);
+        assert_eq!(meta.nsid_string(), Some("ams01".to_string()));
+        assert_eq!(meta.server_cookie, Some(vec![2; 16]));
+        assert_eq!(
+            meta.extended_errors,
+            vec![(18, "prohibited".to_string()), (6, String::new())]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::header_flag::HeaderFlag;
+use crate::message::Message;
+use std::fmt;
+use std::net::SocketAddr;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RejectReason {
+    NotResponse,
+    AddressMismatch,
+    PortMismatch,
+    IdMismatch,
+    QuestionMismatch,
+    //the question matched but not with the letter case the query was sent
+    //with, see draft-vixie-dnsext-dns0x20
+    CaseMismatch,
+}
+
+impl RejectReason {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            RejectReason::NotResponse => "NOT_RESPONSE",
+            RejectReason::AddressMismatch => "ADDRESS_MISMATCH",
+            RejectReason::PortMismatch => "PORT_MISMATCH",
+            RejectReason::IdMismatch => "ID_MISMATCH",
+            RejectReason::QuestionMismatch => "QUESTION_MISMATCH",
+            RejectReason::CaseMismatch => "CASE_MISMATCH",
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+//rfc5452 section 9.1, a udp response is only accepted when it comes from
+//the address and port the query went to and echoes the id and question.
+//A response without question, as some servers send with formerr, is not
+//rejected for that alone, same as the pipeline does
+pub fn validate_response(
+    query: &Message,
+    response: &Message,
+    from_addr: SocketAddr,
+    expected_addr: SocketAddr,
+) -> Result<(), RejectReason> {
+    if !response.header.is_flag_set(HeaderFlag::QueryRespone) {
+        return Err(RejectReason::NotResponse);
+    }
+    if from_addr.ip() != expected_addr.ip() {
+        return Err(RejectReason::AddressMismatch);
+    }
+    if from_addr.port() != expected_addr.port() {
+        return Err(RejectReason::PortMismatch);
+    }
+    if response.header.id != query.header.id {
+        return Err(RejectReason::IdMismatch);
+    }
+    match (query.question.as_ref(), response.question.as_ref()) {
+        (_, None) => Ok(()),
+        (Some(sent), Some(received)) if sent == received => {
+            if sent.name.raw_data() == received.name.raw_data() {
+                Ok(())
+            } else {
+                Err(RejectReason::CaseMismatch)
+            }
+        }
+        _ => Err(RejectReason::QuestionMismatch),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message_builder::MessageBuilder;
+    use crate::name::Name;
+    use crate::rr_type::RRType;
+
+    #[test]
+    fn test_validate_response() {
+        let server: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let query = Message::with_query(Name::new("wWw.ExAmple.com.").unwrap(), RRType::A);
+        let mut response = query.clone();
+        MessageBuilder::new(&mut response).make_response().done();
+        assert_eq!(validate_response(&query, &response, server, server), Ok(()));
+
+        assert_eq!(
+            validate_response(&query, &query, server, server),
+            Err(RejectReason::NotResponse)
+        );
+        let other_server = "192.0.2.54:53".parse().unwrap();
+        assert_eq!(
+            validate_response(&query, &response, other_server, server),
+            Err(RejectReason::AddressMismatch)
+        );
+        let other_port = "192.0.2.53:5353".parse().unwrap();
+        assert_eq!(
+            validate_response(&query, &response, other_port, server),
+            Err(RejectReason::PortMismatch)
+        );
+
+        let mut spoofed = response.clone();
+        spoofed.header.id = query.header.id.wrapping_add(1);
+        assert_eq!(
+            validate_response(&query, &spoofed, server, server),
+            Err(RejectReason::IdMismatch)
+        );
+
+        let mut spoofed = response.clone();
+        spoofed.question.as_mut().unwrap().typ = RRType::AAAA;
+        assert_eq!(
+            validate_response(&query, &spoofed, server, server),
+            Err(RejectReason::QuestionMismatch)
+        );
+        spoofed.question.as_mut().unwrap().typ = RRType::A;
+        spoofed.question.as_mut().unwrap().name = Name::new("www.example.com.").unwrap();
+        assert_eq!(
+            validate_response(&query, &spoofed, server, server),
+            Err(RejectReason::CaseMismatch)
+        );
+        assert_eq!(RejectReason::CaseMismatch.to_string(), "CASE_MISMATCH");
+
+        spoofed.question = None;
+        assert_eq!(validate_response(&query, &spoofed, server, server), Ok(()));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::error::DNSError;
+use crate::name::Name;
+use failure::Result;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+const IP6_ARPA: &str = "ip6.arpa.";
+const IN_ADDR_ARPA: &str = "in-addr.arpa.";
+const IPV6_NIBBLE_COUNT: usize = 32;
+
+fn nibble(octets: &[u8; 16], index: usize) -> u8 {
+    let octet = octets[index / 2];
+    if index % 2 == 0 {
+        octet >> 4
+    } else {
+        octet & 0x0f
+    }
+}
+
+fn nibbles_to_name(octets: &[u8; 16], nibble_count: usize) -> Name {
+    let mut name = String::with_capacity(nibble_count * 2 + IP6_ARPA.len());
+    for i in (0..nibble_count).rev() {
+        name.push(std::char::from_digit(u32::from(nibble(octets, i)), 16).unwrap());
+        name.push('.');
+    }
+    name.push_str(IP6_ARPA);
+    Name::new(&name).unwrap()
+}
+
+pub fn ipv4_to_reverse_name(addr: Ipv4Addr) -> Name {
+    let octets = addr.octets();
+    Name::new(&format!(
+        "{}.{}.{}.{}.{}",
+        octets[3], octets[2], octets[1], octets[0], IN_ADDR_ARPA
+    ))
+    .unwrap()
+}
+
+pub fn ipv6_to_reverse_name(addr: Ipv6Addr) -> Name {
+    nibbles_to_name(&addr.octets(), IPV6_NIBBLE_COUNT)
+}
+
+pub fn reverse_zone_for_prefix(addr: Ipv6Addr, prefix_len: u8) -> Result<Name> {
+    if prefix_len > 128 || prefix_len % 4 != 0 {
+        return Err(DNSError::InvalidPrefixLen(prefix_len).into());
+    }
+    Ok(nibbles_to_name(&addr.octets(), (prefix_len / 4) as usize))
+}
+
+//a prefix which doesn't end on a nibble boundary is covered by several zones,
+//one for each value of the partially masked nibble
+pub fn reverse_zones_for_prefix(addr: Ipv6Addr, prefix_len: u8) -> Result<Vec<Name>> {
+    if prefix_len > 128 {
+        return Err(DNSError::InvalidPrefixLen(prefix_len).into());
+    }
+
+    let remainder = prefix_len % 4;
+    if remainder == 0 {
+        return reverse_zone_for_prefix(addr, prefix_len).map(|name| vec![name]);
+    }
+
+    let nibble_index = (prefix_len / 4) as usize;
+    let free_bits = 4 - remainder;
+    let mut octets = addr.octets();
+    let base = nibble(&octets, nibble_index) & (0x0f << free_bits);
+    let mut zones = Vec::with_capacity(1 << free_bits);
+    for value in 0..(1u8 << free_bits) {
+        let octet = &mut octets[nibble_index / 2];
+        if nibble_index % 2 == 0 {
+            *octet = (*octet & 0x0f) | ((base | value) << 4);
+        } else {
+            *octet = (*octet & 0xf0) | (base | value);
+        }
+        zones.push(nibbles_to_name(&octets, nibble_index + 1));
+    }
+    Ok(zones)
+}
+
+pub fn parse_ip6_arpa(name: &Name) -> Result<(Ipv6Addr, u8)> {
+    let suffix = Name::new(IP6_ARPA).unwrap();
+    if !name.is_subdomain(&suffix) {
+        return Err(DNSError::InvalidReverseName.into());
+    }
+
+    let nibble_count = name.label_count() - suffix.label_count();
+    if nibble_count > IPV6_NIBBLE_COUNT {
+        return Err(DNSError::InvalidReverseName.into());
+    }
+
+    let raw = name.raw_data();
+    let offsets = name.offsets();
+    let mut octets = [0u8; 16];
+    for i in 0..nibble_count {
+        let pos = offsets[nibble_count - 1 - i] as usize;
+        if raw[pos] != 1 {
+            return Err(DNSError::InvalidReverseName.into());
+        }
+        let value = match (raw[pos + 1] as char).to_digit(16) {
+            Some(v) => v as u8,
+            None => return Err(DNSError::InvalidReverseName.into()),
+        };
+        if i % 2 == 0 {
+            octets[i / 2] |= value << 4;
+        } else {
+            octets[i / 2] |= value;
+        }
+    }
+    Ok((Ipv6Addr::from(octets), (nibble_count * 4) as u8))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ipv4_reverse_name() {
+        assert_eq!(
+            ipv4_to_reverse_name(Ipv4Addr::new(192, 0, 2, 1)).to_string(),
+            "1.2.0.192.in-addr.arpa."
+        );
+    }
+
+    #[test]
+    fn test_ipv6_reverse_name() {
+        let addr = "2001:db8::567:89ab".parse::<Ipv6Addr>().unwrap();
+        let name = ipv6_to_reverse_name(addr);
+        assert_eq!(
+            name.to_string(),
+            "b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."
+        );
+        assert_eq!(parse_ip6_arpa(&name).unwrap(), (addr, 128));
+
+        let prefix = "2001:db8::".parse::<Ipv6Addr>().unwrap();
+        let zone = reverse_zone_for_prefix(prefix, 32).unwrap();
+        assert_eq!(zone.to_string(), "8.b.d.0.1.0.0.2.ip6.arpa.");
+        assert_eq!(parse_ip6_arpa(&zone).unwrap(), (prefix, 32));
+        assert_eq!(
+            reverse_zone_for_prefix(prefix, 0).unwrap().to_string(),
+            "ip6.arpa."
+        );
+        assert!(reverse_zone_for_prefix(prefix, 30).is_err());
+        assert!(reverse_zone_for_prefix(prefix, 132).is_err());
+
+        let zones = reverse_zones_for_prefix("2001:db8:8000::".parse().unwrap(), 34).unwrap();
+        let zones: Vec<String> = zones.iter().map(|z| z.to_string()).collect();
+        assert_eq!(
+            zones,
+            vec![
+                "8.8.b.d.0.1.0.0.2.ip6.arpa.",
+                "9.8.b.d.0.1.0.0.2.ip6.arpa.",
+                "a.8.b.d.0.1.0.0.2.ip6.arpa.",
+                "b.8.b.d.0.1.0.0.2.ip6.arpa.",
+            ]
+        );
+
+        assert!(parse_ip6_arpa(&Name::new("1.0.in-addr.arpa.").unwrap()).is_err());
+        assert!(parse_ip6_arpa(&Name::new("10.8.b.d.ip6.arpa.").unwrap()).is_err());
+        assert!(parse_ip6_arpa(&Name::new("g.8.b.d.ip6.arpa.").unwrap()).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::error::DNSError;
 use crate::message_render::MessageRender;
 use crate::name::Name;
+use crate::question::Question;
 use crate::rdata::RData;
 use crate::rdatafield_string_parser::Parser;
 use crate::rr_class::RRClass;
@@ -8,8 +9,17 @@ use crate::rr_type::RRType;
 use crate::util::{InputBuffer, OutputBuffer};
 use failure::{self, Result};
 use std::fmt::Write;
+use std::mem;
 use std::str::FromStr;
 
+const TTL_UNITS: &[(char, u32)] = &[
+    ('w', 604_800),
+    ('d', 86400),
+    ('h', 3600),
+    ('m', 60),
+    ('s', 1),
+];
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct RRTtl(pub u32);
 
@@ -29,15 +39,62 @@ impl RRTtl {
     fn to_string(self) -> String {
         self.0.to_string()
     }
+
+    //the bind unit syntax, 5400 is 1h30m
+    pub fn to_humanized(self) -> String {
+        if self.0 == 0 {
+            return "0s".to_string();
+        }
+        let mut left = self.0;
+        let mut result = String::new();
+        for &(unit, secs) in TTL_UNITS {
+            if left >= secs {
+                write!(&mut result, "{}{}", left / secs, unit).unwrap();
+                left %= secs;
+            }
+        }
+        result
+    }
 }
 
+//plain seconds, or numbers each followed by a w, d, h, m or s unit in
+//any case as bind accepts them, like 1h30m or 2D
 impl FromStr for RRTtl {
     type Err = failure::Error;
     fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
-        match s.parse::<u32>() {
-            Ok(num) => Ok(RRTtl(num)),
-            Err(_) => Err(DNSError::InvalidTtlString.into()),
+        if let Ok(num) = s.parse::<u32>() {
+            return Ok(RRTtl(num));
         }
+
+        let mut ttl: u32 = 0;
+        let mut value: Option<u32> = None;
+        for c in s.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                value = value
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|v| v.checked_add(digit));
+                if value.is_none() {
+                    return Err(DNSError::InvalidTtlString.into());
+                }
+                continue;
+            }
+            let secs = TTL_UNITS
+                .iter()
+                .find(|(unit, _)| *unit == c.to_ascii_lowercase())
+                .map(|(_, secs)| *secs);
+            ttl = match (value.take(), secs) {
+                (Some(v), Some(secs)) => v
+                    .checked_mul(secs)
+                    .and_then(|v| ttl.checked_add(v))
+                    .ok_or(DNSError::InvalidTtlString)?,
+                _ => return Err(DNSError::InvalidTtlString.into()),
+            };
+        }
+        if value.is_some() || s.is_empty() {
+            return Err(DNSError::InvalidTtlString.into());
+        }
+        Ok(RRTtl(ttl))
     }
 }
 
@@ -71,6 +128,16 @@ impl RRset {
         })
     }
 
+    //moves past one record without parsing its rdata
+    pub fn skip_wire(buf: &mut InputBuffer) -> Result<()> {
+        Name::from_wire(buf)?;
+        buf.read_u16()?;
+        buf.read_u16()?;
+        buf.read_u32()?;
+        let rdlen = buf.read_u16()?;
+        buf.read_bytes(rdlen as usize).map(|_| ())
+    }
+
     pub fn rend(&self, render: &mut MessageRender) {
         if self.rdatas.is_empty() {
             self.name.rend(render);
@@ -133,12 +200,58 @@ impl RRset {
         .join("\t")
     }
 
+    //an rrset without rdata is still rendered as one rr, which update
+    //messages use for prerequisites and deletions
     pub fn rr_count(&self) -> usize {
-        self.rdatas.len()
+        self.rdatas.len().max(1)
+    }
+
+    pub fn as_question(&self) -> Question {
+        Question {
+            name: self.name.clone(),
+            typ: self.typ,
+            class: self.class,
+        }
     }
 
     pub fn is_same_rrset(&self, other: &RRset) -> bool {
-        self.typ == other.typ && self.name.eq(&other.name)
+        self.typ == other.typ && self.class == other.class && self.name.eq(&other.name)
+    }
+
+    //rfc4034 section 6.3, records in ascending order of their rdata in wire
+    //format, duplicates are dropped
+    pub fn sort_rdatas(&mut self) {
+        let mut keyed: Vec<(Vec<u8>, RData)> = self
+            .rdatas
+            .drain(..)
+            .map(|rdata| {
+                let mut buf = OutputBuffer::new(0);
+                rdata.to_wire(&mut buf);
+                (buf.take_data(), rdata)
+            })
+            .collect();
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        keyed.dedup_by(|a, b| a.0 == b.0);
+        self.rdatas = keyed.into_iter().map(|(_, rdata)| rdata).collect();
+    }
+
+    //estimated heap bytes behind the rrset, rdata past the enum itself is
+    //counted by its wire length
+    pub fn heap_size(&self) -> usize {
+        let mut buf = OutputBuffer::new(0);
+        let rdata_len: usize = self
+            .rdatas
+            .iter()
+            .map(|rdata| {
+                buf.clear();
+                rdata.to_wire(&mut buf);
+                buf.len()
+            })
+            .sum();
+        self.name.len()
+            + self.name.label_count()
+            + self.rdatas.capacity() * mem::size_of::<RData>()
+            + rdata_len
     }
 }
 
@@ -244,4 +357,31 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_ttl_units() {
+        let cases = vec![
+            ("0", 0, "0s"),
+            ("3600", 3600, "1h"),
+            ("1h30m", 5400, "1h30m"),
+            ("2d", 172_800, "2d"),
+            ("1W2D3H4M5S", 788_645, "1w2d3h4m5s"),
+            ("90s", 90, "1m30s"),
+            ("30m1h", 5400, "1h30m"),
+            ("4294967295", u32::MAX, "7101w3d6h28m15s"),
+        ];
+        for (text, secs, humanized) in cases {
+            let ttl = RRTtl::from_str(text).unwrap();
+            assert_eq!(ttl, RRTtl(secs), "{}", text);
+            assert_eq!(ttl.to_humanized(), humanized);
+            assert_eq!(RRTtl::from_str(humanized).unwrap(), ttl);
+        }
+        for invalid in &["", "h", "1h30", "1x", "-1", "1 h", "4294967296", "7102w"] {
+            assert!(RRTtl::from_str(invalid).is_err(), "{}", invalid);
+        }
+
+        let rrset = RRset::from_str("example.org. 1h IN A 192.0.2.1").unwrap();
+        assert_eq!(rrset.ttl, RRTtl(3600));
+        assert_eq!(rrset.to_string(), "example.org.\t3600\tIN\tA\t192.0.2.1\n");
+    }
 }
@@ -0,0 +1,338 @@
+use crate::answer_selector::{select_answer, AnswerSelector};
+use crate::edns::EdnsConfig;
+use crate::header_flag::HeaderFlag;
+use crate::lookup_result::MAX_CNAME_CHAIN;
+use crate::message::{Message, Section};
+use crate::message_builder::MessageBuilder;
+use crate::middleware::{self, Context};
+use crate::name::Name;
+use crate::opcode::Opcode;
+use crate::rcode::Rcode;
+use crate::rdata::RData;
+use crate::rr_type::RRType;
+use crate::rrset::RRset;
+use crate::zone::{Zone, ZoneLookup};
+use std::net::{IpAddr, SocketAddr};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ClientContext {
+    pub addr: SocketAddr,
+    pub over_tcp: bool,
+}
+
+//turns one query into its response, the transport is up to the caller
+pub trait RequestHandler {
+    fn handle(&self, query: &Message, ctx: ClientContext) -> Message;
+}
+
+//the query echoed back with the qr flag and rcode set and no records
+pub fn error_response(query: &Message, rcode: Rcode) -> Message {
+    let mut response = query.clone();
+    response.sections = [Section(None), Section(None), Section(None)];
+    response.edns = None;
+    MessageBuilder::new(&mut response)
+        .make_response()
+        .clear_flag(HeaderFlag::AuthAnswer)
+        .clear_flag(HeaderFlag::Truncation)
+        .rcode(rcode)
+        .done();
+    response
+}
+
+//answers from the zones it holds, the deepest zone containing the qname
+//is authoritative for it
+#[derive(Default)]
+pub struct ZoneHandler {
+    zones: Vec<Zone>,
+    edns: EdnsConfig,
+    selector: Option<Box<dyn AnswerSelector + Send + Sync>>,
+}
+
+impl ZoneHandler {
+    pub fn new(edns: EdnsConfig) -> Self {
+        ZoneHandler {
+            zones: Vec::new(),
+            edns,
+            selector: None,
+        }
+    }
+
+    pub fn add_zone(&mut self, zone: Zone) -> &mut Self {
+        self.zones.push(zone);
+        self
+    }
+
+    //picks the answer records per client, see answer_selector
+    pub fn set_selector<S>(&mut self, selector: S) -> &mut Self
+    where
+        S: AnswerSelector + Send + Sync + 'static,
+    {
+        self.selector = Some(Box::new(selector));
+        self
+    }
+
+    pub fn find_zone(&self, name: &Name) -> Option<&Zone> {
+        self.zones
+            .iter()
+            .filter(|zone| zone.contains(name))
+            .max_by_key(|zone| zone.origin().label_count())
+    }
+
+    //with a selector the final answer rrset isn't added but returned, it is
+    //picked from once the response edns is in place
+    fn answer(
+        &self,
+        zone: &Zone,
+        name: &Name,
+        typ: RRType,
+        response: &mut Message,
+    ) -> Option<RRset> {
+        let mut selectable = None;
+        let mut builder = MessageBuilder::new(response);
+        builder.set_flag(HeaderFlag::AuthAnswer);
+        let mut name = name.clone();
+        let mut visited = vec![name.clone()];
+        //cnames are followed as long as the target stays in the zone, a
+        //looping chain ends once it gets back to a name already answered
+        for _ in 0..MAX_CNAME_CHAIN {
+            match zone.lookup(&name, typ) {
+                ZoneLookup::Answer(rrset) => {
+                    let target = match rrset.rdatas.first() {
+                        Some(RData::CName(ref cname)) if typ != RRType::CNAME => {
+                            Some(cname.name.clone())
+                        }
+                        _ => None,
+                    };
+                    if target.is_none() && self.selector.is_some() {
+                        selectable = Some(rrset);
+                        break;
+                    }
+                    builder.add_answer(rrset);
+                    match target {
+                        Some(target) if zone.contains(&target) && !visited.contains(&target) => {
+                            visited.push(target.clone());
+                            name = target;
+                        }
+                        _ => break,
+                    }
+                }
+                ZoneLookup::Referral(ns, glue) => {
+                    //data below a zone cut isn't ours to answer
+                    builder.clear_flag(HeaderFlag::AuthAnswer);
+                    builder.add_auth(ns);
+                    for rrset in glue {
+                        builder.add_additional(rrset);
+                    }
+                    break;
+                }
+                negative => {
+                    if negative == ZoneLookup::NXDomain {
+                        builder.rcode(Rcode::NXDomain);
+                    }
+                    if let Some(soa) = zone.negative_soa() {
+                        builder.add_auth(soa);
+                    }
+                    break;
+                }
+            }
+        }
+        builder.done();
+        selectable
+    }
+
+    //each record is a candidate of its own, without a selection the whole
+    //rrset is the answer
+    fn select(&self, query: &Message, peer: IpAddr, rrset: RRset, response: &mut Message) {
+        let candidates: Vec<RRset> = rrset
+            .rdatas
+            .iter()
+            .map(|rdata| RRset {
+                name: rrset.name.clone(),
+                typ: rrset.typ,
+                class: rrset.class,
+                ttl: rrset.ttl,
+                rdatas: vec![rdata.clone()],
+            })
+            .collect();
+        let selected = self.selector.as_ref().map_or(false, |selector| {
+            select_answer(selector.as_ref(), query, peer, &candidates, response)
+        });
+        if !selected {
+            MessageBuilder::new(response).add_answer(rrset).done();
+        }
+    }
+}
+
+impl RequestHandler for ZoneHandler {
+    fn handle(&self, query: &Message, ctx: ClientContext) -> Message {
+        if query.header.opcode != Opcode::Query {
+            return error_response(query, Rcode::NotImp);
+        }
+        let question = match query.question.as_ref() {
+            Some(question) if query.header.qd_count == 1 => question,
+            _ => return error_response(query, Rcode::FormErr),
+        };
+        let zone = match self.find_zone(&question.name) {
+            Some(zone) => zone,
+            None => return error_response(query, Rcode::Refused),
+        };
+
+        let mut response = error_response(query, Rcode::NoError);
+        let selectable = self.answer(zone, &question.name, question.typ, &mut response);
+
+        let mut mctx = Context::new(query, ctx.over_tcp);
+        if let Some(edns) = query.edns.as_ref() {
+            let config = EdnsConfig {
+                dnssec_aware: edns.dnssec_aware,
+                ..self.edns.clone()
+            };
+            response.edns = Some(config.to_edns());
+            response.recalculate_header();
+            mctx.max_udp_size = mctx.max_udp_size.min(self.edns.udp_size.max(512));
+        }
+        if let Some(rrset) = selectable {
+            self.select(query, ctx.addr.ip(), rrset, &mut response);
+        }
+        middleware::truncate(&mut response, &mut mctx);
+        response
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::answer_selector::Selection;
+    use crate::edns::ClientSubnet;
+    use crate::message::SectionType;
+    use crate::question::Question;
+    use crate::rrset::RRset;
+    use std::str::FromStr;
+
+    fn handler() -> ZoneHandler {
+        let mut zone = Zone::new(Name::new("example.com.").unwrap());
+        let mut records = vec![
+            "example.com. 3600 IN SOA ns1.example.com. root.example.com. 1 3600 900 604800 300"
+                .to_string(),
+            "example.com. 3600 IN NS ns1.example.com.".to_string(),
+            "ftp.example.com. 300 IN CNAME www.example.com.".to_string(),
+            "sub.example.com. 3600 IN NS ns.sub.example.com.".to_string(),
+            "ns.sub.example.com. 3600 IN A 192.0.2.54".to_string(),
+            "loop1.example.com. 300 IN CNAME loop2.example.com.".to_string(),
+            "loop2.example.com. 300 IN CNAME LOOP1.example.com.".to_string(),
+        ];
+        for i in 1..=60 {
+            records.push(format!("www.example.com. 300 IN A 192.0.2.{}", i));
+        }
+        for rr in &records {
+            zone.add_rrset(RRset::from_str(rr).unwrap()).unwrap();
+        }
+        let mut handler = ZoneHandler::new(EdnsConfig::default());
+        handler.add_zone(zone);
+        handler
+    }
+
+    fn query(name: &str, typ: RRType) -> Message {
+        Message::with_query(Name::new(name).unwrap(), typ)
+    }
+
+    fn section_len(msg: &Message, section: SectionType) -> usize {
+        msg.section(section).map_or(0, |rrsets| rrsets.len())
+    }
+
+    #[test]
+    fn test_zone_handler() {
+        let handler = handler();
+        let ctx = ClientContext {
+            addr: "192.0.2.100:5353".parse().unwrap(),
+            over_tcp: false,
+        };
+
+        let response = handler.handle(&query("ftp.example.com.", RRType::A), ctx);
+        assert!(response.header.is_flag_set(HeaderFlag::QueryRespone));
+        assert!(response.header.is_flag_set(HeaderFlag::Truncation));
+        assert_eq!(section_len(&response, SectionType::Answer), 0);
+
+        let tcp = ClientContext {
+            over_tcp: true,
+            ..ctx
+        };
+        let response = handler.handle(&query("ftp.example.com.", RRType::A), tcp);
+        assert!(response.header.is_flag_set(HeaderFlag::AuthAnswer));
+        assert_eq!(section_len(&response, SectionType::Answer), 2);
+
+        let response = handler.handle(&query("ftp.example.com.", RRType::A).with_do_bit(true), ctx);
+        assert!(!response.header.is_flag_set(HeaderFlag::Truncation));
+        assert!(response.edns.as_ref().unwrap().dnssec_aware);
+
+        let response = handler.handle(&query("nope.example.com.", RRType::A), ctx);
+        assert_eq!(response.header.rcode, Rcode::NXDomain);
+        assert_eq!(section_len(&response, SectionType::Authority), 1);
+        assert!(response.edns.is_none());
+
+        let response = handler.handle(&query("example.com.", RRType::MX), ctx);
+        assert_eq!(response.header.rcode, Rcode::NoError);
+        assert_eq!(
+            response.section(SectionType::Authority).unwrap()[0].typ,
+            RRType::SOA
+        );
+
+        let response = handler.handle(&query("www.sub.example.com.", RRType::A), ctx);
+        assert!(!response.header.is_flag_set(HeaderFlag::AuthAnswer));
+        assert_eq!(section_len(&response, SectionType::Authority), 1);
+        assert_eq!(section_len(&response, SectionType::Additional), 1);
+
+        let response = handler.handle(&query("loop1.example.com.", RRType::A), ctx);
+        assert_eq!(response.header.rcode, Rcode::NoError);
+        assert_eq!(response.header.an_count, 2);
+
+        let response = handler.handle(&query("example.org.", RRType::A), ctx);
+        assert_eq!(response.header.rcode, Rcode::Refused);
+
+        let mut notify = query("example.com.", RRType::SOA);
+        notify.header.opcode = Opcode::Notify;
+        assert_eq!(handler.handle(&notify, ctx).header.rcode, Rcode::NotImp);
+    }
+
+    struct LastOctet;
+
+    impl AnswerSelector for LastOctet {
+        fn select(
+            &self,
+            client: &ClientSubnet,
+            _question: &Question,
+            candidates: &[RRset],
+        ) -> Option<Selection> {
+            match client.addr {
+                IpAddr::V4(v4) => Some(Selection {
+                    index: usize::from(v4.octets()[3]) % candidates.len(),
+                    scope_prefix: 32,
+                }),
+                IpAddr::V6(_) => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_zone_handler_selector() {
+        let mut handler = handler();
+        handler.set_selector(LastOctet);
+        let ctx = ClientContext {
+            addr: "192.0.2.100:5353".parse().unwrap(),
+            over_tcp: false,
+        };
+
+        let response = handler.handle(&query("ftp.example.com.", RRType::A), ctx);
+        assert!(!response.header.is_flag_set(HeaderFlag::Truncation));
+        assert_eq!(response.header.an_count, 2);
+        let answer = &response.section(SectionType::Answer).unwrap()[1];
+        assert_eq!(answer.rdatas[0].to_string(), "192.0.2.41");
+
+        //the selector passes on v6 clients, who get the whole rrset
+        let v6 = ClientContext {
+            addr: "[2001:db8::1]:5353".parse().unwrap(),
+            over_tcp: true,
+        };
+        let response = handler.handle(&query("www.example.com.", RRType::A), v6);
+        assert_eq!(response.header.an_count, 60);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::rdata_srv::SRV;
+use crate::rdata_svcb::{SvcParam, KEY_PORT, SVCB};
+use crate::rr_type::RRType;
+use crate::rrset::RRset;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::net::IpAddr;
+
+pub const HTTPS_DEFAULT_PORT: u16 = 443;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ServiceTarget {
+    pub host: Name,
+    pub port: u16,
+    //addresses of the host found in the provided rrsets or else in the
+    //address hints, empty means the caller still has to resolve the host
+    pub addrs: Vec<IpAddr>,
+    //svcb params of the record, empty for srv and fallback targets
+    pub params: Vec<SvcParam>,
+}
+
+fn host_addrs(host: &Name, addresses: &[RRset]) -> Vec<IpAddr> {
+    addresses
+        .iter()
+        .filter(|rrset| rrset.name.eq(host))
+        .flat_map(|rrset| rrset.rdatas.iter())
+        .filter_map(|rdata| match rdata {
+            RData::A(ref a) => Some(IpAddr::V4(a.host)),
+            RData::AAAA(ref aaaa) => Some(IpAddr::V6(aaaa.host)),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn resolve_service(rrset: &RRset, addresses: &[RRset]) -> Vec<ServiceTarget> {
+    resolve_service_with(rrset, addresses, &mut rand::thread_rng())
+}
+
+//srv and https rrsets, a plain svcb rrset needs the default port of its
+//scheme and goes through resolve_svcb_with
+pub fn resolve_service_with<R: Rng>(
+    rrset: &RRset,
+    addresses: &[RRset],
+    rng: &mut R,
+) -> Vec<ServiceTarget> {
+    match rrset.typ {
+        RRType::SRV => srv_targets(rrset, addresses, rng),
+        RRType::HTTPS => resolve_svcb_with(rrset, addresses, HTTPS_DEFAULT_PORT, rng),
+        _ => Vec::new(),
+    }
+}
+
+//rfc2782: targets are tried in ascending priority, and within the same
+//priority in an order drawn by weight, a single "." target means the
+//service is decidedly not available
+fn srv_targets<R: Rng>(srv: &RRset, addresses: &[RRset], rng: &mut R) -> Vec<ServiceTarget> {
+    let mut records: Vec<&SRV> = srv
+        .rdatas
+        .iter()
+        .filter_map(|rdata| match rdata {
+            RData::SRV(ref srv) => Some(srv.as_ref()),
+            _ => None,
+        })
+        .collect();
+    if records.len() == 1 && records[0].target.is_root() {
+        return Vec::new();
+    }
+
+    records.sort_by_key(|srv| srv.priority);
+    let mut ordered = Vec::with_capacity(records.len());
+    let mut start = 0;
+    while start < records.len() {
+        let priority = records[start].priority;
+        let end = records[start..]
+            .iter()
+            .position(|srv| srv.priority != priority)
+            .map_or(records.len(), |len| start + len);
+        //zero weight records go first so they keep a small chance of
+        //being picked
+        let mut group: Vec<&SRV> = records[start..end].to_vec();
+        start = end;
+        group.sort_by_key(|srv| srv.weight != 0);
+        while !group.is_empty() {
+            let total: u32 = group.iter().map(|srv| u32::from(srv.weight)).sum();
+            let point = rng.gen_range(0, total + 1);
+            let mut sum = 0;
+            let index = group
+                .iter()
+                .position(|srv| {
+                    sum += u32::from(srv.weight);
+                    sum >= point
+                })
+                .unwrap();
+            ordered.push(group.remove(index));
+        }
+    }
+
+    ordered
+        .into_iter()
+        .map(|srv| ServiceTarget {
+            host: srv.target.clone(),
+            port: srv.port,
+            addrs: host_addrs(&srv.target, addresses),
+            params: Vec::new(),
+        })
+        .collect()
+}
+
+fn hint_addrs(svcb: &SVCB) -> Vec<IpAddr> {
+    svcb.params
+        .iter()
+        .flat_map(|param| match param {
+            SvcParam::Ipv4Hint(addrs) => addrs.iter().map(|&addr| IpAddr::V4(addr)).collect(),
+            SvcParam::Ipv6Hint(addrs) => addrs.iter().map(|&addr| IpAddr::V6(addr)).collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+fn fallback_target(host: &Name, addresses: &[RRset], default_port: u16) -> ServiceTarget {
+    ServiceTarget {
+        host: host.clone(),
+        port: default_port,
+        addrs: host_addrs(host, addresses),
+        params: Vec::new(),
+    }
+}
+
+pub fn resolve_svcb(rrset: &RRset, addresses: &[RRset], default_port: u16) -> Vec<ServiceTarget> {
+    resolve_svcb_with(rrset, addresses, default_port, &mut rand::thread_rng())
+}
+
+//rfc9460: service records are tried in ascending priority, shuffled
+//within the same priority, and unusable ones are skipped. An alias record
+//gives its target, which the caller may look up further with
+//rdata_svcb::resolve, and an alias to "." means there is no service.
+//Without usable records the client falls back to the owner name with the
+//default port and no params
+pub fn resolve_svcb_with<R: Rng>(
+    rrset: &RRset,
+    addresses: &[RRset],
+    default_port: u16,
+    rng: &mut R,
+) -> Vec<ServiceTarget> {
+    let records: Vec<&SVCB> = rrset
+        .rdatas
+        .iter()
+        .filter_map(|rdata| match rdata {
+            RData::SVCB(ref svcb) | RData::HTTPS(ref svcb) => Some(svcb.as_ref()),
+            _ => None,
+        })
+        .collect();
+    if let Some(alias) = records.iter().find(|svcb| svcb.is_alias_mode()) {
+        return alias
+            .effective_target(&rrset.name)
+            .map(|target| fallback_target(target, addresses, default_port))
+            .into_iter()
+            .collect();
+    }
+
+    let mut services: Vec<&SVCB> = records
+        .into_iter()
+        .filter(|svcb| svcb.is_usable())
+        .collect();
+    if services.is_empty() {
+        return vec![fallback_target(&rrset.name, addresses, default_port)];
+    }
+    services.shuffle(rng);
+    services.sort_by_key(|svcb| svcb.priority);
+    services
+        .into_iter()
+        .map(|svcb| {
+            let host = svcb.effective_target(&rrset.name).unwrap();
+            let port = match svcb.param(KEY_PORT) {
+                Some(SvcParam::Port(port)) => *port,
+                _ => default_port,
+            };
+            let mut addrs = host_addrs(host, addresses);
+            if addrs.is_empty() {
+                addrs = hint_addrs(svcb);
+            }
+            ServiceTarget {
+                host: host.clone(),
+                port,
+                addrs,
+                params: svcb.params.clone(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+    use std::str::FromStr;
+
+    fn srv_rrset(records: &[&str]) -> RRset {
+        let mut rrset =
+            RRset::from_str(&format!("_sip._tcp.example.com. 300 IN SRV {}", records[0])).unwrap();
+        for record in &records[1..] {
+            let mut other =
+                RRset::from_str(&format!("_sip._tcp.example.com. 300 IN SRV {}", record)).unwrap();
+            rrset.rdatas.append(&mut other.rdatas);
+        }
+        rrset
+    }
+
+    #[test]
+    fn test_resolve_service() {
+        let srv = srv_rrset(&[
+            "20 0 5060 backup.example.com.",
+            "10 60 5060 big.example.com.",
+            "10 20 5061 small.example.com.",
+            "10 0 5062 zero.example.com.",
+        ]);
+        let addresses = vec![
+            RRset::from_str("big.example.com. 300 IN A 192.0.2.1").unwrap(),
+            RRset::from_str("big.example.com. 300 IN AAAA 2001:db8::1").unwrap(),
+            RRset::from_str("backup.example.com. 300 IN A 192.0.2.9").unwrap(),
+        ];
+
+        let targets = resolve_service_with(&srv, &addresses, &mut StepRng::new(0, 0));
+        let hosts: Vec<String> = targets.iter().map(|t| t.host.to_string()).collect();
+        assert_eq!(
+            hosts,
+            vec![
+                "zero.example.com.",
+                "big.example.com.",
+                "small.example.com.",
+                "backup.example.com.",
+            ]
+        );
+        assert_eq!(targets[1].port, 5060);
+        assert_eq!(
+            targets[1].addrs,
+            vec![
+                "192.0.2.1".parse::<IpAddr>().unwrap(),
+                "2001:db8::1".parse::<IpAddr>().unwrap(),
+            ]
+        );
+        assert!(targets[2].addrs.is_empty());
+
+        for _ in 0..10 {
+            let targets = resolve_service(&srv, &addresses);
+            assert_eq!(targets.len(), 4);
+            assert_eq!(targets[3].host.to_string(), "backup.example.com.");
+        }
+
+        assert!(resolve_service(&srv_rrset(&["0 0 0 ."]), &addresses).is_empty());
+        assert!(resolve_service(&addresses[0], &addresses).is_empty());
+        assert!(targets.iter().all(|target| target.params.is_empty()));
+    }
+
+    fn svcb_rrset(typ: &str, owner: &str, records: &[&str]) -> RRset {
+        let rr = |record: &str| RRset::from_str(&format!("{} 300 IN {} {}", owner, typ, record));
+        let mut rrset = rr(records[0]).unwrap();
+        for record in &records[1..] {
+            rrset.rdatas.append(&mut rr(record).unwrap().rdatas);
+        }
+        rrset
+    }
+
+    #[test]
+    fn test_resolve_svcb() {
+        let https = svcb_rrset(
+            "HTTPS",
+            "example.com.",
+            &[
+                "2 backup.example.com. port=8443",
+                "1 . alpn=h2,h3 ipv4hint=192.0.2.7",
+                "1 svc.example.com. mandatory=key9 key9=x",
+                "3 svc2.example.com. no-default-alpn",
+            ],
+        );
+        let addresses = vec![RRset::from_str("backup.example.com. 300 IN A 192.0.2.9").unwrap()];
+        let targets = resolve_service(&https, &addresses);
+        let hosts: Vec<(String, u16)> = targets
+            .iter()
+            .map(|target| (target.host.to_string(), target.port))
+            .collect();
+        assert_eq!(
+            hosts,
+            vec![
+                ("example.com.".to_string(), 443),
+                ("backup.example.com.".to_string(), 8443),
+            ]
+        );
+        assert_eq!(
+            targets[0].addrs,
+            vec!["192.0.2.7".parse::<IpAddr>().unwrap()]
+        );
+        assert_eq!(targets[0].params.len(), 2);
+        assert_eq!(
+            targets[1].addrs,
+            vec!["192.0.2.9".parse::<IpAddr>().unwrap()]
+        );
+
+        //nothing usable, the owner name with default params
+        let unusable = svcb_rrset(
+            "HTTPS",
+            "example.com.",
+            &["1 svc2.example.com. no-default-alpn"],
+        );
+        let targets = resolve_service(&unusable, &addresses);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].host.to_string(), "example.com.");
+        assert_eq!(targets[0].port, 443);
+        assert!(targets[0].params.is_empty());
+
+        let alias = svcb_rrset("HTTPS", "example.com.", &["0 pool.example.net."]);
+        let targets = resolve_service(&alias, &addresses);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].host.to_string(), "pool.example.net.");
+        let no_service = svcb_rrset("HTTPS", "example.com.", &["0 ."]);
+        assert!(resolve_service(&no_service, &addresses).is_empty());
+
+        let svcb = svcb_rrset(
+            "SVCB",
+            "_dns.example.com.",
+            &["1 dns.example.com. alpn=dot"],
+        );
+        assert!(resolve_service(&svcb, &addresses).is_empty());
+        let targets = resolve_svcb(&svcb, &addresses, 853);
+        assert_eq!(targets[0].host.to_string(), "dns.example.com.");
+        assert_eq!(targets[0].port, 853);
+    }
+}
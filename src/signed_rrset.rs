@@ -0,0 +1,145 @@
+use crate::rdata::RData;
+use crate::rdata_rrsig::RRSIG;
+use crate::rr_type::RRType;
+use crate::rrset::RRset;
+
+//an rrset together with the rrsigs covering it. The rrsigs keep no ttl of
+//their own, rfc4035 section 2.2 has them share the ttl of the rrset
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SignedRRset {
+    pub rrset: RRset,
+    pub signatures: Vec<RRSIG>,
+}
+
+impl SignedRRset {
+    pub fn new(rrset: RRset) -> Self {
+        SignedRRset {
+            rrset,
+            signatures: Vec::new(),
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        !self.signatures.is_empty()
+    }
+
+    //pairs every rrsig with the rrset of its owner, class and covered type.
+    //Rrsigs covering nothing in the list stay behind as plain rrsig rrsets,
+    //so no record is lost
+    pub fn group(rrsets: Vec<RRset>) -> Vec<SignedRRset> {
+        let (sig_rrsets, rrsets): (Vec<RRset>, Vec<RRset>) = rrsets
+            .into_iter()
+            .partition(|rrset| rrset.typ == RRType::RRSIG);
+        let mut grouped: Vec<SignedRRset> = rrsets.into_iter().map(SignedRRset::new).collect();
+        let mut orphans: Vec<RRset> = Vec::new();
+        for sig_rrset in sig_rrsets {
+            let RRset {
+                name,
+                class,
+                ttl,
+                rdatas,
+                ..
+            } = sig_rrset;
+            for rdata in rdatas {
+                let rrsig = match rdata {
+                    RData::RRSIG(rrsig) => rrsig,
+                    _ => continue,
+                };
+                let signed = grouped.iter_mut().find(|signed| {
+                    signed.rrset.typ == rrsig.type_covered
+                        && signed.rrset.class == class
+                        && signed.rrset.name == name
+                });
+                if let Some(signed) = signed {
+                    signed.signatures.push(*rrsig);
+                    continue;
+                }
+                match orphans
+                    .iter_mut()
+                    .find(|orphan| orphan.class == class && orphan.name == name)
+                {
+                    Some(orphan) => orphan.rdatas.push(RData::RRSIG(rrsig)),
+                    None => orphans.push(RRset {
+                        name: name.clone(),
+                        typ: RRType::RRSIG,
+                        class,
+                        ttl,
+                        rdatas: vec![RData::RRSIG(rrsig)],
+                    }),
+                }
+            }
+        }
+        grouped.extend(orphans.into_iter().map(SignedRRset::new));
+        grouped
+    }
+
+    //the rrset followed by its rrsigs, as they go into a message
+    pub fn into_rrsets(self) -> Vec<RRset> {
+        let SignedRRset { rrset, signatures } = self;
+        if signatures.is_empty() {
+            return vec![rrset];
+        }
+        let sig_rrset = RRset {
+            name: rrset.name.clone(),
+            typ: RRType::RRSIG,
+            class: rrset.class,
+            ttl: rrset.ttl,
+            rdatas: signatures
+                .into_iter()
+                .map(|rrsig| RData::RRSIG(Box::new(rrsig)))
+                .collect(),
+        };
+        vec![rrset, sig_rrset]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message::{Message, SectionType};
+    use crate::message_builder::MessageBuilder;
+    use crate::message_render::MessageRender;
+    use crate::name::Name;
+    use std::str::FromStr;
+
+    fn rrsig(covered: &str) -> String {
+        format!(
+            "www.example.com. 300 IN RRSIG {} 13 3 300 20300101000000 20200101000000 1 example.com. AQID",
+            covered
+        )
+    }
+
+    #[test]
+    fn test_group_signatures() {
+        let a = RRset::from_str("www.example.com. 300 IN A 192.0.2.1").unwrap();
+        let txt = RRset::from_str("www.example.com. 300 IN TXT \"x\"").unwrap();
+        let mut msg = Message::with_query(Name::new("www.example.com.").unwrap(), RRType::A);
+        MessageBuilder::new(&mut msg)
+            .make_response()
+            .add_answer(a.clone())
+            .add_answer(RRset::from_str(&rrsig("A")).unwrap())
+            .add_answer(txt.clone())
+            .add_answer(RRset::from_str(&rrsig("AAAA")).unwrap())
+            .done();
+        let mut render = MessageRender::new();
+        msg.rend(&mut render);
+        let msg = Message::from_wire(render.data()).unwrap();
+
+        let grouped = msg.signed_rrsets(SectionType::Answer);
+        assert_eq!(grouped.len(), 3);
+        assert_eq!(grouped[0].rrset, a);
+        assert_eq!(grouped[0].signatures.len(), 1);
+        assert_eq!(grouped[0].signatures[0].type_covered, RRType::A);
+        assert_eq!(grouped[1].rrset, txt);
+        assert!(!grouped[1].is_signed());
+        assert_eq!(grouped[2].rrset, RRset::from_str(&rrsig("AAAA")).unwrap());
+
+        let rrsets: Vec<RRset> = grouped
+            .into_iter()
+            .flat_map(SignedRRset::into_rrsets)
+            .collect();
+        assert_eq!(rrsets.len(), 4);
+        assert_eq!(rrsets[1], RRset::from_str(&rrsig("A")).unwrap());
+        assert!(msg.signed_rrsets(SectionType::Authority).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::error::DNSError;
+use crate::message_render::MessageRender;
+use crate::rdatafield_string_parser::Parser;
+use crate::rr_type::RRType;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::Result;
+use std::collections::BTreeSet;
+
+const MAX_BITMAP_LEN: usize = 32;
+const UNKNOWN_TYPE_PREFIX: &str = "TYPE";
+
+//rfc4034 section 4.1.2, types are grouped in windows of 256, each window
+//is the window number, the bitmap length and the bitmap with trailing zero
+//octets left out. Shared by nsec, nsec3 and csync
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TypeBitmap {
+    types: BTreeSet<u16>,
+}
+
+//rfc3597 section 5, TYPEnnn for types without a mnemonic
+pub(crate) fn type_to_string(typ: RRType) -> String {
+    match typ {
+        RRType::Unknown(value) => format!("{}{}", UNKNOWN_TYPE_PREFIX, value),
+        _ => typ.to_string(),
+    }
+}
+
+pub(crate) fn type_from_str(s: &str) -> Result<RRType> {
+    let upper = s.to_uppercase();
+    match upper.strip_prefix(UNKNOWN_TYPE_PREFIX) {
+        Some(value) if !value.is_empty() && value.bytes().all(|c| c.is_ascii_digit()) => value
+            .parse::<u16>()
+            .map(RRType::new)
+            .map_err(|_| DNSError::UnknownRRType(0).into()),
+        _ => upper.parse(),
+    }
+}
+
+impl TypeBitmap {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn insert(&mut self, typ: RRType) -> bool {
+        self.types.insert(typ.to_u16())
+    }
+
+    pub fn remove(&mut self, typ: RRType) -> bool {
+        self.types.remove(&typ.to_u16())
+    }
+
+    pub fn contains(&self, typ: RRType) -> bool {
+        self.types.contains(&typ.to_u16())
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    //in ascending type order
+    pub fn iter(&self) -> impl Iterator<Item = RRType> + '_ {
+        self.types.iter().map(|&typ| RRType::new(typ))
+    }
+
+    fn windows(&self) -> Vec<(u8, Vec<u8>)> {
+        let mut windows: Vec<(u8, Vec<u8>)> = Vec::new();
+        for &typ in &self.types {
+            let window = (typ >> 8) as u8;
+            let octet = (typ & 0xff) as usize / 8;
+            if windows.last().map(|(w, _)| *w) != Some(window) {
+                windows.push((window, Vec::new()));
+            }
+            let bitmap = &mut windows.last_mut().unwrap().1;
+            if bitmap.len() <= octet {
+                bitmap.resize(octet + 1, 0);
+            }
+            bitmap[octet] |= 0x80 >> (typ & 0x07);
+        }
+        windows
+    }
+
+    pub fn wire_len(&self) -> usize {
+        self.windows()
+            .iter()
+            .map(|(_, bitmap)| bitmap.len() + 2)
+            .sum()
+    }
+
+    //windows must be in ascending order and each bitmap 1 to 32 octets
+    pub fn from_wire(buf: &mut InputBuffer, len: u16) -> Result<Self> {
+        let mut types = BTreeSet::new();
+        let mut read_len = 0;
+        let mut last_window: Option<u8> = None;
+        while read_len < len as usize {
+            let window = buf.read_u8()?;
+            let bitmap_len = buf.read_u8()? as usize;
+            if matches!(last_window, Some(last) if window <= last)
+                || bitmap_len == 0
+                || bitmap_len > MAX_BITMAP_LEN
+            {
+                return Err(DNSError::InvalidTypeBitmap.into());
+            }
+            let bitmap = buf.read_bytes(bitmap_len)?;
+            for (i, &octet) in bitmap.iter().enumerate() {
+                for bit in 0..8 {
+                    if octet & (0x80 >> bit) != 0 {
+                        types.insert(u16::from(window) << 8 | (i * 8 + bit) as u16);
+                    }
+                }
+            }
+            last_window = Some(window);
+            read_len += bitmap_len + 2;
+        }
+        if read_len != len as usize {
+            return Err(DNSError::RdataLenIsNotCorrect.into());
+        }
+        Ok(TypeBitmap { types })
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        for (window, bitmap) in self.windows() {
+            render.write_u8(window);
+            render.write_u8(bitmap.len() as u8);
+            render.write_bytes(&bitmap);
+        }
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        for (window, bitmap) in self.windows() {
+            buf.write_u8(window);
+            buf.write_u8(bitmap.len() as u8);
+            buf.write_bytes(&bitmap);
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        self.iter()
+            .map(type_to_string)
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    //takes every remaining field
+    pub fn from_str<'a>(iter: &mut Parser<'a>) -> Result<Self> {
+        let mut bitmap = TypeBitmap::new();
+        while let Some(s) = iter.next_string() {
+            bitmap.insert(type_from_str(s)?);
+        }
+        Ok(bitmap)
+    }
+}
+
+impl std::iter::FromIterator<RRType> for TypeBitmap {
+    fn from_iter<I: IntoIterator<Item = RRType>>(iter: I) -> Self {
+        TypeBitmap {
+            types: iter.into_iter().map(RRType::to_u16).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::hex::{from_hex, to_hex};
+
+    fn wire(bitmap: &TypeBitmap) -> String {
+        let mut buf = OutputBuffer::new(0);
+        bitmap.to_wire(&mut buf);
+        assert_eq!(buf.len(), bitmap.wire_len());
+        let mut render = MessageRender::new();
+        bitmap.rend(&mut render);
+        assert_eq!(render.data(), buf.data());
+        to_hex(buf.data())
+    }
+
+    fn parse(hex: &str) -> Result<TypeBitmap> {
+        let raw = from_hex(hex).unwrap();
+        TypeBitmap::from_wire(&mut InputBuffer::new(&raw), raw.len() as u16)
+    }
+
+    #[test]
+    fn test_type_bitmap() {
+        //rfc4034 section 4.3
+        let bitmap: TypeBitmap = vec![
+            RRType::A,
+            RRType::MX,
+            RRType::RRSIG,
+            RRType::NSEC,
+            RRType::new(1234),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            wire(&bitmap),
+            format!("0006400100000003041b{}20", "00".repeat(26))
+        );
+        assert_eq!(bitmap.to_string(), "A MX RRSIG NSEC TYPE1234");
+        assert_eq!(parse(&wire(&bitmap)).unwrap(), bitmap);
+        let parsed = TypeBitmap::from_str(&mut Parser::new("a mx rrsig NSEC type1234")).unwrap();
+        assert_eq!(parsed, bitmap);
+
+        let last_bit = format!("20{}01", "00".repeat(31));
+        let cases = vec![
+            (0, "000180".to_string()),
+            (7, "000101".to_string()),
+            (8, "00020080".to_string()),
+            (255, format!("00{}", last_bit)),
+            (256, "010180".to_string()),
+            (65280, "ff0180".to_string()),
+            (65535, format!("ff{}", last_bit)),
+        ];
+        for (typ, hex) in cases {
+            let mut bitmap = TypeBitmap::new();
+            assert!(bitmap.insert(RRType::new(typ)));
+            assert!(!bitmap.insert(RRType::new(typ)));
+            assert!(bitmap.contains(RRType::new(typ)));
+            assert_eq!(wire(&bitmap), hex, "{}", typ);
+            assert_eq!(parse(&hex).unwrap(), bitmap, "{}", typ);
+        }
+
+        let mut bitmap: TypeBitmap = (0..=u16::MAX).map(RRType::new).collect();
+        assert_eq!(bitmap.len(), 65536);
+        assert_eq!(bitmap.wire_len(), 256 * 34);
+        assert_eq!(parse(&wire(&bitmap)).unwrap(), bitmap);
+        assert!(bitmap.remove(RRType::A));
+        assert!(!bitmap.contains(RRType::A));
+        assert!(TypeBitmap::new().to_string().is_empty());
+        assert_eq!(TypeBitmap::new().wire_len(), 0);
+
+        //trailing zero octets are accepted but not written back
+        assert_eq!(parse("00024000").unwrap(), parse("000140").unwrap());
+        assert!(parse("0000").is_err());
+        assert!(parse(&format!("0021{}", "00".repeat(33))).is_err());
+        assert!(parse("010180000140").is_err());
+        assert!(parse("000140000180").is_err());
+        assert!(parse("0002").is_err());
+        assert!(TypeBitmap::from_str(&mut Parser::new("A BOGUS")).is_err());
+        assert!(TypeBitmap::from_str(&mut Parser::new("TYPE65536")).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+static CHARS: &[u8] = b"0123456789abcdefghijklmnopqrstuv";
+
+fn decode_char(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'A'..=b'V' => Some(c - b'A' + 10),
+        b'a'..=b'v' => Some(c - b'a' + 10),
+        _ => None,
+    }
+}
+
+//rfc4648 section 7, the extended hex alphabet keeps the sort order of the
+//data, nsec3 writes it without padding
+pub fn from_base32hex(base32_str: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(base32_str.len() * 5 / 8);
+    let mut buf = 0u32;
+    let mut bits = 0;
+    for c in base32_str.bytes() {
+        buf = buf << 5 | u32::from(decode_char(c)?);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((buf >> bits) as u8);
+            buf &= (1 << bits) - 1;
+        }
+    }
+    //the leftover bits of the last character must be zero padding
+    if bits >= 5 || buf != 0 {
+        return None;
+    }
+    Some(data)
+}
+
+pub fn to_base32hex(data: &[u8]) -> String {
+    let mut s = String::with_capacity((data.len() * 8 + 4) / 5);
+    let mut buf = 0u32;
+    let mut bits = 0;
+    for &b in data {
+        buf = buf << 8 | u32::from(b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            s.push(CHARS[(buf >> bits & 0x1f) as usize] as char);
+        }
+        buf &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        s.push(CHARS[(buf << (5 - bits) & 0x1f) as usize] as char);
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base32hex() {
+        //rfc4648 section 10 without the padding
+        let cases = vec![
+            ("", ""),
+            ("f", "co"),
+            ("fo", "cpng"),
+            ("foo", "cpnmu"),
+            ("foob", "cpnmuog"),
+            ("fooba", "cpnmuoj1"),
+            ("foobar", "cpnmuoj1e8"),
+        ];
+        for (data, encoded) in cases {
+            assert_eq!(to_base32hex(data.as_bytes()), encoded);
+            assert_eq!(from_base32hex(encoded).unwrap(), data.as_bytes());
+            assert_eq!(
+                from_base32hex(&encoded.to_uppercase()).unwrap(),
+                data.as_bytes()
+            );
+        }
+        let data: Vec<u8> = (0..=255).collect();
+        assert_eq!(from_base32hex(&to_base32hex(&data)).unwrap(), data);
+
+        for invalid in &["c", "cp", "cpn", "cq", "co==", "w0"] {
+            assert!(from_base32hex(invalid).is_none(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+static CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const PAD: u8 = b'=';
+
+fn decode_char(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+//rfc4648 section 4 with padding, whitespace is skipped since zone files
+//split long keys over several fields
+pub fn from_base64(base64_str: &str) -> Option<Vec<u8>> {
+    let chars: Vec<u8> = base64_str
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    if chars.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut data = Vec::with_capacity(chars.len() / 4 * 3);
+    let quads = chars.len() / 4;
+    for (i, quad) in chars.chunks(4).enumerate() {
+        let pad = quad.iter().rev().take_while(|&&c| c == PAD).count();
+        if pad > 2 || (pad > 0 && i + 1 != quads) {
+            return None;
+        }
+        let mut buf = 0u32;
+        for &c in &quad[..4 - pad] {
+            buf = buf << 6 | u32::from(decode_char(c)?);
+        }
+        buf <<= 6 * pad as u32;
+        let bytes = [(buf >> 16) as u8, (buf >> 8) as u8, buf as u8];
+        data.extend_from_slice(&bytes[..3 - pad]);
+    }
+    Some(data)
+}
+
+pub fn to_base64(data: &[u8]) -> String {
+    let mut s = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let buf = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |buf, (i, &b)| buf | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(CHARS[(buf >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                s.push(PAD as char);
+            }
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        //rfc4648 section 10
+        let cases = vec![
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (data, encoded) in cases {
+            assert_eq!(to_base64(data.as_bytes()), encoded);
+            assert_eq!(from_base64(encoded).unwrap(), data.as_bytes());
+        }
+        let data: Vec<u8> = (0..=255).collect();
+        assert_eq!(from_base64(&to_base64(&data)).unwrap(), data);
+        assert_eq!(from_base64("Zm9v\n YmE=").unwrap(), b"fooba");
+
+        for invalid in &["Zm9", "Zm9v!", "Z===", "Zg==Zm9v", "Zm=v"] {
+            assert!(from_base64(invalid).is_none(), "{}", invalid);
+        }
+    }
+}
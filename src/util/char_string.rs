@@ -0,0 +1,117 @@
+use crate::error::DNSError;
+use crate::message_render::MessageRender;
+use crate::presentation;
+use crate::util::{InputBuffer, OutputBuffer};
+use failure::{self, Result};
+use std::fmt;
+use std::str::FromStr;
+
+pub const MAX_CHAR_STRING_LEN: usize = 255;
+
+//rfc1035 section 3.3, a length octet followed by at most 255 octets
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct CharString(Vec<u8>);
+
+impl CharString {
+    pub fn new(data: Vec<u8>) -> Result<Self> {
+        if data.len() > MAX_CHAR_STRING_LEN {
+            return Err(DNSError::TooLongCharString(data.len()).into());
+        }
+        Ok(CharString(data))
+    }
+
+    //the text between the quotes, with the escapes of the presentation
+    //module
+    pub fn from_escaped(raw: &[u8]) -> Result<Self> {
+        CharString::new(presentation::unescape(raw)?)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn from_wire(buf: &mut InputBuffer) -> Result<Self> {
+        let len = buf.read_u8()?;
+        buf.read_bytes(len as usize)
+            .map(|data| CharString(data.to_vec()))
+    }
+
+    pub fn rend(&self, render: &mut MessageRender) {
+        render.write_u8(self.0.len() as u8);
+        render.write_bytes(self.0.as_slice());
+    }
+
+    pub fn to_wire(&self, buf: &mut OutputBuffer) {
+        buf.write_u8(self.0.len() as u8);
+        buf.write_bytes(self.0.as_slice());
+    }
+}
+
+//always quoted, so empty strings and spaces survive a round trip
+impl fmt::Display for CharString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&presentation::quote_char_string(&self.0))
+    }
+}
+
+//a single quoted or unquoted string
+impl FromStr for CharString {
+    type Err = failure::Error;
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let raw = s.as_bytes();
+        if raw.first() == Some(&b'"') {
+            if raw.len() < 2 || raw[raw.len() - 1] != b'"' {
+                return Err(DNSError::InCompleteEscape.into());
+            }
+            let inner = &raw[1..raw.len() - 1];
+            //an odd number of backslashes escapes the closing quote
+            if inner.iter().rev().take_while(|&&c| c == b'\\').count() % 2 == 1 {
+                return Err(DNSError::InCompleteEscape.into());
+            }
+            CharString::from_escaped(inner)
+        } else {
+            CharString::from_escaped(raw)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_char_string() {
+        let s = CharString::from_str("\"say \\\"hi\\\"\\010\"").unwrap();
+        assert_eq!(s.as_bytes(), b"say \"hi\"\n");
+        assert_eq!(s.to_string(), "\"say \\\"hi\\\"\\010\"");
+        assert_eq!(CharString::from_str(&s.to_string()).unwrap(), s);
+        assert_eq!(CharString::from_str("a\\032b").unwrap().as_bytes(), b"a b");
+        assert!(CharString::from_str("\"").is_err());
+        assert!(CharString::from_str("\"abc\\\"").is_err());
+        assert_eq!(
+            CharString::from_str("\"abc\\\\\"").unwrap().as_bytes(),
+            b"abc\\"
+        );
+        assert!(CharString::from_str("ab\\").is_err());
+
+        assert_eq!(CharString::default().to_string(), "\"\"");
+        assert!(CharString::new(vec![b'a'; 255]).is_ok());
+        assert!(CharString::new(vec![b'a'; 256]).is_err());
+        assert!(CharString::from_str(&"\\097".repeat(256)).is_err());
+
+        let mut buf = OutputBuffer::new(0);
+        s.to_wire(&mut buf);
+        assert_eq!(buf.data()[0], 9);
+        let mut input = InputBuffer::new(buf.data());
+        assert_eq!(CharString::from_wire(&mut input).unwrap(), s);
+        assert!(CharString::from_wire(&mut InputBuffer::new(&[3, b'a'])).is_err());
+    }
+}
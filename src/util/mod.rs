@@ -1,6 +1,16 @@
+pub mod base32;
+pub mod base64;
+mod char_string;
+pub mod digest;
 pub mod hex;
 mod input_buffer;
 mod output_buffer;
+mod prefix;
+mod secret;
+pub mod time;
 
+pub use self::char_string::{CharString, MAX_CHAR_STRING_LEN};
 pub use self::input_buffer::InputBuffer;
 pub use self::output_buffer::OutputBuffer;
+pub(crate) use self::prefix::{mask_addr, prefix_match};
+pub use self::secret::{ct_eq, SecretKey};
@@ -0,0 +1,45 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+//keeps the first prefix_len bits of addr, longer prefixes keep them all
+pub(crate) fn mask_addr(addr: IpAddr, prefix_len: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(v4) => {
+            let mask = if prefix_len == 0 {
+                0
+            } else {
+                !0u32 << (32 - u32::from(prefix_len.min(32)))
+            };
+            IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+        }
+        IpAddr::V6(v6) => {
+            let mask = if prefix_len == 0 {
+                0
+            } else {
+                !0u128 << (128 - u32::from(prefix_len.min(128)))
+            };
+            IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+        }
+    }
+}
+
+//addresses of different families never match
+pub(crate) fn prefix_match(addr: IpAddr, net: IpAddr, len: u8) -> bool {
+    addr.is_ipv4() == net.is_ipv4() && mask_addr(addr, len) == mask_addr(net, len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_prefix_match() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(mask_addr(ip("192.0.2.130"), 25), ip("192.0.2.128"));
+        assert_eq!(mask_addr(ip("192.0.2.130"), 40), ip("192.0.2.130"));
+        assert_eq!(mask_addr(ip("2001:db8::1"), 0), ip("::"));
+        assert!(prefix_match(ip("10.1.2.3"), ip("10.0.0.0"), 8));
+        assert!(!prefix_match(ip("11.1.2.3"), ip("10.0.0.0"), 8));
+        assert!(prefix_match(ip("2001:db8::1"), ip("2001:db8::"), 32));
+        assert!(!prefix_match(ip("::"), ip("0.0.0.0"), 0));
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+
+//the running time depends only on the lengths, never on where the first
+//differing byte is
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a
+        .iter()
+        .zip(b.iter())
+        .fold(0u8, |diff, (x, y)| diff | (x ^ y));
+    diff == 0
+}
+
+#[derive(Clone)]
+pub struct SecretKey(Vec<u8>);
+
+impl SecretKey {
+    pub fn new(key: Vec<u8>) -> Self {
+        SecretKey(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<&[u8]> for SecretKey {
+    fn from(key: &[u8]) -> Self {
+        SecretKey(key.to_vec())
+    }
+}
+
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &SecretKey) -> bool {
+        ct_eq(self.as_bytes(), other.as_bytes())
+    }
+}
+
+impl Eq for SecretKey {}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretKey({} bytes)", self.0.len())
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        //volatile writes so the compiler can't drop the stores to memory
+        //that is about to be freed
+        for b in self.0.iter_mut() {
+            unsafe { std::ptr::write_volatile(b, 0) };
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ct_eq() {
+        assert!(ct_eq(b"", b""));
+        assert!(ct_eq(b"cookie01", b"cookie01"));
+        assert!(!ct_eq(b"cookie01", b"cookie02"));
+        assert!(!ct_eq(b"cookie01", b"cookie0"));
+        assert!(!ct_eq(&[0x80], &[0x00]));
+
+        let key = SecretKey::from(&b"secret"[..]);
+        assert_eq!(key, SecretKey::new(b"secret".to_vec()));
+        assert_ne!(key, SecretKey::new(b"secreT".to_vec()));
+        assert_eq!(format!("{:?}", key), "SecretKey(6 bytes)");
+    }
+}
@@ -0,0 +1,193 @@
+use crate::error::DNSError;
+use failure::Result;
+use std::cmp::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: i64 = 86400;
+const TIMESTAMP_LEN: usize = 14;
+const SERIAL_HALF: u32 = 1 << 31;
+
+//rfc1982 serial number arithmetic, None when the two are exactly half the
+//number space apart and the order is undefined
+pub fn serial_cmp(a: u32, b: u32) -> Option<Ordering> {
+    match a.wrapping_sub(b) {
+        0 => Some(Ordering::Equal),
+        SERIAL_HALF => None,
+        diff if diff < SERIAL_HALF => Some(Ordering::Greater),
+        _ => Some(Ordering::Less),
+    }
+}
+
+pub fn serial_lt(a: u32, b: u32) -> bool {
+    serial_cmp(a, b) == Some(Ordering::Less)
+}
+
+pub fn serial_le(a: u32, b: u32) -> bool {
+    a == b || serial_lt(a, b)
+}
+
+//seconds since the epoch modulo 2^32, the form rrsig timestamps use
+pub fn to_timestamp(time: SystemTime) -> u32 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as u32)
+}
+
+pub fn now_timestamp() -> u32 {
+    to_timestamp(SystemTime::now())
+}
+
+//rfc4034 section 3.1.5, now must lie between inception and expiration
+//in serial number order
+pub fn is_valid_period(inception: u32, expiration: u32, now: u32) -> bool {
+    serial_le(inception, now) && serial_le(now, expiration)
+}
+
+//days since 1970-01-01 of a proleptic gregorian date and back, from
+//Howard Hinnant's chrono compatible algorithms
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+//YYYYMMDDHHmmSS in utc, timestamps past 2106 have wrapped and are printed
+//as the time they wrapped to
+pub fn format_timestamp(timestamp: u32) -> String {
+    let secs = i64::from(timestamp);
+    let (year, month, day) = civil_from_days(secs.div_euclid(SECS_PER_DAY));
+    let secs = secs.rem_euclid(SECS_PER_DAY);
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+//rfc4034 section 3.2, either YYYYMMDDHHmmSS or the seconds as a decimal
+//number, dates past 2106 wrap modulo 2^32
+pub fn parse_timestamp(s: &str) -> Result<u32> {
+    let invalid = || DNSError::InvalidTimestamp(s.to_string());
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(invalid().into());
+    }
+    if s.len() != TIMESTAMP_LEN {
+        return s.parse::<u32>().map_err(|_| invalid().into());
+    }
+
+    let field = |start: usize, end: usize| s[start..end].parse::<u32>().unwrap();
+    let year = i64::from(field(0, 4));
+    let (month, day) = (field(4, 6), field(6, 8));
+    let (hour, minute, second) = (field(8, 10), field(10, 12), field(12, 14));
+    if year < 1970
+        || month == 0
+        || month > 12
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(invalid().into());
+    }
+    let secs = days_from_civil(year, month, day) * SECS_PER_DAY
+        + i64::from(hour * 3600 + minute * 60 + second);
+    Ok(secs as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timestamp() {
+        let cases = vec![
+            ("19700101000000", 0),
+            ("20000229235959", 951_868_799),
+            ("20030322173103", 1_048_354_263),
+            ("20380119031408", 2_147_483_648),
+            ("21060207062815", u32::MAX),
+        ];
+        for (text, timestamp) in cases {
+            assert_eq!(parse_timestamp(text).unwrap(), timestamp, "{}", text);
+            assert_eq!(format_timestamp(timestamp), text);
+        }
+        assert_eq!(parse_timestamp("21060207062816").unwrap(), 0);
+        assert_eq!(parse_timestamp("1048354263").unwrap(), 1_048_354_263);
+        for invalid in &[
+            "",
+            "-1",
+            "4294967296",
+            "19691231235959",
+            "20010229000000",
+            "20031322000000",
+            "20030300000000",
+            "20030322240000",
+            "20030322176000",
+            "2003032217310x",
+        ] {
+            assert!(parse_timestamp(invalid).is_err(), "{}", invalid);
+        }
+
+        for days in (-800_000..800_000).step_by(997) {
+            let (year, month, day) = civil_from_days(days);
+            assert!(day <= days_in_month(year, month));
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+
+        let time = UNIX_EPOCH + Duration::from_secs(1_048_354_263);
+        assert_eq!(to_timestamp(time), 1_048_354_263);
+        assert_eq!(to_timestamp(UNIX_EPOCH + Duration::from_secs(1 << 32)), 0);
+    }
+
+    #[test]
+    fn test_serial_arithmetic() {
+        assert_eq!(serial_cmp(1, 1), Some(Ordering::Equal));
+        assert!(serial_lt(1, 2));
+        assert!(serial_lt(u32::MAX, 0));
+        assert!(serial_lt(0, SERIAL_HALF - 1));
+        assert_eq!(serial_cmp(0, SERIAL_HALF), None);
+        assert_eq!(serial_cmp(SERIAL_HALF, 0), None);
+        assert!(serial_le(5, 5));
+        assert!(!serial_le(6, 5));
+
+        let now = 1_048_354_263;
+        assert!(is_valid_period(now - 3600, now + 3600, now));
+        assert!(is_valid_period(now, now, now));
+        assert!(!is_valid_period(now + 1, now + 3600, now));
+        assert!(!is_valid_period(now - 3600, now - 1, now));
+        //a signature period spanning the 2106 wrap
+        assert!(is_valid_period(u32::MAX - 10, 10, 5));
+        assert!(!is_valid_period(u32::MAX - 10, 10, 11));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::header_flag::HeaderFlag;
+use crate::message::Message;
+use crate::message_builder::MessageBuilder;
+use crate::name::Name;
+use crate::name_matcher::NameMatcher;
+use crate::rcode::Rcode;
+use crate::util::prefix_match;
+use std::net::IpAddr;
+
+//entries are checked in order and the first matched one decides
+#[derive(Debug, Clone, Default)]
+pub struct Acl {
+    entries: Vec<(bool, IpAddr, u8)>,
+}
+
+impl Acl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn any() -> Self {
+        let mut acl = Acl::new();
+        acl.allow("0.0.0.0".parse().unwrap(), 0)
+            .allow("::".parse().unwrap(), 0);
+        acl
+    }
+
+    pub fn allow(&mut self, net: IpAddr, len: u8) -> &mut Self {
+        self.entries.push((true, net, len));
+        self
+    }
+
+    pub fn deny(&mut self, net: IpAddr, len: u8) -> &mut Self {
+        self.entries.push((false, net, len));
+        self
+    }
+
+    pub fn is_match(&self, addr: IpAddr) -> bool {
+        self.entries
+            .iter()
+            .find(|(_, net, len)| prefix_match(addr, *net, *len))
+            .map(|(allow, _, _)| *allow)
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PolicyAction {
+    NXDomain,
+    NoData,
+    Refused,
+    Drop,
+}
+
+impl PolicyAction {
+    pub fn response(self, query: &Message) -> Option<Message> {
+        let rcode = match self {
+            PolicyAction::NXDomain => Rcode::NXDomain,
+            PolicyAction::NoData => Rcode::NoError,
+            PolicyAction::Refused => Rcode::Refused,
+            PolicyAction::Drop => return None,
+        };
+        let mut response = Message::with_query(
+            query.question.as_ref()?.name.clone(),
+            query.question.as_ref()?.typ,
+        );
+        MessageBuilder::new(&mut response)
+            .id(query.header.id)
+            .opcode(query.header.opcode)
+            .make_response()
+            .rcode(rcode)
+            .done();
+        if query.header.is_flag_set(HeaderFlag::RecursionDesired) {
+            response.header.set_flag(HeaderFlag::RecursionDesired, true);
+        }
+        Some(response)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct View<T> {
+    pub name: String,
+    pub acl: Acl,
+    pub key: Option<Name>,
+    pub data: T,
+    policies: Vec<(NameMatcher, PolicyAction)>,
+}
+
+impl<T> View<T> {
+    pub fn new(name: &str, acl: Acl, data: T) -> Self {
+        View {
+            name: name.to_string(),
+            acl,
+            key: None,
+            data,
+            policies: Vec::new(),
+        }
+    }
+
+    pub fn with_key(mut self, key: Name) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    pub fn add_policy(&mut self, matcher: NameMatcher, action: PolicyAction) -> &mut Self {
+        self.policies.push((matcher, action));
+        self
+    }
+
+    //a view bound to a tsig key only accepts requests signed with it,
+    //a view without key accepts any request from matched clients
+    pub fn is_match(&self, client: IpAddr, key: Option<&Name>) -> bool {
+        if let Some(ref view_key) = self.key {
+            if key != Some(view_key) {
+                return false;
+            }
+        }
+        self.acl.is_match(client)
+    }
+
+    pub fn policy_for(&self, name: &Name) -> Option<PolicyAction> {
+        self.policies
+            .iter()
+            .find(|(matcher, _)| matcher.is_match(name))
+            .map(|(_, action)| *action)
+    }
+
+    pub fn apply_policy(&self, query: &Message) -> Option<PolicyAction> {
+        self.policy_for(&query.question.as_ref()?.name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Views<T> {
+    views: Vec<View<T>>,
+}
+
+impl<T> Default for Views<T> {
+    fn default() -> Self {
+        Views { views: Vec::new() }
+    }
+}
+
+impl<T> Views<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, view: View<T>) -> &mut Self {
+        self.views.push(view);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.views.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.views.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&View<T>> {
+        self.views.iter().find(|view| view.name == name)
+    }
+
+    pub fn select(&self, client: IpAddr, key: Option<&Name>) -> Option<&View<T>> {
+        self.views.iter().find(|view| view.is_match(client, key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rr_type::RRType;
+
+    #[test]
+    fn test_select_view() {
+        let mut internal_acl = Acl::new();
+        internal_acl
+            .deny("10.0.9.0".parse().unwrap(), 24)
+            .allow("10.0.0.0".parse().unwrap(), 8)
+            .allow("fd00::".parse().unwrap(), 8);
+
+        let mut internal = View::new("internal", internal_acl, 1);
+        let mut matcher = NameMatcher::new();
+        matcher.add_pattern("*.ads.example.").unwrap();
+        internal.add_policy(matcher, PolicyAction::NXDomain);
+
+        let key = Name::new("transfer-key.").unwrap();
+        let mut views = Views::new();
+        views
+            .add(View::new("secondary", Acl::any(), 0).with_key(key.clone()))
+            .add(internal)
+            .add(View::new("external", Acl::any(), 2));
+        assert_eq!(views.len(), 3);
+
+        let select = |addr: &str, key: Option<&Name>| {
+            views
+                .select(addr.parse().unwrap(), key)
+                .unwrap()
+                .name
+                .clone()
+        };
+        assert_eq!(select("10.1.2.3", None), "internal");
+        assert_eq!(select("fd12::1", None), "internal");
+        assert_eq!(select("10.0.9.1", None), "external");
+        assert_eq!(select("192.0.2.1", None), "external");
+        assert_eq!(select("10.1.2.3", Some(&key)), "secondary");
+        let other_key = Name::new("other-key.").unwrap();
+        assert_eq!(select("10.1.2.3", Some(&other_key)), "internal");
+
+        let internal = views.get("internal").unwrap();
+        let query = Message::with_query(Name::new("x.ads.example.").unwrap(), RRType::A);
+        assert_eq!(internal.apply_policy(&query), Some(PolicyAction::NXDomain));
+        let response = PolicyAction::NXDomain.response(&query).unwrap();
+        assert_eq!(response.header.id, query.header.id);
+        assert_eq!(response.header.rcode, Rcode::NXDomain);
+        assert!(response.header.is_flag_set(HeaderFlag::QueryRespone));
+        assert_eq!(PolicyAction::Drop.response(&query), None);
+
+        let query = Message::with_query(Name::new("ads.example.").unwrap(), RRType::A);
+        assert_eq!(internal.apply_policy(&query), None);
+        assert_eq!(views.get("external").unwrap().apply_policy(&query), None);
+    }
+}
@@ -0,0 +1,661 @@
+use crate::error::DNSError;
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::rr_type::RRType;
+use crate::rrset::{RRTtl, RRset};
+use crate::util::time::serial_lt;
+use failure::Result;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::mem;
+use std::ops::Bound::{Excluded, Unbounded};
+
+//rfc2181 section 8
+pub const MAX_TTL: u32 = 0x7fff_ffff;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TtlPolicy {
+    pub min_ttl: u32,
+    pub max_ttl: u32,
+}
+
+impl Default for TtlPolicy {
+    fn default() -> Self {
+        TtlPolicy {
+            min_ttl: 0,
+            max_ttl: MAX_TTL,
+        }
+    }
+}
+
+impl TtlPolicy {
+    pub fn clamp(&self, ttl: u32) -> u32 {
+        ttl.max(self.min_ttl).min(self.max_ttl)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ZoneLookup {
+    //the rrset asked for, or the cname at the name
+    Answer(RRset),
+    //ns rrset of the zone cut and the glue found for it
+    Referral(RRset, Vec<RRset>),
+    NoData,
+    NXDomain,
+}
+
+//authoritative data of one zone, kept per owner name
+#[derive(Debug, Clone)]
+pub struct Zone {
+    origin: Name,
+    //canonical order puts the names below a node right after it
+    nodes: BTreeMap<Name, Vec<RRset>>,
+}
+
+//labels to strip from name to reach origin, none when name is outside
+fn depth_below(name: &Name, origin: &Name) -> Option<usize> {
+    let depth = name.label_count().checked_sub(origin.label_count())?;
+    if depth == 0 {
+        return if name == origin { Some(0) } else { None };
+    }
+    if name.strip_left(depth) == *origin {
+        Some(depth)
+    } else {
+        None
+    }
+}
+
+impl Zone {
+    pub fn new(origin: Name) -> Self {
+        Zone {
+            origin,
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    pub fn origin(&self) -> &Name {
+        &self.origin
+    }
+
+    pub fn contains(&self, name: &Name) -> bool {
+        depth_below(name, &self.origin).is_some()
+    }
+
+    pub fn add_rrset(&mut self, mut rrset: RRset) -> Result<()> {
+        if !self.contains(&rrset.name) {
+            return Err(DNSError::OutOfZone.into());
+        }
+        let rrsets = self.nodes.entry(rrset.name.clone()).or_default();
+        match rrsets.iter_mut().find(|old| old.is_same_rrset(&rrset)) {
+            Some(old) => old.rdatas.append(&mut rrset.rdatas),
+            None => rrsets.push(rrset),
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &Name, typ: RRType) -> Option<&RRset> {
+        self.nodes.get(name)?.iter().find(|rrset| rrset.typ == typ)
+    }
+
+    //every rrset, owners in canonical order
+    pub fn rrsets(&self) -> impl Iterator<Item = &RRset> {
+        self.nodes.values().flatten()
+    }
+
+    //data below a zone cut belongs to the child, only the ds and nsec at
+    //the cut itself are the parent's
+    pub fn is_authoritative(&self, name: &Name, typ: RRType) -> bool {
+        match self.zone_cut(name, typ) {
+            None => self.contains(name),
+            Some(ns) => typ == RRType::NSEC && ns.name.eq(name),
+        }
+    }
+
+    pub fn soa(&self) -> Option<&RRset> {
+        self.get(&self.origin, RRType::SOA)
+    }
+
+    pub fn serial(&self) -> Option<u32> {
+        soa_serial(self.soa()?)
+    }
+
+    //edits are staged on the transaction and only reach the zone on commit
+    pub fn begin(&mut self) -> Transaction<'_> {
+        Transaction {
+            zone: self,
+            changes: Vec::new(),
+        }
+    }
+
+    //lowercases owner names, merges duplicate rrsets, sorts and dedups
+    //records and clamps ttls, running it again changes nothing
+    pub fn normalize(&mut self, policy: &TtlPolicy) {
+        self.origin.to_lowercase();
+        for (mut name, rrsets) in mem::take(&mut self.nodes) {
+            name.to_lowercase();
+            let mut merged: Vec<RRset> = Vec::with_capacity(rrsets.len());
+            for mut rrset in rrsets {
+                rrset.name = name.clone();
+                rrset.ttl = RRTtl(policy.clamp(rrset.ttl.0));
+                match merged.iter_mut().find(|old| old.is_same_rrset(&rrset)) {
+                    //rfc2181 section 5.2, an rrset has one ttl, the lowest wins
+                    Some(old) => {
+                        old.ttl = RRTtl(old.ttl.0.min(rrset.ttl.0));
+                        old.rdatas.append(&mut rrset.rdatas);
+                    }
+                    None => merged.push(rrset),
+                }
+            }
+            for rrset in &mut merged {
+                rrset.sort_rdatas();
+            }
+            merged.sort_by_key(|rrset| rrset.typ.to_u16());
+            self.nodes.insert(name, merged);
+        }
+    }
+
+    //rfc2308 section 3, the soa of a negative answer lives as long as the
+    //smaller of its ttl and minimum field
+    pub fn negative_soa(&self) -> Option<RRset> {
+        let mut soa = self.soa()?.clone();
+        if let Some(RData::SOA(ref rdata)) = soa.rdatas.first() {
+            soa.ttl = RRTtl(soa.ttl.0.min(rdata.minimum));
+        }
+        Some(soa)
+    }
+
+    //the topmost ns rrset between the origin and name, the apex ns is the
+    //zone's own. The ds at a cut belongs to the parent side
+    fn zone_cut(&self, name: &Name, typ: RRType) -> Option<&RRset> {
+        let depth = depth_below(name, &self.origin)?;
+        (0..depth)
+            .rev()
+            .filter(|&strip| !(strip == 0 && typ == RRType::DS))
+            .find_map(|strip| self.get(&name.strip_left(strip), RRType::NS))
+    }
+
+    fn glue(&self, ns: &RRset) -> Vec<RRset> {
+        ns.rdatas
+            .iter()
+            .filter_map(|rdata| match rdata {
+                RData::NS(ref ns) => Some(&ns.name),
+                _ => None,
+            })
+            .flat_map(|host| vec![self.get(host, RRType::A), self.get(host, RRType::AAAA)])
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    fn has_descendants(&self, name: &Name) -> bool {
+        self.nodes
+            .range::<Name, _>((Excluded(name), Unbounded))
+            .next()
+            .map_or(false, |(owner, _)| depth_below(owner, name).is_some())
+    }
+
+    pub fn lookup(&self, name: &Name, typ: RRType) -> ZoneLookup {
+        if let Some(ns) = self.zone_cut(name, typ) {
+            return ZoneLookup::Referral(ns.clone(), self.glue(ns));
+        }
+
+        match self.nodes.get(name) {
+            Some(rrsets) => rrsets
+                .iter()
+                .find(|rrset| rrset.typ == typ)
+                .or_else(|| rrsets.iter().find(|rrset| rrset.typ == RRType::CNAME))
+                .map_or(ZoneLookup::NoData, |rrset| {
+                    ZoneLookup::Answer(rrset.clone())
+                }),
+            //an empty non-terminal has names below it
+            None if self.has_descendants(name) => ZoneLookup::NoData,
+            None => ZoneLookup::NXDomain,
+        }
+    }
+}
+
+fn soa_serial(soa: &RRset) -> Option<u32> {
+    match soa.rdatas.first() {
+        Some(RData::SOA(ref soa)) => Some(soa.serial),
+        _ => None,
+    }
+}
+
+fn invalid_change(reason: String) -> failure::Error {
+    DNSError::InvalidZoneChange(reason).into()
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Change {
+    Add(RRset),
+    //without rdatas the whole rrset goes
+    Delete(RRset),
+}
+
+impl Change {
+    fn name(&self) -> &Name {
+        match self {
+            Change::Add(rrset) | Change::Delete(rrset) => &rrset.name,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    zone: &'a mut Zone,
+    changes: Vec<Change>,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn add(&mut self, rrset: RRset) -> Result<&mut Self> {
+        if !self.zone.contains(&rrset.name) {
+            return Err(DNSError::OutOfZone.into());
+        }
+        if rrset.rdatas.is_empty() {
+            return Err(invalid_change(format!(
+                "{} {} has no rdata",
+                rrset.name, rrset.typ
+            )));
+        }
+        self.changes.push(Change::Add(rrset));
+        Ok(self)
+    }
+
+    //removes the records given, or the whole rrset when there are none
+    pub fn delete(&mut self, rrset: RRset) -> Result<&mut Self> {
+        if !self.zone.contains(&rrset.name) {
+            return Err(DNSError::OutOfZone.into());
+        }
+        self.changes.push(Change::Delete(rrset));
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    //the nodes the changes touch as they would look afterwards
+    fn staged(&self) -> BTreeMap<Name, Vec<RRset>> {
+        let mut staged: BTreeMap<Name, Vec<RRset>> = BTreeMap::new();
+        for change in &self.changes {
+            let zone = &self.zone;
+            let rrsets = staged
+                .entry(change.name().clone())
+                .or_insert_with(|| zone.nodes.get(change.name()).cloned().unwrap_or_default());
+            match change {
+                Change::Add(rrset) => {
+                    match rrsets.iter_mut().find(|old| old.is_same_rrset(rrset)) {
+                        //there is only one soa, adding one replaces it
+                        Some(old) if rrset.typ == RRType::SOA => *old = rrset.clone(),
+                        Some(old) => {
+                            old.ttl = rrset.ttl;
+                            for rdata in &rrset.rdatas {
+                                if !old.rdatas.contains(rdata) {
+                                    old.rdatas.push(rdata.clone());
+                                }
+                            }
+                        }
+                        None => rrsets.push(rrset.clone()),
+                    }
+                }
+                Change::Delete(rrset) if rrset.rdatas.is_empty() => {
+                    rrsets.retain(|old| old.typ != rrset.typ)
+                }
+                Change::Delete(rrset) => {
+                    for old in rrsets.iter_mut().filter(|old| old.typ == rrset.typ) {
+                        old.rdatas.retain(|rdata| !rrset.rdatas.contains(rdata));
+                    }
+                    rrsets.retain(|old| !old.rdatas.is_empty());
+                }
+            }
+        }
+        staged
+    }
+
+    fn validate(&self, name: &Name, rrsets: &[RRset]) -> Result<()> {
+        let has = |typ| rrsets.iter().any(|rrset| rrset.typ == typ);
+        if has(RRType::SOA) && !name.eq(&self.zone.origin) {
+            return Err(invalid_change(format!("soa at {}", name)));
+        }
+        if let Some(cname) = rrsets.iter().find(|rrset| rrset.typ == RRType::CNAME) {
+            //rfc2181 section 10.1, the dnssec types may sit next to a cname
+            let others = rrsets.iter().any(|rrset| {
+                !matches!(
+                    rrset.typ,
+                    RRType::CNAME | RRType::RRSIG | RRType::NSEC | RRType::NSEC3
+                )
+            });
+            if cname.rdatas.len() > 1 || others {
+                return Err(invalid_change(format!("cname at {} with other data", name)));
+            }
+        }
+        Ok(())
+    }
+
+    //applies every change or none of them and returns the serial of the
+    //zone. The serial is bumped unless the changes raised it themselves
+    pub fn commit(self) -> Result<u32> {
+        let old_serial = self.zone.serial();
+        if self.changes.is_empty() {
+            return old_serial.ok_or_else(|| invalid_change("zone has no soa".to_string()));
+        }
+
+        let mut staged = self.staged();
+        let origin = self.zone.origin.clone();
+        let apex = staged
+            .entry(origin.clone())
+            .or_insert_with(|| self.zone.nodes.get(&origin).cloned().unwrap_or_default());
+        let soa = match apex.iter_mut().find(|rrset| rrset.typ == RRType::SOA) {
+            Some(soa) if soa.rdatas.len() == 1 => soa,
+            _ => return Err(invalid_change("zone needs exactly one soa".to_string())),
+        };
+        let serial = match (old_serial, soa.rdatas.first_mut()) {
+            (Some(old), Some(RData::SOA(ref mut rdata))) => {
+                if !serial_lt(old, rdata.serial) {
+                    rdata.serial = old.wrapping_add(1);
+                }
+                rdata.serial
+            }
+            (None, Some(RData::SOA(ref rdata))) => rdata.serial,
+            _ => return Err(invalid_change("zone needs exactly one soa".to_string())),
+        };
+        for (name, rrsets) in &staged {
+            self.validate(name, rrsets)?;
+        }
+
+        for (name, rrsets) in staged {
+            if rrsets.is_empty() {
+                self.zone.nodes.remove(&name);
+            } else {
+                self.zone.nodes.insert(name, rrsets);
+            }
+        }
+        Ok(serial)
+    }
+
+    //dropping the transaction has the same effect
+    pub fn rollback(self) {}
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ZoneDiff {
+    pub added: Vec<RRset>,
+    pub removed: Vec<RRset>,
+    //old and new versions of an rrset whose ttl or records differ
+    pub changed: Vec<(RRset, RRset)>,
+}
+
+impl ZoneDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+//record order inside an rrset doesn't matter
+fn same_rrset_data(a: &RRset, b: &RRset) -> bool {
+    a.ttl == b.ttl
+        && a.rdatas.len() == b.rdatas.len()
+        && a.rdatas.iter().all(|rdata| b.rdatas.contains(rdata))
+}
+
+fn diff_node(old: &[RRset], new: &[RRset], diff: &mut ZoneDiff) {
+    for old_rrset in old {
+        match new.iter().find(|rrset| rrset.is_same_rrset(old_rrset)) {
+            None => diff.removed.push(old_rrset.clone()),
+            Some(new_rrset) if !same_rrset_data(old_rrset, new_rrset) => {
+                diff.changed.push((old_rrset.clone(), new_rrset.clone()))
+            }
+            _ => {}
+        }
+    }
+    for new_rrset in new {
+        if !old.iter().any(|rrset| rrset.is_same_rrset(new_rrset)) {
+            diff.added.push(new_rrset.clone());
+        }
+    }
+}
+
+//walks both zones in canonical order side by side, so each owner name is
+//visited once and the result comes out in canonical order
+pub fn diff(old: &Zone, new: &Zone) -> ZoneDiff {
+    let mut diff = ZoneDiff::default();
+    let mut old_nodes = old.nodes.iter().peekable();
+    let mut new_nodes = new.nodes.iter().peekable();
+    loop {
+        let order = match (old_nodes.peek(), new_nodes.peek()) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((old_name, _)), Some((new_name, _))) => old_name.cmp(new_name),
+        };
+        match order {
+            Ordering::Less => diff
+                .removed
+                .extend(old_nodes.next().unwrap().1.iter().cloned()),
+            Ordering::Greater => diff
+                .added
+                .extend(new_nodes.next().unwrap().1.iter().cloned()),
+            Ordering::Equal => diff_node(
+                old_nodes.next().unwrap().1,
+                new_nodes.next().unwrap().1,
+                &mut diff,
+            ),
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn example_zone() -> Zone {
+        let mut zone = Zone::new(Name::new("example.com.").unwrap());
+        for rr in &[
+            "example.com. 3600 IN SOA ns1.example.com. root.example.com. 1 3600 900 604800 300",
+            "example.com. 3600 IN NS ns1.example.com.",
+            "ns1.example.com. 3600 IN A 192.0.2.53",
+            "www.example.com. 300 IN A 192.0.2.1",
+            "www.example.com. 300 IN A 192.0.2.2",
+            "ftp.example.com. 300 IN CNAME www.example.com.",
+            "a.b.example.com. 300 IN TXT \"deep\"",
+            "sub.example.com. 3600 IN NS ns.sub.example.com.",
+            "ns.sub.example.com. 3600 IN A 192.0.2.54",
+        ] {
+            zone.add_rrset(RRset::from_str(rr).unwrap()).unwrap();
+        }
+        zone
+    }
+
+    fn zone_soa_rrset() -> RRset {
+        RRset::from_str(
+            "example.com. 3600 IN SOA ns1.example.com. root.example.com. 1 3600 900 604800 300",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_zone_lookup() {
+        let mut zone = example_zone();
+        let name = |s: &str| Name::new(s).unwrap();
+        assert!(zone
+            .add_rrset(RRset::from_str("www.example.org. 300 IN A 192.0.2.1").unwrap())
+            .is_err());
+        assert!(!zone.contains(&name("aexample.com.")));
+
+        match zone.lookup(&name("WWW.example.com."), RRType::A) {
+            ZoneLookup::Answer(rrset) => assert_eq!(rrset.rdatas.len(), 2),
+            other => panic!("unexpected {:?}", other),
+        }
+        match zone.lookup(&name("ftp.example.com."), RRType::A) {
+            ZoneLookup::Answer(rrset) => assert_eq!(rrset.typ, RRType::CNAME),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            zone.lookup(&name("www.example.com."), RRType::AAAA),
+            ZoneLookup::NoData
+        );
+        assert_eq!(
+            zone.lookup(&name("b.example.com."), RRType::A),
+            ZoneLookup::NoData
+        );
+        assert_eq!(
+            zone.lookup(&name("c.example.com."), RRType::A),
+            ZoneLookup::NXDomain
+        );
+        assert_eq!(
+            zone.lookup(&name("a.example.com."), RRType::A),
+            ZoneLookup::NXDomain
+        );
+        match zone.lookup(&name("host.sub.example.com."), RRType::A) {
+            ZoneLookup::Referral(ns, glue) => {
+                assert_eq!(ns.name, name("sub.example.com."));
+                assert_eq!(glue.len(), 1);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            zone.lookup(&name("sub.example.com."), RRType::DS),
+            ZoneLookup::NoData
+        );
+        assert_eq!(zone.negative_soa().unwrap().ttl, RRTtl(300));
+    }
+
+    #[test]
+    fn test_zone_transaction() {
+        let mut zone = example_zone();
+        let name = |s: &str| Name::new(s).unwrap();
+        assert_eq!(zone.serial(), Some(1));
+
+        let mut txn = zone.begin();
+        txn.add(RRset::from_str("new.example.com. 300 IN A 192.0.2.7").unwrap())
+            .unwrap()
+            .delete(RRset::from_str("www.example.com. 300 IN A 192.0.2.1").unwrap())
+            .unwrap();
+        let mut ftp = RRset::from_str("ftp.example.com. 300 IN CNAME www.example.com.").unwrap();
+        ftp.rdatas.clear();
+        txn.delete(ftp).unwrap();
+        assert!(txn
+            .add(RRset::from_str("www.example.org. 300 IN A 192.0.2.1").unwrap())
+            .is_err());
+        assert_eq!(txn.commit().unwrap(), 2);
+        assert_eq!(zone.serial(), Some(2));
+        assert!(zone.get(&name("new.example.com."), RRType::A).is_some());
+        assert_eq!(
+            zone.get(&name("www.example.com."), RRType::A)
+                .unwrap()
+                .rdatas
+                .len(),
+            1
+        );
+        assert_eq!(
+            zone.lookup(&name("ftp.example.com."), RRType::A),
+            ZoneLookup::NXDomain
+        );
+
+        //a cname next to the address makes the whole transaction fail
+        let mut txn = zone.begin();
+        txn.add(RRset::from_str("other.example.com. 300 IN A 192.0.2.8").unwrap())
+            .unwrap()
+            .add(RRset::from_str("www.example.com. 300 IN CNAME new.example.com.").unwrap())
+            .unwrap();
+        assert!(txn.commit().is_err());
+        assert!(zone.get(&name("other.example.com."), RRType::A).is_none());
+        assert_eq!(zone.serial(), Some(2));
+
+        let mut txn = zone.begin();
+        let mut soa = zone_soa_rrset();
+        soa.rdatas.clear();
+        txn.delete(soa).unwrap();
+        assert!(txn.commit().is_err());
+
+        let mut txn = zone.begin();
+        txn.add(RRset::from_str("other.example.com. 300 IN A 192.0.2.8").unwrap())
+            .unwrap();
+        txn.rollback();
+        assert!(zone.get(&name("other.example.com."), RRType::A).is_none());
+
+        //a serial raised by the transaction is kept
+        let mut txn = zone.begin();
+        txn.add(RRset::from_str(
+                "example.com. 3600 IN SOA ns1.example.com. root.example.com. 10 3600 900 604800 300",
+            ).unwrap())
+            .unwrap();
+        assert_eq!(txn.commit().unwrap(), 10);
+        assert_eq!(zone.begin().commit().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_zone_diff() {
+        let old = example_zone();
+        assert!(diff(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        let mut txn = new.begin();
+        txn.add(RRset::from_str("a.example.com. 300 IN A 192.0.2.10").unwrap())
+            .unwrap()
+            .add(RRset::from_str("z.example.com. 300 IN A 192.0.2.11").unwrap())
+            .unwrap()
+            .delete(RRset::from_str("www.example.com. 300 IN A 192.0.2.2").unwrap())
+            .unwrap();
+        let mut txt = RRset::from_str("a.b.example.com. 300 IN TXT \"deep\"").unwrap();
+        txt.rdatas.clear();
+        txn.delete(txt).unwrap();
+        txn.commit().unwrap();
+
+        let changes = diff(&old, &new);
+        let added: Vec<String> = changes
+            .added
+            .iter()
+            .map(|rrset| rrset.name.to_string())
+            .collect();
+        assert_eq!(added, vec!["a.example.com.", "z.example.com."]);
+        assert_eq!(changes.removed.len(), 1);
+        assert_eq!(changes.removed[0].typ, RRType::TXT);
+        let changed: Vec<RRType> = changes.changed.iter().map(|(old, _)| old.typ).collect();
+        assert_eq!(changed, vec![RRType::SOA, RRType::A]);
+        assert_eq!(changes.changed[1].1.rdatas.len(), 1);
+
+        let back = diff(&new, &old);
+        assert_eq!(back.added, changes.removed);
+        assert_eq!(back.removed, changes.added);
+    }
+
+    #[test]
+    fn test_zone_normalize() {
+        let mut zone = Zone::new(Name::new("Example.COM.").unwrap());
+        for rr in &[
+            "example.com. 3600 IN SOA ns1.example.com. root.example.com. 1 3600 900 604800 300",
+            "WWW.example.com. 30 IN TXT \"b\"",
+            "www.Example.com. 30 IN A 192.0.2.2",
+            "www.example.com. 9999999 IN A 192.0.2.1",
+            "www.example.com. 300 IN A 192.0.2.2",
+        ] {
+            zone.add_rrset(RRset::from_str(rr).unwrap()).unwrap();
+        }
+        let policy = TtlPolicy {
+            min_ttl: 60,
+            max_ttl: 86400,
+        };
+        zone.normalize(&policy);
+        assert_eq!(zone.origin().to_string(), "example.com.");
+
+        let www = Name::new("www.example.com.").unwrap();
+        let (owner, rrsets) = zone.nodes.get_key_value(&www).unwrap();
+        assert_eq!(owner.to_string(), "www.example.com.");
+        let types: Vec<RRType> = rrsets.iter().map(|rrset| rrset.typ).collect();
+        assert_eq!(types, vec![RRType::A, RRType::TXT]);
+        let a = &rrsets[0];
+        assert_eq!(a.name.to_string(), "www.example.com.");
+        assert_eq!(a.ttl, RRTtl(60));
+        let addrs: Vec<String> = a.rdatas.iter().map(|rdata| rdata.to_string()).collect();
+        assert_eq!(addrs, vec!["192.0.2.1", "192.0.2.2"]);
+        assert_eq!(zone.soa().unwrap().ttl, RRTtl(3600));
+
+        let once = zone.clone();
+        zone.normalize(&policy);
+        assert_eq!(zone.nodes, once.nodes);
+        assert_eq!(TtlPolicy::default().clamp(u32::MAX), MAX_TTL);
+    }
+}
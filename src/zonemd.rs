@@ -0,0 +1,274 @@
+use crate::name::Name;
+use crate::rdata::RData;
+use crate::rr_type::RRType;
+use crate::rrset::RRset;
+use crate::util::digest;
+use crate::util::OutputBuffer;
+use crate::zone::Zone;
+
+pub const SCHEME_SIMPLE: u8 = 1;
+pub const HASH_SHA384: u8 = 1;
+pub const HASH_SHA512: u8 = 2;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ZonemdStatus {
+    Verified(u8),
+    Missing,
+    //no record with a scheme and hash algorithm that can be checked
+    Unsupported,
+    //rfc8976 section 4 step 6, more than one record for a scheme and hash
+    Duplicated(u8, u8),
+    SerialMismatch {
+        soa: u32,
+        zonemd: u32,
+    },
+    DigestMismatch {
+        hash_algorithm: u8,
+        expected: Vec<u8>,
+        computed: Vec<u8>,
+    },
+}
+
+impl ZonemdStatus {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, ZonemdStatus::Verified(_))
+    }
+}
+
+fn lowercase(name: &Name) -> Name {
+    let mut name = name.clone();
+    name.to_lowercase();
+    name
+}
+
+//rfc4034 section 6.2 as updated by rfc6840 section 5.1, the names in the
+//rdata of these types are lowercased
+fn canonical_rdata(rdata: &RData) -> RData {
+    let mut rdata = rdata.clone();
+    match rdata {
+        RData::NS(ref mut ns) => ns.name.to_lowercase(),
+        RData::CName(ref mut cname) => cname.name.to_lowercase(),
+        RData::SOA(ref mut soa) => {
+            soa.mname.to_lowercase();
+            soa.rname.to_lowercase();
+        }
+        RData::PTR(ref mut ptr) => ptr.name.to_lowercase(),
+        RData::MX(ref mut mx) => mx.name.to_lowercase(),
+        RData::NAPTR(ref mut naptr) => naptr.replacement.to_lowercase(),
+        RData::SRV(ref mut srv) => srv.target.to_lowercase(),
+        RData::DName(ref mut dname) => dname.target.to_lowercase(),
+        RData::RP(ref mut rp) => {
+            rp.mbox.to_lowercase();
+            rp.txt.to_lowercase();
+        }
+        RData::RRSIG(ref mut rrsig) => rrsig.signer.to_lowercase(),
+        _ => {}
+    }
+    rdata
+}
+
+//rfc8976 section 3.3.1, the apex zonemd and the rrsigs covering it are
+//left out since they can't cover themselves
+fn is_excluded(rdata: &RData, apex: bool) -> bool {
+    apex && match rdata {
+        RData::ZONEMD(_) => true,
+        RData::RRSIG(rrsig) => rrsig.type_covered == RRType::ZONEMD,
+        _ => false,
+    }
+}
+
+//the simple scheme digest over every record in canonical order, none when
+//the hash algorithm isn't supported
+pub fn zone_digest(zone: &Zone, hash_algorithm: u8) -> Option<Vec<u8>> {
+    let hash: fn(&[u8]) -> Vec<u8> = match hash_algorithm {
+        HASH_SHA384 => digest::sha384,
+        HASH_SHA512 => digest::sha512,
+        _ => return None,
+    };
+
+    let mut rrsets: Vec<RRset> = zone
+        .rrsets()
+        .filter_map(|rrset| {
+            let apex = rrset.name.eq(zone.origin());
+            let rdatas: Vec<RData> = rrset
+                .rdatas
+                .iter()
+                .filter(|rdata| !is_excluded(rdata, apex))
+                .map(canonical_rdata)
+                .collect();
+            if rdatas.is_empty() {
+                return None;
+            }
+            let mut canonical = RRset {
+                name: lowercase(&rrset.name),
+                typ: rrset.typ,
+                class: rrset.class,
+                ttl: rrset.ttl,
+                rdatas,
+            };
+            canonical.sort_rdatas();
+            Some(canonical)
+        })
+        .collect();
+    rrsets.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then(a.class.to_u16().cmp(&b.class.to_u16()))
+            .then(a.typ.to_u16().cmp(&b.typ.to_u16()))
+    });
+
+    let mut buf = OutputBuffer::new(0);
+    for rrset in &rrsets {
+        rrset.to_wire(&mut buf);
+    }
+    Some(hash(buf.data()))
+}
+
+//rfc8976 section 4, the zone is verified when any supported zonemd record
+//matches. Otherwise the last problem found is returned
+pub fn verify_zonemd(zone: &Zone) -> ZonemdStatus {
+    let records: Vec<_> = match zone.get(zone.origin(), RRType::ZONEMD) {
+        Some(rrset) => rrset
+            .rdatas
+            .iter()
+            .filter_map(|rdata| match rdata {
+                RData::ZONEMD(zonemd) => Some(zonemd.as_ref()),
+                _ => None,
+            })
+            .collect(),
+        None => return ZonemdStatus::Missing,
+    };
+    let serial = match zone.serial() {
+        Some(serial) => serial,
+        None => return ZonemdStatus::Missing,
+    };
+
+    let supported: Vec<_> = records
+        .into_iter()
+        .filter(|zonemd| {
+            zonemd.scheme == SCHEME_SIMPLE
+                && (zonemd.hash_algorithm == HASH_SHA384 || zonemd.hash_algorithm == HASH_SHA512)
+        })
+        .collect();
+    for (i, zonemd) in supported.iter().enumerate() {
+        if supported[i + 1..].iter().any(|other| {
+            other.scheme == zonemd.scheme && other.hash_algorithm == zonemd.hash_algorithm
+        }) {
+            return ZonemdStatus::Duplicated(zonemd.scheme, zonemd.hash_algorithm);
+        }
+    }
+
+    let mut status = ZonemdStatus::Unsupported;
+    for zonemd in supported {
+        if zonemd.serial != serial {
+            status = ZonemdStatus::SerialMismatch {
+                soa: serial,
+                zonemd: zonemd.serial,
+            };
+            continue;
+        }
+        let computed = zone_digest(zone, zonemd.hash_algorithm).unwrap();
+        if computed == zonemd.digest {
+            return ZonemdStatus::Verified(zonemd.hash_algorithm);
+        }
+        status = ZonemdStatus::DigestMismatch {
+            hash_algorithm: zonemd.hash_algorithm,
+            expected: zonemd.digest.clone(),
+            computed,
+        };
+    }
+    status
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::hex::to_hex;
+    use std::str::FromStr;
+
+    fn build_zone(records: &[&str]) -> Zone {
+        let mut zone = Zone::new(Name::new("example.").unwrap());
+        for record in records {
+            zone.add_rrset(RRset::from_str(record).unwrap()).unwrap();
+        }
+        zone
+    }
+
+    //rfc8976 appendix a.1
+    const SIMPLE_ZONE: &[&str] = &[
+        "example. 86400 IN SOA ns1.example. admin.example. 2018031900 1800 900 604800 86400",
+        "example. 86400 IN NS ns1.example.",
+        "example. 86400 IN NS ns2.example.",
+        "example. 86400 IN ZONEMD 2018031900 1 1 c68090d90a7aed716bc459f9340e3d7c1370d4d24b7e2fc3a1ddc0b9a87153b9a9713b3c9ae5cc27777f98b8e730044c",
+        "ns1.example. 3600 IN A 203.0.113.63",
+        "ns2.example. 3600 IN AAAA 2001:db8::63",
+    ];
+
+    #[test]
+    fn test_verify_zonemd() {
+        let zone = build_zone(SIMPLE_ZONE);
+        assert_eq!(verify_zonemd(&zone), ZonemdStatus::Verified(HASH_SHA384));
+        assert!(zone_digest(&zone, 3).is_none());
+
+        //names are compared in canonical form
+        let mut records = SIMPLE_ZONE.to_vec();
+        records[1] = "example. 86400 IN NS NS1.Example.";
+        assert!(verify_zonemd(&build_zone(&records)).is_verified());
+
+        records.push("ns3.example. 3600 IN A 203.0.113.64");
+        match verify_zonemd(&build_zone(&records)) {
+            ZonemdStatus::DigestMismatch {
+                hash_algorithm,
+                expected,
+                computed,
+            } => {
+                assert_eq!(hash_algorithm, HASH_SHA384);
+                assert_eq!(
+                    to_hex(&expected),
+                    "c68090d90a7aed716bc459f9340e3d7c1370d4d24b7e2fc3a1ddc0b9a87153b9a9713b3c9ae5cc27777f98b8e730044c"
+                );
+                assert_eq!(computed.len(), 48);
+            }
+            status => panic!("unexpected {:?}", status),
+        }
+
+        let mut records = SIMPLE_ZONE.to_vec();
+        records[3] = "example. 86400 IN ZONEMD 2018031901 1 1 c68090d90a7aed716bc459f9340e3d7c";
+        assert_eq!(
+            verify_zonemd(&build_zone(&records)),
+            ZonemdStatus::SerialMismatch {
+                soa: 2018031900,
+                zonemd: 2018031901
+            }
+        );
+
+        records[3] = "example. 86400 IN ZONEMD 2018031900 1 240 c68090d90a7aed716bc459f9340e3d7c";
+        assert_eq!(
+            verify_zonemd(&build_zone(&records)),
+            ZonemdStatus::Unsupported
+        );
+
+        records.remove(3);
+        assert_eq!(verify_zonemd(&build_zone(&records)), ZonemdStatus::Missing);
+        records.push(SIMPLE_ZONE[3]);
+        records.push("example. 86400 IN ZONEMD 2018031900 1 1 00112233445566778899aabb");
+        assert_eq!(
+            verify_zonemd(&build_zone(&records)),
+            ZonemdStatus::Duplicated(SCHEME_SIMPLE, HASH_SHA384)
+        );
+
+        //the apex zonemd isn't part of the digest, so replacing it keeps
+        //the computed value
+        let mut records = SIMPLE_ZONE.to_vec();
+        let digest = zone_digest(&build_zone(&records), HASH_SHA512).unwrap();
+        let zonemd = format!(
+            "example. 86400 IN ZONEMD 2018031900 1 2 {}",
+            to_hex(&digest)
+        );
+        records[3] = &zonemd;
+        assert_eq!(
+            verify_zonemd(&build_zone(&records)),
+            ZonemdStatus::Verified(HASH_SHA512)
+        );
+    }
+}